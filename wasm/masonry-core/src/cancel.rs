@@ -0,0 +1,112 @@
+//! Cancellation of the computation in progress from another thread.
+//!
+//! The layout loops check the flag, so a computation that runs on a web worker or any other
+//! thread can be stopped early, e.g. because its result is already outdated. Layouts that are
+//! computed on different threads at the same time use different slots, each with its own flag
+//! and progress (see [`Layout::set_cancel_slot()`](crate::Layout::set_cancel_slot)). The
+//! functions without a slot use slot 0, which is the default of every layout.
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::convert::usize_to_u32;
+
+/// Number of slots, which is the number of layouts that can be cancelled separately while they
+/// are computed at the same time.
+pub const MAX_SLOTS: usize = 8;
+
+struct Slot {
+    cancelled: AtomicBool,
+    /// Position in the layout the computation in progress reached at the end of its last slice.
+    progress: AtomicU32,
+}
+
+/// Only used to initialize [`SLOTS`], whose elements are not `Copy`.
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_SLOT: Slot = Slot {
+    cancelled: AtomicBool::new(false),
+    progress: AtomicU32::new(0),
+};
+
+static SLOTS: [Slot; MAX_SLOTS] = [EMPTY_SLOT; MAX_SLOTS];
+/// Smallest number of items between two checks of the cancellation flag. Publishing the
+/// progress at every item would touch the shared memory as often as the item itself.
+pub const MIN_SLICE_SIZE: u32 = 64;
+
+/// Number of items between two checks of the cancellation flag.
+static SLICE_SIZE: AtomicU32 = AtomicU32::new(MIN_SLICE_SIZE);
+
+/// Signals the computation in progress to stop.
+pub fn cancel() {
+    stop_slot(0);
+}
+
+/// Signals the computation in progress in the slot to stop.
+pub fn stop_slot(slot: usize) {
+    SLOTS[slot % MAX_SLOTS]
+        .cancelled
+        .store(true, Ordering::SeqCst);
+}
+
+/// Clears the cancellation flag and the progress before the next computation starts.
+pub fn reset() {
+    reset_slot(0);
+}
+
+/// Clears the cancellation flag and the progress of the slot before the next computation in it
+/// starts.
+pub fn reset_slot(slot: usize) {
+    let slot = &SLOTS[slot % MAX_SLOTS];
+    slot.cancelled.store(false, Ordering::SeqCst);
+    slot.progress.store(0, Ordering::Relaxed);
+}
+
+/// Returns true if the computation in progress should stop early.
+///
+/// This is checked inside the layout loops, so it must be cheap.
+#[inline]
+#[must_use]
+pub fn is_cancelled() -> bool {
+    is_slot_cancelled(0)
+}
+
+/// Returns true if the computation in progress in the slot should stop early.
+#[inline]
+#[must_use]
+pub fn is_slot_cancelled(slot: usize) -> bool {
+    SLOTS[slot % MAX_SLOTS].cancelled.load(Ordering::Relaxed)
+}
+
+/// Returns true if the computation in progress in the slot should stop early at the given
+/// position.
+///
+/// The layout loops call this for every item. The items are processed in slices: the progress
+/// is published and the cancellation flag is checked only at the start of every slice, which
+/// keeps the shared memory traffic of huge layouts low.
+#[inline]
+pub(crate) fn is_cancelled_at(slot: usize, position: usize) -> bool {
+    let slice_size = SLICE_SIZE.load(Ordering::Relaxed) as usize;
+    if position % slice_size != 0 {
+        return false;
+    }
+    SLOTS[slot % MAX_SLOTS]
+        .progress
+        .store(usize_to_u32(position), Ordering::Relaxed);
+    is_slot_cancelled(slot)
+}
+
+/// Sets the number of items that are laid out between two checks for cancellation, which is at
+/// least [`MIN_SLICE_SIZE`].
+pub fn set_slice_size(slice_size: u32) {
+    SLICE_SIZE.store(slice_size.max(MIN_SLICE_SIZE), Ordering::Relaxed);
+}
+
+/// Returns the position in the layout that the computation in progress has reached.
+#[must_use]
+pub fn progress() -> u32 {
+    slot_progress(0)
+}
+
+/// Returns the position in the layout that the computation in progress in the slot has reached.
+#[must_use]
+pub fn slot_progress(slot: usize) -> u32 {
+    SLOTS[slot % MAX_SLOTS].progress.load(Ordering::Relaxed)
+}
@@ -0,0 +1,363 @@
+//! Storage of the per-item buffers in fixed-size chunks.
+//!
+//! Growing a single `Vec` to hundreds of thousands of items reallocates and copies megabytes at
+//! once, e.g. in the middle of an import. The chunks are allocated one at a time instead and never
+//! move, so the memory grows incrementally and references to elements stay valid while the buffer
+//! grows. Indexing only takes a shift and a mask.
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::ops::{Bound, Index, IndexMut, Range, RangeBounds};
+
+use crate::util::{abort, UnwrapOrAbort};
+
+const CHUNK_BITS: usize = 12;
+/// Number of elements per chunk.
+const CHUNK_LEN: usize = 1 << CHUNK_BITS;
+const CHUNK_MASK: usize = CHUNK_LEN - 1;
+
+pub(crate) struct ChunkedVec<T> {
+    chunks: Vec<Box<[T]>>,
+    len: usize,
+}
+
+/// A range of elements of a [`ChunkedVec`], which is used like a slice.
+pub(crate) struct Slice<'a, T> {
+    vec: &'a ChunkedVec<T>,
+    start: usize,
+    end: usize,
+}
+
+/// Iterator over the elements of a range, which knows its exact length.
+pub(crate) struct Iter<'a, T> {
+    vec: &'a ChunkedVec<T>,
+    range: Range<usize>,
+}
+
+/// Mutable iterator over the elements of a range, which knows its exact length.
+pub(crate) struct IterMut<'a, T> {
+    /// The chunks between the first and the last one of the range.
+    chunks: core::slice::IterMut<'a, Box<[T]>>,
+    front: core::slice::IterMut<'a, T>,
+    back: core::slice::IterMut<'a, T>,
+    len: usize,
+}
+
+impl<T: Clone> ChunkedVec<T> {
+    pub fn from_elem(value: T, len: usize) -> ChunkedVec<T> {
+        let mut vec = ChunkedVec {
+            chunks: Vec::new(),
+            len: 0,
+        };
+        vec.resize(len, value);
+        vec
+    }
+
+    /// Resizes the buffer like [`Vec::resize()`], but only allocates the missing chunks.
+    pub fn resize(&mut self, new_len: usize, value: T) {
+        while self.capacity() < new_len {
+            self.chunks
+                .push(vec![value.clone(); CHUNK_LEN].into_boxed_slice());
+        }
+        for index in self.len..new_len {
+            self.chunks[index >> CHUNK_BITS][index & CHUNK_MASK] = value.clone();
+        }
+        self.len = new_len;
+    }
+}
+
+impl<T> ChunkedVec<T> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the number of elements that fit into the allocated chunks.
+    pub fn capacity(&self) -> usize {
+        self.chunks.len() << CHUNK_BITS
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    /// Frees the chunks that only hold elements beyond the length.
+    pub fn shrink_to_fit(&mut self) {
+        self.chunks.truncate((self.len + CHUNK_MASK) >> CHUNK_BITS);
+        self.chunks.shrink_to_fit();
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            Some(&self.chunks[index >> CHUNK_BITS][index & CHUNK_MASK])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len {
+            Some(&mut self.chunks[index >> CHUNK_BITS][index & CHUNK_MASK])
+        } else {
+            None
+        }
+    }
+
+    /// Returns the elements in the range, which must lie inside of the buffer.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Slice<'_, T> {
+        let Range { start, end } = bounds(&range, self.len);
+        Slice {
+            vec: self,
+            start,
+            end,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.slice(..).iter()
+    }
+
+    /// Returns the elements in the range mutably, which must lie inside of the buffer.
+    pub fn range_mut(&mut self, range: impl RangeBounds<usize>) -> IterMut<'_, T> {
+        let Range { start, end } = bounds(&range, self.len);
+        let mut iter = IterMut {
+            chunks: [].iter_mut(),
+            front: [].iter_mut(),
+            back: [].iter_mut(),
+            len: end - start,
+        };
+        if start == end {
+            return iter;
+        }
+        let (first, last) = (start >> CHUNK_BITS, (end - 1) >> CHUNK_BITS);
+        let (lower, upper) = (start & CHUNK_MASK, end - (last << CHUNK_BITS));
+        match &mut self.chunks[first..=last] {
+            [chunk] => iter.front = chunk[lower..upper].iter_mut(),
+            [first, chunks @ .., last] => {
+                iter.front = first[lower..].iter_mut();
+                iter.chunks = chunks.iter_mut();
+                iter.back = last[..upper].iter_mut();
+            }
+            [] => {}
+        }
+        iter
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.range_mut(..)
+    }
+
+    pub fn swap(&mut self, a: usize, b: usize) {
+        let (a, b) = (a.min(b), a.max(b));
+        if b >= self.len {
+            abort();
+        }
+        let (chunk_a, chunk_b) = (a >> CHUNK_BITS, b >> CHUNK_BITS);
+        if chunk_a == chunk_b {
+            self.chunks[chunk_a].swap(a & CHUNK_MASK, b & CHUNK_MASK);
+        } else {
+            let (left, right) = self.chunks.split_at_mut(chunk_b);
+            core::mem::swap(
+                &mut left[chunk_a][a & CHUNK_MASK],
+                &mut right[0][b & CHUNK_MASK],
+            );
+        }
+    }
+
+    /// Reverses the order of the elements in the range.
+    pub fn reverse(&mut self, range: impl RangeBounds<usize>) {
+        let Range { mut start, mut end } = bounds(&range, self.len);
+        while start + 1 < end {
+            end -= 1;
+            self.swap(start, end);
+            start += 1;
+        }
+    }
+
+    /// Rotates the range like [`slice::rotate_left()`].
+    pub fn rotate_left(&mut self, range: Range<usize>, mid: usize) {
+        let mid = range.start + mid;
+        self.reverse(range.start..mid);
+        self.reverse(mid..range.end);
+        self.reverse(range);
+    }
+
+    /// Rotates the range like [`slice::rotate_right()`].
+    pub fn rotate_right(&mut self, range: Range<usize>, k: usize) {
+        let mid = range.len() - k;
+        self.rotate_left(range, mid);
+    }
+}
+
+impl<T: Clone> Clone for ChunkedVec<T> {
+    fn clone(&self) -> Self {
+        ChunkedVec {
+            chunks: self.chunks.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T> Index<usize> for ChunkedVec<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, index: usize) -> &T {
+        self.get(index).unwrap_or_abort()
+    }
+}
+
+impl<T> IndexMut<usize> for ChunkedVec<T> {
+    #[inline]
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).unwrap_or_abort()
+    }
+}
+
+impl<'a, T> Slice<'a, T> {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        if index < self.len() {
+            self.vec.get(self.start + index)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a part of the slice with indices relative to the start of this one.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Slice<'a, T> {
+        let Range { start, end } = bounds(&range, self.len());
+        Slice {
+            vec: self.vec,
+            start: self.start + start,
+            end: self.start + end,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'a, T> {
+        Iter {
+            vec: self.vec,
+            range: self.start..self.end,
+        }
+    }
+
+    /// Binary searches the first element for which the predicate is false like
+    /// [`slice::partition_point()`].
+    pub fn partition_point(&self, mut pred: impl FnMut(&T) -> bool) -> usize {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if pred(&self.vec[self.start + mid]) {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+}
+
+impl<T> Clone for Slice<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Slice<'_, T> {}
+
+impl<T> Index<usize> for Slice<'_, T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, index: usize) -> &T {
+        self.get(index).unwrap_or_abort()
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a T> {
+        self.range.next().map(|index| &self.vec[index])
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.range.next_back().map(|index| &self.vec[index])
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a mut T> {
+        loop {
+            if let Some(element) = self.front.next() {
+                self.len -= 1;
+                return Some(element);
+            }
+            match self.chunks.next() {
+                Some(chunk) => self.front = chunk.iter_mut(),
+                None => break,
+            }
+        }
+        let element = self.back.next()?;
+        self.len -= 1;
+        Some(element)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(element) = self.back.next_back() {
+                self.len -= 1;
+                return Some(element);
+            }
+            match self.chunks.next_back() {
+                Some(chunk) => self.back = chunk.iter_mut(),
+                None => break,
+            }
+        }
+        let element = self.front.next_back()?;
+        self.len -= 1;
+        Some(element)
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+/// Converts the range to indices and aborts if it does not lie inside of `0..len`.
+fn bounds(range: &impl RangeBounds<usize>, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end + 1,
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    if start > end || end > len {
+        abort();
+    }
+    start..end
+}
@@ -0,0 +1,46 @@
+//! Picks the color that covers most of an image, e.g. as the background of an item while its
+//! thumbnail loads.
+use alloc::vec;
+
+use crate::util::UnwrapOrAbort;
+
+/// Bits per channel of the colors that are counted together.
+const BUCKET_BITS: u32 = 4;
+/// At most about this many pixels are counted, the others are skipped evenly.
+const MAX_SAMPLES: usize = 1 << 14;
+
+/// Returns the dominant color of an image as `0xRRGGBB`, or `None` if it is fully transparent
+/// or has fewer pixels than its size.
+///
+/// The pixels are given as red, green, blue and alpha bytes row by row, e.g. the data of an
+/// `ImageData`. Similar colors are counted together, weighted by their opacity, and the average
+/// of the most frequent group is returned, so a small but saturated detail does not win over the
+/// background. Large images are sampled, so scaling them down first is not necessary.
+#[must_use]
+pub fn dominant(pixels: &[u8], width: usize, height: usize) -> Option<u32> {
+    let num_pixels = width * height;
+    if pixels.len() < 4 * num_pixels {
+        return None;
+    }
+    let step = (num_pixels / MAX_SAMPLES).max(1);
+    // The weight and the weighted sums of the red, green and blue values of every bucket.
+    let mut buckets = vec![[0_u64; 4]; 1 << (3 * BUCKET_BITS)];
+    for pixel in pixels[..4 * num_pixels].chunks_exact(4).step_by(step) {
+        let opacity = u64::from(pixel[3]);
+        let bucket = pixel[..3].iter().fold(0, |bucket, &value| {
+            bucket << BUCKET_BITS | usize::from(value >> (8 - BUCKET_BITS))
+        });
+        let bucket = &mut buckets[bucket];
+        bucket[0] += opacity;
+        for (sum, &value) in bucket[1..].iter_mut().zip(pixel) {
+            *sum += opacity * u64::from(value);
+        }
+    }
+    let [total, red, green, blue] = buckets.into_iter().max_by_key(|bucket| bucket[0])?;
+    if total == 0 {
+        return None;
+    }
+    let [red, green, blue] =
+        [red, green, blue].map(|sum| u32::try_from((sum + total / 2) / total).unwrap_or_abort());
+    Some(red << 16 | green << 8 | blue)
+}
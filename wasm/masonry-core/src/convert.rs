@@ -0,0 +1,53 @@
+//! Conversions between the integer and float types of lengths, counts and positions.
+//!
+//! Lengths in pixels stay far below 2^24, above which `f32` starts to round them, and counts
+//! and positions of items always fit into `u32`. Floats are converted to whole pixels on purpose,
+//! rounding towards 0 like `as` does, so callers round with `+ 0.5` first where they need to.
+use crate::util::UnwrapOrAbort;
+
+/// Converts a count or position of items, which always fits, to `u32`.
+#[inline]
+#[must_use]
+pub fn usize_to_u32(value: usize) -> u32 {
+    u32::try_from(value).unwrap_or_abort()
+}
+
+/// Converts a length in pixels to `f32`.
+#[inline]
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn u32_to_f32(value: u32) -> f32 {
+    value as f32
+}
+
+/// Converts a count of items to `f64`, which represents every count exactly.
+#[inline]
+#[must_use]
+pub fn usize_to_f64(value: usize) -> f64 {
+    f64::from(usize_to_u32(value))
+}
+
+/// Converts a length to whole pixels. Negative values and `NaN` become 0 and values beyond
+/// `u32::MAX` saturate.
+#[inline]
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn f32_to_u32(value: f32) -> u32 {
+    value as u32
+}
+
+/// Converts a length to whole pixels like [`f32_to_u32()`].
+#[inline]
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn f64_to_u32(value: f64) -> u32 {
+    value as u32
+}
+
+/// Converts a length or ratio to `f32`, rounding to the nearest `f32`.
+#[inline]
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn f64_to_f32(value: f64) -> f32 {
+    value as f32
+}
@@ -0,0 +1,436 @@
+use core::fmt;
+
+use crate::convert::{f64_to_u32, usize_to_u32};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum MasonryType {
+    Vertical,
+    Horizontal,
+    Grid,
+    /// One item per row at the thumbnail size as row height, e.g. for a list view with metadata
+    /// next to the thumbnails.
+    List,
+    /// Square cells like the grid, with every few items a large cell of two columns and two rows.
+    Mosaic,
+    /// Rows that are all as high as the thumbnail size and not scaled to fill the width.
+    FixedRows,
+    /// A single row as high as the thumbnail size that scrolls horizontally. Its computations
+    /// return the content width instead of the height.
+    Filmstrip,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Where an item ends up in the viewport when it is scrolled to.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum ScrollAlignment {
+    /// The top of the item at the top of the viewport.
+    Top,
+    /// The middle of the item in the middle of the viewport.
+    Center,
+    /// The bottom of the item at the bottom of the viewport.
+    Bottom,
+}
+
+/// How the last row of the horizontal layout is laid out if it is not full.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum LastRowBehavior {
+    /// Left-aligned at the thumbnail size. This is the default.
+    Left,
+    /// Stretched to fill the container width like the other rows.
+    Justify,
+    /// Not shown. Its items get an empty transform.
+    Hide,
+}
+
+/// How the scaled positions and sizes of the items are rounded to whole pixels.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum RoundingMode {
+    /// Rounded down. This is the default.
+    Floor,
+    /// Rounded to the nearest pixel.
+    Round,
+    /// The edges of the items are rounded instead of their sizes, so the rounding error is carried
+    /// over to the next item and adjacent items never leave a gap or overlap.
+    Accumulate,
+}
+
+/// An inclusive range of item indices.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy)]
+pub struct ItemRange {
+    pub first: u32,
+    pub last: u32,
+}
+
+/// The index of an item together with its tag, see [`crate::Layout::set_tag()`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy)]
+pub struct TaggedItem {
+    pub index: u32,
+    pub tag: u32,
+}
+
+/// How finely the items of a timeline are grouped into sections, see
+/// [`crate::timeline::timeline()`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum TimelineGranularity {
+    Day,
+    Month,
+    Year,
+}
+
+/// A day, month or year of a timeline and the run of items in it.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy)]
+pub struct TimelineSection {
+    /// Start of the day, month or year in milliseconds since the Unix epoch, or `NaN` for the
+    /// items without a timestamp.
+    pub start: f64,
+    /// Position of the first item of the section in the order of the timeline.
+    pub first: u32,
+    pub len: u32,
+}
+
+/// The section header that sticks to the top of the viewport.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy)]
+pub struct StickyHeader {
+    pub index: u32,
+    /// How far the header is pushed up by the next header.
+    pub offset: u32,
+}
+
+/// How much memory a layout holds, e.g. to decide when to shrink it.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy)]
+pub struct MemoryStats {
+    /// Number of items that are laid out.
+    pub num_items: u32,
+    /// Number of items whose dimensions fit into the allocated memory.
+    pub item_capacity: u32,
+    /// Number of items whose transforms fit into the allocated memory.
+    pub transform_capacity: u32,
+    /// Bytes allocated for all per-item buffers together.
+    pub bytes: u32,
+    /// Size of the whole WebAssembly memory in pages of 64 KiB, or 0 on other targets.
+    pub heap_pages: u32,
+}
+
+/// Shape of the most recent computation, e.g. for a debug overlay.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Default)]
+pub struct LayoutStats {
+    /// Number of items that are not hidden.
+    pub visible_items: u32,
+    /// Number of runs of visible items that share their top, or 0 in a vertical masonry layout,
+    /// which has columns instead of rows.
+    pub rows: u32,
+    /// Number of items in the longest row.
+    pub columns: u32,
+}
+
+/// The layout after a batch of dimensions arrived, see [`crate::Layout::compute_arrivals()`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Default)]
+pub struct HeightUpdate {
+    /// New container height.
+    pub height: u32,
+    /// Position of the first item that was laid out again.
+    pub first: u32,
+    /// Position from which the items kept their arrangement and only moved down by `offset`.
+    /// It is the item count if every item from `first` on was laid out again.
+    pub end: u32,
+    /// How far the items from `end` on moved down, which is negative if they moved up.
+    pub offset: i32,
+    /// Number of visible items that are still laid out with the default aspect ratio because
+    /// their dimension did not arrive yet.
+    pub remaining: u32,
+}
+
+/// A run of visible items that share their top, see [`crate::Layout::row_info()`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy)]
+pub struct RowInfo {
+    pub top: u32,
+    /// Height of the tallest item in the row.
+    pub height: u32,
+    /// Position of the first and last item in the row. Hidden items between them are included.
+    pub first: u32,
+    pub last: u32,
+}
+
+/// A column of the vertical masonry or grid layout, see [`crate::Layout::column_info()`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy)]
+pub struct ColumnInfo {
+    pub left: u32,
+    pub width: u32,
+    /// Bottom of the lowest item in the column.
+    pub bottom: u32,
+    pub num_items: u32,
+}
+
+/// The invariant of the layout that is broken, see [`crate::Layout::validate()`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum ViolationKind {
+    /// Two visible items overlap.
+    Overlap,
+    /// A visible item reaches beyond the right edge of the container.
+    Overflow,
+    /// A visible item has no width or height.
+    Empty,
+}
+
+/// An item, or a pair of items, that breaks an invariant of the layout.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub index: u32,
+    /// The item that overlaps the other one, or the same index for the other kinds.
+    pub other: u32,
+}
+
+impl Violation {
+    pub(crate) fn new(kind: ViolationKind, index: usize, other: usize) -> Violation {
+        Violation {
+            kind,
+            index: usize_to_u32(index),
+            other: usize_to_u32(other),
+        }
+    }
+}
+
+/// The pixel dimensions and EXIF orientation read from the header of an image file, see
+/// [`crate::probe::image_info()`].
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, PartialEq)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    /// The EXIF orientation from 1 to 8, which is 1 if the file has none.
+    pub orientation: u8,
+}
+
+/// A parameter of a computation that cannot be laid out, see [`crate::Layout::check_config()`].
+#[derive(Clone, Copy, PartialEq)]
+pub enum ConfigError {
+    /// The thumbnail size is 0.
+    ThumbnailSize,
+    /// The outer padding on both sides takes up the whole container width.
+    OuterPadding,
+    /// The gap between the items is at least as wide as the content.
+    Padding,
+    /// The content is narrower than the minimum number of columns.
+    Columns,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigError::ThumbnailSize => "thumbnail_size must be positive",
+            ConfigError::OuterPadding => "outer_padding must be less than half of the width",
+            ConfigError::Padding => "padding must be less than the width of the content",
+            ConfigError::Columns => "width must fit at least one pixel per column",
+        })
+    }
+}
+
+/// Parameters of a computation. Lengths are in CSS pixels.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, PartialEq)]
+pub struct MasonryConfig {
+    pub kind: MasonryType,
+    pub thumbnail_size: u32,
+    /// Horizontal space between the items
+    pub gap_x: u32,
+    /// Vertical space between the items
+    pub gap_y: u32,
+    /// Space between the items and the edges of the container
+    pub outer_padding: u32,
+    /// Maximum width of the items together, or 0 if they fill the container
+    pub max_content_width: u32,
+    /// Space below every thumbnail that is reserved for a caption
+    pub caption_height: u32,
+    /// Width of the metadata next to the thumbnail in a row of the list layout, or 0 if the rows
+    /// fill the content width
+    pub metadata_width: u32,
+    /// Number of physical pixels per CSS pixel. All lengths are in CSS pixels, while the layout
+    /// is computed in whole physical pixels.
+    pub device_pixel_ratio: f32,
+    /// Thumbnail size at which the gaps are `gap_x` and `gap_y`, which scale proportionally with
+    /// the thumbnail size otherwise, or 0 if the gaps are fixed
+    pub gap_reference_size: u32,
+    /// Smallest gap that scaling shrinks the gaps to
+    pub min_gap: u32,
+    /// Largest gap that scaling grows the gaps to, or 0 for no limit
+    pub max_gap: u32,
+    /// Height the rows of the horizontal layout aim for instead of the thumbnail size, or 0 to
+    /// use the thumbnail size
+    pub target_row_height: u32,
+    /// Fraction of the target height by which a row of the horizontal layout may be higher to
+    /// end before the item that overflows it, or 0 if rows only ever shrink to fit
+    pub row_height_tolerance: f32,
+}
+
+/// Parameters that replace the config when the container is at least `min_width` wide.
+#[derive(Clone, Copy)]
+pub struct Breakpoint {
+    pub min_width: u32,
+    pub thumbnail_size: u32,
+    /// Gap between the items in both directions
+    pub padding: u32,
+    /// Number of columns, or 0 to derive it from the thumbnail size
+    pub columns: u32,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl MasonryConfig {
+    /// Creates a config with the same gap between the items in both directions.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    #[must_use]
+    pub fn new(kind: MasonryType, thumbnail_size: u32, padding: u32) -> MasonryConfig {
+        MasonryConfig {
+            kind,
+            thumbnail_size,
+            gap_x: padding,
+            gap_y: padding,
+            outer_padding: 0,
+            max_content_width: 0,
+            caption_height: 0,
+            metadata_width: 0,
+            device_pixel_ratio: 1.0,
+            gap_reference_size: 0,
+            min_gap: 0,
+            max_gap: 0,
+            target_row_height: 0,
+            row_height_tolerance: 0.0,
+        }
+    }
+}
+
+impl MasonryConfig {
+    pub const DEFAULT_THUMBNAIL_SIZE: u32 = 300;
+    pub const DEFAULT_PADDING: u32 = 8;
+
+    #[must_use]
+    pub fn with_gaps(self, gap_x: u32, gap_y: u32, outer_padding: u32) -> MasonryConfig {
+        MasonryConfig {
+            gap_x,
+            gap_y,
+            outer_padding,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_max_content_width(self, max_content_width: u32) -> MasonryConfig {
+        MasonryConfig {
+            max_content_width,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_caption_height(self, caption_height: u32) -> MasonryConfig {
+        MasonryConfig {
+            caption_height,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_metadata_width(self, metadata_width: u32) -> MasonryConfig {
+        MasonryConfig {
+            metadata_width,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn with_device_pixel_ratio(self, device_pixel_ratio: f32) -> MasonryConfig {
+        MasonryConfig {
+            device_pixel_ratio,
+            ..self
+        }
+    }
+
+    /// Scales the gaps proportionally with the thumbnail size, so that they are `gap_x` and
+    /// `gap_y` at the reference size and shrink and grow with zooming, but never below `min_gap`
+    /// or above `max_gap` (0 for no limit). A reference size of 0 keeps the gaps fixed.
+    #[must_use]
+    pub fn with_gap_scaling(
+        self,
+        reference_size: u32,
+        min_gap: u32,
+        max_gap: u32,
+    ) -> MasonryConfig {
+        MasonryConfig {
+            gap_reference_size: reference_size,
+            min_gap,
+            max_gap,
+            ..self
+        }
+    }
+
+    /// Sets the height the rows of the horizontal layout aim for independently of the thumbnail
+    /// size of the other kinds, e.g. high rows for a hero-like view or low ones for a dense
+    /// contact sheet, and by which fraction of it a row may be higher instead of shrinking to
+    /// make room for one more item. A target of 0 uses the thumbnail size.
+    #[must_use]
+    pub fn with_row_height(self, target_row_height: u32, tolerance: f32) -> MasonryConfig {
+        MasonryConfig {
+            target_row_height,
+            row_height_tolerance: tolerance,
+            ..self
+        }
+    }
+
+    /// Returns the size the items are laid out at instead of the given thumbnail size, which is
+    /// the target row height for the horizontal layout if the config has one (see
+    /// [`MasonryConfig::with_row_height()`]).
+    #[must_use]
+    pub fn row_thumbnail_size(&self, thumbnail_size: u32) -> u32 {
+        match (self.kind, self.target_row_height) {
+            (MasonryType::Horizontal, target_row_height) if target_row_height > 0 => {
+                target_row_height
+            }
+            _ => thumbnail_size,
+        }
+    }
+
+    /// Returns the gaps for the thumbnail size, scaled with the same factor in both directions if
+    /// the config scales them (see [`MasonryConfig::with_gap_scaling()`]).
+    #[must_use]
+    pub fn scale_gaps(&self, thumbnail_size: u32, (gap_x, gap_y): (u32, u32)) -> (u32, u32) {
+        if self.gap_reference_size == 0 {
+            return (gap_x, gap_y);
+        }
+        let factor = f64::from(thumbnail_size) / f64::from(self.gap_reference_size);
+        let max_gap = match self.max_gap {
+            0 => u32::MAX,
+            max_gap => max_gap,
+        };
+        let scale = |gap: u32| {
+            let gap = f64_to_u32(f64::from(gap) * factor + 0.5);
+            gap.min(max_gap).max(self.min_gap)
+        };
+        (scale(gap_x), scale(gap_y))
+    }
+}
@@ -0,0 +1,112 @@
+//! C ABI of the layout for hosts other than JavaScript.
+//!
+//! A layout is created with [`masonry_layout_new()`] and must be freed with
+//! [`masonry_layout_free()`]. All other functions take the pointer it returned. The layout is not
+//! synchronized, so it must only be used by one thread at a time.
+use alloc::boxed::Box;
+
+use crate::{Layout, MasonryConfig, MasonryType, Transform};
+
+/// Creates a layout for the given number of items, which have to be freed with
+/// [`masonry_layout_free()`].
+#[no_mangle]
+pub extern "C" fn masonry_layout_new(num_items: usize) -> *mut Layout {
+    Box::into_raw(Box::new(Layout::new(
+        num_items,
+        MasonryConfig::DEFAULT_THUMBNAIL_SIZE,
+        MasonryConfig::DEFAULT_PADDING,
+    )))
+}
+
+/// Frees a layout created by [`masonry_layout_new()`]. Null pointers are ignored.
+///
+/// # Safety
+///
+/// The pointer must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn masonry_layout_free(layout: *mut Layout) {
+    if !layout.is_null() {
+        drop(Box::from_raw(layout));
+    }
+}
+
+/// Sets the number of items that are laid out, see [`Layout::resize()`].
+///
+/// # Safety
+///
+/// The layout must have been created by [`masonry_layout_new()`] and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn masonry_layout_resize(layout: *mut Layout, num_items: usize) {
+    (*layout).resize(num_items);
+}
+
+/// Sets the dimension of the item at the given index if it is smaller than the item count.
+///
+/// # Safety
+///
+/// The layout must have been created by [`masonry_layout_new()`] and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn masonry_layout_set_dimension(
+    layout: *mut Layout,
+    index: usize,
+    src_width: u16,
+    src_height: u16,
+) {
+    (*layout).set_dimension(index, src_width, src_height);
+}
+
+/// Computes the transforms of all items and returns the height of the container.
+///
+/// The kind is 0 for the vertical masonry, 1 for the horizontal masonry, 2 for the grid, 3 for the
+/// list, 4 for the mosaic, 5 for the fixed rows and 6 for the filmstrip layout, which returns its
+/// width instead. Any other kind, or a config that cannot be laid out (see
+/// [`Layout::check_config()`]), computes nothing and returns 0.
+///
+/// # Safety
+///
+/// The layout must have been created by [`masonry_layout_new()`] and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn masonry_layout_compute(
+    layout: *mut Layout,
+    width: u32,
+    kind: u32,
+    thumbnail_size: u32,
+    padding: u32,
+) -> u32 {
+    let kind = match kind {
+        0 => MasonryType::Vertical,
+        1 => MasonryType::Horizontal,
+        2 => MasonryType::Grid,
+        3 => MasonryType::List,
+        4 => MasonryType::Mosaic,
+        5 => MasonryType::FixedRows,
+        6 => MasonryType::Filmstrip,
+        _ => return 0,
+    };
+    let config = MasonryConfig::new(kind, thumbnail_size, padding);
+    match (*layout).check_config(width, &config) {
+        Ok(()) => (*layout).compute(width, &config),
+        Err(_) => 0,
+    }
+}
+
+/// Returns a pointer to the transform of the item at the given index, or null if the index is
+/// greater than any item count of the layout.
+///
+/// The transform consists of seven `uint32_t`: the width, height, top and left followed by the
+/// top and left crop offsets and 1 if the thumbnail is cropped or 0 otherwise. The pointer is
+/// valid until the layout is resized, computed again or freed.
+///
+/// # Safety
+///
+/// The layout must have been created by [`masonry_layout_new()`] and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn masonry_layout_get_transform(
+    layout: *const Layout,
+    index: usize,
+) -> *const Transform {
+    match (*layout).get_transform(index) {
+        Some(transform) => transform,
+        None => core::ptr::null(),
+    }
+}
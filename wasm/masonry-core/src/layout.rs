@@ -0,0 +1,4688 @@
+// Main idea of this package:
+// - Take in a list of image dimensions, and a base thumbnail size (e.g. S, M, L)
+// - Output a list of image positions, laid out in a masonry format
+// TODO: Could also use the google photos layout: Groups of masonry layouts, each with a header (e.g. the date)
+use crate::cancel::is_cancelled_at;
+use crate::chunked::ChunkedVec;
+use crate::convert::{f32_to_u32, f64_to_f32, f64_to_u32, u32_to_f32, usize_to_u32};
+use crate::data::{
+    Breakpoint, ConfigError, LastRowBehavior, MasonryConfig, MasonryType, MemoryStats, RoundingMode,
+};
+use crate::util::UnwrapOrAbort;
+use alloc::{vec, vec::Vec};
+use core::cmp;
+use core::mem::size_of;
+use core::ops::Range;
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use crate::packed::{F32x4, U32x4};
+use history::{History, Operation};
+#[cfg(any(test, feature = "invariants"))]
+pub use invariants::{check as check_invariants, Counterexample, Failure};
+pub use paginate::Pages;
+
+mod fork;
+mod history;
+#[cfg(any(test, feature = "invariants"))]
+mod invariants;
+mod paginate;
+mod query;
+mod serialize;
+mod settings;
+mod stream;
+mod validate;
+
+/// Number of items per chunk that is handed to the [`ChunkRunner`]. Smaller tasks are not worth
+/// sharing.
+pub const CHUNK_SIZE: usize = 4096;
+
+/// Runs a task for every chunk index below the given count and returns once all are finished,
+/// e.g. spread over several threads. The chunks are independent of each other.
+pub type ChunkRunner = fn(usize, &(dyn Fn(usize) + Sync));
+
+/// Gap in CSS pixels that the gaps of a narrow container never collapse below, see
+/// [`Layout::set_gap_collapse_width()`].
+const MIN_COLLAPSED_GAP: u32 = 2;
+
+pub struct Layout {
+    num_items: usize,
+    transforms: ChunkedVec<Transform>,
+    items: ChunkedVec<Item>,
+    thumbnail_size: u32,
+    /// Horizontal and vertical space between the items
+    gap_x: u32,
+    gap_y: u32,
+    /// Space between the items and the edges of the container
+    outer_padding: u32,
+    /// Space below every thumbnail that is reserved for a caption
+    caption_height: u32,
+    /// Width of the metadata next to the thumbnail in a row of the list layout, or 0 if the rows
+    /// fill the content width.
+    metadata_width: u32,
+    /// Maximum width of the area the items are laid out in, or 0 if they fill the container.
+    max_content_width: u32,
+    /// Left offset of that area in the most recent computation.
+    content_left: u32,
+    /// Number of physical pixels per CSS pixel. All lengths are scaled by it, so that the
+    /// computed transforms are whole physical pixels.
+    device_pixel_ratio: f32,
+    /// Maximum height of a row in the horizontal layout, or 0 if rows are not capped.
+    max_row_height: u32,
+    /// Number of columns of the vertical masonry and grid layout, or 0 to derive it from the
+    /// thumbnail size.
+    column_count: u16,
+    /// Bounds for the number of columns derived from the thumbnail size, or 0 if unbounded.
+    min_columns: u16,
+    max_columns: u16,
+    /// Parameters for ranges of container widths, sorted by their minimum width.
+    breakpoints: Vec<Breakpoint>,
+    /// Number of columns of the breakpoint that applies to the most recent computation, or 0.
+    breakpoint_columns: u32,
+    /// Container width in CSS pixels below which the gaps collapse, or 0 if they never do.
+    gap_collapse_width: u32,
+    /// Settings and state that are on or off, see [`LayoutFlags`].
+    flags: LayoutFlags,
+    /// Width of the columns without the gap, or the row height of the horizontal layout, in the
+    /// most recent computation.
+    resolved_thumbnail_size: u32,
+    last_row: LastRowBehavior,
+    /// How the items of the horizontal layout are rounded when their rows are scaled.
+    rounding: RoundingMode,
+    /// Bounds for the aspect ratio the items are laid out with.
+    aspect_ratio_limits: AspectRatioLimits,
+    /// Aspect ratio above which items get a full-width row, or 0 if there are no panoramas.
+    panorama_aspect_ratio: f32,
+    /// Aspect ratio of the items whose dimension is not known yet.
+    default_aspect_ratio: AspectRatio,
+    /// How much lower than the shortest column the column of an item in the previous vertical
+    /// masonry layout may be for the item to stay in it, or 0 to always take the shortest one.
+    column_stability: u32,
+    /// Every how many items one is laid out as a large cell in the mosaic layout, or 0 for none.
+    mosaic_period: u32,
+    /// The median aspect ratio of the items, which the cells of the grid and mosaic layouts have
+    /// instead of a square if set.
+    cell_aspect_ratio: Option<AspectRatio>,
+    /// Fraction of the thumbnail size by which a row of the greedy horizontal layout may be
+    /// higher than it to end before an item that would shrink the row further, see
+    /// [`Layout::set_row_height_tolerance()`].
+    row_height_tolerance: f32,
+    /// Height of the tallest item in the most recent computation, used to bound queries.
+    max_item_height: u32,
+    /// Position from which the tops are not sorted anymore because the columns were balanced or
+    /// the layout was reversed.
+    unsorted_from: usize,
+    /// Positions of the items from `unsorted_from` on, sorted by their top, so that the queries
+    /// binary search them as well.
+    unsorted_index: Vec<u32>,
+    /// State at the end of the most recent computation, used to lay out appended items.
+    resume: Option<Resume>,
+    /// Index of the first item whose dimension changed since it was computed.
+    first_dirty: Option<usize>,
+    /// End of the items that changed since they were computed, or `usize::MAX` if the items
+    /// after them could have moved as well, e.g. because items were inserted. Only meaningful
+    /// while `first_dirty` is set.
+    dirty_end: usize,
+    /// Where a horizontal layout may stop laying out rows, see [`Layout::compute_arrivals()`].
+    converge: Option<stream::Convergence>,
+    /// Position of the first item that the most recent computation laid out, or `usize::MAX` if
+    /// it laid out none. The items before it kept their transforms.
+    laid_out_from: usize,
+    /// Transforms before the most recent computation if changes are tracked.
+    previous: Option<Vec<Transform>>,
+    /// Transforms of the most recently published computation if they are double buffered.
+    published: Option<Published>,
+    /// The transforms as floats in one buffer if they are viewed by the application, see
+    /// [`Layout::set_float_view()`].
+    float_view: Option<FloatView>,
+    /// Order in which the items are laid out, if it differs from the order of their dimensions.
+    order: Option<Order>,
+    /// Order of item ids set by the user, which is combined with pinned items into `order`.
+    requested_order: Option<Vec<u32>>,
+    /// Index of the item that is enlarged in place and the fixed height it had before, see
+    /// [`Layout::set_focus()`].
+    focus: Option<(usize, u16)>,
+    /// Shares the per-item work of huge layouts, e.g. with helper threads.
+    chunk_runner: Option<ChunkRunner>,
+    /// Slot of the cancellation flag and progress that the computations check, see
+    /// [`crate::cancel`].
+    cancel_slot: usize,
+    /// Operations that can be undone and redone, if they are recorded.
+    history: Option<History>,
+    /// Incremented whenever the items or the settings change, see [`Layout::fork()`].
+    version: u32,
+    /// Version of the layout that this one is a copy of, if it was created by [`Layout::fork()`].
+    forked_from: Option<u32>,
+    /// Version of the items and settings that the most recent computation laid out.
+    computed_version: Option<u32>,
+}
+
+/// The rectangle of an item followed by how its thumbnail is cropped.
+///
+/// The first four lanes are the width, height, top and left of the item, so the rectangle can be
+/// read directly from memory.
+#[repr(C)]
+#[derive(Clone, Default)]
+pub struct Transform(U32x4, Crop);
+
+/// How far the thumbnail is shifted up and left inside its rectangle when it had to be cropped,
+/// e.g. because the row height is capped, and whether it was cropped at all.
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq)]
+struct Crop {
+    top: u32,
+    left: u32,
+    cropped: u32,
+}
+
+/// Two copies of the transforms, of which one is read while the other one is updated, see
+/// [`Layout::set_double_buffered()`].
+struct Published {
+    buffers: [ChunkedVec<Transform>; 2],
+    /// Index of the buffer that holds the most recently published transforms.
+    front: AtomicUsize,
+}
+
+#[derive(Clone)]
+struct Resume {
+    kind: MasonryType,
+    container_width: u32,
+    num_items: usize,
+    state: ResumeState,
+}
+
+#[derive(Clone)]
+enum ResumeState {
+    /// The last row is not full yet, so it is laid out again together with the appended items.
+    Horizontal {
+        row_start: usize,
+        top: u32,
+    },
+    Vertical(vertical_masonry::ColumnHeights),
+    Grid,
+    List,
+    Mosaic,
+    FixedRows,
+    Filmstrip,
+    /// Items were removed, so the computed items cannot be reused.
+    Restart,
+}
+
+/// The next free cell of the grid and mosaic layouts, see `Layout::layout_cells()`.
+struct CellCursor {
+    /// Top of the current row.
+    top: u32,
+    /// The column at which the next item is placed if it is free.
+    column: usize,
+    /// The cells of the current row that are covered by large items of the row above.
+    covered: Vec<bool>,
+    /// The cells of the next row that are covered by large items of the current row.
+    next_covered: Vec<bool>,
+    /// Items since the most recent large one.
+    since_large: u32,
+}
+
+/// The cells of the grid when it is filled column by column, see
+/// `Layout::layout_grid_by_columns()`.
+struct ColumnCells {
+    n_columns: u32,
+    column_width: u32,
+    item_size: u32,
+    cell_height: u32,
+    /// Left of the first column.
+    left: u32,
+    gap_y: u32,
+}
+
+/// The last two rows of the horizontal layout after an item was moved into the last one, see
+/// `Layout::avoid_widow()`.
+struct Widow {
+    previous_start: usize,
+    previous_top: u32,
+    /// Start, width including the gaps and top of the last row.
+    start: usize,
+    row_width: u32,
+    top: u32,
+}
+
+/// The width, height, top and left of every item in CSS pixels, see [`Layout::set_float_view()`].
+struct FloatView {
+    values: Vec<f32>,
+    /// Incremented after every rewrite of the values.
+    generation: AtomicU32,
+}
+
+/// A permutation of the items, so that their dimensions can stay keyed by a stable id.
+struct Order {
+    /// The item id for every position in the layout.
+    ids: Vec<u32>,
+    /// The position in the layout for every item id.
+    positions: Vec<u32>,
+}
+
+/// The items in the order they are laid out.
+struct OrderedItems<'a> {
+    items: &'a ChunkedVec<Item>,
+    order: Option<&'a Order>,
+}
+
+/// Everything the layout needs to know about an item.
+#[derive(Clone)]
+struct Item {
+    /// The aspect ratio the item is laid out with, which is `source` within the limits.
+    aspect_ratio: AspectRatio,
+    source: AspectRatio,
+    flags: ItemFlags,
+    /// Kind of the section that starts after this section header, if it differs from the kind
+    /// of the computation.
+    section_kind: Option<MasonryType>,
+    /// Space around the item on top of the gaps in CSS pixels: top, right, bottom and left.
+    margin: [u8; 4],
+    /// Height of a full-width item in CSS pixels, e.g. of a section header, or 0 if it follows
+    /// from the aspect ratio.
+    height: u16,
+    /// Opaque number of the application, see [`Layout::set_tag()`].
+    tag: u32,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+struct ItemFlags(u16);
+
+#[derive(Clone, Copy, Default, PartialEq)]
+struct LayoutFlags(u16);
+
+#[derive(Clone, Default, PartialEq)]
+struct AspectRatio {
+    width: u8,
+    height: u8,
+}
+
+/// The smallest width of portrait and height of landscape items in the units of `AspectRatio`,
+/// where the longer side is 100.
+#[derive(Clone, Copy, PartialEq)]
+struct AspectRatioLimits {
+    min_width: u8,
+    min_height: u8,
+}
+
+const MIN_ITEMS_CAPACITY: usize = 1_000;
+const DEFAULT_MOSAIC_PERIOD: u32 = 5;
+
+impl Layout {
+    #[must_use]
+    pub fn new(num_items: usize, thumbnail_size: u32, padding: u32) -> Layout {
+        let capacity = num_items.max(MIN_ITEMS_CAPACITY);
+        Layout {
+            num_items,
+            transforms: ChunkedVec::from_elem(Transform::default(), capacity),
+            items: ChunkedVec::from_elem(Item::unmeasured(&AspectRatio::SQUARE), capacity),
+            thumbnail_size,
+            gap_x: padding,
+            gap_y: padding,
+            outer_padding: 0,
+            caption_height: 0,
+            metadata_width: 0,
+            max_content_width: 0,
+            content_left: 0,
+            device_pixel_ratio: 1.0,
+            max_row_height: 0,
+            column_count: 0,
+            min_columns: 0,
+            max_columns: 0,
+            breakpoints: Vec::new(),
+            breakpoint_columns: 0,
+            gap_collapse_width: 0,
+            flags: LayoutFlags::default(),
+            resolved_thumbnail_size: 0,
+            last_row: LastRowBehavior::Left,
+            rounding: RoundingMode::Floor,
+            aspect_ratio_limits: AspectRatioLimits::DEFAULT,
+            panorama_aspect_ratio: 0.0,
+            default_aspect_ratio: AspectRatio::SQUARE,
+            row_height_tolerance: 0.0,
+            column_stability: 0,
+            mosaic_period: DEFAULT_MOSAIC_PERIOD,
+            cell_aspect_ratio: None,
+            max_item_height: 0,
+            unsorted_from: usize::MAX,
+            unsorted_index: Vec::new(),
+            resume: None,
+            first_dirty: None,
+            dirty_end: usize::MAX,
+            converge: None,
+            laid_out_from: usize::MAX,
+            previous: None,
+            published: None,
+            float_view: None,
+            order: None,
+            requested_order: None,
+            focus: None,
+            chunk_runner: None,
+            cancel_slot: 0,
+            history: None,
+            version: 0,
+            forked_from: None,
+            computed_version: None,
+        }
+    }
+
+    /// Returns the transform of the item, which is the published one if the transforms are double
+    /// buffered.
+    #[must_use]
+    pub fn get_transform(&self, index: usize) -> Option<&Transform> {
+        match &self.published {
+            Some(published) => {
+                published.buffers[published.front.load(Ordering::Acquire)].get(index)
+            }
+            None => self.transforms.get(index),
+        }
+    }
+
+    /// Returns the transform of the item before the most recent computation if changes are
+    /// tracked and the item was computed before.
+    #[must_use]
+    pub fn get_previous_transform(&self, index: usize) -> Option<&Transform> {
+        self.previous.as_ref()?.get(index)
+    }
+
+    pub fn set_dimension(&mut self, index: usize, src_width: u16, src_height: u16) {
+        if let Some(item) = self.items.get_mut(index) {
+            let previous = item.clone();
+            if item.flags.contains(ItemFlags::ROTATED) {
+                item.source.set(src_height, src_width);
+            } else {
+                item.source.set(src_width, src_height);
+            }
+            if item.source != previous.source {
+                item.update_aspect_ratio(
+                    self.aspect_ratio_limits,
+                    self.panorama_aspect_ratio,
+                    &self.default_aspect_ratio,
+                );
+                self.mark_item_dirty(index);
+                self.record(|_| Operation::Replace {
+                    index,
+                    item: previous,
+                });
+            }
+        }
+    }
+
+    pub fn set_thumbnail_size(&mut self, thumbnail_size: u32) {
+        // The reason for this limitation is the way how the thumbnail size is calculated for the
+        // vertical and horizontal masonry layout.
+        const MAX_THUMBNAIL_SIZE: u32 = u32::MAX / 100;
+        self.thumbnail_size = thumbnail_size.min(MAX_THUMBNAIL_SIZE);
+    }
+
+    pub fn set_gaps(&mut self, gap_x: u32, gap_y: u32) {
+        self.gap_x = gap_x;
+        self.gap_y = gap_y;
+    }
+
+    pub fn set_outer_padding(&mut self, outer_padding: u32) {
+        self.outer_padding = outer_padding;
+    }
+
+    pub fn set_max_content_width(&mut self, max_content_width: u32) {
+        self.max_content_width = max_content_width;
+    }
+
+    pub fn set_caption_height(&mut self, caption_height: u32) {
+        self.caption_height = caption_height;
+    }
+
+    pub fn set_metadata_width(&mut self, metadata_width: u32) {
+        self.metadata_width = metadata_width;
+    }
+
+    /// Returns how far the items are inset from the left edge of the container in the most
+    /// recent computation.
+    #[must_use]
+    pub fn content_left(&self) -> u32 {
+        self.content_left
+    }
+
+    /// Returns the number of physical pixels per CSS pixel of the most recent computation, which
+    /// the transforms have to be divided by for CSS.
+    #[must_use]
+    pub fn device_pixel_ratio(&self) -> f32 {
+        self.device_pixel_ratio
+    }
+
+    /// Caps the height of the rows in the horizontal layout. Taller items are cropped at the
+    /// center. A height of 0 disables the cap.
+    pub fn set_max_row_height(&mut self, max_row_height: u32) {
+        if max_row_height != self.max_row_height {
+            self.max_row_height = max_row_height;
+            // Rows that were already computed may be too high now.
+            self.settings_changed();
+        }
+    }
+
+    /// Limits the aspect ratio (width / height) the items are laid out with. Items beyond the
+    /// limits are cropped at the center. A limit of 0 means that there is no limit.
+    pub fn set_aspect_ratio_limits(&mut self, min_aspect_ratio: f32, max_aspect_ratio: f32) {
+        let limits = AspectRatioLimits::new(min_aspect_ratio, max_aspect_ratio);
+        if limits != self.aspect_ratio_limits {
+            self.aspect_ratio_limits = limits;
+            self.update_aspect_ratios();
+        }
+    }
+
+    /// Lays out items that are wider than the given aspect ratio (width / height) in a row of
+    /// their own that spans the full width, like [`Layout::set_full_width()`]. They are not
+    /// clamped to the aspect ratio limits. An aspect ratio of 0 disables it.
+    pub fn set_panorama_aspect_ratio(&mut self, panorama_aspect_ratio: f32) {
+        if panorama_aspect_ratio.to_bits() != self.panorama_aspect_ratio.to_bits() {
+            self.panorama_aspect_ratio = panorama_aspect_ratio;
+            self.update_aspect_ratios();
+        }
+    }
+
+    /// Sets the aspect ratio (width / height) of the items whose dimension is 0 or was not set
+    /// yet, so they can be laid out as placeholders. It is 1 by default.
+    pub fn set_default_aspect_ratio(&mut self, default_aspect_ratio: f32) {
+        let aspect_ratio = AspectRatio::from_ratio(default_aspect_ratio);
+        if aspect_ratio != self.default_aspect_ratio {
+            self.default_aspect_ratio = aspect_ratio;
+            self.update_aspect_ratios();
+        }
+    }
+
+    fn update_aspect_ratios(&mut self) {
+        for item in self.items.iter_mut() {
+            item.update_aspect_ratio(
+                self.aspect_ratio_limits,
+                self.panorama_aspect_ratio,
+                &self.default_aspect_ratio,
+            );
+        }
+        self.settings_changed();
+    }
+
+    /// Sets the thumbnail size, gap and number of columns for ranges of container widths. The
+    /// breakpoint with the largest minimum width that is not wider than the container replaces
+    /// the config of a computation.
+    pub fn set_breakpoints(&mut self, mut breakpoints: Vec<Breakpoint>) {
+        breakpoints.sort_by_key(|breakpoint| breakpoint.min_width);
+        self.breakpoints = breakpoints;
+        self.settings_changed();
+    }
+
+    /// Returns the breakpoints sorted by their minimum width, see [`Layout::set_breakpoints()`].
+    #[must_use]
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Collapses the gaps of containers that are narrower than the given width in CSS pixels, or
+    /// never with 0. The gaps are halved below the width and again every time the container is
+    /// half as wide, down to 2 CSS pixels, and at least two columns are derived from the
+    /// thumbnail size, so that narrow side panels do not fall back to a single column early.
+    pub fn set_gap_collapse_width(&mut self, width: u32) {
+        if width != self.gap_collapse_width {
+            self.gap_collapse_width = width;
+            self.settings_changed();
+        }
+    }
+
+    /// Sets the number of columns of the vertical masonry and grid layout. With 0 it is derived
+    /// from the thumbnail size.
+    pub fn set_column_count(&mut self, column_count: u16) {
+        if column_count != self.column_count {
+            self.column_count = column_count;
+            self.settings_changed();
+        }
+    }
+
+    /// Limits the number of columns that is derived from the thumbnail size. A limit of 0 means
+    /// that there is no limit.
+    pub fn set_column_limits(&mut self, min_columns: u16, max_columns: u16) {
+        if (min_columns, max_columns) != (self.min_columns, self.max_columns) {
+            self.min_columns = min_columns;
+            self.max_columns = max_columns;
+            self.settings_changed();
+        }
+    }
+
+    /// Sizes the columns of the vertical masonry and grid layout so that the items span exactly
+    /// the full width, instead of leaving a gap after the last column.
+    pub fn set_auto_fit(&mut self, auto_fit: bool) {
+        if auto_fit != self.flags.contains(LayoutFlags::AUTO_FIT) {
+            self.flags.set(LayoutFlags::AUTO_FIT, auto_fit);
+            self.settings_changed();
+        }
+    }
+
+    /// Returns the size the thumbnails were laid out with in the most recent computation: the
+    /// width of the columns or the height of the rows without the gaps.
+    #[must_use]
+    pub fn resolved_thumbnail_size(&self) -> u32 {
+        self.resolved_thumbnail_size
+    }
+
+    /// Sets how the last row of the horizontal layout is laid out if it is not full.
+    pub fn set_last_row_behavior(&mut self, last_row: LastRowBehavior) {
+        // The last row is always laid out again, even by an incremental computation.
+        if last_row != self.last_row {
+            self.last_row = last_row;
+            self.version = self.version.wrapping_add(1);
+        }
+    }
+
+    /// Sets how the items of the horizontal layout are rounded to whole pixels when their rows are
+    /// scaled to fill the container width.
+    pub fn set_rounding_mode(&mut self, rounding: RoundingMode) {
+        if rounding != self.rounding {
+            self.rounding = rounding;
+            self.settings_changed();
+        }
+    }
+
+    /// Balances the heights of the rows in the horizontal layout across all items.
+    pub fn set_balance_rows(&mut self, enabled: bool) {
+        if enabled != self.flags.contains(LayoutFlags::BALANCE_ROWS) {
+            self.flags.set(LayoutFlags::BALANCE_ROWS, enabled);
+            self.settings_changed();
+        }
+    }
+
+    /// Lets the greedy horizontal layout end a row before the item that overflows it if the row
+    /// is at most the given fraction higher than the thumbnail size then, and closer to it than
+    /// when the row is shrunk to make room for the item. With a tolerance of 0, rows are only
+    /// ever shrunk. The balanced rows keep as close to the thumbnail size as they can anyway.
+    pub fn set_row_height_tolerance(&mut self, tolerance: f32) {
+        let tolerance = tolerance.max(0.0);
+        if tolerance.to_bits() != self.row_height_tolerance.to_bits() {
+            self.row_height_tolerance = tolerance;
+            self.settings_changed();
+        }
+    }
+
+    /// Moves the last item of the second to last row of the horizontal layout into the last row if
+    /// that would hold a single item otherwise, so that it does not look lost.
+    ///
+    /// Only rows of at least three items give one away, and not across row breaks and full-width
+    /// items, or if both items would not fit into the last row next to each other. Has no effect
+    /// if the last row is hidden.
+    pub fn set_avoid_widows(&mut self, enabled: bool) {
+        if enabled != self.flags.contains(LayoutFlags::AVOID_WIDOWS) {
+            self.flags.set(LayoutFlags::AVOID_WIDOWS, enabled);
+            self.settings_changed();
+        }
+    }
+
+    /// Lays out the items bottom-up, so the last items are at the bottom and the row that is not
+    /// full is at the top. The transforms are still measured from the top. Has no effect on a
+    /// filmstrip, whose only row scrolls sideways.
+    pub fn set_reversed(&mut self, reversed: bool) {
+        if reversed != self.flags.contains(LayoutFlags::REVERSED) {
+            self.flags.set(LayoutFlags::REVERSED, reversed);
+            self.settings_changed();
+        }
+    }
+
+    /// Returns whether the items are laid out bottom-up, see [`Layout::set_reversed()`].
+    #[must_use]
+    pub fn is_reversed(&self) -> bool {
+        self.flags.contains(LayoutFlags::REVERSED)
+    }
+
+    /// Moves the last items of the vertical masonry layout to even out the column heights.
+    pub fn set_balance_columns(&mut self, enabled: bool) {
+        if enabled != self.flags.contains(LayoutFlags::BALANCE_COLUMNS) {
+            self.flags.set(LayoutFlags::BALANCE_COLUMNS, enabled);
+            self.settings_changed();
+        }
+    }
+
+    /// Keeps the items of the vertical masonry layout in the column they had in the previous
+    /// computation unless that column is more than `max_penalty` pixels lower than the shortest
+    /// one, so that small changes of the width move fewer items between the columns. The columns
+    /// end less evenly the larger the penalty is. 0 disables it, which is the default.
+    pub fn set_column_stability(&mut self, max_penalty: u32) {
+        if max_penalty != self.column_stability {
+            self.column_stability = max_penalty;
+            self.settings_changed();
+        }
+    }
+
+    /// Lays out every `period`th item of the mosaic layout as a large cell that spans two columns
+    /// and two rows. If it does not fit into the current row, the next item that fits gets it
+    /// instead. 0 lays out all items as small cells. The default is 5.
+    pub fn set_mosaic_period(&mut self, period: u32) {
+        if period != self.mosaic_period {
+            self.mosaic_period = period;
+            self.settings_changed();
+        }
+    }
+
+    /// Fills the grid column by column instead of row by row, so that the order of the items runs
+    /// from top to bottom and then to the next column.
+    ///
+    /// The items between two items that span the full width or break before fill as few rows as
+    /// they fit into. Spans are ignored in this order, and appended items lay out the items since
+    /// the last such item again, because they change the number of rows.
+    pub fn set_column_major(&mut self, enabled: bool) {
+        if enabled != self.flags.contains(LayoutFlags::COLUMN_MAJOR) {
+            self.flags.set(LayoutFlags::COLUMN_MAJOR, enabled);
+            self.settings_changed();
+        }
+    }
+
+    /// Sizes the cells of the grid and mosaic layouts to the median aspect ratio of the measured
+    /// items instead of squares, so that mostly portrait or landscape items are cropped less.
+    ///
+    /// The median is updated before every computation and lays out all items again when it
+    /// changes. Hidden and full-width items do not count.
+    pub fn set_median_cells(&mut self, enabled: bool) {
+        if enabled != self.cell_aspect_ratio.is_some() {
+            self.cell_aspect_ratio = if enabled {
+                Some(AspectRatio::SQUARE)
+            } else {
+                None
+            };
+            self.settings_changed();
+        }
+    }
+
+    /// Keeps a copy of the transforms before every computation to find the items that changed.
+    pub fn set_track_changes(&mut self, enabled: bool) {
+        self.previous = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// Keeps two more copies of the transforms, so that [`Layout::get_transform()`] returns the
+    /// transforms of a completed computation while another thread computes the next one.
+    ///
+    /// The copies are only updated by [`Layout::publish()`]. The queries always read the transforms
+    /// of the computation in progress.
+    pub fn set_double_buffered(&mut self, enabled: bool) {
+        self.published = if enabled {
+            Some(Published {
+                buffers: [
+                    ChunkedVec::from_elem(Transform::default(), 0),
+                    ChunkedVec::from_elem(Transform::default(), 0),
+                ],
+                front: AtomicUsize::new(0),
+            })
+        } else {
+            None
+        };
+        self.publish();
+    }
+
+    /// Copies the transforms into the buffer that is not read and then flips the buffers, if they
+    /// are double buffered, and rewrites the float view, if there is one. Must only be called once
+    /// a computation completed.
+    ///
+    /// Returns whether views of [`Layout::float_view()`] have to be created again, because it
+    /// moved or its length changed.
+    pub fn publish(&mut self) -> bool {
+        if let Some(published) = self.published.as_mut() {
+            let back = 1 - published.front.load(Ordering::Acquire);
+            let buffer = &mut published.buffers[back];
+            buffer.resize(self.num_items, Transform::default());
+            for (copy, transform) in buffer.iter_mut().zip(self.transforms.iter()) {
+                copy.clone_from(transform);
+            }
+            published.front.store(back, Ordering::Release);
+        }
+        self.write_float_view()
+    }
+
+    /// Keeps the width, height, top and left of every item as floats in CSS pixels in one
+    /// contiguous buffer, e.g. so that a renderer can read them through a typed array without any
+    /// calls.
+    ///
+    /// The buffer is rewritten by [`Layout::publish()`], which copies all transforms, so it is
+    /// disabled by default. Unlike the transforms, which are stored in chunks, the buffer moves
+    /// when it grows.
+    pub fn set_float_view(&mut self, enabled: bool) {
+        if enabled == self.float_view.is_some() {
+            return;
+        }
+        self.float_view = enabled.then(|| FloatView {
+            values: Vec::new(),
+            generation: AtomicU32::new(0),
+        });
+        self.write_float_view();
+    }
+
+    /// Returns the buffer of [`Layout::set_float_view()`], which holds four floats per item, or
+    /// an empty slice if it is disabled.
+    #[must_use]
+    pub fn float_view(&self) -> &[f32] {
+        self.float_view
+            .as_ref()
+            .map_or(&[], |view| view.values.as_slice())
+    }
+
+    /// Returns how often the buffer of [`Layout::set_float_view()`] was rewritten, so that a
+    /// reader can tell whether it changed since it last looked.
+    #[must_use]
+    pub fn float_view_generation(&self) -> u32 {
+        self.float_view
+            .as_ref()
+            .map_or(0, |view| view.generation.load(Ordering::Acquire))
+    }
+
+    /// Copies the transforms into the float view, if there is one, and returns whether it moved
+    /// or its length changed.
+    fn write_float_view(&mut self) -> bool {
+        let view = match self.float_view.as_mut() {
+            Some(view) => view,
+            None => return false,
+        };
+        let (pointer, len) = (view.values.as_ptr(), view.values.len());
+        let ratio = self.device_pixel_ratio;
+        view.values.clear();
+        for transform in self.transforms.iter().take(self.num_items) {
+            let lengths = transform
+                .to_array()
+                .map(|length| u32_to_f32(length) / ratio);
+            view.values.extend_from_slice(&lengths);
+        }
+        view.generation.fetch_add(1, Ordering::Release);
+        view.values.as_ptr() != pointer || view.values.len() != len
+    }
+
+    #[must_use]
+    pub fn num_items(&self) -> usize {
+        self.num_items
+    }
+
+    pub fn resize(&mut self, new_len: usize) {
+        self.set_focus(None, 0);
+        let old_len = self.num_items;
+        // Resizing to the same length is nothing to undo.
+        if new_len != old_len {
+            self.record(|layout| Operation::Resize {
+                len: old_len,
+                items: layout
+                    .items
+                    .slice(new_len.min(old_len)..old_len)
+                    .iter()
+                    .cloned()
+                    .collect(),
+            });
+        }
+        self.resize_items(new_len);
+    }
+
+    /// Adds `count` items at the end, e.g. the next page of a remote library, lays them out below
+    /// the existing items and returns the new container height.
+    ///
+    /// The new items are unmeasured, even if the memory of earlier items is reused, so they get
+    /// the default aspect ratio as an estimate until their dimensions are set. The items before
+    /// keep their transforms. Once the dimensions arrive, [`Layout::compute_incremental()`] only
+    /// lays out the items from the first changed one on. Returns 0 if nothing was computed yet.
+    pub fn append(&mut self, count: usize) -> u32 {
+        let start = self.num_items;
+        self.resize(start + count);
+        let item = Item::unmeasured(&self.default_aspect_ratio);
+        for reused in self.items.range_mut(start..start + count) {
+            *reused = item.clone();
+        }
+        self.compute_appended()
+    }
+
+    /// Changes the item count like [`Layout::resize()`] without recording it.
+    fn resize_items(&mut self, new_len: usize) {
+        self.num_items = new_len;
+        self.version = self.version.wrapping_add(1);
+        if let Some(resume) = self.resume.as_mut() {
+            if new_len < resume.num_items {
+                resume.state = ResumeState::Restart;
+            }
+        }
+        let len = self.transforms.len().min(self.items.len());
+        if new_len > len {
+            self.transforms.resize(new_len, Transform::default());
+            let item = Item::unmeasured(&self.default_aspect_ratio);
+            self.items.resize(new_len, item);
+        }
+    }
+
+    /// Frees the memory of the items beyond the item count, which [`Layout::resize()`] keeps to
+    /// avoid allocating again when the items grow back.
+    ///
+    /// The items are stored in chunks of 4096, so only the chunks beyond the item count are freed.
+    /// The dimensions of the freed items are forgotten, so they are unmeasured when the items grow
+    /// again.
+    pub fn shrink_to_fit(&mut self) {
+        let capacity = self.num_items.max(MIN_ITEMS_CAPACITY);
+        self.transforms.truncate(capacity);
+        self.transforms.shrink_to_fit();
+        self.items.truncate(capacity);
+        self.items.shrink_to_fit();
+        if let Some(previous) = self.previous.as_mut() {
+            previous.shrink_to_fit();
+        }
+        if let Some(published) = self.published.as_mut() {
+            for buffer in &mut published.buffers {
+                buffer.shrink_to_fit();
+            }
+        }
+    }
+
+    /// Returns the lengths and capacities of the per-item buffers and the memory they take up.
+    #[must_use]
+    pub fn memory_stats(&self) -> MemoryStats {
+        let previous = self.previous.as_ref().map_or(0, Vec::capacity);
+        let published = self.published.as_ref().map_or(0, |published| {
+            published.buffers[0].capacity() + published.buffers[1].capacity()
+        });
+        let order = self
+            .order
+            .as_ref()
+            .map_or(0, |order| order.ids.capacity() + order.positions.capacity());
+        let requested_order = self.requested_order.as_ref().map_or(0, Vec::capacity);
+        let bytes = self.items.capacity() * size_of::<Item>()
+            + (self.transforms.capacity() + previous + published) * size_of::<Transform>()
+            + (order + requested_order) * size_of::<u32>();
+
+        #[cfg(target_arch = "wasm32")]
+        let heap_pages = usize_to_u32(core::arch::wasm32::memory_size::<0>());
+        #[cfg(not(target_arch = "wasm32"))]
+        let heap_pages = 0;
+
+        MemoryStats {
+            num_items: usize_to_u32(self.num_items),
+            item_capacity: usize_to_u32(self.items.capacity()),
+            transform_capacity: usize_to_u32(self.transforms.capacity()),
+            bytes: usize_to_u32(bytes),
+            heap_pages,
+        }
+    }
+
+    /// Hides every item whose bit is not set in the bitmask, so that it is skipped by the layout.
+    ///
+    /// Bit `i % 8` of byte `i / 8` belongs to the item with index `i`. Items beyond the end of the
+    /// bitmask are visible.
+    pub fn set_visibility(&mut self, bitmask: &[u8]) {
+        for index in 0..self.items.len() {
+            let visible = bitmask
+                .get(index / 8)
+                .map_or(true, |byte| byte & (1 << (index % 8)) != 0);
+            self.set_flag(index, ItemFlags::HIDDEN, !visible);
+        }
+    }
+
+    /// Inserts `count` items at the given index and shifts the following items back.
+    pub fn insert(&mut self, index: usize, count: usize) {
+        self.set_focus(None, 0);
+        let index = index.min(self.num_items);
+        self.record(|_| Operation::Remove { index, count });
+        self.insert_items(index, count);
+    }
+
+    /// Inserts unmeasured items at a valid index without recording it.
+    fn insert_items(&mut self, index: usize, count: usize) {
+        let old_len = self.num_items;
+        self.resize_items(old_len + count);
+        self.items.rotate_right(index..old_len + count, count);
+        self.transforms.rotate_right(index..old_len + count, count);
+        for item in self.items.range_mut(index..index + count) {
+            *item = Item::unmeasured(&self.default_aspect_ratio);
+        }
+        self.mark_dirty(index);
+    }
+
+    /// Removes `count` items at the given index and shifts the following items forward.
+    pub fn remove(&mut self, index: usize, count: usize) {
+        self.set_focus(None, 0);
+        let index = index.min(self.num_items);
+        let count = count.min(self.num_items - index);
+        self.record(|layout| Operation::Insert {
+            index,
+            items: layout
+                .items
+                .slice(index..index + count)
+                .iter()
+                .cloned()
+                .collect(),
+        });
+        self.remove_items(index, count);
+    }
+
+    /// Removes items in a valid range without recording it.
+    fn remove_items(&mut self, index: usize, count: usize) {
+        self.items.rotate_left(index..self.num_items, count);
+        self.transforms.rotate_left(index..self.num_items, count);
+        // Not resized, so the computed items are not thrown away (see Layout::resize()).
+        self.num_items -= count;
+        self.mark_dirty(index);
+    }
+
+    /// Moves `count` items starting at `from` so that they start at `to` afterwards.
+    pub fn move_items(&mut self, from: usize, count: usize, to: usize) {
+        self.set_focus(None, 0);
+        if from >= self.num_items {
+            return;
+        }
+        let count = count.min(self.num_items - from);
+        let to = to.min(self.num_items - count);
+        if to != from {
+            self.record(|_| Operation::Move {
+                from: to,
+                count,
+                to: from,
+            });
+        }
+        self.move_range(from, count, to);
+    }
+
+    /// Moves items in a valid range without recording it.
+    fn move_range(&mut self, from: usize, count: usize, to: usize) {
+        match to.cmp(&from) {
+            cmp::Ordering::Less => self.items.rotate_right(to..from + count, count),
+            cmp::Ordering::Greater => self.items.rotate_left(from..to + count, count),
+            cmp::Ordering::Equal => return,
+        }
+        self.mark_dirty(from.min(to));
+    }
+
+    /// Lays out the items in the given order of item ids instead of the order of their dimensions.
+    ///
+    /// Transforms and query results are indexed by the position in the layout. Positions without
+    /// a valid id keep the item with the same index.
+    pub fn set_order(&mut self, ids: Vec<u32>) {
+        let previous = self.requested_order.replace(ids);
+        self.record(|_| Operation::Order(previous));
+    }
+
+    /// Returns the order of item ids set with [`Layout::set_order()`], if any.
+    #[must_use]
+    pub fn requested_order(&self) -> Option<&[u32]> {
+        self.requested_order.as_deref()
+    }
+
+    /// Lays out the items in the order of their dimensions again.
+    pub fn clear_order(&mut self) {
+        let previous = self.requested_order.take();
+        self.record(|_| Operation::Order(previous));
+    }
+
+    /// Shares the per-item work of layouts with more than [`CHUNK_SIZE`] items with the given
+    /// runner, or does all work on the calling thread if it is `None`, which is the default.
+    ///
+    /// The result does not depend on how the runner spreads the chunks.
+    pub fn set_chunk_runner(&mut self, chunk_runner: Option<ChunkRunner>) {
+        self.chunk_runner = chunk_runner;
+    }
+
+    /// Selects the slot of the cancellation flag and progress that the computations check, so
+    /// that layouts that are computed on different threads at the same time can be cancelled
+    /// separately (see [`crate::cancel`]). Every layout starts with slot 0.
+    pub fn set_cancel_slot(&mut self, slot: usize) {
+        self.cancel_slot = slot;
+    }
+
+    /// Forgets the state of the most recent computation, e.g. because it was interrupted, so that
+    /// appended and incremental computations lay out all items again.
+    pub fn invalidate(&mut self) {
+        self.resume = None;
+    }
+
+    /// Returns the transforms of the laid out items as flat lanes, see [`Transform::to_lanes()`].
+    pub fn export_transforms(&self) -> Vec<u32> {
+        self.transforms
+            .iter()
+            .take(self.num_items)
+            .flat_map(Transform::to_lanes)
+            .collect()
+    }
+
+    /// Replaces the transforms with the ones computed by another instance of the layout, e.g. in
+    /// a web worker that does not share the memory.
+    ///
+    /// The state of the most recent computation is forgotten because it belongs to the other
+    /// instance.
+    pub fn import_transforms(&mut self, lanes: &[u32]) {
+        self.save_previous();
+        for (transform, lanes) in self
+            .transforms
+            .iter_mut()
+            .zip(lanes.chunks_exact(Transform::LANES))
+        {
+            *transform = Transform::from_lanes(lanes);
+        }
+        self.laid_out_from = 0;
+        self.invalidate();
+    }
+
+    /// Pins the item at the given index, so that it is laid out before all items that are not
+    /// pinned. The first pinned item is always placed at the top left.
+    pub fn set_pinned(&mut self, index: usize, pinned: bool) {
+        if let Some(item) = self.items.get_mut(index) {
+            item.flags.set(ItemFlags::PINNED, pinned);
+        }
+    }
+
+    /// Lays out the item at the given index in a row of its own that spans the full width.
+    pub fn set_full_width(&mut self, index: usize, full_width: bool) {
+        self.set_flag(index, ItemFlags::FULL_WIDTH, full_width);
+    }
+
+    /// Sets the EXIF orientation (1 to 8) of the item at the given index. The orientations 5 to 8
+    /// are rotated by 90 degrees, so the width and height of the dimension are swapped.
+    pub fn set_orientation(&mut self, index: usize, orientation: u8) {
+        let rotated = (5..=8).contains(&orientation);
+        if let Some(item) = self.items.get_mut(index) {
+            if item.flags.contains(ItemFlags::ROTATED) != rotated {
+                item.flags.set(ItemFlags::ROTATED, rotated);
+                let AspectRatio { width, height } = item.source;
+                item.source = AspectRatio {
+                    width: height,
+                    height: width,
+                };
+                item.update_aspect_ratio(
+                    self.aspect_ratio_limits,
+                    self.panorama_aspect_ratio,
+                    &self.default_aspect_ratio,
+                );
+                self.mark_item_dirty(index);
+            }
+        }
+    }
+
+    /// Lays out the section after the section header at the given index with its own kind, or
+    /// with the kind of the computation if it is `None`. The section ends at the next header.
+    pub fn set_section_kind(&mut self, index: usize, kind: Option<MasonryType>) {
+        if let Some(item) = self.items.get_mut(index) {
+            if item.section_kind != kind {
+                item.section_kind = kind;
+                self.mark_item_dirty(index);
+            }
+        }
+    }
+
+    /// Lets the item at the given index span two columns, two rows or both in the grid and mosaic
+    /// layouts. Spans larger than 2 are treated as 2.
+    ///
+    /// The vertical masonry layout only uses the columns, since the height follows from the width.
+    /// The horizontal, fixed rows, list and filmstrip layouts ignore the span.
+    pub fn set_span(&mut self, index: usize, columns: u32, rows: u32) {
+        self.set_flag(index, ItemFlags::WIDE, columns > 1);
+        self.set_flag(index, ItemFlags::TALL, rows > 1);
+    }
+
+    /// Adds space around the item at the given index on top of the gaps, e.g. around a hero image
+    /// or a section header. The margins are in CSS pixels and at most 255 per side.
+    ///
+    /// The vertical masonry and list layouts make room for the margins: the item is narrower by
+    /// its left and right margin and the items above and below it move away by the others. The
+    /// horizontal, fixed rows, grid, mosaic and filmstrip layouts ignore the margins.
+    pub fn set_item_margin(&mut self, index: usize, top: u32, right: u32, bottom: u32, left: u32) {
+        let margin = [top, right, bottom, left].map(|side| u8::try_from(side).unwrap_or(u8::MAX));
+        if let Some(item) = self.items.get_mut(index) {
+            if item.margin != margin {
+                item.margin = margin;
+                self.mark_item_dirty(index);
+            }
+        }
+    }
+
+    /// Starts a new row at the item with the given index.
+    pub fn set_break_before(&mut self, index: usize, break_before: bool) {
+        self.set_flag(index, ItemFlags::BREAK_BEFORE, break_before);
+    }
+
+    /// Gives the full-width item at the given index a fixed height in CSS pixels instead of the
+    /// one that follows from its aspect ratio, e.g. for a section header, or removes it with 0.
+    /// Items that are not full width ignore it.
+    pub fn set_fixed_height(&mut self, index: usize, height: u16) {
+        if let Some(item) = self.items.get_mut(index) {
+            if item.height != height {
+                item.height = height;
+                self.mark_item_dirty(index);
+            }
+        }
+    }
+
+    /// Attaches an opaque number to the item at the given index, e.g. the id the application
+    /// knows it by. The tag moves with the item when items are inserted, removed or moved, and is
+    /// not used by the layout. Every item starts with 0.
+    pub fn set_tag(&mut self, index: usize, tag: u32) {
+        if let Some(item) = self.items.get_mut(index) {
+            item.tag = tag;
+        }
+    }
+
+    /// Returns the tag of the item that is laid out at the given position, e.g. one returned by
+    /// [`Layout::item_at()`], or `None` if there is no item at that position.
+    #[must_use]
+    pub fn tag(&self, position: usize) -> Option<u32> {
+        (position < self.num_items).then(|| self.ordered_items().get(position).tag)
+    }
+
+    /// Enlarges the item at the given index in place, e.g. for an inline preview, or returns the
+    /// focused item to its size with `None`.
+    ///
+    /// The item gets a row of its own that spans the full width, like with
+    /// [`Layout::set_full_width()`], with the given height in CSS pixels or the height that follows
+    /// from its aspect ratio for 0. The following items reflow below it. Unlike section headers,
+    /// it does not stick to the top. Inserting, removing, moving or resizing the items ends the
+    /// focus.
+    pub fn set_focus(&mut self, index: Option<usize>, height: u16) {
+        if let Some((focused, own_height)) = self.focus.take() {
+            self.set_flag(focused, ItemFlags::FOCUSED, false);
+            self.set_fixed_height(focused, own_height);
+        }
+        let index = index.filter(|&index| index < self.num_items);
+        if let Some((index, own_height)) =
+            index.and_then(|index| Some((index, self.items.get(index)?.height)))
+        {
+            self.focus = Some((index, own_height));
+            self.set_flag(index, ItemFlags::FOCUSED, true);
+            self.set_fixed_height(index, height);
+        }
+    }
+
+    /// Returns the index of the item enlarged with [`Layout::set_focus()`], if any.
+    #[must_use]
+    pub fn focus(&self) -> Option<usize> {
+        self.focus.map(|(index, _)| index)
+    }
+
+    /// Returns the index of the item that is laid out at the given position.
+    #[must_use]
+    pub fn item_index(&self, position: usize) -> usize {
+        self.ordered_items().index(position)
+    }
+
+    /// Applies the config and computes the layout of the given kind. Returns the container height.
+    pub fn compute(&mut self, container_width: u32, config: &MasonryConfig) -> u32 {
+        self.prepare();
+        let container_width = self.apply_config(config, container_width);
+        self.compute_kind(config.kind, container_width)
+    }
+
+    /// Applies the config and computes the layout of the items before the given position only, e.g.
+    /// the ones in the viewport. Returns the height of the container so far.
+    ///
+    /// The rest is laid out by [`Layout::compute_remaining()`]. The last row before the position
+    /// is not full yet, so it can change then. A reversed layout is laid out completely like
+    /// [`Layout::compute()`], because its first rows depend on the last items.
+    pub fn compute_until(
+        &mut self,
+        end: usize,
+        container_width: u32,
+        config: &MasonryConfig,
+    ) -> u32 {
+        self.prepare();
+        let container_width = self.apply_config(config, container_width);
+        if self.flags.contains(LayoutFlags::REVERSED) {
+            return self.compute_kind(config.kind, container_width);
+        }
+        let num_items = self.num_items;
+        // An empty layout has nothing to resume from.
+        self.num_items = end.max(1).min(num_items);
+        let height = self.compute_kind(config.kind, container_width);
+        self.num_items = num_items;
+        height
+    }
+
+    /// Applies the config and lays out the items from the start position up to the end position
+    /// again, assuming that the items before the start did not change since the most recent
+    /// computation, e.g. because only the last section changed. Returns the height of the
+    /// container so far.
+    ///
+    /// Like after [`Layout::compute_until()`], the rest is laid out by
+    /// [`Layout::compute_remaining()`]. The layout starts earlier at the first item whose
+    /// dimension changed, and at the first item if the width or config changed, nothing was
+    /// computed yet or the layout has sections of other kinds. A reversed layout is laid out
+    /// completely.
+    pub fn compute_range(
+        &mut self,
+        start: usize,
+        end: usize,
+        container_width: u32,
+        config: &MasonryConfig,
+    ) -> u32 {
+        self.prepare();
+        let container_width = self.apply_config(config, container_width);
+        if self.flags.contains(LayoutFlags::REVERSED) {
+            return self.compute_kind(config.kind, container_width);
+        }
+        let num_items = self.num_items;
+        let end = end.max(1).min(num_items);
+        let start = match &self.resume {
+            Some(resume)
+                if resume.kind == config.kind
+                    && resume.container_width == container_width
+                    && !matches!(resume.state, ResumeState::Restart)
+                    && !self.flags.contains(LayoutFlags::HAS_SECTIONS) =>
+            {
+                // The transforms of the first item to lay out have to be computed already.
+                start
+                    .min(resume.num_items.saturating_sub(1))
+                    .min(self.first_dirty.unwrap_or(usize::MAX))
+                    .min(end.saturating_sub(1))
+            }
+            _ => 0,
+        };
+        self.num_items = end;
+        let height = if start == 0 {
+            self.compute_kind(config.kind, container_width)
+        } else {
+            self.resume = None;
+            self.lay_out_from(config.kind, container_width, start)
+        };
+        self.num_items = num_items;
+        height
+    }
+
+    /// Lays out the items after a computation that stopped early with [`Layout::compute_until()`]
+    /// and returns the container height.
+    pub fn compute_remaining(&mut self) -> u32 {
+        self.laid_out_from = usize::MAX;
+        self.lay_out_appended()
+    }
+
+    /// Returns the first parameter of a computation with the given container width and config
+    /// that cannot be laid out, e.g. because the padding leaves no space for the items.
+    ///
+    /// The breakpoint that applies to the width replaces the config like in the computation. The
+    /// computations do not check the config themselves, so this should be called before them. A
+    /// container without width or a layout without items is always valid, because the
+    /// computations lay out nothing and return a height of 0 then.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`ConfigError`] of the first invalid parameter.
+    pub fn check_config(
+        &self,
+        container_width: u32,
+        config: &MasonryConfig,
+    ) -> Result<(), ConfigError> {
+        let (thumbnail_size, gap_x, breakpoint_columns) = match self.breakpoint(container_width) {
+            Some(breakpoint) => (
+                breakpoint.thumbnail_size,
+                breakpoint.padding,
+                breakpoint.columns,
+            ),
+            None => (config.thumbnail_size, config.gap_x, 0),
+        };
+        let thumbnail_size = config.row_thumbnail_size(thumbnail_size);
+        let gaps = config.scale_gaps(thumbnail_size, (gap_x, gap_x));
+        let (gap_x, _) = self.collapse_gaps(container_width, gaps);
+        // Nothing is laid out, so there is nothing that could go wrong.
+        if container_width == 0 || self.is_empty() {
+            return Ok(());
+        }
+        if thumbnail_size == 0 {
+            return Err(ConfigError::ThumbnailSize);
+        }
+        let available = container_width.saturating_sub(config.outer_padding.saturating_mul(2));
+        if available == 0 {
+            return Err(ConfigError::OuterPadding);
+        }
+        let content_width = match config.max_content_width {
+            0 => available,
+            max_content_width => available.min(max_content_width),
+        };
+        if gap_x >= content_width {
+            return Err(ConfigError::Padding);
+        }
+        if matches!(
+            config.kind,
+            MasonryType::Vertical | MasonryType::Grid | MasonryType::Mosaic
+        ) {
+            let min_columns = match (breakpoint_columns, self.column_count) {
+                (0, 0) => self
+                    .min_column_count(container_width < self.gap_collapse_width)
+                    .max(1),
+                (0, column_count) => u32::from(column_count),
+                (columns, _) => columns,
+            };
+            // Every column needs at least one pixel next to its gap, which the last column does
+            // not have if they fit the width.
+            let width = if self.flags.contains(LayoutFlags::AUTO_FIT) {
+                u64::from(content_width) + u64::from(gap_x)
+            } else {
+                u64::from(content_width)
+            };
+            if u64::from(min_columns) * (u64::from(gap_x) + 1) > width {
+                return Err(ConfigError::Columns);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the config and estimates the container height without laying out the items.
+    ///
+    /// Items that are not measured yet are assumed to have the average aspect ratio of the
+    /// measured items, or the default aspect ratio if there are none.
+    pub fn estimate_height(&mut self, container_width: u32, config: &MasonryConfig) -> f32 {
+        let container_width = self.apply_config(config, container_width);
+        let (mut count, mut measured) = (0_u32, 0_u32);
+        // Sums of width / height and height / width, in f64 so that they stay precise for huge
+        // galleries.
+        let (mut ratios, mut inverse_ratios) = (0.0_f64, 0.0_f64);
+        // Full-width items have a row of their own, so they are summed up separately.
+        let (mut full_width_count, mut full_width_inverse_ratios) = (0_u32, 0.0_f64);
+        for item in self.items.iter().take(self.num_items) {
+            if item.is_hidden() {
+                continue;
+            }
+            if item.is_full_width() {
+                full_width_count += 1;
+                let (width, height) = (
+                    f64::from(item.aspect_ratio.width),
+                    f64::from(item.aspect_ratio.height),
+                );
+                full_width_inverse_ratios += height / width;
+                continue;
+            }
+            count += 1;
+            if !item.source.is_unmeasured() {
+                measured += 1;
+                let (width, height) = (
+                    f64::from(item.aspect_ratio.width),
+                    f64::from(item.aspect_ratio.height),
+                );
+                ratios += width / height;
+                inverse_ratios += height / width;
+            }
+        }
+        if count + full_width_count == 0 || self.thumbnail_size == 0 || container_width == 0 {
+            return 0.0;
+        }
+        let (aspect_ratio, inverse_aspect_ratio) = if measured > 0 {
+            (
+                ratios / f64::from(measured),
+                inverse_ratios / f64::from(measured),
+            )
+        } else {
+            let default = &self.default_aspect_ratio;
+            let (width, height) = (f64::from(default.width), f64::from(default.height));
+            (width / height, height / width)
+        };
+
+        let (gap_x, gap_y) = (f64::from(self.gap_x), f64::from(self.gap_y));
+        // The captions are part of the vertical space between the thumbnails.
+        let gap_y = gap_y + f64::from(self.caption_height);
+        let (content_height, full_width) = match config.kind {
+            MasonryType::Vertical => {
+                let (n_columns, column_width) = self.column_layout(container_width);
+                let item_height = (f64::from(column_width) - gap_x) * inverse_aspect_ratio;
+                let height = f64::from(count) / f64::from(n_columns) * (item_height + gap_y);
+                (
+                    height,
+                    f64::from(n_columns) * f64::from(column_width) - gap_x,
+                )
+            }
+            MasonryType::Horizontal => {
+                let width = f64::from(self.content_width(container_width));
+                let item_width = f64::from(self.thumbnail_size) * aspect_ratio;
+                // The rows are scaled to fill the width.
+                let per_row = ((width + gap_x) / (item_width + gap_x)).max(1.0);
+                let row_height = (width - (per_row - 1.0) * gap_x) / (per_row * aspect_ratio);
+                (
+                    f64::from(count) / per_row * (row_height + gap_y),
+                    width - gap_x,
+                )
+            }
+            MasonryType::Grid | MasonryType::Mosaic => {
+                let (n_columns, column_width) = self.column_layout(container_width);
+                // Every large cell of the mosaic layout takes the place of four small ones.
+                let cells = match self.mosaic_period {
+                    period if config.kind == MasonryType::Mosaic && period > 0 && n_columns > 1 => {
+                        count + 3 * (count / period)
+                    }
+                    _ => count,
+                };
+                let n_rows = (cells + n_columns - 1) / n_columns;
+                let cell_height = self.cell_height(column_width - self.gap_x);
+                let height = f64::from(n_rows) * (f64::from(cell_height) + gap_y);
+                (
+                    height,
+                    f64::from(n_columns) * f64::from(column_width) - gap_x,
+                )
+            }
+            MasonryType::FixedRows => {
+                let width = f64::from(self.content_width(container_width));
+                let item_width = f64::from(self.thumbnail_size) * aspect_ratio;
+                // The rows are not scaled, so only whole items fit into them.
+                let per_row = f64_to_u32((width + gap_x) / (item_width + gap_x)).max(1);
+                let row_height = f64::from(self.thumbnail_size) + gap_y;
+                (f64::from(count) / f64::from(per_row) * row_height, width)
+            }
+            MasonryType::List => {
+                let row_height = f64::from(self.thumbnail_size) + gap_y;
+                (
+                    f64::from(count) * row_height,
+                    f64::from(self.content_width(container_width)),
+                )
+            }
+            MasonryType::Filmstrip => {
+                return self.estimate_filmstrip_width(count + full_width_count, aspect_ratio)
+            }
+        };
+        let full_width_height =
+            full_width * full_width_inverse_ratios + f64::from(full_width_count) * gap_y;
+        f64_to_f32(content_height + full_width_height + 2.0 * f64::from(self.outer_padding))
+    }
+
+    /// Returns the estimated width of a filmstrip of `count` items with the given average aspect
+    /// ratio, since the filmstrip grows to the right and its computations return the width.
+    fn estimate_filmstrip_width(&self, count: u32, aspect_ratio: f64) -> f32 {
+        let item_width = f64::from(self.thumbnail_size) * aspect_ratio + f64::from(self.gap_x);
+        let width = f64::from(count) * item_width - f64::from(self.gap_x);
+        f64_to_f32(width + 2.0 * f64::from(self.outer_padding))
+    }
+
+    /// Returns the layout kind that suits the aspect ratios of the visible items best, e.g. for an
+    /// automatic view mode.
+    ///
+    /// Mostly square items fill the cells of the grid without cropping, mostly landscape items
+    /// keep their size best in the rows of the horizontal layout and everything else, including
+    /// portraits and mixed sizes, goes into the columns of the vertical masonry layout. Items whose
+    /// dimension is not known yet are ignored, and without any the vertical masonry layout is
+    /// returned.
+    #[must_use]
+    pub fn suggest_layout(&self) -> MasonryType {
+        // Percent of the measured items that make a kind the suggestion.
+        const GRID_SHARE: u64 = 75;
+        const HORIZONTAL_SHARE: u64 = 65;
+        // Items whose shorter side is at least this many percent of the longer one count as
+        // square.
+        const MIN_SQUARE_SIDE: u32 = 85;
+
+        let (mut measured, mut square, mut landscape) = (0_u64, 0_u64, 0_u64);
+        for item in self.items.iter().take(self.num_items) {
+            if item.is_hidden() || item.source.is_unmeasured() {
+                continue;
+            }
+            let (width, height) = (item.source.width(), item.source.height());
+            measured += 1;
+            if 100 * width.min(height) >= MIN_SQUARE_SIDE * width.max(height) {
+                square += 1;
+            } else if width > height {
+                landscape += 1;
+            }
+        }
+        if measured > 0 && 100 * square >= GRID_SHARE * measured {
+            MasonryType::Grid
+        } else if measured > 0 && 100 * landscape >= HORIZONTAL_SHARE * measured {
+            MasonryType::Horizontal
+        } else {
+            MasonryType::Vertical
+        }
+    }
+
+    /// Applies the config, with the parameters of the breakpoint for the container width instead
+    /// if there is one. Returns the container width in physical pixels.
+    fn apply_config(&mut self, config: &MasonryConfig, container_width: u32) -> u32 {
+        let breakpoint = self.breakpoint(container_width);
+        let (thumbnail_size, (gap_x, gap_y), breakpoint_columns) = match breakpoint {
+            Some(breakpoint) => (
+                breakpoint.thumbnail_size,
+                (breakpoint.padding, breakpoint.padding),
+                breakpoint.columns,
+            ),
+            None => (config.thumbnail_size, (config.gap_x, config.gap_y), 0),
+        };
+        let thumbnail_size = config.row_thumbnail_size(thumbnail_size);
+        let gaps = config.scale_gaps(thumbnail_size, (gap_x, gap_y));
+        let (gap_x, gap_y) = self.collapse_gaps(container_width, gaps);
+        let narrow = container_width < self.gap_collapse_width;
+        if breakpoint_columns != self.breakpoint_columns
+            || narrow != self.flags.contains(LayoutFlags::NARROW)
+            || config.device_pixel_ratio.to_bits() != self.device_pixel_ratio.to_bits()
+        {
+            self.breakpoint_columns = breakpoint_columns;
+            self.flags.set(LayoutFlags::NARROW, narrow);
+            self.device_pixel_ratio = config.device_pixel_ratio;
+            if let Some(resume) = self.resume.as_mut() {
+                resume.state = ResumeState::Restart;
+            }
+        }
+
+        self.set_thumbnail_size(self.device_pixels(thumbnail_size));
+        self.set_gaps(self.device_pixels(gap_x), self.device_pixels(gap_y));
+        self.set_outer_padding(self.device_pixels(config.outer_padding));
+        self.set_max_content_width(self.device_pixels(config.max_content_width));
+        self.set_caption_height(self.device_pixels(config.caption_height));
+        self.set_metadata_width(self.device_pixels(config.metadata_width));
+        self.set_row_height_tolerance(config.row_height_tolerance);
+        self.device_pixels(container_width)
+    }
+
+    /// Converts a length in CSS pixels to whole physical pixels.
+    fn device_pixels(&self, length: u32) -> u32 {
+        device_pixels(length, self.device_pixel_ratio)
+    }
+
+    fn compute_kind(&mut self, kind: MasonryType, container_width: u32) -> u32 {
+        self.flags.set(LayoutFlags::HAS_SECTIONS, false);
+        if self.is_empty() || container_width == 0 || self.thumbnail_size == 0 {
+            return self.compute_nothing(kind, container_width);
+        }
+        // A filmstrip has a single row, so there is no row that could move to the top.
+        if self.flags.contains(LayoutFlags::REVERSED) && kind != MasonryType::Filmstrip {
+            return self.compute_reversed(kind, container_width);
+        }
+        let sections = self.sections(kind);
+        if sections.is_empty() {
+            self.lay_out(kind, container_width)
+        } else {
+            self.compute_sections(kind, container_width, &sections)
+        }
+    }
+
+    /// Lays out no items because there are none or the container has no width, so that every
+    /// layout kind has a height of 0 and no item is returned by the queries.
+    ///
+    /// Appended items are laid out from scratch, e.g. once the container got a width.
+    fn compute_nothing(&mut self, kind: MasonryType, container_width: u32) -> u32 {
+        for transform in self.transforms.range_mut(..self.num_items) {
+            *transform = Transform::default();
+        }
+        self.max_item_height = 0;
+        self.set_unsorted_from(usize::MAX);
+        self.first_dirty = None;
+        self.laid_out_from = 0;
+        self.resume = Some(Resume {
+            kind,
+            container_width,
+            num_items: self.num_items,
+            state: ResumeState::Restart,
+        });
+        0
+    }
+
+    /// Returns the start position and kind of every section after the first one whose kind
+    /// differs from the kind of the section before it.
+    ///
+    /// A section starts after a visible section header. The header itself is laid out with the
+    /// section before it, which makes every layout kind continue below it.
+    fn sections(&self, kind: MasonryType) -> Vec<(usize, MasonryType)> {
+        let items = self.ordered_items();
+        let mut current = kind;
+        let mut sections = Vec::new();
+        for position in 0..self.num_items {
+            let item = items.get(position);
+            if item.is_section_header() && !item.is_hidden() {
+                let section_kind = item.section_kind.unwrap_or(kind);
+                if section_kind != current {
+                    sections.push((position + 1, section_kind));
+                    current = section_kind;
+                }
+            }
+        }
+        sections
+    }
+
+    /// Lays out the sections one after another with their own kind.
+    ///
+    /// The horizontal sections fill the rows one by one and only the columns of the last section
+    /// are balanced.
+    fn compute_sections(
+        &mut self,
+        kind: MasonryType,
+        container_width: u32,
+        sections: &[(usize, MasonryType)],
+    ) -> u32 {
+        let num_items = self.num_items;
+        let balance_columns = self.flags.replace(LayoutFlags::BALANCE_COLUMNS, false);
+        let outer_padding = self.outer_padding;
+        let mut top = outer_padding;
+        let mut height = 0;
+        let mut max_item_height = 0;
+        let mut unsorted_from = usize::MAX;
+        let starts = core::iter::once((0, kind)).chain(sections.iter().copied());
+        let ends = sections
+            .iter()
+            .map(|&(start, _)| start)
+            .chain(Some(num_items));
+        for ((start, section_kind), end) in starts.zip(ends) {
+            if start == end {
+                continue;
+            }
+            self.num_items = end;
+            if end == num_items {
+                self.flags
+                    .set(LayoutFlags::BALANCE_COLUMNS, balance_columns);
+            }
+            height = self.lay_out_section(section_kind, container_width, start, top);
+            if height == 0 {
+                break;
+            }
+            max_item_height = max_item_height.max(self.max_item_height);
+            // Every section starts below the ones before it, so the first unsorted item stays.
+            unsorted_from = unsorted_from.min(self.unsorted_from);
+            top = height - outer_padding;
+        }
+        self.num_items = num_items;
+        self.flags
+            .set(LayoutFlags::BALANCE_COLUMNS, balance_columns);
+        self.max_item_height = max_item_height;
+        self.set_unsorted_from(unsorted_from);
+        self.flags.set(LayoutFlags::HAS_SECTIONS, true);
+        // Appended items continue the last section, but everything else starts over.
+        if let Some(resume) = self.resume.as_mut() {
+            resume.kind = kind;
+        }
+        height
+    }
+
+    /// Lays out the items from `start` on with the given kind, starting at the given top.
+    fn lay_out_section(
+        &mut self,
+        kind: MasonryType,
+        container_width: u32,
+        start: usize,
+        top: u32,
+    ) -> u32 {
+        match kind {
+            MasonryType::Vertical => {
+                let (n_columns, _) = self.column_layout(container_width);
+                let mut columns = vertical_masonry::ColumnHeights::new(n_columns as usize);
+                columns.fill(top);
+                self.max_item_height = 0;
+                self.layout_vertical(container_width, start, columns)
+            }
+            MasonryType::Horizontal => {
+                self.max_item_height = self.thumbnail_size + self.caption_height;
+                self.layout_horizontal(container_width, start, top)
+            }
+            // The other kinds continue below the section header before the start.
+            MasonryType::Grid => self.layout_grid(container_width, start),
+            MasonryType::List => self.layout_list(container_width, start),
+            MasonryType::Mosaic => self.layout_mosaic(container_width, start),
+            MasonryType::FixedRows => self.layout_fixed_rows(container_width, start),
+            MasonryType::Filmstrip => self.layout_filmstrip(container_width, start),
+        }
+    }
+
+    fn lay_out(&mut self, kind: MasonryType, container_width: u32) -> u32 {
+        match kind {
+            MasonryType::Vertical => self.compute_vertical(container_width),
+            MasonryType::Horizontal => self.compute_horizontal(container_width),
+            MasonryType::Grid => self.compute_grid(container_width),
+            MasonryType::List => self.compute_list(container_width),
+            MasonryType::Mosaic => self.compute_mosaic(container_width),
+            MasonryType::FixedRows => self.compute_fixed_rows(container_width),
+            MasonryType::Filmstrip => self.compute_filmstrip(container_width),
+        }
+    }
+
+    /// Lays out only the items that were added since the most recent computation with the same
+    /// config and returns the new container height.
+    ///
+    /// Falls back to a full computation if items were removed in the meantime. Returns 0 if
+    /// nothing was computed yet.
+    pub fn compute_appended(&mut self) -> u32 {
+        self.prepare();
+        self.lay_out_appended()
+    }
+
+    fn lay_out_appended(&mut self) -> u32 {
+        let Resume {
+            kind,
+            container_width,
+            num_items,
+            state,
+        } = match self.resume.take() {
+            Some(resume) => resume,
+            None => return 0,
+        };
+        let height = match state {
+            ResumeState::Restart => return self.compute_kind(kind, container_width),
+            ResumeState::Horizontal { row_start, top } => {
+                self.layout_horizontal(container_width, row_start, top)
+            }
+            ResumeState::Vertical(columns) => {
+                self.layout_vertical(container_width, num_items, columns)
+            }
+            ResumeState::Grid => self.layout_grid(container_width, num_items),
+            ResumeState::List => self.layout_list(container_width, num_items),
+            ResumeState::Mosaic => self.layout_mosaic(container_width, num_items),
+            ResumeState::FixedRows => self.layout_fixed_rows(container_width, num_items),
+            ResumeState::Filmstrip => self.layout_filmstrip(container_width, num_items),
+        };
+        // The last section can have another kind than the computation.
+        if let Some(resume) = self.resume.as_mut() {
+            resume.kind = kind;
+        }
+        height
+    }
+
+    /// Lays out the items from the last to the first and turns the result upside down, so the
+    /// last items are at the bottom right and the first row is the one that is not full.
+    ///
+    /// The tops of a vertical masonry layout are not sorted afterwards (see `unsorted_from`).
+    fn compute_reversed(&mut self, kind: MasonryType, container_width: u32) -> u32 {
+        let order = self.order.take();
+        let items = OrderedItems {
+            items: &self.items,
+            order: order.as_ref(),
+        };
+        let ids = (0..self.num_items)
+            .rev()
+            .map(|position| usize_to_u32(items.index(position)))
+            .collect();
+        self.order = Some(Order::new(ids, self.items.len()));
+        // Moved items would end up at the top, where the tops are expected to be sorted.
+        let balance_columns = self.flags.replace(LayoutFlags::BALANCE_COLUMNS, false);
+        let height = self.lay_out(kind, container_width);
+        self.flags
+            .set(LayoutFlags::BALANCE_COLUMNS, balance_columns);
+        self.order = order;
+        // The state belongs to the reversed order, so everything is laid out again next time.
+        if let Some(resume) = self.resume.as_mut() {
+            resume.state = ResumeState::Restart;
+        }
+        if height == 0 {
+            return 0;
+        }
+
+        self.transforms.reverse(..self.num_items);
+        let gap_y = self.gap_y;
+        let content_left = self.content_left;
+        let right = self
+            .transforms
+            .slice(..self.num_items)
+            .iter()
+            .map(Transform::right)
+            .max()
+            .unwrap_or(0);
+        let mut top = self.outer_padding;
+        for transform in self.transforms.range_mut(..self.num_items) {
+            if transform.is_empty() {
+                // Hidden items get the top of the item before them.
+                *transform = Transform::new(0, 0, top, 0);
+                continue;
+            }
+            // The trailing gap stays at the bottom.
+            top = height - gap_y - transform.bottom();
+            let left = content_left + right - transform.right();
+            transform.0 = transform.0.set::<2>(top).set::<3>(left);
+        }
+        // The bottoms of a vertical masonry layout are sorted now, but not the tops.
+        self.find_unsorted_from(0, None);
+        height
+    }
+
+    /// Lays out the items again starting at the first item whose dimension changed since the
+    /// most recent computation and returns the new container height.
+    ///
+    /// Appended items are laid out as well (see [`Layout::compute_appended()`]).
+    pub fn compute_incremental(&mut self) -> u32 {
+        self.prepare();
+        let start = match (self.first_dirty, &self.resume) {
+            (Some(first_dirty), Some(resume)) if first_dirty < resume.num_items => first_dirty,
+            _ => return self.lay_out_appended(),
+        };
+        let (kind, container_width) = match self.resume.take() {
+            Some(Resume {
+                state: ResumeState::Restart,
+                kind,
+                container_width,
+                ..
+            }) => return self.compute_kind(kind, container_width),
+            // The sections before the item could have other kinds.
+            Some(resume) if self.flags.contains(LayoutFlags::HAS_SECTIONS) => {
+                return self.compute_kind(resume.kind, resume.container_width)
+            }
+            Some(resume) => (resume.kind, resume.container_width),
+            None => return 0,
+        };
+        self.lay_out_from(kind, container_width, start)
+    }
+
+    /// Lays out the items again starting at the given position, continuing below the transforms
+    /// of the items before it.
+    fn lay_out_from(&mut self, kind: MasonryType, container_width: u32, start: usize) -> u32 {
+        match kind {
+            MasonryType::Horizontal => {
+                // The transforms from the start on may belong to other items since an insertion
+                // or removal, so the row of the last laid out item before it is laid out again.
+                let last = (0..start).rev().find(|&i| !self.transforms[i].is_empty());
+                let last = match last {
+                    Some(last) => last,
+                    None => return self.compute_kind(kind, container_width),
+                };
+                let top = self.transforms[last].top();
+                let row_start = (0..=last)
+                    .rev()
+                    .take_while(|&i| {
+                        let transform = &self.transforms[i];
+                        transform.is_empty() || transform.top() == top
+                    })
+                    .last()
+                    .unwrap_or(last);
+                self.layout_horizontal(container_width, row_start, top)
+            }
+            MasonryType::Vertical => {
+                let columns = self.column_heights_at(start, container_width);
+                self.layout_vertical(container_width, start, columns)
+            }
+            MasonryType::Grid => self.layout_grid(container_width, start),
+            MasonryType::List => self.layout_list(container_width, start),
+            MasonryType::Mosaic => self.layout_mosaic(container_width, start),
+            MasonryType::FixedRows => self.layout_fixed_rows(container_width, start),
+            MasonryType::Filmstrip => self.layout_filmstrip(container_width, start),
+        }
+    }
+
+    /// Returns how many items were inserted at the start since the most recent computation, which
+    /// is the largest count that [`Layout::compute_prepended()`] accepts.
+    ///
+    /// This is 0 if nothing was computed yet or the first item was not touched since, e.g.
+    /// because the items were appended instead.
+    #[must_use]
+    pub fn prepended_items(&self) -> usize {
+        match (&self.resume, self.first_dirty) {
+            (Some(resume), Some(0)) => self.num_items.saturating_sub(resume.num_items),
+            _ => 0,
+        }
+    }
+
+    /// Lays out the first `count` items, which were inserted at the start since the most recent
+    /// computation, above the items that were computed before and returns how much the latter
+    /// moved down, or to the right in a filmstrip.
+    ///
+    /// The items that were computed before keep their arrangement, so the new container height is
+    /// the previous one plus the returned offset. Returns `None` without laying out anything if
+    /// `count` is more than [`Layout::prepended_items()`], e.g. because nothing was computed yet.
+    pub fn compute_prepended(&mut self, count: usize) -> Option<u32> {
+        if count > self.prepended_items() {
+            return None;
+        }
+        self.prepare();
+        let (resume, max_item_height, unsorted_from) = match self.resume.take() {
+            Some(resume) => (resume, self.max_item_height, self.unsorted_from),
+            None => return Some(0),
+        };
+        if count == 0 {
+            self.resume = Some(resume);
+            return Some(0);
+        }
+
+        // The new items are laid out on their own. Moved items would end up in the middle.
+        let num_items = core::mem::replace(&mut self.num_items, count);
+        let balance_columns = self.flags.replace(LayoutFlags::BALANCE_COLUMNS, false);
+        let height = self.compute_kind(resume.kind, resume.container_width);
+        self.flags
+            .set(LayoutFlags::BALANCE_COLUMNS, balance_columns);
+        self.num_items = num_items;
+        if height == 0 {
+            // Cancelled, so the items are laid out again next time.
+            self.resume = Some(Resume {
+                state: ResumeState::Restart,
+                ..resume
+            });
+            return Some(0);
+        }
+
+        // The outer padding at the end of the new items is the one before the previous items. The
+        // width of a filmstrip does not include the gap after its last item, unlike the heights.
+        let filmstrip = resume.kind == MasonryType::Filmstrip;
+        let offset = if filmstrip {
+            height - 2 * self.outer_padding + self.gap_x
+        } else {
+            height - 2 * self.outer_padding
+        };
+        for transform in self.transforms.iter_mut().take(self.num_items).skip(count) {
+            transform.0 = if filmstrip {
+                transform.0.set::<3>(transform.left() + offset)
+            } else {
+                transform.0.set::<2>(transform.top() + offset)
+            };
+        }
+        self.max_item_height = self.max_item_height.max(max_item_height);
+        self.set_unsorted_from(unsorted_from.saturating_add(count));
+        let num_items = resume.num_items + count;
+        let state = match resume.state {
+            ResumeState::Horizontal { row_start, top } => ResumeState::Horizontal {
+                row_start: row_start + count,
+                top: top + offset,
+            },
+            ResumeState::Vertical(_) => {
+                ResumeState::Vertical(self.column_heights_at(num_items, resume.container_width))
+            }
+            state => state,
+        };
+        self.resume = Some(Resume {
+            num_items,
+            state,
+            ..resume
+        });
+        Some(offset)
+    }
+
+    // Main idea: Keep looping over images until containerWidth is reached, then:
+    // - Either adjust row height or add/remove item to make it fit full-width, whatever is the closest
+    // (I think this is how google photos does it)
+    // Could also have an approximated version for very large lists, and just properly compute for what in and close to the viewport
+    // TODO: Look up proper masonry algorithm, e.g. https://euler.stephan-brumme.com/215/
+    // TODO: Alternatively, could layout based on aspect ratio blogpost https://medium.com/@danrschlosser/building-the-image-grid-from-google-photos-6a09e193c74a
+    pub fn compute_horizontal(&mut self, container_width: u32) -> u32 {
+        // The last row is not scaled, so its items are exactly as high as the thumbnail size.
+        self.max_item_height = self.thumbnail_size + self.caption_height;
+        if self.flags.contains(LayoutFlags::BALANCE_ROWS) {
+            self.layout_balanced(container_width)
+        } else {
+            let outer_padding = self.outer_padding;
+            self.layout_horizontal(container_width, 0, outer_padding)
+        }
+    }
+
+    fn layout_horizontal(&mut self, input_width: u32, row_start: usize, row_top: u32) -> u32 {
+        self.resume = None;
+        if self.is_empty() || self.thumbnail_size == 0 {
+            return 0;
+        }
+
+        let height = self.thumbnail_size;
+        let max_width = self.content_width(input_width);
+        let container_width = u32_to_f32(max_width);
+        let (gap_x, gap_y) = (self.gap_x, self.gap_y);
+        let (content_left, _) = self.content_box(input_width);
+        let max_row_height = self.row_height_cap();
+        let caption_height = self.caption_height;
+        let device_pixel_ratio = self.device_pixel_ratio;
+        let capped_height = height.min(max_row_height) + caption_height;
+
+        let (mut top, mut row_width, mut start) = (row_top, 0, row_start);
+
+        let items = OrderedItems {
+            items: &self.items,
+            order: self.order.as_ref(),
+        };
+        let widths = items.scaled_sides(
+            self.chunk_runner,
+            row_start..self.num_items,
+            height,
+            |aspect_ratio| (aspect_ratio.width(), aspect_ratio.height()),
+        );
+        for end in row_start..self.num_items {
+            if is_cancelled_at(self.cancel_slot, end) {
+                return 0;
+            }
+
+            let item = items.get(end);
+            if item.is_hidden() {
+                self.transforms[end] = Transform::new(0, 0, top, 0);
+                continue;
+            }
+            if let Some(converge) = self.converges_at(end, row_width, content_left) {
+                return self.converge(input_width, row_start..end, top, converge);
+            }
+
+            if item.breaks_before() && row_width > 0 {
+                // Leave the unfinished row as it is, like the last row, and start a new row.
+                fit_row(
+                    self.transforms.range_mut(start..end),
+                    max_row_height,
+                    caption_height,
+                );
+                row_width = 0;
+                start = end;
+                top += capped_height + gap_y;
+            }
+
+            if item.is_full_width() {
+                // Leave the unfinished row as it is, like the last row.
+                if row_width > 0 {
+                    fit_row(
+                        self.transforms.range_mut(start..end),
+                        max_row_height,
+                        caption_height,
+                    );
+                    top += capped_height + gap_y;
+                }
+                let full_width = max_width.saturating_sub(gap_x);
+                let full_height = item.full_width_height(full_width, device_pixel_ratio);
+                self.transforms[end] = Transform::new(full_width, full_height, top, content_left);
+                fit_row(
+                    self.transforms.range_mut(end..=end),
+                    max_row_height,
+                    caption_height,
+                );
+                let full_height = self.transforms[end].height();
+                self.max_item_height = self.max_item_height.max(full_height);
+
+                // Start a new row below
+                row_width = 0;
+                start = end + 1;
+                top += full_height + gap_y;
+                continue;
+            }
+
+            // Correct aspect ratio for very wide/narrow images
+            let width = widths[end - row_start];
+
+            self.transforms[end] = Transform::new(width, height, top, content_left + row_width);
+
+            row_width += width + gap_x;
+
+            // Check if adding this image to the row would exceed the container width
+            if row_width > max_width {
+                // If it exceeds it, scale all current items in the row accordingly and start a new
+                // row, which begins with this image if the row is closer to the target without it.
+                let without = row_width - (width + gap_x);
+                let early = self.ends_row_early(without, row_width, max_width);
+                let (row_end, scaled_width) = if early {
+                    (end, without)
+                } else {
+                    (end + 1, row_width)
+                };
+                let factor = container_width / u32_to_f32(scaled_width);
+                let row = self.transforms.range_mut(start..row_end);
+                scale_row(row, factor, content_left, self.rounding);
+                let row = self.transforms.range_mut(start..row_end);
+                fit_row(row, max_row_height, caption_height);
+
+                // Start a new row
+                let row = self.transforms.slice(start..row_end);
+                let row_height = row.iter().map(Transform::height).max().unwrap_or(0);
+                self.max_item_height = self.max_item_height.max(row_height);
+                row_width = 0;
+                start = row_end;
+                top += row_height + gap_y;
+                if early {
+                    self.transforms[end] = Transform::new(width, height, top, content_left);
+                    row_width = width + gap_x;
+                }
+            }
+        }
+        self.finish_horizontal(input_width, row_start, (start, row_width, top))
+    }
+
+    /// Lays out the last row of the greedy horizontal layout, given its start, width and top, and
+    /// stores the state after all items from `row_start` on were computed. Returns the height.
+    fn finish_horizontal(
+        &mut self,
+        input_width: u32,
+        row_start: usize,
+        (mut start, mut row_width, mut top): (usize, u32, u32),
+    ) -> u32 {
+        // The row before a widow changes as well, so it is laid out again with appended items.
+        let (mut resume_start, mut resume_top, mut laid_out_from) = (start, top, row_start);
+        if row_width > 0 {
+            if let Some(widow) = self.avoid_widow(start, input_width) {
+                (resume_start, resume_top) = (widow.previous_start, widow.previous_top);
+                (start, row_width, top) = (widow.start, widow.row_width, widow.top);
+                laid_out_from = laid_out_from.min(widow.previous_start);
+            }
+        }
+        // If a new row was just started, there is no last row; its height was already added in the loop
+        let last_row_height = if row_width == 0 {
+            0
+        } else {
+            let container_width = u32_to_f32(self.content_width(input_width));
+            let (content_left, _) = self.content_box(input_width);
+            self.lay_out_last_row(start, row_width, top, container_width, content_left)
+        };
+        self.finish(
+            laid_out_from,
+            MasonryType::Horizontal,
+            input_width,
+            ResumeState::Horizontal {
+                row_start: resume_start,
+                top: resume_top,
+            },
+        );
+        top + last_row_height + self.outer_padding
+    }
+
+    /// Lays out the last row of the horizontal layout, which is not full, according to the
+    /// [`LastRowBehavior`] and returns its height including the gap below it.
+    fn lay_out_last_row(
+        &mut self,
+        start: usize,
+        row_width: u32,
+        top: u32,
+        container_width: f32,
+        row_left: u32,
+    ) -> u32 {
+        let max_row_height = self.row_height_cap();
+        let caption_height = self.caption_height;
+        let gap_y = self.gap_y;
+        let row = start..self.num_items;
+        match self.last_row {
+            LastRowBehavior::Left => {
+                fit_row(
+                    self.transforms.range_mut(row),
+                    max_row_height,
+                    caption_height,
+                );
+                self.thumbnail_size.min(max_row_height) + caption_height + gap_y
+            }
+            LastRowBehavior::Justify => {
+                let factor = container_width / u32_to_f32(row_width);
+                let rounding = self.rounding;
+                scale_row(
+                    self.transforms.range_mut(row.clone()),
+                    factor,
+                    row_left,
+                    rounding,
+                );
+                fit_row(
+                    self.transforms.range_mut(row.clone()),
+                    max_row_height,
+                    caption_height,
+                );
+                let row_height = self
+                    .transforms
+                    .slice(row)
+                    .iter()
+                    .map(Transform::height)
+                    .max();
+                let row_height = row_height.unwrap_or(0);
+                self.max_item_height = self.max_item_height.max(row_height);
+                row_height + gap_y
+            }
+            LastRowBehavior::Hide => {
+                for transform in self.transforms.range_mut(row) {
+                    *transform = Transform::new(0, 0, top, 0);
+                }
+                0
+            }
+        }
+    }
+
+    /// Lays out the rows of the horizontal layout so that their heights deviate as little as
+    /// possible from the thumbnail size, instead of filling them one after another.
+    ///
+    /// This is the linear partition problem, which is solved for every run of items between
+    /// full-width items and row breaks. Because a changed item can move the row breaks before it,
+    /// appended items are not laid out on their own but everything is computed again.
+    fn layout_balanced(&mut self, input_width: u32) -> u32 {
+        self.resume = None;
+        if self.is_empty() || self.thumbnail_size == 0 {
+            return 0;
+        }
+
+        let container_width = self.content_width(input_width);
+        let height = self.thumbnail_size;
+        let max_width = container_width;
+        let (gap_x, gap_y) = (self.gap_x, self.gap_y);
+        let outer_padding = self.outer_padding;
+        let (content_left, _) = self.content_box(input_width);
+        let max_row_height = self.row_height_cap();
+        let caption_height = self.caption_height;
+        let device_pixel_ratio = self.device_pixel_ratio;
+
+        let mut top = outer_padding;
+        // Visible items since the last full-width item or row break with their width
+        let mut run = Vec::new();
+        let mut last_row = None;
+        for position in 0..=self.num_items {
+            if is_cancelled_at(self.cancel_slot, position) {
+                return 0;
+            }
+
+            let item =
+                (position < self.num_items).then(|| self.ordered_items().get(position).clone());
+            if let Some(item) = &item {
+                if item.is_hidden() {
+                    // The top is set once the rows around it are laid out.
+                    self.transforms[position] = Transform::default();
+                    continue;
+                }
+                let ends_run = item.is_full_width() || (item.breaks_before() && !run.is_empty());
+                if !ends_run {
+                    run.push((position, item.aspect_ratio.correct_width(height)));
+                    continue;
+                }
+            }
+
+            // The run ends here, like the greedy layout leaves an unfinished row as it is.
+            let (run_top, unfinished) =
+                self.place_balanced_run(&run, top, container_width, content_left);
+            top = run_top;
+            run.clear();
+            match (&item, unfinished) {
+                (None, unfinished) => last_row = unfinished,
+                (Some(_), Some((start, _))) => {
+                    let row = self.transforms.range_mut(start..position);
+                    fit_row(row, max_row_height, caption_height);
+                    top += height.min(max_row_height) + caption_height + gap_y;
+                }
+                (Some(_), None) => {}
+            }
+            match item {
+                Some(item) if item.is_full_width() => {
+                    let full_width = max_width.saturating_sub(gap_x);
+                    let full_height = item.full_width_height(full_width, device_pixel_ratio);
+                    self.transforms[position] =
+                        Transform::new(full_width, full_height, top, content_left);
+                    fit_row(
+                        self.transforms.range_mut(position..=position),
+                        max_row_height,
+                        caption_height,
+                    );
+                    let full_height = self.transforms[position].height();
+                    self.max_item_height = self.max_item_height.max(full_height);
+                    top += full_height + gap_y;
+                }
+                Some(item) => run.push((position, item.aspect_ratio.correct_width(height))),
+                None => {}
+            }
+        }
+
+        // Hidden items are at the top of the next row.
+        let mut next_top = top;
+        for transform in self.transforms.iter_mut().take(self.num_items).rev() {
+            if transform.is_empty() {
+                *transform = Transform::new(0, 0, next_top, 0);
+            } else {
+                next_top = transform.top();
+            }
+        }
+
+        let last_row =
+            last_row.map(
+                |(start, row_width)| match self.avoid_widow(start, input_width) {
+                    Some(widow) => (widow.start, widow.row_width, widow.top),
+                    None => (start, row_width, top),
+                },
+            );
+        let last_row_height = match last_row {
+            Some((start, row_width, row_top)) => {
+                top = row_top;
+                let container_width = u32_to_f32(container_width);
+                self.lay_out_last_row(start, row_width, top, container_width, content_left)
+            }
+            None => 0,
+        };
+        // Every computation has to start from the beginning.
+        self.finish(
+            0,
+            MasonryType::Horizontal,
+            input_width,
+            ResumeState::Restart,
+        );
+        top + last_row_height + outer_padding
+    }
+
+    /// Moves the last item of the row before the last row of the horizontal layout into it if the
+    /// last row from `start` on would only hold one item, and lays out both rows again.
+    ///
+    /// Returns `None` if there is nothing to move, see [`Layout::set_avoid_widows()`].
+    fn avoid_widow(&mut self, start: usize, input_width: u32) -> Option<Widow> {
+        if !self.flags.contains(LayoutFlags::AVOID_WIDOWS) || self.last_row == LastRowBehavior::Hide
+        {
+            return None;
+        }
+        let items = OrderedItems {
+            items: &self.items,
+            order: self.order.as_ref(),
+        };
+        let mut last_row = (start..self.num_items).filter(|&i| !items.get(i).is_hidden());
+        let widow = last_row.next()?;
+        if last_row.next().is_some() || items.get(widow).breaks_before() {
+            return None;
+        }
+        // The visible items of the row before, which ends at a row break or a full-width item.
+        let mut previous = Vec::new();
+        for position in (0..start).rev() {
+            let (item, transform) = (items.get(position), &self.transforms[position]);
+            if item.is_hidden() {
+                continue;
+            }
+            let top = previous
+                .first()
+                .map(|&first: &usize| self.transforms[first].top());
+            if item.is_full_width() || top.map_or(false, |top| transform.top() != top) {
+                break;
+            }
+            previous.push(position);
+            if item.breaks_before() {
+                break;
+            }
+        }
+        previous.reverse();
+        if previous.len() < 3 {
+            return None;
+        }
+
+        let (content_left, _) = self.content_box(input_width);
+        let max_width = self.content_width(input_width);
+        let (height, gap_x, gap_y) = (self.thumbnail_size, self.gap_x, self.gap_y);
+        let max_row_height = self.row_height_cap();
+        let previous_start = previous[0];
+        let previous_top = self.transforms[previous_start].top();
+        let moved = previous[previous.len() - 1];
+        // The last row is not scaled, so the widow stays if both items do not fit next to each
+        // other.
+        let last_row_width = [moved, widow]
+            .iter()
+            .map(|&position| items.get(position).aspect_ratio.correct_width(height) + gap_x)
+            .sum::<u32>();
+        if last_row_width - gap_x > max_width {
+            return None;
+        }
+        // Scale the rest of the row to the full width again.
+        let mut row_width = 0;
+        for position in previous_start..moved {
+            let item = items.get(position);
+            if !item.is_hidden() {
+                let width = item.aspect_ratio.correct_width(height);
+                let left = content_left + row_width;
+                self.transforms[position] = Transform::new(width, height, previous_top, left);
+                row_width += width + gap_x;
+            }
+        }
+        let factor = u32_to_f32(max_width) / u32_to_f32(row_width);
+        let row = self.transforms.range_mut(previous_start..moved);
+        scale_row(row, factor, content_left, self.rounding);
+        let row = self.transforms.range_mut(previous_start..moved);
+        fit_row(row, max_row_height, self.caption_height);
+        let previous_height = self.transforms[previous_start].height();
+        self.max_item_height = self.max_item_height.max(previous_height);
+
+        let top = previous_top + previous_height + gap_y;
+        let mut row_width = 0;
+        for position in moved..self.num_items {
+            let item = items.get(position);
+            self.transforms[position] = if item.is_hidden() {
+                Transform::new(0, 0, top, 0)
+            } else {
+                let width = item.aspect_ratio.correct_width(height);
+                let left = content_left + row_width;
+                row_width += width + gap_x;
+                Transform::new(width, height, top, left)
+            };
+        }
+        Some(Widow {
+            previous_start,
+            previous_top,
+            start: moved,
+            row_width,
+            top,
+        })
+    }
+
+    /// Lays out the rows of a run of the balanced horizontal layout and returns the top below
+    /// them. If the last row is not full, it is left as it is and its start position and width
+    /// are returned as well.
+    fn place_balanced_run(
+        &mut self,
+        run: &[(usize, u32)],
+        mut top: u32,
+        container_width: u32,
+        row_left: u32,
+    ) -> (u32, Option<(usize, u32)>) {
+        let height = self.thumbnail_size;
+        let max_width = container_width;
+        let (gap_x, gap_y) = (self.gap_x, self.gap_y);
+        let max_row_height = self.row_height_cap();
+        let caption_height = self.caption_height;
+
+        let row_starts = partition_rows(run, max_width, height, gap_x);
+        for (index, &row_start) in row_starts.iter().enumerate() {
+            let row_end = row_starts.get(index + 1).copied().unwrap_or(run.len());
+            let row = run.get(row_start..row_end).unwrap_or_abort();
+            let mut row_width = 0;
+            for &(position, width) in row {
+                let left = row_left + row_width;
+                self.transforms[position] = Transform::new(width, height, top, left);
+                row_width += width + gap_x;
+            }
+
+            let (first, last) = match (row.first(), row.last()) {
+                (Some(&(first, _)), Some(&(last, _))) => (first, last),
+                _ => continue,
+            };
+            if row_end == run.len() && row_width <= max_width {
+                return (top, Some((first, row_width)));
+            }
+            let factor = u32_to_f32(container_width) / u32_to_f32(row_width);
+            let rounding = self.rounding;
+            scale_row(
+                self.transforms.range_mut(first..=last),
+                factor,
+                row_left,
+                rounding,
+            );
+            fit_row(
+                self.transforms.range_mut(first..=last),
+                max_row_height,
+                caption_height,
+            );
+            let row = self.transforms.slice(first..=last);
+            let row_height = row.iter().map(Transform::height).max().unwrap_or(0);
+            self.max_item_height = self.max_item_height.max(row_height);
+            top += row_height + gap_y;
+        }
+        (top, None)
+    }
+
+    // Main idea: Initialize with N columns of identical widths
+    // loop over images, put them in the column that has the least height filled
+    pub fn compute_vertical(&mut self, container_width: u32) -> u32 {
+        use vertical_masonry::ColumnHeights;
+
+        if self.thumbnail_size == 0 {
+            self.resume = None;
+            return 0;
+        }
+
+        let (n_columns, _) = self.column_layout(container_width);
+        self.max_item_height = 0;
+        let mut columns = ColumnHeights::new(n_columns as usize);
+        columns.fill(self.outer_padding);
+        self.layout_vertical(container_width, 0, columns)
+    }
+
+    fn layout_vertical(
+        &mut self,
+        input_width: u32,
+        start: usize,
+        mut columns: vertical_masonry::ColumnHeights,
+    ) -> u32 {
+        let previous_columns = self.previous_columns();
+        // The items before the start keep their transforms.
+        let unsorted_before = Some(self.unsorted_from).filter(|&position| position < start);
+        self.resume = None;
+        if self.is_empty() || self.thumbnail_size == 0 {
+            return 0;
+        }
+
+        let (n_columns, column_width) = self.column_layout(input_width);
+        let (gap_x, gap_y) = (self.gap_x, self.gap_y);
+        let outer_padding = self.outer_padding;
+        let (content_left, _) = self.content_box(input_width);
+        let item_width = column_width - gap_x;
+        let full_width = n_columns * column_width - gap_x;
+        let caption_height = self.caption_height;
+        let device_pixel_ratio = self.device_pixel_ratio;
+
+        let items = OrderedItems {
+            items: &self.items,
+            order: self.order.as_ref(),
+        };
+        let heights = items.scaled_sides(
+            self.chunk_runner,
+            start..self.num_items,
+            item_width,
+            |aspect_ratio| (aspect_ratio.height(), aspect_ratio.width()),
+        );
+        let (mut has_wide_items, mut has_margins) = (false, false);
+        for (position, transform) in self
+            .transforms
+            .iter_mut()
+            .enumerate()
+            .take(self.num_items)
+            .skip(start)
+        {
+            if is_cancelled_at(self.cancel_slot, position) {
+                return 0;
+            }
+
+            let item = items.get(position);
+            if item.is_hidden() {
+                *transform = Transform::new(0, 0, columns.min_column().0, 0);
+                continue;
+            }
+
+            if item.breaks_before() {
+                // Continue below all columns.
+                columns.fill(columns.max_height());
+            }
+
+            let [margin_top, margin_right, margin_bottom, margin_left] =
+                item.margin(device_pixel_ratio);
+            let margins = (margin_top + margin_bottom, margin_left + margin_right);
+            has_margins |= item.has_margin();
+
+            if item.is_full_width() {
+                // Place the item below all columns and continue below it in every column.
+                let top = columns.max_height() + margin_top;
+                let (width, height) =
+                    item.full_width_size(full_width, margins.1, caption_height, device_pixel_ratio);
+                self.max_item_height = self.max_item_height.max(height);
+                columns.fill(top + height + margin_bottom + gap_y);
+                *transform = Transform::new(width, height, top, content_left + margin_left);
+                continue;
+            }
+
+            if item.span().0 > 1 && n_columns > 1 {
+                let slot_width = 2 * column_width - gap_x;
+                let (width, height) = item.size_in_slot(slot_width, margins.1, caption_height);
+                self.max_item_height = self.max_item_height.max(height);
+                let (top, column_index) =
+                    columns.place_in_pair(n_columns, height + margins.0 + gap_y);
+                let left = content_left + column_index * column_width + margin_left;
+                *transform = Transform::new(width, height, top + margin_top, left);
+                has_wide_items = true;
+                continue;
+            }
+
+            let previous_column = previous_columns
+                .and_then(|previous| previous_column(transform, previous, gap_x))
+                .filter(|&previous_column| previous_column < n_columns);
+            let (top, column_index) = columns.stable_column(previous_column, self.column_stability);
+            let (width, height) = if item.has_margin() {
+                item.size_in_slot(item_width, margins.1, caption_height)
+            } else {
+                (item_width, heights[position - start] + caption_height)
+            };
+            self.max_item_height = self.max_item_height.max(height);
+            let left = content_left + column_index * column_width + margin_left;
+
+            // SAFETY: ColumnHeights::min_column returns a valid column index and the previous
+            // column is checked against the number of columns.
+            unsafe {
+                columns.set_min_column(column_index, top + margins.0 + height + gap_y);
+            }
+
+            *transform = Transform::new(width, height, top + margin_top, left);
+        }
+        let height = columns.max_height();
+        self.finish(
+            start,
+            MasonryType::Vertical,
+            input_width,
+            ResumeState::Vertical(columns),
+        );
+        if previous_columns.is_some() || has_wide_items || has_margins || unsorted_before.is_some()
+        {
+            // An item that stayed in a lower column, spans two columns or has a top margin starts
+            // below the items after it.
+            self.find_unsorted_from(start, unsorted_before);
+        }
+        let height = if self.flags.contains(LayoutFlags::BALANCE_COLUMNS) {
+            self.balance_trailing_items(input_width)
+        } else {
+            height
+        };
+        height + outer_padding
+    }
+
+    /// Returns the left of the first column and the width of the items in the previous
+    /// computation, whose transforms are still in place, if it was a vertical masonry layout as
+    /// well and the columns are kept stable.
+    fn previous_columns(&self) -> Option<(u32, u32)> {
+        match &self.resume {
+            Some(resume) if self.column_stability > 0 && resume.kind == MasonryType::Vertical => {
+                Some((self.content_left, self.resolved_thumbnail_size))
+            }
+            _ => None,
+        }
+    }
+
+    /// Sets `unsorted_from` to the first item from `start` on that starts above the item before
+    /// it, or to the given position before the start.
+    fn find_unsorted_from(&mut self, start: usize, unsorted_before: Option<usize>) {
+        let transforms = self.transforms.slice(..self.num_items);
+        let unsorted_from = unsorted_before.unwrap_or_else(|| {
+            (start.max(1)..transforms.len())
+                .find(|&position| transforms[position].top() < transforms[position - 1].top())
+                .unwrap_or(usize::MAX)
+        });
+        self.set_unsorted_from(unsorted_from);
+    }
+
+    /// Sets the position from which the tops are not sorted and indexes the items from there on
+    /// by their top.
+    fn set_unsorted_from(&mut self, position: usize) {
+        self.unsorted_from = position;
+        self.unsorted_index.clear();
+        if position < self.num_items {
+            let transforms = self.transforms.slice(..self.num_items);
+            self.unsorted_index
+                .extend(usize_to_u32(position)..usize_to_u32(self.num_items));
+            self.unsorted_index
+                .sort_unstable_by_key(|&position| transforms[position as usize].top());
+        }
+    }
+
+    /// Moves the last items of the vertical masonry layout to other columns so that the columns
+    /// end as evenly as possible and returns the new container height.
+    ///
+    /// A moved item can start above the item before it, so the tops are not sorted from there on
+    /// (see `unsorted_from`). Appended items are laid out again together with the moved items.
+    fn balance_trailing_items(&mut self, container_width: u32) -> u32 {
+        // Trying every combination quickly gets expensive, so the search stops after this many steps.
+        const MAX_STEPS: u32 = 10_000;
+
+        let (n_columns, column_width) = self.column_layout(container_width);
+        let (gap_x, gap_y) = (self.gap_x, self.gap_y);
+        let outer_padding = self.outer_padding;
+        let (content_left, _) = self.content_box(container_width);
+
+        // The visible items after the last item that spans or starts below all columns
+        let mut tail = Vec::new();
+        let mut tail_start = self.num_items;
+        for position in (0..self.num_items).rev() {
+            let item = self.ordered_items().get(position);
+            if item.is_hidden() {
+                continue;
+            }
+            if item.is_full_width()
+                || item.breaks_before()
+                || item.span().0 > 1
+                || item.has_margin()
+                || tail.len() == n_columns as usize
+            {
+                break;
+            }
+            tail.push(self.transforms[position].height() + gap_y);
+            tail_start = position;
+        }
+        tail.reverse();
+
+        let heights = self.column_heights_before(tail_start, container_width);
+        // Hidden items get the top of the item before them.
+        let mut top = self
+            .transforms
+            .slice(..tail_start)
+            .iter()
+            .rev()
+            .find(|transform| !transform.is_empty())
+            .map_or(outer_padding, Transform::top);
+        let mut search = ColumnSearch {
+            items: &tail,
+            columns: vec![0; tail.len()],
+            heights: heights.clone(),
+            best: Vec::new(),
+            best_spread: u32::MAX,
+            steps: MAX_STEPS,
+        };
+        search.run(0);
+        let columns = search.best;
+
+        let item_width = column_width - gap_x;
+        let mut heights = heights;
+        let mut tail_items = tail.iter().zip(columns);
+        for transform in self
+            .transforms
+            .iter_mut()
+            .take(self.num_items)
+            .skip(tail_start)
+        {
+            if transform.is_empty() {
+                *transform = Transform::new(0, 0, top, 0);
+                continue;
+            }
+            let (&height, column) = tail_items.next().unwrap_or_abort();
+            top = heights[column as usize];
+            heights[column as usize] += height;
+            let left = content_left + column * column_width;
+            *transform = Transform::new(item_width, height - gap_y, top, left);
+        }
+
+        self.set_unsorted_from(self.unsorted_from.min(tail_start));
+        self.crop_clamped_items(tail_start, MasonryType::Vertical);
+        // The moved items are laid out again when items are appended.
+        let columns = self.column_heights_at(tail_start, container_width);
+        if let Some(resume) = self.resume.as_mut() {
+            resume.num_items = tail_start;
+            resume.state = ResumeState::Vertical(columns);
+        }
+        heights.into_iter().max().unwrap_or(0)
+    }
+
+    // Simple Grid layout, replacement for the react-window dependency
+    pub fn compute_grid(&mut self, container_width: u32) -> u32 {
+        self.layout_grid(container_width, 0)
+    }
+
+    fn layout_grid(&mut self, input_width: u32, start: usize) -> u32 {
+        if self.flags.contains(LayoutFlags::COLUMN_MAJOR) {
+            return self.layout_grid_by_columns(input_width, start);
+        }
+        self.layout_cells(input_width, start, MasonryType::Grid)
+    }
+
+    /// Lays out the grid column by column from the start of the run that contains `start`, see
+    /// [`Layout::set_column_major()`].
+    ///
+    /// A run is filled from the top of its first column, so the tops are not sorted from its
+    /// second column on (see `unsorted_from`).
+    fn layout_grid_by_columns(&mut self, input_width: u32, start: usize) -> u32 {
+        // The items before the start keep their transforms.
+        let start = self.column_run_start(start);
+        let unsorted_before = Some(self.unsorted_from).filter(|&position| position < start);
+        self.resume = None;
+        if self.is_empty() || self.thumbnail_size == 0 {
+            return 0;
+        }
+
+        let (n_columns, column_width) = self.column_layout(input_width);
+        let gap_y = self.gap_y;
+        let (content_left, _) = self.content_box(input_width);
+        let item_size = column_width - self.gap_x;
+        let caption_height = self.caption_height;
+        let device_pixel_ratio = self.device_pixel_ratio;
+        let cell_height = self.cell_height(item_size) + caption_height;
+        let cells = ColumnCells {
+            n_columns,
+            column_width,
+            item_size,
+            cell_height,
+            left: content_left,
+            gap_y,
+        };
+        self.max_item_height = match start {
+            0 => cell_height,
+            _ => self.max_item_height.max(cell_height),
+        };
+
+        let full_width = n_columns * column_width - self.gap_x;
+        let mut top = self.top_below(start);
+        let mut run = Vec::new();
+        let items = OrderedItems {
+            items: &self.items,
+            order: self.order.as_ref(),
+        };
+        for position in start..self.num_items {
+            if is_cancelled_at(self.cancel_slot, position) {
+                return 0;
+            }
+
+            let item = items.get(position);
+            if item.is_hidden() {
+                self.transforms[position] = Transform::new(0, 0, top, 0);
+                continue;
+            }
+            if item.is_full_width() || item.breaks_before() {
+                top = cells.place(&mut self.transforms, &run, top);
+                run.clear();
+            }
+            if item.is_full_width() {
+                let height =
+                    item.full_width_height(full_width, device_pixel_ratio) + caption_height;
+                self.max_item_height = self.max_item_height.max(height);
+                self.transforms[position] = Transform::new(full_width, height, top, content_left);
+                top += height + gap_y;
+            } else {
+                run.push(position);
+            }
+        }
+        top = cells.place(&mut self.transforms, &run, top);
+        self.finish(start, MasonryType::Grid, input_width, ResumeState::Grid);
+        self.find_unsorted_from(start, unsorted_before);
+        top + self.outer_padding
+    }
+
+    /// Returns the first position of the run of the column-major grid that contains the given
+    /// position. A run starts after a visible item that spans the full width or at a visible item
+    /// that breaks before.
+    fn column_run_start(&self, position: usize) -> usize {
+        let items = self.ordered_items();
+        let starts_run = |position: usize| {
+            let item = items.get(position);
+            !item.is_hidden() && item.breaks_before()
+        };
+        let mut start = position.min(self.num_items);
+        while start > 0 && !(start < self.num_items && starts_run(start)) {
+            let previous = items.get(start - 1);
+            if !previous.is_hidden() && previous.is_full_width() {
+                break;
+            }
+            start -= 1;
+        }
+        start
+    }
+
+    /// Returns the top below the visible items before the given position, which are looked at
+    /// back to the last item that spans the full width, or the outer padding if there are none.
+    fn top_below(&self, end: usize) -> u32 {
+        let items = self.ordered_items();
+        let mut bottom = None;
+        for (position, transform) in self.transforms.slice(..end).iter().enumerate().rev() {
+            if transform.is_empty() {
+                continue;
+            }
+            bottom = bottom.max(Some(transform.bottom()));
+            if items.get(position).is_full_width() {
+                break;
+            }
+        }
+        bottom.map_or(self.outer_padding, |bottom| bottom + self.gap_y)
+    }
+
+    /// Lays out one item per row with the thumbnail size as row height, e.g. for a list view that
+    /// shows the metadata of every item next to its thumbnail.
+    ///
+    /// A row is as wide as the thumbnail size, the horizontal gap and the metadata width together,
+    /// or fills the content width if no metadata width is set. Its transform covers the whole row,
+    /// so that the queries find the item at any point of it. The thumbnail is not cropped, the
+    /// renderer places it in the row, e.g. in a square at its left edge.
+    pub fn compute_list(&mut self, container_width: u32) -> u32 {
+        self.layout_list(container_width, 0)
+    }
+
+    fn layout_list(&mut self, input_width: u32, start: usize) -> u32 {
+        self.resume = None;
+        if self.is_empty() || self.thumbnail_size == 0 {
+            return 0;
+        }
+
+        let (content_left, content_width) = self.content_box(input_width);
+        let gap_y = self.gap_y;
+        let outer_padding = self.outer_padding;
+        let caption_height = self.caption_height;
+        let row_width = match self.metadata_width {
+            0 => content_width,
+            metadata_width => {
+                (self.thumbnail_size + self.gap_x + metadata_width).min(content_width)
+            }
+        };
+        let row_height = self.thumbnail_size + caption_height;
+        // Items before the start keep their height, e.g. full-width items or other sections.
+        self.max_item_height = match start {
+            0 => row_height,
+            _ => self.max_item_height.max(row_height),
+        };
+
+        let items = OrderedItems {
+            items: &self.items,
+            order: self.order.as_ref(),
+        };
+        let device_pixel_ratio = self.device_pixel_ratio;
+        // Continue below the last visible item before the first item.
+        let mut top = self
+            .transforms
+            .slice(..start)
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, transform)| !transform.is_empty())
+            .map_or(outer_padding, |(position, transform)| {
+                let [_, _, margin_bottom, _] = items.get(position).margin(device_pixel_ratio);
+                transform.bottom() + margin_bottom + gap_y
+            });
+
+        let transforms = self.transforms.range_mut(start..self.num_items);
+        for (position, transform) in (start..).zip(transforms) {
+            if is_cancelled_at(self.cancel_slot, position) {
+                return 0;
+            }
+
+            let item = items.get(position);
+            if item.is_hidden() {
+                *transform = Transform::new(0, 0, top, 0);
+                continue;
+            }
+
+            let [margin_top, margin_right, margin_bottom, margin_left] =
+                item.margin(device_pixel_ratio);
+            let (width, height) = if item.is_full_width() {
+                let (width, height) = item.full_width_size(
+                    content_width,
+                    margin_left + margin_right,
+                    caption_height,
+                    device_pixel_ratio,
+                );
+                self.max_item_height = self.max_item_height.max(height);
+                (width, height)
+            } else {
+                let width = row_width.saturating_sub(margin_left + margin_right).max(1);
+                (width, row_height)
+            };
+            top += margin_top;
+            *transform = Transform::new(width, height, top, content_left + margin_left);
+            top += height + margin_bottom + gap_y;
+        }
+        self.finish(start, MasonryType::List, input_width, ResumeState::List);
+        top + outer_padding
+    }
+
+    /// Lays out the items in rows that are all as high as the thumbnail size, with every item as
+    /// wide as its aspect ratio needs, e.g. for videos that are scrubbed row by row.
+    ///
+    /// Unlike the horizontal layout, the rows are not scaled to fill the width, so they end
+    /// unevenly on the right and the rows are always the same distance apart, apart from items
+    /// that span the full width. An item that is wider than the content width is cropped to it.
+    pub fn compute_fixed_rows(&mut self, container_width: u32) -> u32 {
+        self.layout_fixed_rows(container_width, 0)
+    }
+
+    fn layout_fixed_rows(&mut self, input_width: u32, start: usize) -> u32 {
+        self.resume = None;
+        if self.is_empty() || self.thumbnail_size == 0 {
+            return 0;
+        }
+
+        let (content_left, content_width) = self.content_box(input_width);
+        let (gap_x, gap_y) = (self.gap_x, self.gap_y);
+        let outer_padding = self.outer_padding;
+        let caption_height = self.caption_height;
+        let device_pixel_ratio = self.device_pixel_ratio;
+        let thumbnail_size = self.thumbnail_size;
+        let row_height = thumbnail_size + caption_height + gap_y;
+        let content_right = content_left + content_width;
+        // Items before the start keep their height, e.g. full-width items or other sections.
+        self.max_item_height = match start {
+            0 => thumbnail_size + caption_height,
+            _ => self.max_item_height.max(thumbnail_size + caption_height),
+        };
+
+        let items = OrderedItems {
+            items: &self.items,
+            order: self.order.as_ref(),
+        };
+        // Continue next to the last visible item before the first item, or below it if it spans
+        // the full width.
+        let previous = self
+            .transforms
+            .slice(..start)
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, transform)| !transform.is_empty());
+        let (mut left, mut top) = match previous {
+            Some((position, transform)) if items.get(position).is_full_width() => {
+                (content_left, transform.bottom() + gap_y)
+            }
+            Some((_, transform)) => (transform.right() + gap_x, transform.top()),
+            None => (content_left, outer_padding),
+        };
+
+        let transforms = self.transforms.range_mut(start..self.num_items);
+        for (position, transform) in (start..).zip(transforms) {
+            if is_cancelled_at(self.cancel_slot, position) {
+                return 0;
+            }
+
+            let item = items.get(position);
+            if item.is_hidden() {
+                *transform = Transform::new(0, 0, top, 0);
+                continue;
+            }
+
+            let width = if item.is_full_width() {
+                content_width
+            } else {
+                item.aspect_ratio
+                    .correct_width(thumbnail_size)
+                    .min(content_width)
+            };
+            // Start a new row if the item does not fit or needs a row on its own.
+            if left > content_left
+                && (left + width > content_right || item.is_full_width() || item.breaks_before())
+            {
+                top += row_height;
+                left = content_left;
+            }
+
+            // width | height | top | left
+            if item.is_full_width() {
+                let height = item.full_width_height(width, device_pixel_ratio) + caption_height;
+                self.max_item_height = self.max_item_height.max(height);
+                *transform = Transform::new(width, height, top, left);
+                top += height + gap_y;
+            } else {
+                *transform = Transform::new(width, thumbnail_size + caption_height, top, left);
+                left += width + gap_x;
+            }
+        }
+        self.finish(
+            start,
+            MasonryType::FixedRows,
+            input_width,
+            ResumeState::FixedRows,
+        );
+        if left == content_left {
+            top + outer_padding
+        } else {
+            top + row_height + outer_padding
+        }
+    }
+
+    /// Lays out the items in a single row that is as high as the thumbnail size and grows to the
+    /// right, e.g. for the filmstrip below an image viewer, and returns its width instead of a
+    /// height.
+    ///
+    /// Every item is as wide as its aspect ratio needs and the items are the horizontal gap
+    /// apart. The container width is not used, and spans, breaks and full widths are ignored, so
+    /// section headers are items of the row like any other.
+    pub fn compute_filmstrip(&mut self, container_width: u32) -> u32 {
+        self.layout_filmstrip(container_width, 0)
+    }
+
+    fn layout_filmstrip(&mut self, input_width: u32, start: usize) -> u32 {
+        self.resume = None;
+        if self.is_empty() || self.thumbnail_size == 0 {
+            return 0;
+        }
+
+        let gap_x = self.gap_x;
+        let outer_padding = self.outer_padding;
+        let thumbnail_size = self.thumbnail_size;
+        let height = thumbnail_size + self.caption_height;
+        self.max_item_height = height;
+
+        // Continue to the right of the last visible item before the first item.
+        let mut left = self
+            .transforms
+            .slice(..start)
+            .iter()
+            .rev()
+            .find(|transform| !transform.is_empty())
+            .map_or(outer_padding, |transform| transform.right() + gap_x);
+        let mut right = left.saturating_sub(gap_x);
+
+        let items = OrderedItems {
+            items: &self.items,
+            order: self.order.as_ref(),
+        };
+        let transforms = self.transforms.range_mut(start..self.num_items);
+        for (position, transform) in (start..).zip(transforms) {
+            if is_cancelled_at(self.cancel_slot, position) {
+                return 0;
+            }
+
+            let item = items.get(position);
+            if item.is_hidden() {
+                *transform = Transform::new(0, 0, outer_padding, left);
+                continue;
+            }
+
+            // width | height | top | left
+            let width = item.aspect_ratio.correct_width(thumbnail_size);
+            *transform = Transform::new(width, height, outer_padding, left);
+            right = left + width;
+            left = right + gap_x;
+        }
+        self.finish(
+            start,
+            MasonryType::Filmstrip,
+            input_width,
+            ResumeState::Filmstrip,
+        );
+        right + outer_padding
+    }
+
+    /// Lays out the items in the square cells of a grid, with every few items a large cell that
+    /// spans two columns and two rows (see [`Layout::set_mosaic_period()`]).
+    ///
+    /// The items are packed row by row into the first free cell, so the small items fill the
+    /// cells next to and below the large ones and no holes are left. A large cell only goes where
+    /// it fits into the current row, otherwise the next item that fits gets it. Items that span
+    /// the full width or break before start below all large cells.
+    pub fn compute_mosaic(&mut self, container_width: u32) -> u32 {
+        self.layout_mosaic(container_width, 0)
+    }
+
+    fn layout_mosaic(&mut self, input_width: u32, start: usize) -> u32 {
+        self.layout_cells(input_width, start, MasonryType::Mosaic)
+    }
+
+    /// Lays out the items from `start` on in the cells of the grid or mosaic layout, continuing
+    /// next to the last visible item before it.
+    ///
+    /// The items are packed row by row into the first free cells. An item that spans two columns
+    /// but does not fit into the rest of the row leaves the cells before the next free pair empty.
+    fn layout_cells(&mut self, input_width: u32, start: usize, kind: MasonryType) -> u32 {
+        self.resume = None;
+        if self.is_empty() || self.thumbnail_size == 0 {
+            return 0;
+        }
+
+        let (n_columns, column_width) = self.column_layout(input_width);
+        let n_columns = n_columns as usize;
+        let (gap_x, gap_y) = (self.gap_x, self.gap_y);
+        let outer_padding = self.outer_padding;
+        let (content_left, _) = self.content_box(input_width);
+        let item_size = column_width - gap_x;
+        let caption_height = self.caption_height;
+        let device_pixel_ratio = self.device_pixel_ratio;
+        let cell_height = self.cell_height(item_size) + caption_height;
+        let row_height = cell_height + gap_y;
+        let period = match kind {
+            MasonryType::Mosaic => self.mosaic_period,
+            _ => 0,
+        };
+        // Items before the start keep their height, e.g. full-width items or other sections.
+        self.max_item_height = match start {
+            0 => cell_height,
+            _ => self.max_item_height.max(cell_height),
+        };
+
+        let full_width = usize_to_u32(n_columns) * column_width - gap_x;
+        let mut cursor = self.cell_cursor_before(start, input_width, period);
+        let items = OrderedItems {
+            items: &self.items,
+            order: self.order.as_ref(),
+        };
+        let transforms = self.transforms.range_mut(start..self.num_items);
+        for (position, transform) in (start..).zip(transforms) {
+            if is_cancelled_at(self.cancel_slot, position) {
+                return 0;
+            }
+
+            let item = items.get(position);
+            if item.is_hidden() {
+                *transform = Transform::new(0, 0, cursor.top, 0);
+                continue;
+            }
+
+            // width | height | top | left
+            if item.is_full_width() || item.breaks_before() {
+                cursor.close_rows(row_height);
+            }
+            if item.is_full_width() {
+                let height =
+                    item.full_width_height(full_width, device_pixel_ratio) + caption_height;
+                self.max_item_height = self.max_item_height.max(height);
+                *transform = Transform::new(full_width, height, cursor.top, content_left);
+                cursor.top += height + gap_y;
+                cursor.since_large = 0;
+                continue;
+            }
+
+            let (columns, rows) = match item.span() {
+                (_, rows) if n_columns < 2 => (1, rows),
+                (1, 1) if period > 0 && cursor.since_large + 1 >= period => {
+                    // The large cell goes to the next item if it does not fit here.
+                    cursor.skip_covered(row_height);
+                    if cursor.fits(2) {
+                        (2, 2)
+                    } else {
+                        (1, 1)
+                    }
+                }
+                span => span,
+            };
+            cursor.find_free(columns, row_height);
+            let column = cursor.column;
+            let left = content_left + usize_to_u32(column) * column_width;
+            let (width, height) = (
+                usize_to_u32(columns) * column_width - gap_x,
+                rows * row_height - gap_y,
+            );
+            self.max_item_height = self.max_item_height.max(height);
+            *transform = Transform::new(width, height, cursor.top, left);
+            if rows > 1 {
+                cursor.next_covered[column..column + columns].fill(true);
+            }
+            cursor.column += columns;
+            cursor.since_large = match (columns, rows) {
+                (1, 1) => cursor.since_large + 1,
+                _ => 0,
+            };
+        }
+        let height = cursor.bottom(row_height) + outer_padding;
+        let state = match kind {
+            MasonryType::Mosaic => ResumeState::Mosaic,
+            _ => ResumeState::Grid,
+        };
+        self.finish(start, kind, input_width, state);
+        height
+    }
+
+    /// Restores the free cells of the grid or mosaic layout after the last visible item before
+    /// the given position.
+    ///
+    /// The tops are sorted and no item spans more than two rows, so only the items of the row
+    /// above are looked at. With a period, the small items since the last large one are counted.
+    fn cell_cursor_before(&self, end: usize, container_width: u32, period: u32) -> CellCursor {
+        let (n_columns, column_width) = self.column_layout(container_width);
+        let (content_left, _) = self.content_box(container_width);
+        let gap_x = self.gap_x;
+        let item_size = column_width - gap_x;
+        let row_height = self.cell_height(item_size) + self.caption_height + self.gap_y;
+        let mut cursor = CellCursor::new(n_columns as usize, self.outer_padding);
+
+        let items = self.ordered_items();
+        let visible = || {
+            self.transforms
+                .slice(..end)
+                .iter()
+                .enumerate()
+                .rev()
+                .filter(|(_, transform)| !transform.is_empty())
+                .map(|(position, transform)| (items.get(position).is_full_width(), transform))
+        };
+        let last = match visible().next() {
+            Some((true, last)) => {
+                cursor.top = last.bottom() + self.gap_y;
+                return cursor;
+            }
+            Some((false, last)) => last,
+            None => return cursor,
+        };
+        cursor.top = last.top();
+        cursor.column = ((last.right() - content_left + gap_x) / column_width) as usize;
+        for (is_full_width, transform) in visible() {
+            if is_full_width || transform.top() + row_height < cursor.top {
+                break;
+            }
+            let first = ((transform.left() - content_left) / column_width) as usize;
+            let columns = first..first + ((transform.width() + gap_x) / column_width) as usize;
+            if transform.top() < cursor.top && transform.bottom() > cursor.top {
+                cursor.covered[columns].fill(true);
+            } else if transform.bottom() > cursor.top + row_height {
+                cursor.next_covered[columns].fill(true);
+            }
+        }
+        // Counting up to one period is enough to know whether the next item gets the large cell.
+        if period > 0 {
+            let small = visible()
+                .take(period as usize)
+                .take_while(|&(is_full_width, transform)| {
+                    !is_full_width
+                        && transform.width() == item_size
+                        && transform.height() < row_height
+                })
+                .count();
+            cursor.since_large = usize_to_u32(small);
+        }
+        cursor
+    }
+}
+
+impl Layout {
+    fn is_empty(&self) -> bool {
+        self.num_items == 0
+    }
+
+    fn ordered_items(&self) -> OrderedItems<'_> {
+        OrderedItems {
+            items: &self.items,
+            order: self.order.as_ref(),
+        }
+    }
+
+    fn mark_dirty(&mut self, index: usize) {
+        self.mark_dirty_until(index, usize::MAX);
+    }
+
+    /// Marks the positions from `index` up to `end` as dirty, where the items from `end` on kept
+    /// their position.
+    fn mark_dirty_until(&mut self, index: usize, end: usize) {
+        self.dirty_end = match self.first_dirty {
+            Some(_) => self.dirty_end.max(end),
+            None => end,
+        };
+        self.first_dirty = Some(self.first_dirty.map_or(index, |first| first.min(index)));
+        self.version = self.version.wrapping_add(1);
+    }
+
+    /// Lays out all items again in the next computation because a setting changed.
+    fn settings_changed(&mut self) {
+        if let Some(resume) = self.resume.as_mut() {
+            resume.state = ResumeState::Restart;
+        }
+        self.version = self.version.wrapping_add(1);
+    }
+
+    /// Marks the position of the item with the given index as dirty.
+    fn mark_item_dirty(&mut self, index: usize) {
+        let position = match &self.order {
+            Some(order) => order.position(index),
+            None => index,
+        };
+        self.mark_dirty_until(position, position + 1);
+    }
+
+    /// Sets a flag that affects the layout and marks the item as dirty if it changed.
+    fn set_flag(&mut self, index: usize, flag: u16, enabled: bool) {
+        if let Some(item) = self.items.get_mut(index) {
+            if item.flags.contains(flag) != enabled {
+                item.flags.set(flag, enabled);
+                self.mark_item_dirty(index);
+            }
+        }
+    }
+
+    /// Updates everything that is derived from the items before a computation.
+    fn prepare(&mut self) {
+        self.update_order();
+        self.update_cell_aspect_ratio();
+        self.computed_version = Some(self.version);
+        self.save_previous();
+        self.laid_out_from = usize::MAX;
+    }
+
+    /// Combines the requested order with the pinned items, which always come first.
+    fn update_order(&mut self) {
+        let has_pinned = self.items.iter().take(self.num_items).any(Item::is_pinned);
+        let ids = match (&self.requested_order, has_pinned) {
+            (None, false) => None,
+            (Some(ids), false) => Some(ids.clone()),
+            (requested, true) => {
+                let ids = requested
+                    .clone()
+                    .unwrap_or_else(|| (0..usize_to_u32(self.num_items)).collect());
+                let (mut pinned, rest): (Vec<u32>, Vec<u32>) = ids
+                    .into_iter()
+                    .partition(|&id| self.items.get(id as usize).map_or(false, Item::is_pinned));
+                pinned.extend(rest);
+                Some(pinned)
+            }
+        };
+
+        // Everything after the first item that moved has to be computed again.
+        let current = self.order.as_ref().map(|order| order.ids.as_slice());
+        if let Some(first_changed) = first_difference(current, ids.as_deref()) {
+            self.mark_dirty(first_changed);
+        }
+        self.order = ids.map(|ids| Order::new(ids, self.items.len()));
+    }
+
+    /// Updates the aspect ratio of the cells to the median of the measured items if it is used.
+    fn update_cell_aspect_ratio(&mut self) {
+        let current = match &self.cell_aspect_ratio {
+            Some(current) => current,
+            None => return,
+        };
+        let mut aspect_ratios: Vec<&AspectRatio> = self
+            .items
+            .iter()
+            .take(self.num_items)
+            .filter(|item| {
+                !item.is_hidden() && !item.is_full_width() && !item.source.is_unmeasured()
+            })
+            .map(|item| &item.aspect_ratio)
+            .collect();
+        let middle = aspect_ratios.len() / 2;
+        let median = match aspect_ratios.get(middle) {
+            Some(_) => {
+                aspect_ratios.select_nth_unstable_by(middle, |a, b| a.cmp_ratio(b));
+                aspect_ratios[middle].clone()
+            }
+            None => AspectRatio::SQUARE,
+        };
+        if median != *current {
+            self.cell_aspect_ratio = Some(median);
+            if let Some(resume) = self.resume.as_mut() {
+                resume.state = ResumeState::Restart;
+            }
+        }
+    }
+
+    /// Returns the height of a cell of the grid and mosaic layouts with the given width.
+    fn cell_height(&self, width: u32) -> u32 {
+        match &self.cell_aspect_ratio {
+            Some(aspect_ratio) => aspect_ratio.correct_height(width),
+            None => width,
+        }
+    }
+
+    fn save_previous(&mut self) {
+        if let Some(previous) = self.previous.as_mut() {
+            previous.clear();
+            previous.extend(self.transforms.slice(..self.num_items).iter().cloned());
+        }
+    }
+
+    /// Returns the breakpoint with the largest minimum width that fits into the container.
+    fn breakpoint(&self, container_width: u32) -> Option<Breakpoint> {
+        self.breakpoints
+            .iter()
+            .rev()
+            .find(|breakpoint| breakpoint.min_width <= container_width)
+            .copied()
+    }
+
+    /// Returns the gaps in CSS pixels for the container width, which are halved once if it is
+    /// narrower than the gap collapse width and again every time it is half as wide.
+    fn collapse_gaps(&self, container_width: u32, (gap_x, gap_y): (u32, u32)) -> (u32, u32) {
+        let collapse = |mut gap: u32| {
+            let mut width = self.gap_collapse_width;
+            while container_width < width && gap > MIN_COLLAPSED_GAP {
+                gap = (gap / 2).max(MIN_COLLAPSED_GAP);
+                width /= 2;
+            }
+            gap
+        };
+        (collapse(gap_x), collapse(gap_y))
+    }
+
+    /// Returns the minimum number of columns that is derived from the thumbnail size, which is
+    /// at least two in a narrow container.
+    fn min_column_count(&self, narrow: bool) -> u32 {
+        if narrow {
+            u32::from(self.min_columns).max(2)
+        } else {
+            u32::from(self.min_columns)
+        }
+    }
+
+    /// Returns the maximum height of a row in the horizontal layout.
+    fn row_height_cap(&self) -> u32 {
+        match self.max_row_height {
+            0 => u32::MAX,
+            max_row_height => self.device_pixels(max_row_height),
+        }
+    }
+
+    /// Returns whether a row of the greedy horizontal layout ends before the item that made it
+    /// overflow, given the width of the row without and with that item, because the row is
+    /// closer to the thumbnail size stretched than shrunk and within the row height tolerance.
+    fn ends_row_early(&self, without: u32, with: u32, max_width: u32) -> bool {
+        // An item that is wider than the container by itself has to shrink anyway.
+        if self.row_height_tolerance <= 0.0 || without == 0 || with - without > max_width {
+            return false;
+        }
+        let max_width = u32_to_f32(max_width);
+        let stretched = max_width / u32_to_f32(without) - 1.0;
+        let shrunk = 1.0 - max_width / u32_to_f32(with);
+        stretched <= self.row_height_tolerance && stretched < shrunk
+    }
+
+    /// Returns the left offset and the width of the area the items are laid out in, which is
+    /// centered in the container if it is wider than the maximum content width.
+    fn content_box(&self, container_width: u32) -> (u32, u32) {
+        let available = container_width.saturating_sub(2 * self.outer_padding);
+        let width = match self.max_content_width {
+            0 => available,
+            max_content_width => available.min(max_content_width),
+        };
+        let left = self.outer_padding + (available - width) / 2;
+        (left, width)
+    }
+
+    /// Returns the width available to the rows of the horizontal layout.
+    fn content_width(&self, container_width: u32) -> u32 {
+        self.content_box(container_width).1.max(self.thumbnail_size)
+    }
+
+    /// Returns the number of columns and their width for the vertical masonry and grid layout.
+    fn column_layout(&self, container_width: u32) -> (u32, u32) {
+        let (_, container_width) = self.content_box(container_width);
+        let column_count = match self.breakpoint_columns {
+            0 => u32::from(self.column_count),
+            columns => columns,
+        };
+        let (n_columns, container_width) = if column_count > 0 {
+            // The thumbnail size follows from the container width instead.
+            (column_count, container_width)
+        } else {
+            let padded_width = container_width.max(self.thumbnail_size);
+            // Every column needs at least one pixel next to its gap, even if the gap is wider
+            // than the thumbnails.
+            let fitting = (container_width / (self.gap_x + 1)).max(1);
+            let n_columns = padded_width.div_int(self.thumbnail_size).min(fitting);
+            let max_columns = match self.max_columns {
+                0 => u32::MAX,
+                max_columns => u32::from(max_columns),
+            };
+            let clamped = n_columns
+                .min(max_columns)
+                .max(self.min_column_count(self.flags.contains(LayoutFlags::NARROW)));
+            if clamped > n_columns {
+                // The columns have to be narrower than the thumbnail size anyway.
+                (clamped, container_width)
+            } else {
+                (clamped, padded_width)
+            }
+        };
+        // Rounded down, so the columns never overflow.
+        let column_width = if self.flags.contains(LayoutFlags::AUTO_FIT) {
+            // The last column has no gap after it.
+            (container_width + self.gap_x) / n_columns
+        } else {
+            container_width / n_columns
+        };
+        (n_columns, column_width.max(self.gap_x + 1))
+    }
+
+    /// Stores the state after all items from `start` on were computed.
+    fn finish(
+        &mut self,
+        start: usize,
+        kind: MasonryType,
+        container_width: u32,
+        state: ResumeState,
+    ) {
+        // Items before the start that changed in the meantime still need to be computed again.
+        self.first_dirty = self.first_dirty.filter(|&first| first < start);
+        self.laid_out_from = self.laid_out_from.min(start);
+        self.set_unsorted_from(usize::MAX);
+        self.content_left = self.content_box(container_width).0;
+        self.resolved_thumbnail_size = match kind {
+            MasonryType::Horizontal
+            | MasonryType::List
+            | MasonryType::FixedRows
+            | MasonryType::Filmstrip => self.thumbnail_size,
+            MasonryType::Vertical | MasonryType::Grid | MasonryType::Mosaic => {
+                self.column_layout(container_width).1 - self.gap_x
+            }
+        };
+        self.crop_clamped_items(start, kind);
+        self.resume = Some(Resume {
+            kind,
+            container_width,
+            num_items: self.num_items,
+            state,
+        });
+    }
+
+    /// Adds the crop of the items from `start` on that were laid out with another aspect ratio
+    /// than their thumbnail, i.e. items whose aspect ratio was clamped and the cells of the grid and
+    /// mosaic layouts.
+    fn crop_clamped_items(&mut self, start: usize, kind: MasonryType) {
+        // The rows of the list are wider than any thumbnail, which the renderer places in them.
+        if kind == MasonryType::List {
+            return;
+        }
+        let cell_width = self.resolved_thumbnail_size;
+        let cell_height = self.cell_height(cell_width) + self.caption_height;
+        let cell_aspect_ratio = self
+            .cell_aspect_ratio
+            .clone()
+            .unwrap_or(AspectRatio::SQUARE);
+        let items = OrderedItems {
+            items: &self.items,
+            order: self.order.as_ref(),
+        };
+        for (position, transform) in self
+            .transforms
+            .iter_mut()
+            .enumerate()
+            .take(self.num_items)
+            .skip(start)
+        {
+            let item = items.get(position);
+            // Placeholders of items that are not measured yet have nothing to crop.
+            if item.source.is_unmeasured() || transform.is_empty() {
+                continue;
+            }
+            let cell;
+            let laid_out = match kind {
+                MasonryType::Grid | MasonryType::Mosaic
+                    if !item.is_full_width()
+                        && (transform.width(), transform.height()) == (cell_width, cell_height) =>
+                {
+                    &cell_aspect_ratio
+                }
+                MasonryType::Grid | MasonryType::Mosaic if !item.is_full_width() => {
+                    // Items that span more than one cell.
+                    let height = transform.height() - self.caption_height;
+                    cell =
+                        AspectRatio::from_ratio(u32_to_f32(transform.width()) / u32_to_f32(height));
+                    &cell
+                }
+                // Items that are wider than the content are cut off at its width.
+                MasonryType::FixedRows
+                    if transform.width() < item.aspect_ratio.correct_width(self.thumbnail_size) =>
+                {
+                    let height = transform.height() - self.caption_height;
+                    cell =
+                        AspectRatio::from_ratio(u32_to_f32(transform.width()) / u32_to_f32(height));
+                    &cell
+                }
+                _ => &item.aspect_ratio,
+            };
+            if *laid_out != item.source {
+                transform.crop_to(laid_out, &item.source);
+            }
+        }
+    }
+
+    /// Restores the column heights of the vertical masonry layout before the item at `end`.
+    fn column_heights_at(
+        &self,
+        end: usize,
+        container_width: u32,
+    ) -> vertical_masonry::ColumnHeights {
+        let heights = self.column_heights_before(end, container_width);
+        let mut columns = vertical_masonry::ColumnHeights::new(heights.len());
+        for (index, height) in heights.into_iter().enumerate() {
+            // SAFETY: The index is smaller than the number of columns.
+            unsafe {
+                columns.set_min_column(usize_to_u32(index), height);
+            }
+        }
+        columns
+    }
+
+    /// Returns the height of every column of the vertical masonry layout before the given position.
+    fn column_heights_before(&self, end: usize, container_width: u32) -> Vec<u32> {
+        let (n_columns, column_width) = self.column_layout(container_width);
+        let gap_y = self.gap_y;
+        let outer_padding = self.outer_padding;
+        let (content_left, _) = self.content_box(container_width);
+        let mut heights = vec![outer_padding; n_columns as usize];
+        // Every column is at least as high as the top of the last item that starts below all.
+        let mut min_height = 0;
+        for (position, transform) in self.transforms.slice(..end).iter().enumerate() {
+            if transform.is_empty() {
+                continue;
+            }
+            let item = self.ordered_items().get(position);
+            let [margin_top, _, margin_bottom, _] = item.margin(self.device_pixel_ratio);
+            if item.breaks_before() {
+                min_height = transform.top() - margin_top;
+            }
+            // Full width items span all columns.
+            let first = ((transform.left() - content_left) / column_width) as usize;
+            let last = ((transform.right() - content_left - 1) / column_width) as usize;
+            for height in heights.iter_mut().take(last + 1).skip(first) {
+                *height = (*height).max(transform.bottom() + margin_bottom + gap_y);
+            }
+        }
+
+        for height in &mut heights {
+            *height = (*height).max(min_height);
+        }
+        heights
+    }
+}
+
+impl PartialEq for Transform {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_array() == other.to_array() && self.1 == other.1
+    }
+}
+
+impl Transform {
+    /// The number of lanes of a transform in exported transforms.
+    pub const LANES: usize = 7;
+
+    fn new(width: u32, height: u32, top: u32, left: u32) -> Transform {
+        Transform(U32x4::new(width, height, top, left), Crop::default())
+    }
+
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.0.get::<0>()
+    }
+
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.0.get::<1>()
+    }
+
+    #[must_use]
+    pub fn top(&self) -> u32 {
+        self.0.get::<2>()
+    }
+
+    #[must_use]
+    pub fn left(&self) -> u32 {
+        self.0.get::<3>()
+    }
+
+    #[must_use]
+    pub fn bottom(&self) -> u32 {
+        self.top() + self.height()
+    }
+
+    #[must_use]
+    pub fn right(&self) -> u32 {
+        self.left() + self.width()
+    }
+
+    /// Returns true for items that are not laid out, e.g. because they are hidden.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.width() == 0 || self.height() == 0
+    }
+
+    /// Returns the lanes as width, height, top and left.
+    #[must_use]
+    pub fn to_array(&self) -> [u32; 4] {
+        self.0.to_array()
+    }
+
+    /// Returns width, height, top, left, crop top, crop left and 1 if the item is cropped.
+    #[must_use]
+    pub fn to_lanes(&self) -> [u32; Transform::LANES] {
+        let [width, height, top, left] = self.to_array();
+        let Crop {
+            top: crop_top,
+            left: crop_left,
+            cropped,
+        } = self.1;
+        [width, height, top, left, crop_top, crop_left, cropped]
+    }
+
+    /// Reads a transform from the lanes returned by [`Transform::to_lanes()`].
+    fn from_lanes(lanes: &[u32]) -> Transform {
+        let mut transform = Transform::new(lanes[0], lanes[1], lanes[2], lanes[3]);
+        transform.1 = Crop {
+            top: lanes[4],
+            left: lanes[5],
+            cropped: lanes[6],
+        };
+        transform
+    }
+
+    /// Returns how far the thumbnail is shifted up inside the rectangle when it is cropped.
+    ///
+    /// A cropped thumbnail is scaled to cover the rectangle and centered, so it overhangs the
+    /// rectangle by this offset at the top and roughly as much at the bottom.
+    #[must_use]
+    pub fn crop_top(&self) -> u32 {
+        self.1.top
+    }
+
+    /// Returns how far the thumbnail is shifted left inside the rectangle when it is cropped.
+    ///
+    /// The thumbnail overhangs the rectangle by this offset on the left and roughly as much on the
+    /// right, so it is about `width + 2 * crop_left` wide.
+    #[must_use]
+    pub fn crop_left(&self) -> u32 {
+        self.1.left
+    }
+
+    /// Returns true if part of the thumbnail is cut off.
+    #[must_use]
+    pub fn is_cropped(&self) -> bool {
+        self.1.cropped != 0
+    }
+
+    /// Returns the width, height, top and left of the transform that lies the fraction `t`
+    /// between this transform at 0 and the other one at 1, e.g. to animate between two layouts.
+    ///
+    /// `t` is clamped to the range 0 to 1. If one of them is empty, e.g. because the item is only
+    /// hidden in one of the layouts, the other one is returned as it is.
+    #[must_use]
+    pub fn interpolate(&self, other: &Transform, t: f32) -> [f32; 4] {
+        let (from, to) = match (self.is_empty(), other.is_empty()) {
+            (true, false) => (other, other),
+            (false, true) => (self, self),
+            _ => (self, other),
+        };
+        let t = t.clamp(0.0, 1.0);
+        let (from, to) = (from.to_array(), to.to_array());
+        [0, 1, 2, 3].map(|lane| {
+            u32_to_f32(from[lane]) + (u32_to_f32(to[lane]) - u32_to_f32(from[lane])) * t
+        })
+    }
+
+    /// Cuts off the top and bottom equally so the item is at most as high as the given height.
+    fn crop_height(&mut self, max_height: u32) {
+        let height = self.height();
+        if height > max_height {
+            self.0 = self.0.set::<1>(max_height);
+            self.1.top = (height - max_height) / 2;
+            self.1.cropped = 1;
+        }
+    }
+
+    /// Cuts off the sides of an item that was laid out with the `clamped` aspect ratio instead of
+    /// its `source` aspect ratio, on top of any crop of its height.
+    fn crop_to(&mut self, clamped: &AspectRatio, source: &AspectRatio) {
+        let width = self.width();
+        // The height before it was cropped
+        let height = clamped.correct_height(width);
+        let source_width = source.correct_width(height);
+        let source_height = source.correct_height(width);
+        if source_width > width {
+            self.1.left = (source_width - width) / 2;
+        } else if source_height > height {
+            self.1.top += (source_height - height) / 2;
+        }
+        self.1.cropped = 1;
+    }
+}
+
+/// Converts a length in CSS pixels to whole physical pixels.
+fn device_pixels(length: u32, device_pixel_ratio: f32) -> u32 {
+    f64_to_u32(f64::from(length) * f64::from(device_pixel_ratio) + 0.5)
+}
+
+/// Returns the column of an item in the previous vertical masonry layout, given the left of its
+/// first column and the width of its items, or `None` if the item had another width.
+fn previous_column(transform: &Transform, (left, width): (u32, u32), gap: u32) -> Option<u32> {
+    if transform.width() != width {
+        return None;
+    }
+    Some(transform.left().checked_sub(left)? / (width + gap))
+}
+
+/// Splits a run of items with the given widths into rows and returns the index of the first item
+/// of every row. The items of a row are `gap` apart.
+///
+/// The rows are chosen so that the sum of the squared differences between their scaled height and
+/// the thumbnail size is minimal. The last row is not scaled if it is not full, so it has no cost.
+fn partition_rows(items: &[(usize, u32)], max_width: u32, height: u32, gap: u32) -> Vec<usize> {
+    // Rows that would be less than half as high are not worth considering.
+    let max_row_width = 2 * max_width;
+    let target = f64::from(height);
+
+    // The lowest cost for the first items and the index where their last row starts. The costs
+    // add up over the whole run, so they are summed in f64 to keep them precise for huge runs.
+    let mut costs = vec![0.0_f64; items.len() + 1];
+    let mut starts = vec![0; items.len() + 1];
+    for end in 1..=items.len() {
+        let mut best = (f64::INFINITY, end - 1);
+        let mut row_width = 0;
+        for start in (0..end).rev() {
+            row_width += items[start].1 + gap;
+            let cost = if end == items.len() && row_width <= max_width {
+                0.0
+            } else {
+                let deviation = target * f64::from(max_width) / f64::from(row_width) - target;
+                deviation * deviation
+            };
+            if costs[start] + cost < best.0 {
+                best = (costs[start] + cost, start);
+            }
+            if row_width > max_row_width {
+                break;
+            }
+        }
+        costs[end] = best.0;
+        starts[end] = best.1;
+    }
+
+    let mut row_starts = Vec::new();
+    let mut end = items.len();
+    while end > 0 {
+        end = starts[end];
+        row_starts.push(end);
+    }
+    row_starts.reverse();
+    row_starts
+}
+
+/// Depth-first search for the columns of the last items of the vertical masonry layout with the
+/// smallest difference between the highest and the lowest column.
+struct ColumnSearch<'a> {
+    /// Height of every item including the gap below it
+    items: &'a [u32],
+    /// Column of every item on the current path
+    columns: Vec<u32>,
+    heights: Vec<u32>,
+    best: Vec<u32>,
+    best_spread: u32,
+    steps: u32,
+}
+
+impl ColumnSearch<'_> {
+    fn run(&mut self, index: usize) {
+        let height = match self.items.get(index) {
+            Some(&height) => height,
+            None => {
+                let max = self.heights.iter().max().copied().unwrap_or(0);
+                let min = self.heights.iter().min().copied().unwrap_or(0);
+                if max - min < self.best_spread {
+                    self.best_spread = max - min;
+                    self.best.clone_from(&self.columns);
+                }
+                return;
+            }
+        };
+        if self.steps == 0 {
+            return;
+        }
+        self.steps -= 1;
+
+        // Shortest columns first, so the first path is the one the layout took.
+        let mut candidates: Vec<u32> = (0..usize_to_u32(self.heights.len())).collect();
+        candidates.sort_by_key(|&column| self.heights[column as usize]);
+        for column in candidates {
+            let top = self.heights[column as usize];
+            self.heights[column as usize] = top + height;
+            self.columns[index] = column;
+            self.run(index + 1);
+            self.heights[column as usize] = top;
+        }
+    }
+}
+
+/// Scales the width, height and left of the items in a row of the horizontal layout, which starts
+/// at the given left offset.
+fn scale_row<'a>(
+    row: impl Iterator<Item = &'a mut Transform>,
+    factor: f32,
+    row_left: u32,
+    rounding: RoundingMode,
+) {
+    if rounding == RoundingMode::Accumulate {
+        for transform in row {
+            let left = u32_to_f32(transform.left().saturating_sub(row_left)) * factor;
+            let right = left + u32_to_f32(transform.width()) * factor;
+            let (left, right) = (f32_to_u32(left + 0.5), f32_to_u32(right + 0.5));
+            let height = f32_to_u32(u32_to_f32(transform.height()) * factor + 0.5);
+            transform.0 = U32x4::new(right - left, height, transform.top(), row_left + left);
+        }
+        return;
+    }
+    // width | height | top | left
+    let factor = F32x4::from(factor).set::<2>(1.0); // Do not scale top
+    let half = match rounding {
+        RoundingMode::Round => F32x4::from(0.5).set::<2>(0.0),
+        _ => F32x4::from(0.0),
+    };
+    let offset = U32x4::new(0, 0, 0, row_left);
+    for transform in row {
+        let relative = transform
+            .0
+            .set::<3>(transform.left().saturating_sub(row_left));
+        transform.0 = U32x4::from(F32x4::from(relative) * factor + half);
+        transform.0 += offset;
+    }
+}
+
+/// Caps the height of the items in a row of the horizontal layout and reserves the space for the
+/// captions below them.
+fn fit_row<'a>(row: impl Iterator<Item = &'a mut Transform>, max_height: u32, caption_height: u32) {
+    for transform in row {
+        transform.crop_height(max_height);
+        transform.0 = transform.0.set::<1>(transform.height() + caption_height);
+    }
+}
+
+impl CellCursor {
+    fn new(n_columns: usize, top: u32) -> CellCursor {
+        CellCursor {
+            top,
+            column: 0,
+            covered: vec![false; n_columns],
+            next_covered: vec![false; n_columns],
+            since_large: 0,
+        }
+    }
+
+    /// Moves the cursor to the first free cell, in the next row if the current one is full.
+    fn skip_covered(&mut self, row_height: u32) {
+        loop {
+            while self.covered.get(self.column) == Some(&true) {
+                self.column += 1;
+            }
+            if self.column < self.covered.len() {
+                return;
+            }
+            self.next_row(row_height);
+        }
+    }
+
+    /// Moves the cursor to the first free cell that has the given number of free columns from
+    /// there on, which are at most all columns.
+    fn find_free(&mut self, columns: usize, row_height: u32) {
+        loop {
+            self.skip_covered(row_height);
+            if self.fits(columns) {
+                return;
+            }
+            self.column += 1;
+        }
+    }
+
+    /// Returns true if the given number of columns from the cursor on are free.
+    fn fits(&self, columns: usize) -> bool {
+        self.covered
+            .get(self.column..self.column + columns)
+            .map_or(false, |cells| !cells.contains(&true))
+    }
+
+    /// Moves the cursor below all items, so that the next one starts a new row.
+    fn close_rows(&mut self, row_height: u32) {
+        while self.column > 0 || self.covered.contains(&true) {
+            self.next_row(row_height);
+        }
+    }
+
+    fn next_row(&mut self, row_height: u32) {
+        self.top += row_height;
+        self.column = 0;
+        core::mem::swap(&mut self.covered, &mut self.next_covered);
+        self.next_covered.fill(false);
+    }
+
+    /// Returns the bottom of the items including the gap after them.
+    fn bottom(&self, row_height: u32) -> u32 {
+        let rows = if self.next_covered.contains(&true) {
+            2
+        } else {
+            u32::from(self.column > 0 || self.covered.contains(&true))
+        };
+        self.top + rows * row_height
+    }
+}
+
+impl ColumnCells {
+    /// Places the items at the given positions column by column in as few rows as they fit into,
+    /// starting at the given top, and returns the top below them.
+    fn place(&self, transforms: &mut ChunkedVec<Transform>, run: &[usize], top: u32) -> u32 {
+        let rows = (usize_to_u32(run.len()) + self.n_columns - 1) / self.n_columns;
+        for (index, &position) in run.iter().enumerate() {
+            let (column, row) = (usize_to_u32(index) / rows, usize_to_u32(index) % rows);
+            transforms[position] = Transform::new(
+                self.item_size,
+                self.cell_height,
+                top + row * (self.cell_height + self.gap_y),
+                self.left + column * self.column_width,
+            );
+        }
+        top + rows * (self.cell_height + self.gap_y)
+    }
+}
+
+impl Order {
+    fn new(ids: Vec<u32>, num_items: usize) -> Order {
+        let mut positions = vec![u32::MAX; num_items];
+        for (position, &id) in ids.iter().enumerate() {
+            if let Some(slot) = positions.get_mut(id as usize) {
+                *slot = usize_to_u32(position);
+            }
+        }
+        Order { ids, positions }
+    }
+
+    fn position(&self, id: usize) -> usize {
+        match self.positions.get(id) {
+            Some(&position) if position != u32::MAX => position as usize,
+            _ => id,
+        }
+    }
+}
+
+impl<'a> OrderedItems<'a> {
+    /// Returns the side of every item in the range of positions that results from scaling it to
+    /// the given length, e.g. the heights at a width. `sides` returns the side to compute and the
+    /// side of the length. The sides are at least 1, e.g. for wide items in narrow columns. Four
+    /// items are divided at once, and big ranges are split into chunks for the chunk runner.
+    fn scaled_sides(
+        &self,
+        chunk_runner: Option<ChunkRunner>,
+        positions: Range<usize>,
+        length: u32,
+        sides: impl Fn(&AspectRatio) -> (u32, u32) + Sync,
+    ) -> Vec<u32> {
+        let mut scaled = vec![0; positions.len()];
+        if let Some(run_chunks) = chunk_runner.filter(|_| scaled.len() > CHUNK_SIZE) {
+            let chunks = (scaled.len() + CHUNK_SIZE - 1) / CHUNK_SIZE;
+            let (len, output) = (scaled.len(), scaled.as_mut_ptr() as usize);
+            run_chunks(chunks, &|chunk| {
+                let start = chunk * CHUNK_SIZE;
+                let end = (start + CHUNK_SIZE).min(len);
+                // SAFETY: Every chunk writes to its own part of the output.
+                let output = unsafe {
+                    core::slice::from_raw_parts_mut((output as *mut u32).add(start), end - start)
+                };
+                let start = positions.start + start;
+                self.scale_sides_into(start..start + output.len(), length, &sides, output);
+            });
+            return scaled;
+        }
+        self.scale_sides_into(positions, length, &sides, &mut scaled);
+        scaled
+    }
+
+    fn scale_sides_into(
+        &self,
+        positions: Range<usize>,
+        length: u32,
+        sides: &impl Fn(&AspectRatio) -> (u32, u32),
+        output: &mut [u32],
+    ) {
+        let mut positions = positions.peekable();
+        let mut output = output.iter_mut();
+        while positions.peek().is_some() {
+            let (mut dividends, mut divisors) = ([0; 4], [1; 4]);
+            for (lane, position) in positions.by_ref().take(4).enumerate() {
+                let (side, other_side) = sides(&self.get(position).aspect_ratio);
+                dividends[lane] = length * side;
+                divisors[lane] = other_side;
+            }
+            let [a0, a1, a2, a3] = dividends;
+            let [b0, b1, b2, b3] = divisors;
+            let quotients = U32x4::new(a0, a1, a2, a3).div_round(U32x4::new(b0, b1, b2, b3));
+            for (quotient, slot) in quotients.to_array().into_iter().zip(output.by_ref()) {
+                *slot = quotient.max(1);
+            }
+        }
+    }
+
+    fn index(&self, position: usize) -> usize {
+        match self.order.and_then(|order| order.ids.get(position)) {
+            Some(&id) if (id as usize) < self.items.len() => id as usize,
+            _ => position,
+        }
+    }
+
+    fn get(&self, position: usize) -> &'a Item {
+        self.items.get(self.index(position)).unwrap_or_abort()
+    }
+}
+
+impl Item {
+    fn is_hidden(&self) -> bool {
+        self.flags.contains(ItemFlags::HIDDEN)
+    }
+
+    fn is_pinned(&self) -> bool {
+        self.flags.contains(ItemFlags::PINNED)
+    }
+
+    /// Returns the number of columns and rows the item spans.
+    fn span(&self) -> (usize, u32) {
+        (
+            1 + usize::from(self.flags.contains(ItemFlags::WIDE)),
+            1 + u32::from(self.flags.contains(ItemFlags::TALL)),
+        )
+    }
+
+    fn is_full_width(&self) -> bool {
+        self.flags
+            .contains(ItemFlags::FULL_WIDTH | ItemFlags::PANORAMA | ItemFlags::FOCUSED)
+    }
+
+    /// Returns true for items that were explicitly marked as full width, unlike panoramas.
+    fn is_section_header(&self) -> bool {
+        self.flags.contains(ItemFlags::FULL_WIDTH)
+    }
+
+    fn breaks_before(&self) -> bool {
+        self.flags.contains(ItemFlags::BREAK_BEFORE)
+    }
+
+    fn has_margin(&self) -> bool {
+        self.margin != [0; 4]
+    }
+
+    /// Returns the width and height of the item in a slot of the given width, which it fills apart
+    /// from its left and right margin. Margins that reach the slot width still leave a content box
+    /// of at least 1 by 1 pixels.
+    fn size_in_slot(&self, slot_width: u32, margin: u32, caption_height: u32) -> (u32, u32) {
+        let width = slot_width.saturating_sub(margin).max(1);
+        (
+            width,
+            self.aspect_ratio.correct_height(width).max(1) + caption_height,
+        )
+    }
+
+    /// Returns the width and height of a full-width item in a slot of the given width, like
+    /// [`Item::size_in_slot()`] but with the fixed height if there is one.
+    fn full_width_size(
+        &self,
+        slot_width: u32,
+        margin: u32,
+        caption_height: u32,
+        device_pixel_ratio: f32,
+    ) -> (u32, u32) {
+        let width = slot_width.saturating_sub(margin).max(1);
+        (
+            width,
+            self.full_width_height(width, device_pixel_ratio).max(1) + caption_height,
+        )
+    }
+
+    /// Returns the height of a full-width item of the given width without the caption.
+    fn full_width_height(&self, width: u32, device_pixel_ratio: f32) -> u32 {
+        match self.height {
+            0 => self.aspect_ratio.correct_height(width),
+            height => device_pixels(u32::from(height), device_pixel_ratio),
+        }
+    }
+
+    /// Returns the top, right, bottom and left margin in physical pixels.
+    fn margin(&self, device_pixel_ratio: f32) -> [u32; 4] {
+        self.margin
+            .map(|side| device_pixels(u32::from(side), device_pixel_ratio))
+    }
+
+    /// Returns an item whose dimension is not known yet.
+    fn unmeasured(default_aspect_ratio: &AspectRatio) -> Item {
+        Item {
+            aspect_ratio: default_aspect_ratio.clone(),
+            source: AspectRatio::default(),
+            flags: ItemFlags::default(),
+            section_kind: None,
+            margin: [0; 4],
+            height: 0,
+            tag: 0,
+        }
+    }
+
+    /// Derives the aspect ratio the item is laid out with from its source aspect ratio.
+    fn update_aspect_ratio(
+        &mut self,
+        limits: AspectRatioLimits,
+        panorama_aspect_ratio: f32,
+        default_aspect_ratio: &AspectRatio,
+    ) {
+        if self.source.is_unmeasured() {
+            self.flags.set(ItemFlags::PANORAMA, false);
+            self.aspect_ratio = default_aspect_ratio.clamp(limits);
+            return;
+        }
+        let panorama = panorama_aspect_ratio > 0.0
+            && self.source.width > self.source.height
+            && f32::from(self.source.width) > panorama_aspect_ratio * f32::from(self.source.height);
+        self.flags.set(ItemFlags::PANORAMA, panorama);
+        // Panoramas get enough space to be shown completely.
+        self.aspect_ratio = if panorama {
+            self.source.clone()
+        } else {
+            self.source.clamp(limits)
+        };
+    }
+}
+
+impl ItemFlags {
+    const HIDDEN: u16 = 1;
+    const PINNED: u16 = 1 << 1;
+    const FULL_WIDTH: u16 = 1 << 2;
+    const BREAK_BEFORE: u16 = 1 << 3;
+    /// Set for items that are wider than the panorama aspect ratio.
+    const PANORAMA: u16 = 1 << 4;
+    /// Set for items whose dimension is swapped because of their orientation.
+    const ROTATED: u16 = 1 << 5;
+    /// Set for items that span two columns.
+    const WIDE: u16 = 1 << 6;
+    /// Set for items that span two rows of the grid and mosaic layouts.
+    const TALL: u16 = 1 << 7;
+    /// Set for the item that is enlarged in place, see [`Layout::set_focus()`].
+    const FOCUSED: u16 = 1 << 8;
+
+    fn contains(self, flag: u16) -> bool {
+        self.0 & flag != 0
+    }
+
+    fn set(&mut self, flag: u16, enabled: bool) {
+        if enabled {
+            self.0 |= flag;
+        } else {
+            self.0 &= !flag;
+        }
+    }
+}
+
+impl LayoutFlags {
+    /// Set if the container of the most recent computation was narrower than the gap collapse
+    /// width, which keeps at least two columns.
+    const NARROW: u16 = 1;
+    /// Set if the columns are sized so that they span the full width without a trailing gap.
+    const AUTO_FIT: u16 = 1 << 1;
+    /// Set if the last items of the vertical masonry layout are moved to even out the columns.
+    const BALANCE_COLUMNS: u16 = 1 << 2;
+    /// Set if the grid is filled column by column instead of row by row.
+    const COLUMN_MAJOR: u16 = 1 << 3;
+    /// Set if the items are laid out bottom-up, see `compute_reversed()`.
+    const REVERSED: u16 = 1 << 4;
+    /// Set if the most recent computation consisted of sections of different kinds.
+    const HAS_SECTIONS: u16 = 1 << 5;
+    /// Set if the rows of the horizontal layout are balanced instead of filled one by one.
+    const BALANCE_ROWS: u16 = 1 << 6;
+    /// Set if a single item in the last row of the horizontal layout gets company from the row
+    /// before it.
+    const AVOID_WIDOWS: u16 = 1 << 7;
+
+    fn contains(self, flag: u16) -> bool {
+        self.0 & flag != 0
+    }
+
+    fn set(&mut self, flag: u16, enabled: bool) {
+        if enabled {
+            self.0 |= flag;
+        } else {
+            self.0 &= !flag;
+        }
+    }
+
+    /// Sets the flag like [`LayoutFlags::set()`] and returns whether it was set before.
+    fn replace(&mut self, flag: u16, enabled: bool) -> bool {
+        let previous = self.contains(flag);
+        self.set(flag, enabled);
+        previous
+    }
+
+    /// Returns the flags without the given one.
+    fn without(mut self, flag: u16) -> LayoutFlags {
+        self.set(flag, false);
+        self
+    }
+}
+
+impl AspectRatio {
+    const SQUARE: AspectRatio = AspectRatio {
+        width: 1,
+        height: 1,
+    };
+
+    /// Returns the aspect ratio for a width / height ratio, or a square one if it is not positive.
+    fn from_ratio(ratio: f32) -> AspectRatio {
+        if ratio > 1.0 {
+            AspectRatio {
+                width: 100,
+                height: round_side(100.0 / ratio),
+            }
+        } else if ratio > 0.0 && ratio < 1.0 {
+            AspectRatio {
+                width: round_side(100.0 * ratio),
+                height: 100,
+            }
+        } else {
+            AspectRatio::SQUARE
+        }
+    }
+
+    fn is_unmeasured(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Compares the width / height ratios.
+    fn cmp_ratio(&self, other: &AspectRatio) -> cmp::Ordering {
+        (self.width() * other.height()).cmp(&(other.width() * self.height()))
+    }
+
+    fn set(&mut self, src_width: u16, src_height: u16) {
+        let (width, height) = correct_aspect_ratio(src_width, src_height);
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Returns the aspect ratio within the limits.
+    fn clamp(&self, limits: AspectRatioLimits) -> AspectRatio {
+        let (mut width, mut height) = (self.width, self.height);
+        match width.cmp(&height) {
+            cmp::Ordering::Greater => height = height.max(limits.min_height),
+            cmp::Ordering::Less => width = width.max(limits.min_width),
+            cmp::Ordering::Equal => {}
+        }
+        AspectRatio { width, height }
+    }
+
+    fn width(&self) -> u32 {
+        u32::from(self.width)
+    }
+
+    fn height(&self) -> u32 {
+        u32::from(self.height)
+    }
+
+    fn correct_width(&self, height: u32) -> u32 {
+        (height * self.width()).div_int(self.height())
+    }
+
+    fn correct_height(&self, width: u32) -> u32 {
+        (width * self.height()).div_int(self.width())
+    }
+}
+
+impl AspectRatioLimits {
+    /// At most 3 times as wide as high or vice versa
+    const DEFAULT: AspectRatioLimits = AspectRatioLimits {
+        min_width: 100 / 3,
+        min_height: 100 / 3,
+    };
+
+    fn new(min_aspect_ratio: f32, max_aspect_ratio: f32) -> AspectRatioLimits {
+        AspectRatioLimits {
+            min_width: round_side(100.0 * min_aspect_ratio),
+            min_height: if max_aspect_ratio > 0.0 {
+                round_side(100.0 / max_aspect_ratio)
+            } else {
+                1
+            },
+        }
+    }
+}
+
+/// Rounds a side relative to a longer side of 100 and keeps it visible.
+fn round_side(side: f32) -> u8 {
+    if side > 1.0 {
+        u8::try_from(f32_to_u32(side.min(100.0) + 0.5)).unwrap_or(100)
+    } else {
+        1
+    }
+}
+
+/// Returns the first position at which two orders differ. A missing order or position keeps the
+/// item with the same index.
+fn first_difference(a: Option<&[u32]>, b: Option<&[u32]>) -> Option<usize> {
+    let id = |order: Option<&[u32]>, position: usize| {
+        order
+            .and_then(|ids| ids.get(position))
+            .map_or(position, |&id| id as usize)
+    };
+    let len = a.map_or(0, <[u32]>::len).max(b.map_or(0, <[u32]>::len));
+    (0..len).find(|&position| id(a, position) != id(b, position))
+}
+
+// Scales the longer side to 100, so the aspect ratio fits into two bytes. Images with extreme
+// aspect ratios are clamped later (see `AspectRatioLimits`), but the shorter side is at least 1
+// so that it can be divided by. Without a width or height the image is not measured yet.
+fn correct_aspect_ratio(w: u16, h: u16) -> (u8, u8) {
+    if w == 0 || h == 0 {
+        (0, 0)
+    } else if w > h {
+        let height = u8::try_from((100 * u32::from(h)).div_int(u32::from(w)).max(1)).unwrap_or(100);
+        (100, height)
+    } else if h > w {
+        let width = u8::try_from((100 * u32::from(w)).div_int(u32::from(h)).max(1)).unwrap_or(100);
+        (width, 100)
+    } else {
+        (1, 1)
+    }
+}
+
+trait DivInt<Rhs = Self> {
+    type Output;
+
+    fn div_int(self, rhs: Rhs) -> Self::Output;
+}
+
+impl DivInt for u16 {
+    type Output = Self;
+
+    #[inline]
+    fn div_int(self, rhs: Self) -> Self::Output {
+        (self.saturating_add(rhs >> 1)) / rhs
+    }
+}
+
+impl DivInt for u32 {
+    type Output = Self;
+
+    #[inline]
+    fn div_int(self, rhs: Self) -> Self::Output {
+        (self.saturating_add(rhs >> 1)) / rhs
+    }
+}
+
+/// <http://0x80.pl/notesen/2018-10-03-simd-index-of-min.html>
+mod vertical_masonry {
+    use alloc::{boxed::Box, vec};
+    use core::ptr;
+
+    use crate::convert::usize_to_u32;
+    use crate::util::UnwrapOrAbort;
+
+    use crate::packed::U32x4;
+
+    type Mask = U32x4;
+
+    #[derive(Clone)]
+    pub struct ColumnHeights {
+        heights: Box<[U32x4]>,
+        padding_mask: Mask,
+    }
+
+    impl ColumnHeights {
+        pub fn new(columns: usize) -> Self {
+            // If the number of columns cannot be divided by 4, it is padded with u32::MAX.
+            // This way it won't effect the search in Self::min_index().
+            let rest = columns % 4;
+            let (len, padding_mask) = if rest == 0 {
+                (columns / 4, U32x4::ZERO)
+            } else {
+                (
+                    (columns / 4) + 1,
+                    U32x4::from(usize_to_u32(rest)).less_than(U32x4::new(1, 2, 3, 4)),
+                )
+            };
+            Self {
+                heights: {
+                    let mut heights = vec![U32x4::ZERO; len].into_boxed_slice();
+                    *heights.last_mut().unwrap_or_abort() = padding_mask;
+                    heights
+                },
+                padding_mask,
+            }
+        }
+
+        /// Returns the shortest column as (value, index) pair.
+        pub fn min_column(&self) -> (u32, u32) {
+            let (&first, heights) = self.heights.split_first().unwrap_or_abort();
+
+            let mut indices = U32x4::new(0, 1, 2, 3);
+            let increment = U32x4::from(4);
+
+            let mut min_values = first;
+            let mut min_indices = U32x4::new(0, 1, 2, 3);
+
+            for values in heights {
+                indices += increment;
+
+                // compare
+                let less: Mask = values.less_than(min_values);
+
+                // update
+                min_values = values.min(min_values);
+                min_indices = indices.blend(min_indices, less);
+            }
+
+            min_values
+                .to_array()
+                .into_iter()
+                .zip(min_indices.to_array())
+                .min_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)))
+                .unwrap_or_abort()
+        }
+
+        /// Returns the height of the column with the given index.
+        pub fn get(&self, index: u32) -> u32 {
+            let index = index as usize;
+            self.heights[index / 4].to_array()[index % 4]
+        }
+
+        /// # Safety
+        ///
+        /// The index must smaller than the total number of columns, otherwise this will result in undefined behaviour.
+        pub unsafe fn set_min_column(&mut self, index: u32, value: u32) {
+            // SAFETY: This only works because the layout of a U32x4 is [u32; 4].
+            // If the index is out of bounds, chaos will fall upon us but this should
+            // never happen because the passed index is the shortest column index.
+            let height_ptr = self.heights.as_mut_ptr().cast::<u32>();
+            ptr::write(height_ptr.add(index as usize), value);
+        }
+
+        /// Places an item with the given height in the two neighboring columns that end highest
+        /// together and returns its top and the index of the first one.
+        pub fn place_in_pair(&mut self, columns: u32, height: u32) -> (u32, u32) {
+            let (top, index) = (0..columns - 1)
+                .map(|index| (self.get(index).max(self.get(index + 1)), index))
+                .min()
+                .unwrap_or_abort();
+            // SAFETY: Both indices are smaller than the number of columns.
+            unsafe {
+                self.set_min_column(index, top + height);
+                self.set_min_column(index + 1, top + height);
+            }
+            (top, index)
+        }
+
+        /// Returns the shortest column as (value, index) pair, or the given previous column of the
+        /// item if it ends at most `stability` below the shortest one.
+        pub fn stable_column(&self, previous_column: Option<u32>, stability: u32) -> (u32, u32) {
+            let (top, column_index) = self.min_column();
+            match previous_column {
+                Some(previous_column) if self.get(previous_column) <= top + stability => {
+                    (self.get(previous_column), previous_column)
+                }
+                _ => (top, column_index),
+            }
+        }
+
+        /// Sets all columns to the same height.
+        pub fn fill(&mut self, value: u32) {
+            for heights in self.heights.iter_mut() {
+                *heights = U32x4::from(value);
+            }
+            // Keep the padding columns at u32::MAX (see Self::new()).
+            let last = self.heights.last_mut().unwrap_or_abort();
+            *last = self.padding_mask.blend(*last, self.padding_mask);
+        }
+
+        pub fn max_height(&self) -> u32 {
+            // Ignore padding columns or Self::max_height() will always return u32::MAX (see Self::new()).
+            let (&last, heights) = self.heights.split_last().unwrap_or_abort();
+            let last = U32x4::ZERO.blend(last, self.padding_mask);
+
+            heights
+                .iter()
+                .fold(last, |max, &x| max.max(x))
+                .to_array()
+                .into_iter()
+                .max()
+                .unwrap_or_abort()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Layout;
+    use crate::data::{MasonryConfig, MasonryType};
+    use alloc::vec::Vec;
+
+    fn layout(dimensions: &[(u16, u16)]) -> Layout {
+        let mut layout = Layout::new(dimensions.len(), 100, 10);
+        for (index, &(width, height)) in dimensions.iter().enumerate() {
+            layout.set_dimension(index, width, height);
+        }
+        layout
+    }
+
+    fn dimensions(num_items: u16) -> Vec<(u16, u16)> {
+        (0..num_items)
+            .map(|i| (100 + i * 13, 100 + i * 7))
+            .collect()
+    }
+
+    fn transforms(layout: &Layout, range: core::ops::Range<usize>) -> Vec<[u32; 4]> {
+        range
+            .map(|index| layout.get_transform(index).unwrap().to_array())
+            .collect()
+    }
+
+    #[test]
+    fn prepend_without_insert() {
+        let mut layout = layout(&dimensions(10));
+        let config = MasonryConfig::new(MasonryType::Grid, 100, 10);
+        let height = layout.compute(550, &config);
+        assert_eq!(layout.compute_prepended(3), None);
+        assert_eq!(layout.compute_appended(), height);
+    }
+
+    #[test]
+    fn prepend_more_than_inserted() {
+        let mut layout = layout(&dimensions(10));
+        let config = MasonryConfig::new(MasonryType::Vertical, 100, 10);
+        layout.compute(550, &config);
+        layout.insert(0, 2);
+        assert_eq!(layout.prepended_items(), 2);
+        assert_eq!(layout.compute_prepended(3), None);
+        assert!(layout.compute_prepended(2).unwrap() > 0);
+    }
+
+    #[test]
+    fn prepend_to_filmstrip() {
+        let all = dimensions(20);
+        let config = MasonryConfig::new(MasonryType::Filmstrip, 100, 10);
+        let mut full = layout(&all);
+        let full_width = full.compute(550, &config);
+        let mut prepended = layout(&all[5..]);
+        let width = prepended.compute(550, &config);
+        prepended.insert(0, 5);
+        for (index, &(width, height)) in all[..5].iter().enumerate() {
+            prepended.set_dimension(index, width, height);
+        }
+        let offset = prepended.compute_prepended(5).unwrap();
+        assert_eq!(width + offset, full_width);
+        assert_eq!(transforms(&prepended, 0..20), transforms(&full, 0..20));
+    }
+
+    #[test]
+    fn reversed_filmstrip() {
+        let all = dimensions(20);
+        let config = MasonryConfig::new(MasonryType::Filmstrip, 100, 10);
+        let mut forward = layout(&all);
+        let mut reversed = layout(&all);
+        reversed.set_reversed(true);
+        assert_eq!(
+            reversed.compute(550, &config),
+            forward.compute(550, &config)
+        );
+        assert_eq!(transforms(&reversed, 0..20), transforms(&forward, 0..20));
+    }
+}
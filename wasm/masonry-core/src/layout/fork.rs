@@ -0,0 +1,181 @@
+//! Copies of a layout that are computed with another config, e.g. the neighboring zoom levels in
+//! the background, so that the layout can switch to them without computing them again.
+use alloc::vec::Vec;
+use core::mem;
+
+use super::{ChunkedVec, Layout, LayoutFlags, Transform};
+
+impl Layout {
+    /// Returns a copy of the items and settings without the computed transforms, e.g. to compute
+    /// the layout with another thumbnail size on another thread while this one stays readable.
+    ///
+    /// The copy is computed like any layout and taken over with [`Layout::adopt()`]. Changes are
+    /// not tracked, the transforms are not double buffered and the recorded changes are not
+    /// copied.
+    #[must_use]
+    pub fn fork(&mut self) -> Layout {
+        // Combine the pinned items with the requested order now, so that the next computation of
+        // this layout does not count the combined order as a change.
+        self.update_order();
+        Layout {
+            num_items: self.num_items,
+            transforms: ChunkedVec::from_elem(Transform::default(), self.num_items),
+            items: self.items.clone(),
+            thumbnail_size: self.thumbnail_size,
+            gap_x: self.gap_x,
+            gap_y: self.gap_y,
+            outer_padding: self.outer_padding,
+            caption_height: self.caption_height,
+            metadata_width: self.metadata_width,
+            max_content_width: self.max_content_width,
+            content_left: self.content_left,
+            device_pixel_ratio: self.device_pixel_ratio,
+            max_row_height: self.max_row_height,
+            column_count: self.column_count,
+            min_columns: self.min_columns,
+            max_columns: self.max_columns,
+            breakpoints: self.breakpoints.clone(),
+            breakpoint_columns: self.breakpoint_columns,
+            gap_collapse_width: self.gap_collapse_width,
+            flags: self.flags.without(LayoutFlags::HAS_SECTIONS),
+            resolved_thumbnail_size: self.resolved_thumbnail_size,
+            last_row: self.last_row,
+            rounding: self.rounding,
+            aspect_ratio_limits: self.aspect_ratio_limits,
+            panorama_aspect_ratio: self.panorama_aspect_ratio,
+            default_aspect_ratio: self.default_aspect_ratio.clone(),
+            row_height_tolerance: self.row_height_tolerance,
+            column_stability: self.column_stability,
+            mosaic_period: self.mosaic_period,
+            cell_aspect_ratio: self.cell_aspect_ratio.clone(),
+            max_item_height: 0,
+            unsorted_from: usize::MAX,
+            unsorted_index: Vec::new(),
+            resume: None,
+            first_dirty: None,
+            dirty_end: usize::MAX,
+            converge: None,
+            laid_out_from: usize::MAX,
+            previous: None,
+            published: None,
+            float_view: None,
+            // The order is derived from the requested order by the computation of the copy.
+            order: None,
+            requested_order: self.requested_order.clone(),
+            focus: self.focus,
+            chunk_runner: self.chunk_runner,
+            cancel_slot: self.cancel_slot,
+            history: None,
+            version: 0,
+            forked_from: Some(self.version),
+            computed_version: None,
+        }
+    }
+
+    /// Returns a copy like [`Layout::fork()`] that keeps the transforms and the state of the most
+    /// recent computation, e.g. to cache them and switch back to them with [`Layout::adopt()`]
+    /// later, or `None` if the items or settings changed since or it was not computed to the end.
+    pub fn fork_computed(&mut self) -> Option<Layout> {
+        if !self.is_up_to_date() {
+            return None;
+        }
+        let mut fork = self.fork();
+        fork.transforms = self.transforms.clone();
+        fork.copy_state(self);
+        fork.resume.clone_from(&self.resume);
+        Some(fork)
+    }
+
+    /// Returns true if the most recent computation laid out all items and neither the items nor
+    /// the settings changed since, so that computing again with the same config and width would
+    /// give the same transforms.
+    #[must_use]
+    pub fn is_up_to_date(&mut self) -> bool {
+        self.update_order();
+        self.computed_version == Some(self.version) && self.is_computed()
+    }
+
+    /// Returns true if the copy was returned by [`Layout::fork()`] of this layout and the items
+    /// and settings did not change since.
+    #[must_use]
+    pub fn is_fork_current(&self, fork: &Layout) -> bool {
+        fork.forked_from == Some(self.version)
+    }
+
+    /// Takes over the transforms and the state of a copy returned by [`Layout::fork()`] that was
+    /// computed to the end, and returns whether it was taken over.
+    ///
+    /// Nothing changes if the items or settings of this layout changed since the copy was made or
+    /// the computation of the copy was cancelled. Afterwards this layout is in the state of the
+    /// computation of the copy, so appended and incremental computations continue from there,
+    /// and the copy has to be computed again before it can be taken over another time.
+    pub fn adopt(&mut self, fork: &mut Layout) -> bool {
+        self.update_order();
+        if !self.is_fork_current(fork) || !fork.is_computed() {
+            return false;
+        }
+
+        self.prepare();
+        mem::swap(&mut self.transforms, &mut fork.transforms);
+        self.copy_state(fork);
+        self.resume = fork.resume.take();
+        // The copy laid out every item, including the ones that changed before it was made.
+        self.first_dirty = None;
+        self.laid_out_from = 0;
+        true
+    }
+
+    /// Returns the transform of the item the fraction `t` between its transform in this layout at 0
+    /// and in the copy at 1, see [`Transform::interpolate()`], or `None` if the index is out of
+    /// bounds.
+    ///
+    /// This animates e.g. a pinch zoom continuously between the layouts of two thumbnail sizes.
+    /// The transform of this layout is returned as it is if the copy was not returned by
+    /// [`Layout::fork()`] of this layout, the items or settings changed since or the copy was not
+    /// computed to the end.
+    #[must_use]
+    pub fn interpolate_transform(&self, fork: &Layout, index: usize, t: f32) -> Option<[f32; 4]> {
+        if index >= self.num_items {
+            return None;
+        }
+        let from = self.get_transform(index)?;
+        let to = if self.is_fork_current(fork) && fork.is_computed() {
+            fork.get_transform(index).unwrap_or(from)
+        } else {
+            from
+        };
+        Some(from.interpolate(to, t))
+    }
+
+    /// Returns true if the most recent computation laid out all items and was not cancelled.
+    fn is_computed(&self) -> bool {
+        matches!(&self.resume, Some(resume) if resume.num_items == self.num_items)
+    }
+
+    /// Copies the settings that the most recent computation of the other layout derived from its
+    /// config and the state it left, apart from the transforms and the state to resume from.
+    fn copy_state(&mut self, other: &Layout) {
+        self.thumbnail_size = other.thumbnail_size;
+        self.gap_x = other.gap_x;
+        self.gap_y = other.gap_y;
+        self.outer_padding = other.outer_padding;
+        self.caption_height = other.caption_height;
+        self.metadata_width = other.metadata_width;
+        self.max_content_width = other.max_content_width;
+        self.content_left = other.content_left;
+        self.device_pixel_ratio = other.device_pixel_ratio;
+        self.breakpoint_columns = other.breakpoint_columns;
+        self.flags.set(
+            LayoutFlags::NARROW,
+            other.flags.contains(LayoutFlags::NARROW),
+        );
+        self.resolved_thumbnail_size = other.resolved_thumbnail_size;
+        self.flags.set(
+            LayoutFlags::HAS_SECTIONS,
+            other.flags.contains(LayoutFlags::HAS_SECTIONS),
+        );
+        self.max_item_height = other.max_item_height;
+        self.unsorted_from = other.unsorted_from;
+        self.unsorted_index.clone_from(&other.unsorted_index);
+    }
+}
@@ -0,0 +1,210 @@
+//! An optional log of the changes to the items, so that the application can undo destructive
+//! operations, e.g. removing items from the gallery, without keeping a copy of the items itself.
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::mem;
+
+use super::{Item, Layout};
+
+/// A change to the items, which is the inverse of the change that was recorded.
+pub(super) enum Operation {
+    /// Puts back the item at the index, e.g. with its previous dimension.
+    Replace { index: usize, item: Item },
+    /// Inserts the items at the index.
+    Insert { index: usize, items: Vec<Item> },
+    /// Removes `count` items at the index.
+    Remove { index: usize, count: usize },
+    /// Changes the item count and puts back the items after the previous count.
+    Resize { len: usize, items: Vec<Item> },
+    /// Moves `count` items starting at `from` so that they start at `to` afterwards.
+    Move {
+        from: usize,
+        count: usize,
+        to: usize,
+    },
+    /// Replaces the requested order.
+    Order(Option<Vec<u32>>),
+}
+
+/// The operations that undo and redo the most recent changes.
+pub(super) struct History {
+    /// At most this many operations are kept, the oldest ones are forgotten first.
+    limit: usize,
+    undo: VecDeque<Operation>,
+    redo: Vec<Operation>,
+}
+
+impl Layout {
+    /// Records the changes of the dimensions, the item count, the order and the positions of the
+    /// items, so that the most recent ones can be undone with [`Layout::undo()`].
+    ///
+    /// At most `limit` changes are kept. Every removed item is kept until its removal is
+    /// forgotten. A limit of 0 disables the log, which is the default.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        if limit == 0 {
+            self.history = None;
+            return;
+        }
+        let history = self.history.get_or_insert_with(|| History {
+            limit,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        });
+        history.limit = limit;
+        history.undo.truncate(limit);
+        history.redo.truncate(limit);
+    }
+
+    /// Forgets all recorded changes, e.g. because the items were replaced by other ones.
+    pub fn clear_history(&mut self) {
+        if let Some(history) = self.history.as_mut() {
+            history.undo.clear();
+            history.redo.clear();
+        }
+    }
+
+    /// Reverts the most recent recorded change and returns whether there was one.
+    ///
+    /// The reverted items are marked as dirty like after the change itself, so the layout has to
+    /// be computed again, e.g. with [`Layout::compute_incremental()`].
+    pub fn undo(&mut self) -> bool {
+        let operation = match self
+            .history
+            .as_mut()
+            .and_then(|history| history.undo.pop_front())
+        {
+            Some(operation) => operation,
+            None => return false,
+        };
+        let inverse = self.apply(operation);
+        if let Some(history) = self.history.as_mut() {
+            history.redo.push(inverse);
+        }
+        true
+    }
+
+    /// Applies the most recently undone change again and returns whether there was one.
+    ///
+    /// Any other change after [`Layout::undo()`] forgets the undone changes.
+    pub fn redo(&mut self) -> bool {
+        let operation = match self.history.as_mut().and_then(|history| history.redo.pop()) {
+            Some(operation) => operation,
+            None => return false,
+        };
+        let inverse = self.apply(operation);
+        if let Some(history) = self.history.as_mut() {
+            history.push(inverse);
+        }
+        true
+    }
+
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        self.history
+            .as_ref()
+            .map_or(false, |history| !history.undo.is_empty())
+    }
+
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        self.history
+            .as_ref()
+            .map_or(false, |history| !history.redo.is_empty())
+    }
+
+    /// Records the operation that reverts a change, if the log is enabled.
+    pub(super) fn record(&mut self, inverse: impl FnOnce(&Layout) -> Operation) {
+        if self.history.is_some() {
+            let operation = inverse(self);
+            if let Some(history) = self.history.as_mut() {
+                history.redo.clear();
+                history.push(operation);
+            }
+        }
+    }
+
+    /// Applies an operation without recording it and returns the operation that reverts it.
+    fn apply(&mut self, operation: Operation) -> Operation {
+        match operation {
+            Operation::Replace { index, mut item } => {
+                self.derive_aspect_ratio(&mut item);
+                let previous = mem::replace(&mut self.items[index], item);
+                self.mark_item_dirty(index);
+                Operation::Replace {
+                    index,
+                    item: previous,
+                }
+            }
+            Operation::Insert { index, items } => {
+                let count = items.len();
+                self.insert_items(index, count);
+                self.put_items(index, items);
+                Operation::Remove { index, count }
+            }
+            Operation::Remove { index, count } => {
+                let items = self
+                    .items
+                    .slice(index..index + count)
+                    .iter()
+                    .cloned()
+                    .collect();
+                self.remove_items(index, count);
+                Operation::Insert { index, items }
+            }
+            Operation::Resize { len, items } => {
+                let old_len = self.num_items;
+                let removed = self
+                    .items
+                    .slice(len.min(old_len)..old_len)
+                    .iter()
+                    .cloned()
+                    .collect();
+                self.resize_items(len);
+                if len > old_len {
+                    self.put_items(old_len, items);
+                    self.mark_dirty(old_len);
+                }
+                Operation::Resize {
+                    len: old_len,
+                    items: removed,
+                }
+            }
+            Operation::Move { from, count, to } => {
+                self.move_range(from, count, to);
+                Operation::Move {
+                    from: to,
+                    count,
+                    to: from,
+                }
+            }
+            Operation::Order(ids) => Operation::Order(mem::replace(&mut self.requested_order, ids)),
+        }
+    }
+
+    /// Puts back items starting at the index. Their aspect ratios are derived again, because the
+    /// limits could have changed in the meantime.
+    fn put_items(&mut self, index: usize, items: Vec<Item>) {
+        for (position, mut item) in (index..).zip(items) {
+            self.derive_aspect_ratio(&mut item);
+            self.items[position] = item;
+        }
+    }
+
+    fn derive_aspect_ratio(&self, item: &mut Item) {
+        item.update_aspect_ratio(
+            self.aspect_ratio_limits,
+            self.panorama_aspect_ratio,
+            &self.default_aspect_ratio,
+        );
+    }
+}
+
+impl History {
+    /// Adds an operation that undoes a change and forgets the oldest one if the log is full.
+    fn push(&mut self, operation: Operation) {
+        if self.undo.len() == self.limit {
+            self.undo.pop_back();
+        }
+        self.undo.push_front(operation);
+    }
+}
@@ -0,0 +1,202 @@
+//! Randomized checks of the invariants of all layout kinds, e.g. to fuzz the algorithms in a
+//! scheduled job whenever a new kind lands.
+//!
+//! Every iteration generates items and a config from its own seed and computes them with every
+//! kind. The failures are returned as counterexamples with everything needed to reproduce them,
+//! so the check can run for a long time and report all of them at the end.
+use alloc::vec::Vec;
+
+use super::{Layout, Transform};
+use crate::data::{MasonryConfig, MasonryType, Violation, ViolationKind};
+use crate::util::UnwrapOrAbort;
+
+/// The kinds that every iteration computes.
+const KINDS: [MasonryType; 7] = [
+    MasonryType::Vertical,
+    MasonryType::Horizontal,
+    MasonryType::Grid,
+    MasonryType::List,
+    MasonryType::Mosaic,
+    MasonryType::FixedRows,
+    MasonryType::Filmstrip,
+];
+
+/// How a computation breaks an invariant.
+#[derive(Clone, Copy)]
+pub enum Failure {
+    /// An item overlaps another one, lies outside of the container or has no size, see
+    /// [`Layout::validate()`].
+    Violation(Violation),
+    /// The returned container size does not fit the items: `extent` is the bottom of the lowest
+    /// item plus the vertical gap, or the right edge of the rightmost item in the filmstrip, plus
+    /// the outer padding.
+    Height { height: u32, extent: u32 },
+}
+
+/// A computation that broke an invariant.
+pub struct Counterexample {
+    /// Seed of the iteration, which [`check()`] with a single iteration repeats.
+    pub seed: u64,
+    pub container_width: u32,
+    pub config: MasonryConfig,
+    /// The width and height of every item, where 0 is an unknown dimension.
+    pub dimensions: Vec<(u16, u16)>,
+    pub failure: Failure,
+}
+
+/// Computes every layout kind with random items and configs for the given number of iterations
+/// and returns the computations that break an invariant.
+///
+/// No two visible items may overlap, every visible item must have a size and lie inside of the
+/// container, apart from the filmstrip, which scrolls sideways, and the returned container height
+/// must reach exactly to the lowest item plus the vertical gap and the outer padding. Configs that
+/// [`Layout::check_config()`] rejects are skipped, and the containers fit at least one thumbnail
+/// next to the outer padding. The same seed always generates the same iterations.
+#[must_use]
+pub fn check(seed: u64, iterations: u32) -> Vec<Counterexample> {
+    let mut random = Random::new(seed);
+    let mut counterexamples = Vec::new();
+    for _ in 0..iterations {
+        let seed = random.next();
+        counterexamples.extend(check_iteration(seed));
+    }
+    counterexamples
+}
+
+/// Computes every kind with the items and config of the seed.
+fn check_iteration(seed: u64) -> Vec<Counterexample> {
+    let mut random = Random::new(seed);
+    let dimensions = random_dimensions(&mut random);
+    let config = MasonryConfig::new(
+        MasonryType::Vertical,
+        20 + random.below(400),
+        random.below(24),
+    )
+    .with_gaps(random.below(24), random.below(24), random.below(24))
+    .with_caption_height(if random.below(4) == 0 {
+        random.below(40)
+    } else {
+        0
+    })
+    .with_device_pixel_ratio([1.0, 1.5, 2.0][random.below(3) as usize]);
+    // Narrower containers cannot fit a single thumbnail, so every item would overflow.
+    let container_width = config.thumbnail_size + 2 * config.outer_padding + random.below(2000);
+
+    let mut layout = Layout::new(dimensions.len(), config.thumbnail_size, config.gap_x);
+    for (index, &(width, height)) in dimensions.iter().enumerate() {
+        layout.set_dimension(index, width, height);
+    }
+    let mut counterexamples = Vec::new();
+    for kind in KINDS {
+        let config = MasonryConfig { kind, ..config };
+        if layout.check_config(container_width, &config).is_err() {
+            continue;
+        }
+        let height = layout.compute(container_width, &config);
+        if let Some(failure) = layout.find_failure(container_width, kind, height) {
+            counterexamples.push(Counterexample {
+                seed,
+                container_width,
+                config,
+                dimensions: dimensions.clone(),
+                failure,
+            });
+        }
+    }
+    counterexamples
+}
+
+/// Returns up to a few hundred items, some of them with unknown dimensions or extreme aspect
+/// ratios.
+fn random_dimensions(random: &mut Random) -> Vec<(u16, u16)> {
+    let count = random.below(300) as usize;
+    (0..count)
+        .map(|_| match random.below(20) {
+            0 => (0, 0),
+            1 => (1 + random.side(20), 2000 + random.side(4000)),
+            2 => (2000 + random.side(4000), 1 + random.side(20)),
+            _ => (1 + random.side(6000), 1 + random.side(6000)),
+        })
+        .collect()
+}
+
+impl Layout {
+    /// Returns the first invariant that the most recent computation of the kind breaks.
+    fn find_failure(
+        &self,
+        container_width: u32,
+        kind: MasonryType,
+        height: u32,
+    ) -> Option<Failure> {
+        let filmstrip = kind == MasonryType::Filmstrip;
+        let violation = self
+            .validate(container_width)
+            .into_iter()
+            .find(|violation| !(filmstrip && violation.kind == ViolationKind::Overflow));
+        if let Some(violation) = violation {
+            return Some(Failure::Violation(violation));
+        }
+        let visible = self
+            .computed()
+            .iter()
+            .filter(|transform| !transform.is_empty());
+        // Every row ends with the vertical gap, including the last one.
+        let edge = if filmstrip {
+            visible.map(Transform::right).max()
+        } else {
+            visible
+                .map(|transform| transform.bottom() + self.gap_y)
+                .max()
+        };
+        let extent = edge? + self.outer_padding;
+        if height == extent {
+            None
+        } else {
+            Some(Failure::Height { height, extent })
+        }
+    }
+}
+
+/// A xorshift generator, which is good enough to pick parameters and keeps the crate free of
+/// dependencies.
+struct Random(u64);
+
+impl Random {
+    fn new(seed: u64) -> Random {
+        // The state must not be 0, or it stays 0.
+        Random(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a number from 0 up to the bound, which must not be 0.
+    fn below(&mut self, bound: u32) -> u32 {
+        u32::try_from(self.next() % u64::from(bound)).unwrap_or_abort()
+    }
+
+    /// Returns a side of an image from 0 up to the bound, which must not be 0.
+    fn side(&mut self, bound: u16) -> u16 {
+        u16::try_from(self.below(u32::from(bound))).unwrap_or_abort()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::check;
+
+    #[test]
+    fn no_counterexamples() {
+        let counterexamples = check(0x5EED, 200);
+        assert!(
+            counterexamples.is_empty(),
+            "{} counterexamples, the first with seed {}",
+            counterexamples.len(),
+            counterexamples[0].seed
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Splitting the most recent computation into pages of a fixed height, e.g. to print or export a
+//! contact sheet, or over several containers, e.g. the panes of a compare view.
+//!
+//! The pages are cut between the items rather than reflowing them: every page starts at the top
+//! of the first item that would cross the bottom of the page before, so the items below it move
+//! to the next page together and keep their columns and rows.
+use alloc::vec::Vec;
+
+use super::{Layout, Transform, U32x4};
+use crate::convert::usize_to_u32;
+
+/// The items of every page and their transforms relative to the top of their page, see
+/// [`Layout::paginate()`].
+pub struct Pages {
+    /// Offsets into `items` and `transforms` at which the pages start.
+    starts: Vec<usize>,
+    /// Height of every page up to the bottom of its lowest item and the outer padding.
+    heights: Vec<u32>,
+    /// Positions of the items, page by page and in reading order within a page.
+    items: Vec<u32>,
+    transforms: Vec<Transform>,
+}
+
+impl Pages {
+    /// Returns the number of pages.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// Returns the positions of the items on the page and their transforms relative to the top
+    /// of the page, or `None` if there are fewer pages.
+    #[must_use]
+    pub fn page(&self, page: usize) -> Option<(&[u32], &[Transform])> {
+        let start = *self.starts.get(page)?;
+        let end = self
+            .starts
+            .get(page + 1)
+            .copied()
+            .unwrap_or(self.items.len());
+        Some((&self.items[start..end], &self.transforms[start..end]))
+    }
+
+    /// Returns the height of the content of the page, from its top to the bottom of its lowest
+    /// item followed by the outer padding, e.g. for the height of a container, or `None` if
+    /// there are fewer pages.
+    #[must_use]
+    pub fn height(&self, page: usize) -> Option<u32> {
+        self.heights.get(page).copied()
+    }
+}
+
+impl Layout {
+    /// Splits the most recent computation into pages of the given height, so that no item
+    /// straddles a page break, e.g. for printing.
+    ///
+    /// Every page after the first starts with the outer padding, like the first one, followed by
+    /// the items that did not fit on the page before. Only items that are higher than a page by
+    /// themselves cross the bottom of their page. Hidden items are on no page.
+    #[must_use]
+    pub fn paginate(&self, page_height: u32) -> Pages {
+        let entries = self.reading_entries();
+        let breaks = self.page_breaks(&entries, page_height);
+        self.collect_pages(entries, &breaks)
+    }
+
+    /// Distributes the most recent computation over the given number of containers of the same
+    /// width, e.g. the panes of a compare view, as pages of the lowest height that fits them.
+    ///
+    /// The containers continue each other in reading order, so the items of the second one
+    /// follow the last ones of the first one, and every item is in exactly one of them. Compute
+    /// the layout at the width of a container first. There may be fewer containers than
+    /// requested, e.g. for a single row of items.
+    #[must_use]
+    pub fn split_into(&self, containers: usize) -> Pages {
+        let entries = self.reading_entries();
+        let bottom = entries
+            .iter()
+            .map(|(_, transform)| transform.bottom())
+            .max()
+            .unwrap_or(0);
+        // Fewer pages never need a lower height, so the lowest one is found by bisection.
+        let (mut low, mut high) = (1, bottom + self.outer_padding);
+        while low < high {
+            let height = low + (high - low) / 2;
+            if self.page_breaks(&entries, height).len() <= containers.max(1) {
+                high = height;
+            } else {
+                low = height + 1;
+            }
+        }
+        let breaks = self.page_breaks(&entries, high);
+        self.collect_pages(entries, &breaks)
+    }
+
+    /// Returns the positions and transforms of the laid out items in reading order.
+    fn reading_entries(&self) -> Vec<(u32, Transform)> {
+        let mut entries: Vec<(u32, Transform)> = self
+            .computed()
+            .iter()
+            .enumerate()
+            .filter(|(_, transform)| !transform.is_empty())
+            .map(|(position, transform)| (usize_to_u32(position), transform.clone()))
+            .collect();
+        entries.sort_by_key(|(position, transform)| (transform.top(), transform.left(), *position));
+        entries
+    }
+
+    /// Returns the index of the first entry of every page and the offset of the top of the page
+    /// in the computed layout.
+    fn page_breaks(&self, entries: &[(u32, Transform)], page_height: u32) -> Vec<(usize, u32)> {
+        let mut breaks: Vec<(usize, u32)> = Vec::new();
+        for (index, (_, transform)) in entries.iter().enumerate() {
+            let starts_page = match breaks.last() {
+                None => true,
+                Some(&(_, page_top)) => {
+                    let first_top = page_top + self.outer_padding;
+                    transform.bottom() - page_top > page_height && transform.top() > first_top
+                }
+            };
+            if starts_page {
+                let page_top = if breaks.is_empty() {
+                    0
+                } else {
+                    transform.top().saturating_sub(self.outer_padding)
+                };
+                breaks.push((index, page_top));
+            }
+        }
+        breaks
+    }
+
+    fn collect_pages(&self, entries: Vec<(u32, Transform)>, breaks: &[(usize, u32)]) -> Pages {
+        let mut pages = Pages {
+            starts: breaks.iter().map(|&(start, _)| start).collect(),
+            heights: Vec::with_capacity(breaks.len()),
+            items: Vec::with_capacity(entries.len()),
+            transforms: Vec::with_capacity(entries.len()),
+        };
+        let mut breaks = breaks.iter().peekable();
+        let mut page_top = 0;
+        for (index, (position, mut transform)) in entries.into_iter().enumerate() {
+            if let Some((_, top)) = breaks.next_if(|&&(start, _)| start == index) {
+                page_top = *top;
+                pages.heights.push(0);
+            }
+            let [width, height, top, left] = transform.to_array();
+            transform.0 = U32x4::new(width, height, top - page_top, left);
+            if let Some(page_height) = pages.heights.last_mut() {
+                *page_height = (*page_height).max(transform.bottom() + self.outer_padding);
+            }
+            pages.items.push(position);
+            pages.transforms.push(transform);
+        }
+        pages
+    }
+}
@@ -0,0 +1,715 @@
+//! Geometric queries on the most recent computation.
+//!
+//! In every layout kind the items are placed from top to bottom, so the top offsets of the
+//! transforms are sorted in ascending order. This makes it possible to binary search them instead
+//! of checking every item. The exceptions are the items from `unsorted_from` on, e.g. the last few
+//! items of a vertical masonry layout with balanced columns or a column-major grid, which the
+//! computation indexes by their top to binary search them as well. Hidden items have an empty
+//! transform and are never part of a result.
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
+
+use super::{Layout, Resume, Transform};
+use crate::chunked::Slice;
+use crate::convert::{f32_to_u32, f64_to_f32, f64_to_u32, u32_to_f32, usize_to_f64, usize_to_u32};
+use crate::data::{ColumnInfo, Direction, LayoutStats, MasonryType, RowInfo, ScrollAlignment};
+
+/// How far ahead [`Layout::prefetch_window()`] looks, in seconds of scrolling at the velocity.
+const PREFETCH_SECONDS: f32 = 0.5;
+/// How far ahead [`Layout::prefetch_window()`] looks at most, in viewport heights.
+const MAX_PREFETCH_VIEWPORTS: f32 = 4.0;
+
+impl Layout {
+    /// Returns the computed transforms of all items.
+    pub(super) fn computed(&self) -> Slice<'_, Transform> {
+        let len = self.num_items.min(self.transforms.len());
+        self.transforms.slice(..len)
+    }
+
+    /// Returns the indices of the items whose tops are not sorted.
+    fn unsorted(&self) -> Range<usize> {
+        self.unsorted_from.min(self.num_items)..self.num_items
+    }
+
+    /// Returns the first and last index of the items that intersect the vertical range
+    /// `start..end`, or `None` if there are none.
+    #[must_use]
+    pub fn items_in_range(&self, start: u32, end: u32) -> Option<(usize, usize)> {
+        let mut band = self.items_in_band(start, end);
+        let first = band.next()?;
+        Some(band.fold((first, first), |(first, last), position| {
+            (first.min(position), last.max(position))
+        }))
+    }
+
+    /// Returns the first and last index of the items that intersect the horizontal range
+    /// `start..end`, or `None` if there are none, e.g. for a strip that scrolls horizontally.
+    ///
+    /// The lefts are not sorted like the tops, so every call checks all items.
+    #[must_use]
+    pub fn items_in_range_x(&self, start: u32, end: u32) -> Option<(usize, usize)> {
+        if start >= end {
+            return None;
+        }
+        let mut band = self
+            .computed()
+            .iter()
+            .enumerate()
+            .filter(|(_, transform)| {
+                transform.left() < end && transform.right() > start && !transform.is_empty()
+            })
+            .map(|(position, _)| position);
+        let first = band.next()?;
+        Some((first, band.next_back().unwrap_or(first)))
+    }
+
+    /// Returns the positions of the visible items that intersect the vertical range
+    /// `start..end`, the sorted ones in order and then the unsorted ones by their top.
+    ///
+    /// Both are binary searched, so only the items whose top lies at most the height of the
+    /// tallest item above the range are looked at.
+    fn items_in_band(&self, start: u32, end: u32) -> impl Iterator<Item = usize> + '_ {
+        let transforms = self.computed();
+        let sorted = transforms.slice(..self.unsorted().start);
+        // No item can intersect the range if it starts more than the tallest item above it.
+        let above = |top: u32| top.saturating_add(self.max_item_height) <= start;
+        let lower = sorted.partition_point(|t| above(t.top()));
+        let upper = sorted.partition_point(|t| t.top() < end);
+        // The index can be longer than the transforms if items were removed since.
+        let top = move |&position: &u32| {
+            transforms
+                .get(position as usize)
+                .map_or(u32::MAX, Transform::top)
+        };
+        let index = &self.unsorted_index;
+        let unsorted = index.partition_point(|position| above(top(position)))
+            ..index.partition_point(|position| top(position) < end);
+        // An empty or inverted range, e.g. from a negative viewport height, contains nothing.
+        let (sorted, unsorted) = if start < end {
+            (lower..upper, unsorted)
+        } else {
+            (0..0, 0..0)
+        };
+        sorted
+            .chain(index[unsorted].iter().map(|&position| position as usize))
+            .filter(move |&i| {
+                transforms.get(i).map_or(false, |transform| {
+                    transform.top() < end && transform.bottom() > start && !transform.is_empty()
+                })
+            })
+    }
+
+    /// Returns the first and last index of the items that are in the viewport now or will be
+    /// soon, given the scroll velocity in pixels per second (positive when scrolling down), or
+    /// `None` if there are none.
+    ///
+    /// The viewport is extended in the scroll direction by the distance of half a second at the
+    /// velocity, but at most four viewport heights, so that the thumbnails ahead of the scroll
+    /// position can be loaded first.
+    #[must_use]
+    pub fn prefetch_window(
+        &self,
+        scroll_top: u32,
+        velocity: f32,
+        viewport_height: u32,
+    ) -> Option<(usize, usize)> {
+        let speed = if velocity < 0.0 { -velocity } else { velocity };
+        let max_ahead = u32_to_f32(viewport_height) * MAX_PREFETCH_VIEWPORTS;
+        // A NaN velocity looks nowhere ahead.
+        let ahead = f32_to_u32((speed * PREFETCH_SECONDS).clamp(0.0, max_ahead));
+        let end = scroll_top.saturating_add(viewport_height);
+        if velocity < 0.0 {
+            self.items_in_range(scroll_top.saturating_sub(ahead), end)
+        } else {
+            self.items_in_range(scroll_top, end.saturating_add(ahead))
+        }
+    }
+
+    /// Returns the index of the item that contains the point, or `None` if the point lies in the
+    /// padding between items.
+    #[must_use]
+    pub fn item_at(&self, x: u32, y: u32) -> Option<usize> {
+        let transforms = self.computed();
+        self.items_in_band(y, y.saturating_add(1)).find(|&i| {
+            let transform = &transforms[i];
+            transform.top() <= y
+                && y < transform.bottom()
+                && transform.left() <= x
+                && x < transform.right()
+        })
+    }
+
+    /// Returns the indices of all items that overlap the rectangle, the ones whose tops are sorted
+    /// in ascending order first.
+    pub fn items_in_rect(
+        &self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> impl Iterator<Item = usize> + '_ {
+        let (right, bottom) = (x.saturating_add(width), y.saturating_add(height));
+        let transforms = self.computed();
+        self.items_in_band(y, bottom).filter(move |&i| {
+            let transform = &transforms[i];
+            transform.top() < bottom
+                && y < transform.bottom()
+                && transform.left() < right
+                && x < transform.right()
+        })
+    }
+
+    /// Returns the indices of the items from `anchor` to `focus` in reading order, both included,
+    /// or none if either is not laid out.
+    ///
+    /// The reading order sorts the items by their top and then by their left, so a range spans
+    /// whole rows of the grid, horizontal and list layouts and the items between both tops across
+    /// all columns of the vertical masonry layout, in whichever order both are given.
+    pub fn items_between(&self, anchor: usize, focus: usize) -> impl Iterator<Item = usize> + '_ {
+        let transforms = self.computed();
+        let key = move |index: usize| reading_key(transforms, index);
+        let range = key(anchor)
+            .zip(key(focus))
+            .map(|(anchor, focus)| (anchor.min(focus), anchor.max(focus)));
+        (0..transforms.len()).filter(move |&index| {
+            range.map_or(false, |(first, last)| {
+                key(index).map_or(false, |key| first <= key && key <= last)
+            })
+        })
+    }
+
+    /// Returns the index of the item after the item at `index` in reading order, see
+    /// [`Layout::items_between()`], or `None` for the last one or an item that is not laid out.
+    ///
+    /// This is the order in which the items appear on the screen, e.g. for the arrow keys of a
+    /// full-screen viewer: from left to right within a row, with section headers, pinned items
+    /// and spanning items where they are placed rather than where their index is.
+    #[must_use]
+    pub fn next_visual(&self, index: usize) -> Option<usize> {
+        self.adjacent_visual(index, true)
+    }
+
+    /// Returns the index of the item before the item at `index` in reading order, see
+    /// [`Layout::next_visual()`].
+    #[must_use]
+    pub fn prev_visual(&self, index: usize) -> Option<usize> {
+        self.adjacent_visual(index, false)
+    }
+
+    fn adjacent_visual(&self, index: usize, after: bool) -> Option<usize> {
+        let transforms = self.computed();
+        let current = reading_key(transforms, index)?;
+        let keys = (0..transforms.len()).filter_map(|index| reading_key(transforms, index));
+        let adjacent = if after {
+            keys.filter(|&key| key > current).min()
+        } else {
+            keys.filter(|&key| key < current).max()
+        };
+        adjacent.map(|(_, _, index)| index)
+    }
+
+    /// Returns the index of the item next to the item at `index` in the given direction.
+    ///
+    /// Columns and rows do not line up in a masonry layout, so the neighbor is the item in the
+    /// nearest row (or column) that is closest to the center of the current item.
+    #[must_use]
+    pub fn neighbor(&self, index: usize, direction: Direction) -> Option<usize> {
+        let transforms = self.computed();
+        let current = transforms.get(index).filter(|t| !t.is_empty())?;
+        let (center_x, center_y) = (
+            current.left() + current.width() / 2,
+            current.top() + current.height() / 2,
+        );
+
+        match direction {
+            Direction::Down => {
+                // Only items below the current item are candidates and their tops are sorted,
+                // except for the unsorted ones at the end.
+                let unsorted = self.unsorted();
+                let sorted_end = unsorted.start.max(index + 1);
+                let candidates = |range: Range<usize>| {
+                    range.filter(|&i| {
+                        transforms[i].top() >= current.bottom() && !transforms[i].is_empty()
+                    })
+                };
+                // The nearest row ends where the first candidate below ends.
+                let mut band_end = u32::MAX;
+                for i in candidates(index + 1..sorted_end) {
+                    if transforms[i].top() >= band_end {
+                        break;
+                    }
+                    band_end = band_end.min(transforms[i].bottom());
+                }
+                for i in candidates(sorted_end..transforms.len()) {
+                    if transforms[i].top() < band_end {
+                        band_end = band_end.min(transforms[i].bottom());
+                    }
+                }
+                candidates(index + 1..sorted_end)
+                    .take_while(|&i| transforms[i].top() < band_end)
+                    .chain(
+                        candidates(sorted_end..transforms.len())
+                            .filter(|&i| transforms[i].top() < band_end),
+                    )
+                    .min_by_key(|&i| {
+                        (
+                            distance(center_x, transforms[i].left(), transforms[i].right()),
+                            transforms[i].top(),
+                        )
+                    })
+            }
+            Direction::Up => {
+                // Walking backwards, the first sorted candidate has the largest top offset.
+                let sorted_end = self.unsorted().start.min(index);
+                let candidates = |range: Range<usize>| {
+                    range.rev().filter(|&i| {
+                        transforms[i].bottom() <= current.top() && !transforms[i].is_empty()
+                    })
+                };
+                let band_start = candidates(sorted_end..index)
+                    .chain(candidates(0..sorted_end).take(1))
+                    .map(|i| transforms[i].top())
+                    .max()?;
+                candidates(sorted_end..index)
+                    .chain(candidates(0..sorted_end).take_while(|&i| {
+                        transforms[i].top().saturating_add(self.max_item_height) > band_start
+                    }))
+                    .filter(|&i| transforms[i].bottom() > band_start)
+                    .min_by_key(|&i| {
+                        (
+                            distance(center_x, transforms[i].left(), transforms[i].right()),
+                            u32::MAX - transforms[i].bottom(),
+                        )
+                    })
+            }
+            Direction::Left | Direction::Right => {
+                let (first, last) = self.items_in_range(current.top(), current.bottom())?;
+                (first..=last)
+                    .filter(|&i| !transforms[i].is_empty())
+                    .filter(|&i| match direction {
+                        Direction::Left => transforms[i].right() <= current.left(),
+                        _ => transforms[i].left() >= current.right(),
+                    })
+                    .min_by_key(|&i| {
+                        let gap = match direction {
+                            Direction::Left => current.left() - transforms[i].right(),
+                            _ => transforms[i].left() - current.right(),
+                        };
+                        (
+                            distance(center_y, transforms[i].top(), transforms[i].bottom()),
+                            gap,
+                        )
+                    })
+            }
+        }
+    }
+
+    /// Returns the section header that sticks to the top of the viewport at the given scroll
+    /// offset and how far it is pushed up by the next header.
+    ///
+    /// Section headers are the items marked with [`Layout::set_full_width()`]. The sticky one is
+    /// the last header that starts at or above `scroll_top`.
+    #[must_use]
+    pub fn sticky_header(&self, scroll_top: u32) -> Option<(usize, u32)> {
+        let transforms = self.computed();
+        let items = self.ordered_items();
+        let is_header = |&i: &usize| items.get(i).is_section_header() && !transforms[i].is_empty();
+        let sorted = transforms.slice(..self.unsorted().start);
+        let end = sorted.partition_point(|t| t.top() <= scroll_top);
+        let header = (0..end).rev().find(is_header)?;
+        let bottom = scroll_top + transforms[header].height();
+        let push = (end..sorted.len())
+            .take_while(|&i| transforms[i].top() < bottom)
+            .find(is_header)
+            .map_or(0, |next| bottom - transforms[next].top());
+        Some((header, push))
+    }
+
+    /// Returns a coarse profile of the most recent computation with two values for each of the
+    /// given number of buckets, which split the layout of the given height into equal bands, e.g.
+    /// to draw a minimap next to the scrollbar.
+    ///
+    /// The first value is the fraction of the band that the items cover, from 0 for empty space
+    /// to 1 for a band full of thumbnails. The second is 1 if a section header (see
+    /// [`Layout::set_full_width()`]) starts in the band and 0 otherwise. Every call looks at all
+    /// items once.
+    #[must_use]
+    pub fn minimap(&self, buckets: usize, height: u32) -> Vec<f32> {
+        let mut minimap = vec![0.0; 2 * buckets];
+        let width = self
+            .resume
+            .as_ref()
+            .map_or(0, |resume| self.content_box(resume.container_width).1);
+        if buckets == 0 || height == 0 || width == 0 {
+            return minimap;
+        }
+        let bucket_height = f64::from(height) / usize_to_f64(buckets);
+        let area = bucket_height * f64::from(width);
+        let items = self.ordered_items();
+        for (position, transform) in self.computed().iter().enumerate() {
+            if transform.is_empty() {
+                continue;
+            }
+            let top = f64::from(transform.top());
+            let bottom = f64::from(transform.bottom().min(height));
+            let first = f64_to_u32(top / bucket_height) as usize;
+            if first >= buckets {
+                continue;
+            }
+            if items.get(position).is_section_header() {
+                minimap[2 * first + 1] = 1.0;
+            }
+            // Tall items and small buckets share an item with every band it reaches into.
+            let mut bucket = first;
+            while bucket < buckets && usize_to_f64(bucket) * bucket_height < bottom {
+                let start = top.max(usize_to_f64(bucket) * bucket_height);
+                let end = bottom.min(usize_to_f64(bucket + 1) * bucket_height);
+                minimap[2 * bucket] +=
+                    f64_to_f32((end - start) * f64::from(transform.width()) / area);
+                bucket += 1;
+            }
+        }
+        // Rounding can make a full band exceed 1.
+        for coverage in minimap.iter_mut().step_by(2) {
+            *coverage = coverage.min(1.0);
+        }
+        minimap
+    }
+
+    /// Returns the position of the section header that is in effect at the top of each of the
+    /// given number of buckets, which split the layout of the given height into equal bands, e.g.
+    /// to label the track of a fast-scroll scrubber.
+    ///
+    /// A section header (see [`Layout::set_full_width()`]) is in effect from its top until the
+    /// next one starts, and the bands above the first header get the first one. The result is
+    /// empty if there are no headers. Every call looks at all items once.
+    #[must_use]
+    pub fn scrubber_labels(&self, buckets: usize, height: u32) -> Vec<u32> {
+        let items = self.ordered_items();
+        let mut headers: Vec<(u32, usize)> = self
+            .computed()
+            .iter()
+            .enumerate()
+            .filter(|&(position, transform)| {
+                !transform.is_empty() && items.get(position).is_section_header()
+            })
+            .map(|(position, transform)| (transform.top(), position))
+            .collect();
+        if headers.is_empty() || buckets == 0 {
+            return Vec::new();
+        }
+        headers.sort_unstable();
+        let bucket_height = f64::from(height) / usize_to_f64(buckets);
+        (0..buckets)
+            .map(|bucket| {
+                let top = f64_to_u32(usize_to_f64(bucket) * bucket_height);
+                let end = headers.partition_point(|&(header_top, _)| header_top <= top);
+                usize_to_u32(headers[end.saturating_sub(1)].1)
+            })
+            .collect()
+    }
+
+    /// Returns the top of the section header at the given position, e.g. one returned by
+    /// [`Layout::scrubber_labels()`] to scroll to, or `None` if the item at the position is not a
+    /// section header or not laid out.
+    #[must_use]
+    pub fn section_top(&self, position: usize) -> Option<u32> {
+        let transform = self.computed().get(position).filter(|t| !t.is_empty())?;
+        self.ordered_items()
+            .get(position)
+            .is_section_header()
+            .then(|| transform.top())
+    }
+
+    /// Counts the visible items, rows and columns of the most recent computation.
+    ///
+    /// A vertical masonry layout has no rows, so it counts none, and its longest row is the one
+    /// of the first items, one per column.
+    #[must_use]
+    pub fn stats(&self) -> LayoutStats {
+        let mut stats = LayoutStats::default();
+        let (mut top, mut row_len) = (None, 0);
+        for transform in self.computed().iter().filter(|t| !t.is_empty()) {
+            stats.visible_items += 1;
+            if top == Some(transform.top()) {
+                row_len += 1;
+            } else {
+                top = Some(transform.top());
+                stats.rows += 1;
+                row_len = 1;
+            }
+            stats.columns = stats.columns.max(row_len);
+        }
+        if let Some(Resume {
+            kind: MasonryType::Vertical,
+            ..
+        }) = self.resume
+        {
+            stats.rows = 0;
+        }
+        stats
+    }
+
+    /// Returns the top, height and items of the row with the given index, or `None` if there are
+    /// fewer rows.
+    ///
+    /// The rows are runs of items that share their top, so in a vertical masonry layout only the
+    /// first row has more than one item, see [`Layout::column_info()`] instead. Every call checks
+    /// the items up to the end of the row.
+    #[must_use]
+    pub fn row_info(&self, row: usize) -> Option<RowInfo> {
+        let transforms = self.computed();
+        let mut current: Option<RowInfo> = None;
+        let mut rows = 0;
+        for (position, transform) in transforms.iter().enumerate() {
+            if transform.is_empty() {
+                continue;
+            }
+            match current.as_mut() {
+                Some(info) if info.top == transform.top() => {
+                    info.height = info.height.max(transform.height());
+                    info.last = usize_to_u32(position);
+                }
+                _ => {
+                    if current.is_some() {
+                        if rows == row {
+                            return current;
+                        }
+                        rows += 1;
+                    }
+                    current = Some(RowInfo {
+                        top: transform.top(),
+                        height: transform.height(),
+                        first: usize_to_u32(position),
+                        last: usize_to_u32(position),
+                    });
+                }
+            }
+        }
+        current.filter(|_| rows == row)
+    }
+
+    /// Returns the tops of the rows in ascending order, e.g. to snap the scroll position to row
+    /// starts, so that no row is cut in half at the top of the viewport.
+    ///
+    /// The rows are runs of items that share their top, like in [`Layout::stats()`], including
+    /// full-width items and section headers. A vertical masonry layout has no rows, so it returns
+    /// none.
+    #[must_use]
+    pub fn snap_offsets(&self) -> Vec<u32> {
+        match self.resume {
+            Some(Resume {
+                kind: MasonryType::Vertical,
+                ..
+            })
+            | None => return Vec::new(),
+            _ => {}
+        }
+        let mut tops: Vec<u32> = self
+            .computed()
+            .iter()
+            .filter(|transform| !transform.is_empty())
+            .map(Transform::top)
+            .collect();
+        // Already sorted and mostly adjacent duplicates, except for column-major grids.
+        tops.dedup();
+        tops.sort_unstable();
+        tops.dedup();
+        tops
+    }
+
+    /// Returns the row top of [`Layout::snap_offsets()`] that is closest to the scroll position,
+    /// or `None` without rows.
+    #[must_use]
+    pub fn nearest_snap(&self, scroll_top: u32) -> Option<u32> {
+        let tops = self.snap_offsets();
+        let next = tops.partition_point(|&top| top < scroll_top);
+        let candidates = next.checked_sub(1).into_iter().chain(Some(next));
+        candidates
+            .filter_map(|index| tops.get(index).copied())
+            .min_by_key(|&top| distance(scroll_top, top, top + 1))
+    }
+
+    /// Returns the left offset, width and bottom of the column with the given index in a vertical
+    /// masonry or grid layout, together with its number of items.
+    ///
+    /// Returns `None` if the column has no items, e.g. because there are fewer columns, or if the
+    /// most recent computation was not a column layout. Items that span the full width are not
+    /// part of any column.
+    #[must_use]
+    pub fn column_info(&self, column: usize) -> Option<ColumnInfo> {
+        match self.resume {
+            Some(Resume {
+                kind: MasonryType::Vertical | MasonryType::Grid,
+                ..
+            }) => {}
+            _ => return None,
+        }
+        let width = self.resolved_thumbnail_size;
+        let left = (width + self.gap_x)
+            .checked_mul(u32::try_from(column).ok()?)?
+            .checked_add(self.content_left)?;
+        let (bottom, num_items) = self
+            .computed()
+            .iter()
+            .filter(|t| t.left() == left && t.width() == width)
+            .fold((0, 0), |(bottom, count), t| {
+                (bottom.max(t.bottom()), count + 1)
+            });
+        if num_items == 0 {
+            return None;
+        }
+        Some(ColumnInfo {
+            left,
+            width,
+            bottom,
+            num_items,
+        })
+    }
+
+    /// Returns the position of the first item that the most recent computation laid out, e.g.
+    /// to render only the items from there on again. The items before it kept their transforms.
+    ///
+    /// This is 0 after a full computation and the item count if nothing was laid out, e.g.
+    /// because the computation was cancelled.
+    #[must_use]
+    pub fn laid_out_from(&self) -> usize {
+        self.laid_out_from.min(self.num_items)
+    }
+
+    /// Returns the indices of the items whose transform changed in the most recent computation.
+    ///
+    /// Returns nothing if changes are not tracked (see [`Layout::set_track_changes()`]).
+    pub fn changed_items(&self) -> impl Iterator<Item = usize> + '_ {
+        let previous = self.previous.as_deref().unwrap_or_default();
+        let len = self.previous.as_ref().map_or(0, |_| self.num_items);
+        self.computed()
+            .iter()
+            .take(len)
+            .enumerate()
+            .filter(move |&(i, transform)| previous.get(i) != Some(transform))
+            .map(|(i, _)| i)
+    }
+
+    /// Returns the scroll offset that keeps the anchor item at the same position in the viewport
+    /// after a computation, given how far the viewport was scrolled past its top before.
+    ///
+    /// If the previous transform is known, the offset is scaled with the height of the item so
+    /// that the same part of it stays in view.
+    #[must_use]
+    pub fn anchor_offset(&self, index: usize, offset_within_item: f32) -> Option<f32> {
+        let current = self.computed().get(index)?;
+        let offset = match self.get_previous_transform(index) {
+            Some(previous) if previous.height() > 0 => {
+                offset_within_item * u32_to_f32(current.height()) / u32_to_f32(previous.height())
+            }
+            _ => offset_within_item,
+        };
+        Some((u32_to_f32(current.top()) + offset).max(0.0))
+    }
+
+    /// Returns the scroll offset that brings the item with the given index to the top, center or
+    /// bottom of a viewport of the given height, e.g. to reveal the selected item, or `None` if
+    /// it is out of bounds or not laid out.
+    ///
+    /// The offset is never negative, but it is not clamped to the container height, which the
+    /// browser does when it scrolls.
+    #[must_use]
+    pub fn offset_of(
+        &self,
+        index: usize,
+        alignment: ScrollAlignment,
+        viewport_height: u32,
+    ) -> Option<f32> {
+        let transform = self.computed().get(index).filter(|t| !t.is_empty())?;
+        Some(align(
+            transform.top(),
+            transform.height(),
+            alignment,
+            viewport_height,
+        ))
+    }
+
+    /// Returns the horizontal scroll offset that brings the item with the given index to the
+    /// left, center or right of a viewport of the given width like [`Layout::offset_of()`], with
+    /// [`ScrollAlignment::Top`] for the left and [`ScrollAlignment::Bottom`] for the right.
+    #[must_use]
+    pub fn offset_of_x(
+        &self,
+        index: usize,
+        alignment: ScrollAlignment,
+        viewport_width: u32,
+    ) -> Option<f32> {
+        let transform = self.computed().get(index).filter(|t| !t.is_empty())?;
+        Some(align(
+            transform.left(),
+            transform.width(),
+            alignment,
+            viewport_width,
+        ))
+    }
+}
+
+/// Returns the scroll offset that aligns the span `start..start + length` with a viewport of the
+/// given length, which is never negative.
+fn align(start: u32, length: u32, alignment: ScrollAlignment, viewport: u32) -> f32 {
+    let (start, length, viewport) = (u32_to_f32(start), u32_to_f32(length), u32_to_f32(viewport));
+    let offset = match alignment {
+        ScrollAlignment::Top => start,
+        ScrollAlignment::Center => start + (length - viewport) / 2.0,
+        ScrollAlignment::Bottom => start + length - viewport,
+    };
+    offset.max(0.0)
+}
+
+/// Returns the key that sorts the items in reading order, by their top and then by their left, or
+/// `None` for an item that is not laid out.
+fn reading_key(transforms: Slice<'_, Transform>, index: usize) -> Option<(u32, u32, usize)> {
+    transforms
+        .get(index)
+        .filter(|transform| !transform.is_empty())
+        .map(|transform| (transform.top(), transform.left(), index))
+}
+
+/// Returns the distance of a point to the range `start..end` or 0 if it lies inside of it.
+fn distance(point: u32, start: u32, end: u32) -> u32 {
+    if point < start {
+        start - point
+    } else if point >= end {
+        point - end + 1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::data::{MasonryConfig, MasonryType};
+    use crate::layout::Layout;
+
+    fn layout(kind: MasonryType) -> Layout {
+        let mut layout = Layout::new(30, 100, 10);
+        for index in 0..30 {
+            let side = u16::try_from(index).unwrap();
+            layout.set_dimension(index, 100 + side * 13, 100 + side * 7);
+        }
+        layout.compute(550, &MasonryConfig::new(kind, 100, 10));
+        layout
+    }
+
+    #[test]
+    fn inverted_range() {
+        let layout = layout(MasonryType::Vertical);
+        assert!(layout.items_in_range(100, 300).is_some());
+        assert_eq!(layout.items_in_range(300, 100), None);
+        assert_eq!(layout.items_in_range(150, 150), None);
+        assert_eq!(layout.items_in_range_x(300, 100), None);
+    }
+
+    #[test]
+    fn item_at_bottom_edge() {
+        let layout = layout(MasonryType::Grid);
+        assert_eq!(layout.item_at(15, 15), Some(0));
+        assert_eq!(layout.item_at(u32::MAX, u32::MAX), None);
+    }
+}
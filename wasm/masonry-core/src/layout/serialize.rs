@@ -0,0 +1,274 @@
+//! A compact binary format of the dimensions and the computed transforms, so that a layout can be
+//! restored without computing it again, e.g. from a cache when the application starts.
+use alloc::vec::Vec;
+
+use super::{AspectRatio, Item, ItemFlags, Layout, Transform};
+use crate::convert::usize_to_u32;
+use crate::data::MasonryType;
+
+/// The bytes every serialized layout starts with.
+const MAGIC: [u8; 4] = *b"MSNY";
+/// Incremented whenever the format changes, so that old caches are rejected.
+const VERSION: u32 = 1;
+/// Marks the absence of a requested order or of an unsorted position.
+const NONE: u32 = u32::MAX;
+/// The bytes per item: the source aspect ratio, the flags and the section kind.
+const ITEM_BYTES: usize = 4;
+/// The numbers per item of [`Layout::export_margins()`].
+const MARGIN_LANES: usize = 5;
+
+impl Layout {
+    /// Returns the dimensions, flags, order and computed transforms of the items together with
+    /// the container height of the most recent computation.
+    ///
+    /// The configuration is not included, because the application sets it anyway. All numbers
+    /// are little-endian.
+    #[must_use]
+    pub fn serialize(&self, container_height: u32) -> Vec<u8> {
+        let order = self.requested_order.as_deref().unwrap_or_default();
+        let mut bytes = Vec::with_capacity(
+            28 + 4 * order.len() + self.num_items * (ITEM_BYTES + 4 * Transform::LANES),
+        );
+        bytes.extend_from_slice(&MAGIC);
+        let unsorted_from = u32::try_from(self.unsorted_from).unwrap_or(NONE);
+        let order_len = self
+            .requested_order
+            .as_ref()
+            .map_or(NONE, |ids| usize_to_u32(ids.len()));
+        for value in [
+            VERSION,
+            usize_to_u32(self.num_items),
+            container_height,
+            self.max_item_height,
+            unsorted_from,
+            order_len,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for &id in order {
+            bytes.extend_from_slice(&id.to_le_bytes());
+        }
+        for item in self.items.iter().take(self.num_items) {
+            bytes.extend_from_slice(&item_lanes(item));
+        }
+        for transform in self.transforms.iter().take(self.num_items) {
+            for lane in transform.to_lanes() {
+                bytes.extend_from_slice(&lane.to_le_bytes());
+            }
+        }
+        bytes
+    }
+
+    /// Restores the items from bytes returned by [`Layout::serialize()`] and returns the
+    /// container height they were computed with, or `None` without changing anything if the
+    /// bytes are not a serialized layout of this version.
+    ///
+    /// Afterwards the transforms and queries work like after a computation. The aspect ratios are
+    /// derived with the current configuration, but the transforms are not computed again. The
+    /// state of the serialized computation is not included, so the next appended or incremental
+    /// computation lays out all items again.
+    pub fn deserialize(&mut self, bytes: &[u8]) -> Option<u32> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC || reader.u32()? != VERSION {
+            return None;
+        }
+        let num_items = reader.u32()? as usize;
+        let container_height = reader.u32()?;
+        let max_item_height = reader.u32()?;
+        let unsorted_from = match reader.u32()? {
+            NONE => usize::MAX,
+            position => position as usize,
+        };
+        let requested_order = match reader.u32()? {
+            NONE => None,
+            len => Some(
+                reader
+                    .take((len as usize).checked_mul(4)?)?
+                    .chunks_exact(4)
+                    .map(read_u32)
+                    .collect::<Vec<u32>>(),
+            ),
+        };
+        let items = reader.take(num_items.checked_mul(ITEM_BYTES)?)?;
+        let lanes = reader.take(num_items.checked_mul(4 * Transform::LANES)?)?;
+        if !reader.0.is_empty() {
+            return None;
+        }
+        let items: Vec<u32> = items.iter().map(|&lane| u32::from(lane)).collect();
+        let lanes: Vec<u32> = lanes.chunks_exact(4).map(read_u32).collect();
+        if !self.restore(&items, requested_order, &lanes) {
+            return None;
+        }
+        self.max_item_height = max_item_height;
+        // The flags are restored without the focus, see `item_lanes()`.
+        self.focus = None;
+        self.set_unsorted_from(unsorted_from);
+        Some(container_height)
+    }
+
+    /// Returns the source aspect ratio, the flags and the kind of the section after the item for
+    /// every item, in the units of the binary format, see [`Layout::restore()`].
+    ///
+    /// Every item takes four numbers: the width and height of the aspect ratio, where the longer
+    /// side is 100 and both are 0 if the dimension is not known, the flags and the section kind,
+    /// which is 0 if the section has the kind of the computation or the [`MasonryType`] plus 1.
+    #[must_use]
+    pub fn export_items(&self) -> Vec<u32> {
+        self.items
+            .iter()
+            .take(self.num_items)
+            .flat_map(|item| item_lanes(item).map(u32::from))
+            .collect()
+    }
+
+    /// Returns the margins and the fixed height of every item, which the binary format leaves
+    /// out: five numbers per item, the top, right, bottom and left margin in CSS pixels followed
+    /// by the height of [`Layout::set_fixed_height()`].
+    #[must_use]
+    pub fn export_margins(&self) -> Vec<u32> {
+        self.items
+            .iter()
+            .take(self.num_items)
+            .flat_map(|item| {
+                let [top, right, bottom, left] = item.margin.map(u32::from);
+                [top, right, bottom, left, u32::from(item.height)]
+            })
+            .collect()
+    }
+
+    /// Sets the margins and fixed heights returned by [`Layout::export_margins()`], or returns
+    /// false without changing anything if they are not five numbers for every item.
+    pub fn import_margins(&mut self, lanes: &[u32]) -> bool {
+        if lanes.len() != MARGIN_LANES * self.num_items {
+            return false;
+        }
+        for (index, lanes) in lanes.chunks_exact(MARGIN_LANES).enumerate() {
+            self.set_item_margin(index, lanes[0], lanes[1], lanes[2], lanes[3]);
+            self.set_fixed_height(index, u16::try_from(lanes[4]).unwrap_or(u16::MAX));
+        }
+        true
+    }
+
+    /// Replaces the items, the requested order and the transforms with the ones returned by
+    /// [`Layout::export_items()`] and [`Layout::export_transforms()`], or returns false without
+    /// changing anything if they do not fit together.
+    ///
+    /// Afterwards the transforms and queries work like after a computation. The aspect ratios are
+    /// derived with the current configuration, but the transforms are not computed again. The
+    /// state of the restored computation is not included, so the next appended or incremental
+    /// computation lays out all items again. The recorded changes are forgotten.
+    pub fn restore(&mut self, items: &[u32], order: Option<Vec<u32>>, transforms: &[u32]) -> bool {
+        let num_items = items.len() / ITEM_BYTES;
+        if items.len() % ITEM_BYTES != 0 || transforms.len() != num_items * Transform::LANES {
+            return false;
+        }
+        let mut restored = Vec::with_capacity(num_items);
+        for lanes in items.chunks_exact(ITEM_BYTES) {
+            let mut bytes = [0; ITEM_BYTES];
+            for (byte, &lane) in bytes.iter_mut().zip(lanes) {
+                match u8::try_from(lane) {
+                    Ok(lane) => *byte = lane,
+                    Err(_) => return false,
+                }
+            }
+            let section_kind = match bytes[3] {
+                0 => None,
+                1 => Some(MasonryType::Vertical),
+                2 => Some(MasonryType::Horizontal),
+                3 => Some(MasonryType::Grid),
+                4 => Some(MasonryType::List),
+                5 => Some(MasonryType::Mosaic),
+                6 => Some(MasonryType::FixedRows),
+                7 => Some(MasonryType::Filmstrip),
+                _ => return false,
+            };
+            let mut item = Item {
+                aspect_ratio: AspectRatio::default(),
+                source: AspectRatio {
+                    width: bytes[0],
+                    height: bytes[1],
+                },
+                flags: ItemFlags(u16::from(bytes[2])),
+                section_kind,
+                margin: [0; 4],
+                height: 0,
+                tag: 0,
+            };
+            item.update_aspect_ratio(
+                self.aspect_ratio_limits,
+                self.panorama_aspect_ratio,
+                &self.default_aspect_ratio,
+            );
+            restored.push(item);
+        }
+
+        self.resize_items(num_items);
+        for (item, restored) in self.items.iter_mut().zip(restored) {
+            *item = restored;
+        }
+        self.requested_order = order;
+        self.update_order();
+        self.save_previous();
+        for (transform, lanes) in self
+            .transforms
+            .iter_mut()
+            .zip(transforms.chunks_exact(Transform::LANES))
+        {
+            *transform = Transform::from_lanes(lanes);
+        }
+        let transforms = self.computed();
+        let max_item_height = transforms.iter().map(Transform::height).max();
+        self.max_item_height = max_item_height.unwrap_or(0);
+        self.find_unsorted_from(0, None);
+        // The transforms belong to the restored items, so nothing has to be computed again.
+        self.first_dirty = None;
+        self.laid_out_from = 0;
+        self.invalidate();
+        self.clear_history();
+        true
+    }
+}
+
+/// Returns the source aspect ratio, the flags and the section kind of an item.
+fn item_lanes(item: &Item) -> [u8; ITEM_BYTES] {
+    let section_kind = match item.section_kind {
+        None => 0,
+        Some(MasonryType::Vertical) => 1,
+        Some(MasonryType::Horizontal) => 2,
+        Some(MasonryType::Grid) => 3,
+        Some(MasonryType::List) => 4,
+        Some(MasonryType::Mosaic) => 5,
+        Some(MasonryType::FixedRows) => 6,
+        Some(MasonryType::Filmstrip) => 7,
+    };
+    [
+        item.source.width,
+        item.source.height,
+        item.flags.0.to_le_bytes()[0],
+        section_kind,
+    ]
+}
+
+/// Reads a little-endian number from four bytes.
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// The bytes of a serialized layout that were not read yet.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    /// Returns the next `len` bytes, or `None` if there are not enough left.
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.0.len() {
+            return None;
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(taken)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(read_u32)
+    }
+}
@@ -0,0 +1,160 @@
+//! The settings of a layout by name, e.g. to attach them to a snapshot of a layout glitch.
+//!
+//! Only the settings that the config of a computation does not replace are included, and the
+//! breakpoints, which are a table, are returned by [`Layout::breakpoints()`] instead.
+use alloc::vec::Vec;
+
+use super::{AspectRatio, AspectRatioLimits, Layout, LayoutFlags};
+use crate::convert::{f64_to_f32, f64_to_u32};
+use crate::data::{LastRowBehavior, RoundingMode};
+
+/// The name of every setting together with the largest value it takes. Flags are 0 or 1 and
+/// choices are the index of the variant.
+const SETTINGS: [(&str, f64); 20] = [
+    ("maxRowHeight", u32::MAX as f64),
+    ("minAspectRatio", f32::MAX as f64),
+    ("maxAspectRatio", f32::MAX as f64),
+    ("panoramaAspectRatio", f32::MAX as f64),
+    ("defaultAspectRatio", f32::MAX as f64),
+    ("gapCollapseWidth", u32::MAX as f64),
+    ("columnCount", u16::MAX as f64),
+    ("minColumns", u16::MAX as f64),
+    ("maxColumns", u16::MAX as f64),
+    ("autoFit", 1.0),
+    ("lastRow", 2.0),
+    ("rounding", 2.0),
+    ("balanceRows", 1.0),
+    ("avoidWidows", 1.0),
+    ("reversed", 1.0),
+    ("balanceColumns", 1.0),
+    ("columnStability", u32::MAX as f64),
+    ("mosaicPeriod", u32::MAX as f64),
+    ("columnMajor", 1.0),
+    ("medianCells", 1.0),
+];
+
+impl Layout {
+    /// Returns the name and value of every setting, e.g. `("columnCount", 4.0)`. Flags are 0 or 1
+    /// and choices like the [`LastRowBehavior`] are the index of the variant.
+    ///
+    /// The aspect ratios are the ones the layout rounded them to, so setting them again with
+    /// [`Layout::import_settings()`] lays out the items exactly like before.
+    #[must_use]
+    pub fn export_settings(&self) -> Vec<(&'static str, f64)> {
+        let values = [
+            f64::from(self.max_row_height),
+            f64::from(self.aspect_ratio_limits.min_aspect_ratio()),
+            f64::from(self.aspect_ratio_limits.max_aspect_ratio()),
+            f64::from(self.panorama_aspect_ratio),
+            f64::from(self.default_aspect_ratio.ratio()),
+            f64::from(self.gap_collapse_width),
+            f64::from(self.column_count),
+            f64::from(self.min_columns),
+            f64::from(self.max_columns),
+            f64::from(u8::from(self.flags.contains(LayoutFlags::AUTO_FIT))),
+            f64::from(self.last_row as u8),
+            f64::from(self.rounding as u8),
+            f64::from(u8::from(self.flags.contains(LayoutFlags::BALANCE_ROWS))),
+            f64::from(u8::from(self.flags.contains(LayoutFlags::AVOID_WIDOWS))),
+            f64::from(u8::from(self.flags.contains(LayoutFlags::REVERSED))),
+            f64::from(u8::from(self.flags.contains(LayoutFlags::BALANCE_COLUMNS))),
+            f64::from(self.column_stability),
+            f64::from(self.mosaic_period),
+            f64::from(u8::from(self.flags.contains(LayoutFlags::COLUMN_MAJOR))),
+            f64::from(u8::from(self.cell_aspect_ratio.is_some())),
+        ];
+        SETTINGS
+            .iter()
+            .zip(values)
+            .map(|(&(name, _), value)| (name, value))
+            .collect()
+    }
+
+    /// Sets the settings returned by [`Layout::export_settings()`], or returns false without
+    /// changing anything if one of them is unknown or out of range. Settings that are missing
+    /// keep their value.
+    pub fn import_settings(&mut self, settings: &[(&str, f64)]) -> bool {
+        let is_valid = |&(name, value): &(&str, f64)| {
+            SETTINGS
+                .iter()
+                .any(|&(known, max)| known == name && (0.0..=max).contains(&value))
+        };
+        if !settings.iter().all(is_valid) {
+            return false;
+        }
+        let mut aspect_ratio_limits = (
+            self.aspect_ratio_limits.min_aspect_ratio(),
+            self.aspect_ratio_limits.max_aspect_ratio(),
+        );
+        let mut column_limits = (self.min_columns, self.max_columns);
+        for &(name, value) in settings {
+            let flag = value > 0.0;
+            match name {
+                "maxRowHeight" => self.set_max_row_height(f64_to_u32(value)),
+                "minAspectRatio" => aspect_ratio_limits.0 = f64_to_f32(value),
+                "maxAspectRatio" => aspect_ratio_limits.1 = f64_to_f32(value),
+                "panoramaAspectRatio" => self.set_panorama_aspect_ratio(f64_to_f32(value)),
+                "defaultAspectRatio" => self.set_default_aspect_ratio(f64_to_f32(value)),
+                "gapCollapseWidth" => self.set_gap_collapse_width(f64_to_u32(value)),
+                "columnCount" => self.set_column_count(to_u16(value)),
+                "minColumns" => column_limits.0 = to_u16(value),
+                "maxColumns" => column_limits.1 = to_u16(value),
+                "autoFit" => self.set_auto_fit(flag),
+                "lastRow" => self.set_last_row_behavior(match f64_to_u32(value) {
+                    0 => LastRowBehavior::Left,
+                    1 => LastRowBehavior::Justify,
+                    _ => LastRowBehavior::Hide,
+                }),
+                "rounding" => self.set_rounding_mode(match f64_to_u32(value) {
+                    0 => RoundingMode::Floor,
+                    1 => RoundingMode::Round,
+                    _ => RoundingMode::Accumulate,
+                }),
+                "balanceRows" => self.set_balance_rows(flag),
+                "avoidWidows" => self.set_avoid_widows(flag),
+                "reversed" => self.set_reversed(flag),
+                "balanceColumns" => self.set_balance_columns(flag),
+                "columnStability" => self.set_column_stability(f64_to_u32(value)),
+                "mosaicPeriod" => self.set_mosaic_period(f64_to_u32(value)),
+                "columnMajor" => self.set_column_major(flag),
+                _ => self.set_median_cells(flag),
+            }
+        }
+        self.set_aspect_ratio_limits(aspect_ratio_limits.0, aspect_ratio_limits.1);
+        self.set_column_limits(column_limits.0, column_limits.1);
+        true
+    }
+}
+
+/// Converts a column setting, which is validated to fit.
+fn to_u16(value: f64) -> u16 {
+    u16::try_from(f64_to_u32(value)).unwrap_or(u16::MAX)
+}
+
+impl AspectRatioLimits {
+    /// Returns the limit that [`AspectRatioLimits::new()`] rounds to the same width, or 0.
+    fn min_aspect_ratio(self) -> f32 {
+        if self.min_width > 1 {
+            f32::from(self.min_width) / 100.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the limit that [`AspectRatioLimits::new()`] rounds to the same height, or 0.
+    fn max_aspect_ratio(self) -> f32 {
+        if self.min_height > 1 {
+            100.0 / f32::from(self.min_height)
+        } else {
+            0.0
+        }
+    }
+}
+
+impl AspectRatio {
+    /// Returns the width divided by the height, which `AspectRatio::from_ratio()` rounds back to
+    /// the same sides.
+    fn ratio(&self) -> f32 {
+        f32::from(self.width) / f32::from(self.height)
+    }
+}
@@ -0,0 +1,133 @@
+//! Partial layouts while the dimensions of the items arrive in batches, e.g. during an import.
+//!
+//! The items are laid out with the default aspect ratio until their dimension arrives. A batch
+//! mostly changes a few neighbouring rows of the horizontal layout, since the greedy rows line up
+//! with the previous computation again a few rows below the last arrival, so the rows after that
+//! are only moved instead of laid out again.
+use core::ops::Range;
+
+use super::{Layout, LayoutFlags, Resume, ResumeState};
+use crate::convert::usize_to_u32;
+use crate::data::{HeightUpdate, MasonryType};
+use crate::util::UnwrapOrAbort;
+
+/// Where the horizontal layout of a batch may stop laying out rows.
+#[derive(Clone, Copy)]
+pub(super) struct Convergence {
+    /// Start and top of the row that the previous computation resumes at, from which on the rows
+    /// are laid out again anyway, e.g. because they avoid a widow.
+    resume_start: usize,
+    resume_top: u32,
+    /// Position from which the rows kept their items and how far they moved down.
+    converged: Option<(usize, i64)>,
+}
+
+impl Layout {
+    /// Lays out the items whose dimensions were set since the most recent computation and
+    /// returns the new container height together with the part of the layout that changed.
+    ///
+    /// This is meant for dimensions that arrive in batches: the items are laid out with the
+    /// default aspect ratio (see [`Layout::set_default_aspect_ratio()`]) until their dimension
+    /// arrives, and every batch is laid out like [`Layout::compute_incremental()`]. The
+    /// horizontal layout stops at the first row behind the batch that starts at the same item
+    /// as before and moves the rows below it by the offset of the update, which usually leaves
+    /// only a few rows to lay out. The other kinds, sections, balanced and reversed layouts and
+    /// changes that move items, e.g. [`Layout::insert()`], lay out every item from the first
+    /// change on.
+    pub fn compute_arrivals(&mut self) -> HeightUpdate {
+        self.converge = match &self.resume {
+            Some(Resume {
+                kind: MasonryType::Horizontal,
+                state: ResumeState::Horizontal { row_start, top },
+                ..
+            }) if self.first_dirty.is_some()
+                && !self.flags.contains(LayoutFlags::HAS_SECTIONS)
+                && !self.flags.contains(LayoutFlags::BALANCE_ROWS)
+                && !self.flags.contains(LayoutFlags::REVERSED) =>
+            {
+                Some(Convergence {
+                    resume_start: *row_start,
+                    resume_top: *top,
+                    converged: None,
+                })
+            }
+            _ => None,
+        };
+        let height = self.compute_incremental();
+        let (end, offset) = self
+            .converge
+            .take()
+            .and_then(|converge| converge.converged)
+            .unwrap_or((self.num_items, 0));
+        let remaining = self
+            .items
+            .iter()
+            .take(self.num_items)
+            .filter(|item| !item.is_hidden() && item.source.is_unmeasured())
+            .count();
+        HeightUpdate {
+            height,
+            first: usize_to_u32(self.laid_out_from.min(self.num_items)),
+            end: usize_to_u32(end),
+            offset: i32::try_from(offset).unwrap_or_abort(),
+            remaining: usize_to_u32(remaining),
+        }
+    }
+
+    /// Returns where the horizontal layout stops if the rows from the given position on can be
+    /// kept, because a row starts at it like in the previous computation and no item from it on
+    /// changed. `row_width` is the width of the row that the position is in so far.
+    pub(super) fn converges_at(
+        &self,
+        position: usize,
+        row_width: u32,
+        content_left: u32,
+    ) -> Option<Convergence> {
+        let converge = self.converge?;
+        if row_width > 0
+            || converge.converged.is_some()
+            || position < self.dirty_end
+            || position >= converge.resume_start
+        {
+            return None;
+        }
+        // The transform was not laid out again yet.
+        let previous = &self.transforms[position];
+        if previous.is_empty() || previous.left() != content_left {
+            return None;
+        }
+        Some(converge)
+    }
+
+    /// Moves the rows from the end of the rows that were laid out to the top of the next row and
+    /// lays out the rows from the previous resume position on again, like appended items.
+    pub(super) fn converge(
+        &mut self,
+        input_width: u32,
+        laid_out: Range<usize>,
+        top: u32,
+        converge: Convergence,
+    ) -> u32 {
+        let offset = i64::from(top) - i64::from(self.transforms[laid_out.end].top());
+        let moved = laid_out.end..converge.resume_start;
+        for transform in self.transforms.range_mut(moved) {
+            let top = i64::from(transform.top()) + offset;
+            transform.0 = transform.0.set::<2>(u32::try_from(top).unwrap_or_abort());
+        }
+        self.converge = Some(Convergence {
+            converged: Some((laid_out.end, offset)),
+            ..converge
+        });
+        // Every changed item was laid out, so the resumed rows do not keep them dirty.
+        self.first_dirty = None;
+        self.laid_out_from = self.laid_out_from.min(laid_out.start);
+        let resume_top = i64::from(converge.resume_top) + offset;
+        let height = self.layout_horizontal(
+            input_width,
+            converge.resume_start,
+            u32::try_from(resume_top).unwrap_or_abort(),
+        );
+        self.crop_clamped_items(laid_out.start, MasonryType::Horizontal);
+        height
+    }
+}
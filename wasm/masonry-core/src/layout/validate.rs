@@ -0,0 +1,57 @@
+//! Checks of the invariants of the most recent computation, e.g. in debug builds of the
+//! application or in tests.
+use alloc::vec::Vec;
+
+use super::Layout;
+use crate::data::{LastRowBehavior, Violation, ViolationKind};
+
+impl Layout {
+    /// Returns every place where the most recent computation breaks an invariant of the layout.
+    ///
+    /// No two visible items may overlap, every visible item must lie inside of the container and
+    /// have a size. The container width is the one that was passed to the computation. Hidden
+    /// items are ignored, and so are the last rows that [`LastRowBehavior::Hide`] hides, whose
+    /// items have neither width nor height. An empty result means that the layout is valid.
+    #[must_use]
+    pub fn validate(&self, container_width: u32) -> Vec<Violation> {
+        let container_width = self.device_pixels(container_width);
+        let transforms = self.computed();
+        let items = self.ordered_items();
+        let mut violations = Vec::new();
+        let mut visible = Vec::new();
+        let hides_last_rows = self.last_row == LastRowBehavior::Hide;
+        for position in 0..transforms.len() {
+            let transform = &transforms[position];
+            let in_hidden_row =
+                hides_last_rows && transform.width() == 0 && transform.height() == 0;
+            if items.get(position).is_hidden() || in_hidden_row {
+                continue;
+            }
+            if transform.width() == 0 || transform.height() == 0 {
+                violations.push(Violation::new(ViolationKind::Empty, position, position));
+                continue;
+            }
+            if transform.right() > container_width {
+                violations.push(Violation::new(ViolationKind::Overflow, position, position));
+            }
+            visible.push(position);
+        }
+
+        // Only the items that start above the bottom of an item can overlap it.
+        visible.sort_unstable_by_key(|&position| transforms[position].top());
+        for (i, &position) in visible.iter().enumerate() {
+            let transform = &transforms[position];
+            for &other in &visible[i + 1..] {
+                let next = &transforms[other];
+                if next.top() >= transform.bottom() {
+                    break;
+                }
+                if next.left() < transform.right() && transform.left() < next.right() {
+                    let (first, second) = (position.min(other), position.max(other));
+                    violations.push(Violation::new(ViolationKind::Overlap, first, second));
+                }
+            }
+        }
+        violations
+    }
+}
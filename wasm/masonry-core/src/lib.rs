@@ -0,0 +1,42 @@
+//! Masonry layout algorithms without any bindings.
+//!
+//! A [`Layout`] takes the dimensions of the items and computes a [`Transform`] for every item
+//! at a container width and [`MasonryConfig`]. The `masonry` crate exposes it to JavaScript and
+//! runs the computations on a web worker.
+#![deny(clippy::pedantic)]
+#![no_std]
+#![feature(stdsimd)]
+extern crate alloc;
+extern crate core;
+// Provides the allocator and panic handler of static and dynamic libraries.
+#[cfg(feature = "ffi")]
+extern crate std;
+
+pub mod cancel;
+mod chunked;
+pub mod color;
+pub mod convert;
+// The code that `wasm_bindgen` generates to free the `Copy` structs drops a copy.
+#[cfg_attr(feature = "wasm", allow(clippy::drop_copy))]
+mod data;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod layout;
+mod packed;
+pub mod placeholder;
+pub mod probe;
+pub mod resize;
+pub mod selection;
+pub mod similarity;
+pub mod timeline;
+mod util;
+
+pub use data::{
+    Breakpoint, ColumnInfo, ConfigError, Direction, HeightUpdate, ImageInfo, ItemRange,
+    LastRowBehavior, LayoutStats, MasonryConfig, MasonryType, MemoryStats, RoundingMode, RowInfo,
+    ScrollAlignment, StickyHeader, TaggedItem, TimelineGranularity, TimelineSection, Violation,
+    ViolationKind,
+};
+#[cfg(any(test, feature = "invariants"))]
+pub use layout::{check_invariants, Counterexample, Failure};
+pub use layout::{ChunkRunner, Layout, Pages, Transform, CHUNK_SIZE};
@@ -0,0 +1,14 @@
+//! Four lanes of `u32` or `f32` for the per-item kernels of the layouts.
+//!
+//! The `simd128` feature selects the vector instructions if the build also enables the `simd128`
+//! target feature, e.g. with `-C target-feature=+simd128`. Any other build uses scalar lanes with
+//! the same API, so the layouts compile and compute the same transforms either way.
+#[cfg(not(all(feature = "simd128", target_feature = "simd128")))]
+mod vec;
+#[cfg(all(feature = "simd128", target_feature = "simd128"))]
+mod wide;
+
+#[cfg(not(all(feature = "simd128", target_feature = "simd128")))]
+pub use vec::*;
+#[cfg(all(feature = "simd128", target_feature = "simd128"))]
+pub use wide::*;
@@ -1,4 +1,6 @@
-use core::ops::{AddAssign, Mul};
+use core::ops::{Add, AddAssign, Mul};
+
+use crate::convert::{f32_to_u32, u32_to_f32};
 
 #[repr(transparent)]
 #[derive(Clone, Copy)]
@@ -61,6 +63,18 @@ impl U32x4 {
         ])
     }
 
+    /// Divides the lanes and rounds the quotients to the nearest integer.
+    pub fn div_round(self, divisor: U32x4) -> U32x4 {
+        let [a0, a1, a2, a3] = self.0;
+        let [b0, b1, b2, b3] = divisor.0;
+        U32x4([
+            a0.saturating_add(b0 >> 1) / b0,
+            a1.saturating_add(b1 >> 1) / b1,
+            a2.saturating_add(b2 >> 1) / b2,
+            a3.saturating_add(b3 >> 1) / b3,
+        ])
+    }
+
     pub fn to_array(self) -> [u32; 4] {
         self.into()
     }
@@ -81,7 +95,7 @@ impl From<u32> for U32x4 {
 impl From<F32x4> for U32x4 {
     fn from(value: F32x4) -> Self {
         let [v0, v1, v2, v3] = value.0;
-        U32x4([v0 as u32, v1 as u32, v2 as u32, v3 as u32])
+        U32x4([v0, v1, v2, v3].map(f32_to_u32))
     }
 }
 
@@ -121,7 +135,17 @@ impl From<f32> for F32x4 {
 impl From<U32x4> for F32x4 {
     fn from(value: U32x4) -> Self {
         let [v0, v1, v2, v3] = value.0;
-        F32x4([v0 as f32, v1 as f32, v2 as f32, v3 as f32])
+        F32x4([v0, v1, v2, v3].map(u32_to_f32))
+    }
+}
+
+impl Add for F32x4 {
+    type Output = F32x4;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let [a0, a1, a2, a3] = self.0;
+        let [b0, b1, b2, b3] = rhs.0;
+        F32x4([a0 + b0, a1 + b1, a2 + b2, a3 + b3])
     }
 }
 
@@ -1,10 +1,11 @@
 use core::{
     arch::wasm32::{
-        f32x4_convert_u32x4, f32x4_mul, f32x4_replace_lane, f32x4_splat, u32x4, u32x4_add,
-        u32x4_extract_lane, u32x4_lt, u32x4_max, u32x4_min, u32x4_replace_lane, u32x4_splat,
+        f32x4_add, f32x4_convert_u32x4, f32x4_div, f32x4_mul, f32x4_replace_lane, f32x4_splat,
+        i32x4_add, i32x4_mul, i32x4_sub, u32x4, u32x4_add, u32x4_extract_lane, u32x4_gt, u32x4_le,
+        u32x4_lt, u32x4_max, u32x4_min, u32x4_replace_lane, u32x4_shr, u32x4_splat,
         u32x4_trunc_sat_f32x4, v128, v128_bitselect,
     },
-    ops::{AddAssign, Mul},
+    ops::{Add, AddAssign, Mul},
     ptr,
 };
 
@@ -56,6 +57,25 @@ impl U32x4 {
         U32x4(u32x4_lt(self.0, other.0))
     }
 
+    /// Divides the lanes and rounds the quotients to the nearest integer.
+    ///
+    /// There is no integer division, so the quotients are approximated with floats and corrected
+    /// afterwards. This is exact as long as the quotients are below 2^22.
+    #[target_feature(enable = "simd128")]
+    pub fn div_round(self, divisor: U32x4) -> U32x4 {
+        let dividend = u32x4_add(self.0, u32x4_shr(divisor.0, 1));
+        let quotient = u32x4_trunc_sat_f32x4(f32x4_div(
+            f32x4_convert_u32x4(dividend),
+            f32x4_convert_u32x4(divisor.0),
+        ));
+        // The approximation is off by at most one. The masks are -1 where a lane is off.
+        let too_big = u32x4_gt(i32x4_mul(quotient, divisor.0), dividend);
+        let quotient = i32x4_add(quotient, too_big);
+        let next = i32x4_add(i32x4_mul(quotient, divisor.0), divisor.0);
+        let too_small = u32x4_le(next, dividend);
+        U32x4(i32x4_sub(quotient, too_small))
+    }
+
     pub fn to_array(self) -> [u32; 4] {
         self.into()
     }
@@ -83,7 +103,7 @@ impl From<F32x4> for U32x4 {
 
 impl From<U32x4> for [u32; 4] {
     fn from(value: U32x4) -> Self {
-        unsafe { ptr::read_unaligned(&value as *const U32x4 as *const [u32; 4]) }
+        unsafe { ptr::read_unaligned(ptr::addr_of!(value).cast::<[u32; 4]>()) }
     }
 }
 
@@ -116,6 +136,15 @@ impl From<U32x4> for F32x4 {
     }
 }
 
+impl Add for F32x4 {
+    type Output = F32x4;
+
+    #[target_feature(enable = "simd128")]
+    fn add(self, rhs: Self) -> Self::Output {
+        F32x4(f32x4_add(self.0, rhs.0))
+    }
+}
+
 impl Mul for F32x4 {
     type Output = F32x4;
 
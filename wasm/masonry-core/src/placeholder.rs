@@ -0,0 +1,180 @@
+//! Encodes tiny placeholders of thumbnails in the `ThumbHash` format, which are shown in the
+//! rectangles of the items while their thumbnails load.
+//!
+//! A hash takes about 25 bytes and keeps the average color, the rough shape and the aspect ratio
+//! of the image. Any `ThumbHash` decoder, e.g. the `thumbhash` package, renders it as a blurry
+//! image of up to 32 pixels per side, which the browser scales to the transform of the item.
+use alloc::{vec, vec::Vec};
+use core::f64::consts::{PI, TAU};
+
+use crate::convert::{f64_to_u32, usize_to_f64};
+
+/// Images with more pixels per side take longer to encode without improving the placeholder.
+pub const MAX_PLACEHOLDER_SIZE: usize = 100;
+
+/// Returns the `ThumbHash` of an image whose pixels are given as red, green, blue and alpha bytes
+/// row by row, e.g. the data of an `ImageData`, or `None` if the image is empty, larger than
+/// [`MAX_PLACEHOLDER_SIZE`] on a side or has fewer pixels than its size.
+#[must_use]
+pub fn encode(pixels: &[u8], width: usize, height: usize) -> Option<Vec<u8>> {
+    let num_pixels = width * height;
+    if num_pixels == 0
+        || width > MAX_PLACEHOLDER_SIZE
+        || height > MAX_PLACEHOLDER_SIZE
+        || pixels.len() < 4 * num_pixels
+    {
+        return None;
+    }
+    let pixels = &pixels[..4 * num_pixels];
+
+    // The average color of the opaque parts, which the transparent parts are composited on.
+    let mut average = [0.0; 3];
+    let mut total_alpha = 0.0;
+    for pixel in pixels.chunks_exact(4) {
+        let alpha = f64::from(pixel[3]) / 255.0;
+        for (sum, &value) in average.iter_mut().zip(pixel) {
+            *sum += alpha / 255.0 * f64::from(value);
+        }
+        total_alpha += alpha;
+    }
+    if total_alpha > 0.0 {
+        average = average.map(|sum| sum / total_alpha);
+    }
+    let has_alpha = total_alpha < usize_to_f64(num_pixels);
+
+    // Luminance, yellow-blue, red-green and alpha.
+    let mut channels = [vec![], vec![], vec![], vec![]];
+    for pixel in pixels.chunks_exact(4) {
+        let alpha = f64::from(pixel[3]) / 255.0;
+        let [r, g, b] = [0, 1, 2]
+            .map(|lane| average[lane] * (1.0 - alpha) + alpha / 255.0 * f64::from(pixel[lane]));
+        channels[0].push((r + g + b) / 3.0);
+        channels[1].push((r + g) / 2.0 - b);
+        channels[2].push(r - g);
+        channels[3].push(alpha);
+    }
+
+    // Fewer luminance factors leave room for the alpha channel.
+    let luminance_limit = if has_alpha { 5.0 } else { 7.0 };
+    let longer_side = usize_to_f64(width.max(height));
+    let lx = round(luminance_limit * usize_to_f64(width) / longer_side).max(1);
+    let ly = round(luminance_limit * usize_to_f64(height) / longer_side).max(1);
+    let image = Image { width, height };
+    let l = image.encode_channel(&channels[0], lx.max(3), ly.max(3));
+    let p = image.encode_channel(&channels[1], 3, 3);
+    let q = image.encode_channel(&channels[2], 3, 3);
+
+    let is_landscape = width > height;
+    let header24 = round(63.0 * l.dc)
+        | round(31.5 + 31.5 * p.dc) << 6
+        | round(31.5 + 31.5 * q.dc) << 12
+        | round(31.0 * l.scale) << 18
+        | u32::from(has_alpha) << 23;
+    let header16 = (if is_landscape { ly } else { lx })
+        | round(63.0 * p.scale) << 3
+        | round(63.0 * q.scale) << 9
+        | u32::from(is_landscape) << 15;
+    let mut hash = Vec::with_capacity(32);
+    hash.extend_from_slice(&header24.to_le_bytes()[..3]);
+    hash.extend_from_slice(&header16.to_le_bytes()[..2]);
+    let mut factors = vec![l, p, q];
+    if has_alpha {
+        let a = image.encode_channel(&channels[3], 5, 5);
+        hash.push(u8::try_from(round(15.0 * a.dc) | round(15.0 * a.scale) << 4).unwrap_or(u8::MAX));
+        factors.push(a);
+    }
+
+    // Two factors per byte, the first one in the lower half.
+    let start = hash.len();
+    for (index, &factor) in factors.iter().flat_map(|channel| &channel.ac).enumerate() {
+        if index % 2 == 0 {
+            hash.push(0);
+        }
+        hash[start + index / 2] |=
+            u8::try_from(round(15.0 * factor) << (index % 2 * 4)).unwrap_or(u8::MAX);
+    }
+    Some(hash)
+}
+
+struct Image {
+    width: usize,
+    height: usize,
+}
+
+/// A channel as the factors of the cosines of its discrete cosine transform.
+struct Channel {
+    /// The constant factor, which is the average of the channel.
+    dc: f64,
+    /// The other factors, divided by the largest one of them and scaled to the range 0 to 1.
+    ac: Vec<f64>,
+    scale: f64,
+}
+
+impl Image {
+    /// Returns the factors of the cosines with up to `nx` and `ny` half periods across the
+    /// image, leaving out the higher frequencies of both directions together.
+    fn encode_channel(&self, channel: &[f64], nx: u32, ny: u32) -> Channel {
+        let (width, height) = (self.width, self.height);
+        let mut dc = 0.0;
+        let mut ac = Vec::new();
+        let mut scale: f64 = 0.0;
+        let mut fx = vec![0.0; width];
+        for cy in 0..ny {
+            let mut cx = 0;
+            while cx * ny < nx * (ny - cy) {
+                for (x, fx) in fx.iter_mut().enumerate() {
+                    *fx = cos(PI / usize_to_f64(width) * f64::from(cx) * (usize_to_f64(x) + 0.5));
+                }
+                let mut factor = 0.0;
+                for (y, row) in channel.chunks_exact(width).enumerate() {
+                    let fy =
+                        cos(PI / usize_to_f64(height) * f64::from(cy) * (usize_to_f64(y) + 0.5));
+                    for (&value, &fx) in row.iter().zip(&fx) {
+                        factor += value * fx * fy;
+                    }
+                }
+                factor /= usize_to_f64(width * height);
+                if cx > 0 || cy > 0 {
+                    ac.push(factor);
+                    scale = scale.max(if factor < 0.0 { -factor } else { factor });
+                } else {
+                    dc = factor;
+                }
+                cx += 1;
+            }
+        }
+        if scale > 0.0 {
+            for factor in &mut ac {
+                *factor = 0.5 + 0.5 / scale * *factor;
+            }
+        }
+        Channel { dc, ac, scale }
+    }
+}
+
+/// Rounds a value that is not negative to the nearest integer, halves up like `Math.round()`.
+fn round(value: f64) -> u32 {
+    f64_to_u32(value + 0.5)
+}
+
+/// The cosine of an angle that is not negative, which `core` does not provide.
+fn cos(angle: f64) -> f64 {
+    let mut angle = angle % TAU;
+    if angle > PI {
+        angle = TAU - angle;
+    }
+    // cos(x) = -cos(pi - x), so the series only has to converge up to pi / 2.
+    let (angle, sign) = if angle > PI / 2.0 {
+        (PI - angle, -1.0)
+    } else {
+        (angle, 1.0)
+    };
+    let square = angle * angle;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for n in 1..=8 {
+        term *= -square / f64::from((2 * n - 1) * (2 * n));
+        sum += term;
+    }
+    sign * sum
+}
@@ -0,0 +1,228 @@
+//! Reads the pixel dimensions and EXIF orientation from the headers of image files without
+//! decoding them, e.g. to lay out the files of an import before their thumbnails exist.
+//!
+//! JPEG, PNG, `WebP` and TIFF are recognized. Only the first few kilobytes are needed in most
+//! cases: everything up to the frame header of a JPEG, the header chunks of a `WebP` and the first
+//! directory of a TIFF.
+use crate::data::ImageInfo;
+
+/// The orientation of images without EXIF orientation.
+const UPRIGHT: u8 = 1;
+
+/// Returns the dimensions and orientation of the image whose file starts with the bytes, or
+/// `None` if it is not one of the recognized formats or the header is cut off.
+///
+/// The dimension is the one stored in the file, before the orientation is applied, like the one
+/// expected by [`crate::Layout::set_dimension()`] together with
+/// [`crate::Layout::set_orientation()`].
+#[must_use]
+pub fn image_info(bytes: &[u8]) -> Option<ImageInfo> {
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        probe_jpeg(bytes)
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        probe_png(bytes)
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        probe_webp(bytes)
+    } else if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+        let tiff = probe_tiff(bytes)?;
+        Some(ImageInfo {
+            width: tiff.width?,
+            height: tiff.height?,
+            orientation: tiff.orientation,
+        })
+    } else {
+        None
+    }
+}
+
+fn probe_png(bytes: &[u8]) -> Option<ImageInfo> {
+    // The first chunk is always the image header.
+    if bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    Some(ImageInfo {
+        width: be_u32(bytes, 16)?,
+        height: be_u32(bytes, 20)?,
+        orientation: UPRIGHT,
+    })
+}
+
+fn probe_jpeg(bytes: &[u8]) -> Option<ImageInfo> {
+    let mut orientation = UPRIGHT;
+    let mut offset = 2;
+    loop {
+        // Markers can be preceded by any number of fill bytes.
+        while *bytes.get(offset)? == 0xFF && *bytes.get(offset + 1)? == 0xFF {
+            offset += 1;
+        }
+        if *bytes.get(offset)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(offset + 1)?;
+        offset += 2;
+        // Markers without a segment.
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            continue;
+        }
+        let len = usize::from(be_u16(bytes, offset)?);
+        let segment = bytes.get(offset + 2..offset + len)?;
+        match marker {
+            // The start of a frame, apart from the markers that share the range.
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                return Some(ImageInfo {
+                    width: u32::from(be_u16(segment, 3)?),
+                    height: u32::from(be_u16(segment, 1)?),
+                    orientation,
+                });
+            }
+            0xE1 if segment.starts_with(b"Exif\0\0") => {
+                if let Some(tiff) = probe_tiff(&segment[6..]) {
+                    orientation = tiff.orientation;
+                }
+            }
+            // The compressed data starts without a frame before it.
+            0xD9 | 0xDA => return None,
+            _ => {}
+        }
+        offset += len;
+    }
+}
+
+fn probe_webp(bytes: &[u8]) -> Option<ImageInfo> {
+    let mut canvas = None;
+    let mut orientation = UPRIGHT;
+    let mut offset = 12;
+    while let Some(name) = bytes.get(offset..offset + 4) {
+        let len = match le_u32(bytes, offset + 4) {
+            Some(len) => len as usize,
+            None => break,
+        };
+        let start = offset + 8;
+        let data = match bytes.get(start..start.saturating_add(len)) {
+            Some(data) => data,
+            // Only the header chunks are needed, the image data can be cut off.
+            None => bytes.get(start..).unwrap_or_default(),
+        };
+        match name {
+            // Lossy: a key frame starts with its size after the frame tag and start code.
+            b"VP8 " if canvas.is_none() && data.get(3..6)? == [0x9D, 0x01, 0x2A] => {
+                return Some(ImageInfo {
+                    width: u32::from(le_u16(data, 6)? & 0x3FFF),
+                    height: u32::from(le_u16(data, 8)? & 0x3FFF),
+                    orientation,
+                });
+            }
+            // Lossless: the size minus one in two 14-bit fields after the signature.
+            b"VP8L" if canvas.is_none() && *data.first()? == 0x2F => {
+                let bits = le_u32(data, 1)?;
+                return Some(ImageInfo {
+                    width: (bits & 0x3FFF) + 1,
+                    height: ((bits >> 14) & 0x3FFF) + 1,
+                    orientation,
+                });
+            }
+            // Extended: the canvas size minus one in 24 bits each. The EXIF chunk comes after the
+            // frames, so it is looked for until the end of the bytes.
+            b"VP8X" => {
+                let width = le_u32(data, 4)? & 0x00FF_FFFF;
+                let height = le_u32(data, 6)? >> 8;
+                canvas = Some((width + 1, height + 1));
+            }
+            b"EXIF" => {
+                // Some encoders keep the marker of the JPEG segment.
+                let tiff = data.strip_prefix(b"Exif\0\0").unwrap_or(data);
+                if let Some(tiff) = probe_tiff(tiff) {
+                    orientation = tiff.orientation;
+                }
+            }
+            _ => {}
+        }
+        if data.len() < len {
+            break;
+        }
+        // Chunks are padded to an even size.
+        offset = start + len + len % 2;
+    }
+    let (width, height) = canvas?;
+    Some(ImageInfo {
+        width,
+        height,
+        orientation,
+    })
+}
+
+/// The tags of the first directory of a TIFF file.
+struct Tiff {
+    width: Option<u32>,
+    height: Option<u32>,
+    orientation: u8,
+}
+
+/// Reads the first image file directory of a TIFF file, which is also the format of EXIF data.
+fn probe_tiff(tiff: &[u8]) -> Option<Tiff> {
+    let little_endian = match tiff.get(..4)? {
+        b"II*\0" => true,
+        b"MM\0*" => false,
+        _ => return None,
+    };
+    let u16_at = |offset| {
+        if little_endian {
+            le_u16(tiff, offset)
+        } else {
+            be_u16(tiff, offset)
+        }
+    };
+    let u32_at = |offset| {
+        if little_endian {
+            le_u32(tiff, offset)
+        } else {
+            be_u32(tiff, offset)
+        }
+    };
+    let directory = u32_at(4)? as usize;
+    let mut result = Tiff {
+        width: None,
+        height: None,
+        orientation: UPRIGHT,
+    };
+    for entry in 0..usize::from(u16_at(directory)?) {
+        let entry = directory + 2 + 12 * entry;
+        // A short value is stored in the first two bytes of the value field.
+        let value = match u16_at(entry + 2)? {
+            3 => u32::from(u16_at(entry + 8)?),
+            4 => u32_at(entry + 8)?,
+            _ => continue,
+        };
+        match u16_at(entry)? {
+            0x0100 => result.width = Some(value),
+            0x0101 => result.height = Some(value),
+            0x0112 => {
+                if let Ok(orientation @ 1..=8) = u8::try_from(value) {
+                    result.orientation = orientation;
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(result)
+}
+
+fn be_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let bytes = bytes.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn le_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let bytes = bytes.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn be_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn le_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
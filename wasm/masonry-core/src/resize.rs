@@ -0,0 +1,72 @@
+//! Scales decoded images down to thumbnails by averaging the pixels that every thumbnail pixel
+//! covers.
+//!
+//! Decoding and encoding are left to the callers, e.g. the optional decoders of the `masonry`
+//! crate or `createImageBitmap()` and `OffscreenCanvas.convertToBlob()` in a browser.
+use alloc::{vec, vec::Vec};
+
+/// Returns the size of the thumbnail of an image, which keeps the aspect ratio and is at most
+/// `max_edge` pixels on its longer side. Images are never scaled up and no side is shorter than
+/// 1 pixel.
+#[must_use]
+pub fn thumbnail_size(width: usize, height: usize, max_edge: usize) -> (usize, usize) {
+    let longer_side = width.max(height);
+    if longer_side <= max_edge || max_edge == 0 {
+        return (width, height);
+    }
+    // Rounded to the nearest pixel.
+    let scale = |side: usize| ((2 * side * max_edge + longer_side) / (2 * longer_side)).max(1);
+    (scale(width), scale(height))
+}
+
+/// Scales an image down to the size returned by [`thumbnail_size()`] and returns its pixels, or
+/// `None` if it is empty or has fewer pixels than its size.
+///
+/// The pixels are given and returned as red, green, blue and alpha bytes row by row. The colors
+/// are weighted by their opacity, so transparent pixels do not darken the edges around them.
+#[must_use]
+pub fn downscale(pixels: &[u8], width: usize, height: usize, max_edge: usize) -> Option<Vec<u8>> {
+    if width == 0 || height == 0 || pixels.len() < 4 * width * height {
+        return None;
+    }
+    let (thumbnail_width, thumbnail_height) = thumbnail_size(width, height, max_edge);
+    if (thumbnail_width, thumbnail_height) == (width, height) {
+        return Some(pixels[..4 * width * height].to_vec());
+    }
+
+    let mut thumbnail = vec![0; 4 * thumbnail_width * thumbnail_height];
+    // The weighted sums of a row of thumbnail pixels and their opacity.
+    let mut sums = vec![[0_u64; 4]; thumbnail_width];
+    // The first column of every thumbnail pixel and the one after it, which partition the row.
+    let columns: Vec<usize> = (0..=thumbnail_width)
+        .map(|x| x * width / thumbnail_width)
+        .collect();
+    for y in 0..thumbnail_height {
+        let rows = y * height / thumbnail_height..(y + 1) * height / thumbnail_height;
+        let num_rows = rows.len();
+        sums.fill([0; 4]);
+        for row in pixels[4 * width * rows.start..4 * width * rows.end].chunks_exact(4 * width) {
+            for (sum, columns) in sums.iter_mut().zip(columns.windows(2)) {
+                for pixel in row[4 * columns[0]..4 * columns[1]].chunks_exact(4) {
+                    let alpha = u64::from(pixel[3]);
+                    for (sum, &value) in sum.iter_mut().zip(&pixel[..3]) {
+                        *sum += alpha * u64::from(value);
+                    }
+                    sum[3] += alpha;
+                }
+            }
+        }
+        let out = &mut thumbnail[4 * thumbnail_width * y..4 * thumbnail_width * (y + 1)];
+        for ((pixel, sum), columns) in out.chunks_exact_mut(4).zip(&sums).zip(columns.windows(2)) {
+            let alpha = sum[3];
+            let covered = (num_rows * (columns[1] - columns[0])) as u64;
+            if alpha > 0 {
+                for (value, &sum) in pixel.iter_mut().zip(&sum[..3]) {
+                    *value = u8::try_from((sum + alpha / 2) / alpha).unwrap_or(u8::MAX);
+                }
+            }
+            pixel[3] = u8::try_from((alpha + covered / 2) / covered).unwrap_or(u8::MAX);
+        }
+    }
+    Some(thumbnail)
+}
@@ -0,0 +1,142 @@
+//! A set of selected items whose ranges and marquees are resolved with the geometry of a
+//! [`Layout`], so that shift-click and drag-to-select behave the same in every layout kind.
+//!
+//! The indices are positions in the layout like the ones returned by the queries.
+use alloc::vec::Vec;
+
+use crate::layout::Layout;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// The selected items as a bit set.
+#[derive(Clone, Default)]
+pub struct Selection {
+    words: Vec<u64>,
+    count: usize,
+}
+
+impl Selection {
+    #[must_use]
+    pub fn new() -> Selection {
+        Selection::default()
+    }
+
+    /// Returns the number of selected items.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    #[must_use]
+    pub fn contains(&self, index: usize) -> bool {
+        self.words
+            .get(index / WORD_BITS)
+            .map_or(false, |word| word & bit(index) != 0)
+    }
+
+    /// Selects or deselects the item at the given index.
+    pub fn set(&mut self, index: usize, selected: bool) {
+        if selected == self.contains(index) {
+            return;
+        }
+        if selected {
+            if self.words.len() <= index / WORD_BITS {
+                self.words.resize(index / WORD_BITS + 1, 0);
+            }
+            self.count += 1;
+        } else {
+            self.count -= 1;
+        }
+        self.words[index / WORD_BITS] ^= bit(index);
+    }
+
+    /// Flips the item at the given index and returns whether it is selected afterwards, e.g. for
+    /// a click with the modifier key that adds to the selection.
+    pub fn toggle(&mut self, index: usize) -> bool {
+        let selected = !self.contains(index);
+        self.set(index, selected);
+        selected
+    }
+
+    pub fn clear(&mut self) {
+        self.words.clear();
+        self.count = 0;
+    }
+
+    /// Returns the selected indices in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words
+            .iter()
+            .enumerate()
+            .flat_map(|(word_index, &word)| {
+                (0..WORD_BITS)
+                    .filter(move |bit| word & (1 << bit) != 0)
+                    .map(move |bit| word_index * WORD_BITS + bit)
+            })
+    }
+
+    /// Selects the items from `anchor` to `focus` in reading order, see
+    /// [`Layout::items_between()`], e.g. for a shift-click. Items that are selected already stay
+    /// selected.
+    pub fn select_range(&mut self, layout: &Layout, anchor: usize, focus: usize) {
+        for index in layout.items_between(anchor, focus) {
+            self.set(index, true);
+        }
+    }
+
+    /// Selects the items that overlap the rectangle, see [`Layout::items_in_rect()`], e.g. for a
+    /// marquee. Items that are selected already stay selected.
+    pub fn select_rect(&mut self, layout: &Layout, x: u32, y: u32, width: u32, height: u32) {
+        for index in layout.items_in_rect(x, y, width, height) {
+            self.set(index, true);
+        }
+    }
+
+    /// Shifts the selection like [`Layout::insert()`] shifts the items.
+    pub fn insert(&mut self, index: usize, count: usize) {
+        self.remap(|selected| {
+            Some(if selected >= index {
+                selected + count
+            } else {
+                selected
+            })
+        });
+    }
+
+    /// Shifts the selection like [`Layout::remove()`] shifts the items and drops the removed ones.
+    pub fn remove(&mut self, index: usize, count: usize) {
+        self.remap(|selected| match selected.checked_sub(index) {
+            None => Some(selected),
+            Some(offset) if offset < count => None,
+            Some(_) => Some(selected - count),
+        });
+    }
+
+    /// Moves the selection like [`Layout::move_items()`] moves the items, for a valid range.
+    pub fn move_items(&mut self, from: usize, count: usize, to: usize) {
+        self.remap(|selected| {
+            Some(if (from..from + count).contains(&selected) {
+                selected - from + to
+            } else if to < from && (to..from).contains(&selected) {
+                selected + count
+            } else if to > from && (from + count..to + count).contains(&selected) {
+                selected - count
+            } else {
+                selected
+            })
+        });
+    }
+
+    /// Replaces every selected index with the one returned by `f`, or deselects it for `None`.
+    fn remap(&mut self, f: impl Fn(usize) -> Option<usize>) {
+        let selected: Vec<usize> = self.iter().filter_map(f).collect();
+        self.clear();
+        for index in selected {
+            self.set(index, true);
+        }
+    }
+}
+
+fn bit(index: usize) -> u64 {
+    1 << (index % WORD_BITS)
+}
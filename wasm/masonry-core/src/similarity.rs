@@ -0,0 +1,66 @@
+//! Perceptual hashes of images, which are equal or differ in few bits for images that look alike,
+//! e.g. to find duplicates that were resized or compressed again.
+use alloc::vec::Vec;
+
+/// Columns of the grid that is compared, one more than the number of bits per row.
+const GRID_WIDTH: usize = 9;
+const GRID_HEIGHT: usize = 8;
+
+/// Returns the difference hash (dHash) of an image, or `None` if it is empty or has fewer pixels
+/// than its size.
+///
+/// The pixels are given as red, green, blue and alpha bytes row by row, e.g. the data of an
+/// `ImageData`. The image is scaled to 9 by 8 gray values, composited on white, and every bit
+/// tells whether a value is brighter than the one to its right. Images that look alike have
+/// hashes that differ in few bits, see [`hash_distance()`]. Any size works, but scaling the image
+/// down to a thumbnail first is faster and hardly changes the hash.
+#[must_use]
+pub fn perceptual_hash(pixels: &[u8], width: usize, height: usize) -> Option<u64> {
+    if width == 0 || height == 0 || pixels.len() < 4 * width * height {
+        return None;
+    }
+    // Luma in thousandths, with the transparent parts white.
+    let gray: Vec<u32> = pixels[..4 * width * height]
+        .chunks_exact(4)
+        .map(|pixel| {
+            let luma =
+                299 * u32::from(pixel[0]) + 587 * u32::from(pixel[1]) + 114 * u32::from(pixel[2]);
+            let alpha = u32::from(pixel[3]);
+            (luma * alpha + 255_000 * (255 - alpha)) / 255
+        })
+        .collect();
+
+    // The cells partition the image, or repeat pixels if it is smaller than the grid.
+    let cell = |index: usize, cells: usize, len: usize| {
+        let start = index * len / cells;
+        start..(((index + 1) * len) / cells).max(start + 1)
+    };
+    let mut grid = [[0; GRID_WIDTH]; GRID_HEIGHT];
+    for (y, row) in grid.iter_mut().enumerate() {
+        let rows = cell(y, GRID_HEIGHT, height);
+        for (x, value) in row.iter_mut().enumerate() {
+            let columns = cell(x, GRID_WIDTH, width);
+            let sum: u64 = rows
+                .clone()
+                .flat_map(|y| gray[y * width + columns.start..y * width + columns.end].iter())
+                .map(|&gray| u64::from(gray))
+                .sum();
+            *value = sum / (rows.len() * columns.len()) as u64;
+        }
+    }
+
+    let mut hash = 0;
+    for row in &grid {
+        for pair in row.windows(2) {
+            hash = hash << 1 | u64::from(pair[0] > pair[1]);
+        }
+    }
+    Some(hash)
+}
+
+/// Returns the number of bits in which two hashes of [`perceptual_hash()`] differ. Up to about 10
+/// of the 64 bits usually means the images look alike.
+#[must_use]
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
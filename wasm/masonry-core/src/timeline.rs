@@ -0,0 +1,165 @@
+//! Groups items into chronological sections by their timestamps, e.g. for a timeline of photos
+//! with a header per day, month or year.
+//!
+//! The granularity follows from the density of the timestamps: the finest one whose sections hold
+//! at least [`MIN_ITEMS_PER_SECTION`] items on average, so a single trip gets a header per day and
+//! a decade of sparse imports one per year.
+use alloc::vec::Vec;
+
+use crate::convert::usize_to_u32;
+use crate::data::{TimelineGranularity, TimelineSection};
+
+/// Average number of items per section below which the next coarser granularity is chosen.
+pub const MIN_ITEMS_PER_SECTION: usize = 8;
+
+const MS_PER_MINUTE: i64 = 60_000;
+const MS_PER_DAY: i64 = 24 * 60 * MS_PER_MINUTE;
+
+/// The items of a timeline in their order and the sections they are grouped into, see
+/// [`timeline()`].
+pub struct Timeline {
+    pub granularity: TimelineGranularity,
+    /// Indices of the items, the newest first and the ones without a timestamp last.
+    pub order: Vec<u32>,
+    /// The sections in the same order, which split `order` into consecutive runs.
+    pub sections: Vec<TimelineSection>,
+}
+
+/// Sorts the items by their timestamps in milliseconds since the Unix epoch, the newest first,
+/// and groups them into sections of the granularity that suits their density.
+///
+/// Days, months and years start at midnight in the time zone with the given offset to UTC in
+/// minutes, e.g. `-new Date().getTimezoneOffset()`. Items whose timestamp is not finite, e.g.
+/// `NaN` for photos without a date, form a last section whose start is `NaN`. Items with equal
+/// timestamps keep their order.
+#[must_use]
+pub fn timeline(timestamps: &[f64], utc_offset_minutes: i32) -> Timeline {
+    let offset = i64::from(utc_offset_minutes) * MS_PER_MINUTE;
+    // Local days since the epoch, or `None` without a timestamp.
+    let days: Vec<Option<i64>> = timestamps
+        .iter()
+        .map(|&timestamp| {
+            timestamp
+                .is_finite()
+                .then(|| (whole_ms(timestamp) + offset).div_euclid(MS_PER_DAY))
+        })
+        .collect();
+    let mut order: Vec<u32> = (0..usize_to_u32(timestamps.len())).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (timestamps[a as usize], timestamps[b as usize]);
+        match (a.is_finite(), b.is_finite()) {
+            (true, true) => b.partial_cmp(&a).unwrap_or(core::cmp::Ordering::Equal),
+            (a, b) => b.cmp(&a),
+        }
+    });
+
+    let granularity = [
+        TimelineGranularity::Day,
+        TimelineGranularity::Month,
+        TimelineGranularity::Year,
+    ]
+    .into_iter()
+    .find(|&granularity| {
+        let num_sections = sections(&order, &days, granularity).count();
+        num_sections * MIN_ITEMS_PER_SECTION <= order.len()
+    })
+    .unwrap_or(TimelineGranularity::Year);
+
+    let mut start = 0;
+    let sections = sections(&order, &days, granularity)
+        .map(|(key, len)| {
+            let first_day = key.map(|key| match granularity {
+                TimelineGranularity::Day => key,
+                TimelineGranularity::Month => {
+                    days_from_civil(key.div_euclid(12), key.rem_euclid(12) + 1, 1)
+                }
+                TimelineGranularity::Year => days_from_civil(key, 1, 1),
+            });
+            let section = TimelineSection {
+                start: first_day.map_or(f64::NAN, |day| ms_to_f64(day * MS_PER_DAY - offset)),
+                first: usize_to_u32(start),
+                len: usize_to_u32(len),
+            };
+            start += len;
+            section
+        })
+        .collect();
+    Timeline {
+        granularity,
+        order,
+        sections,
+    }
+}
+
+/// Returns the key of every run of items in the same section and the number of items in it. The
+/// key is the day since the epoch, the month since year 0 or the year.
+fn sections<'a>(
+    order: &'a [u32],
+    days: &'a [Option<i64>],
+    granularity: TimelineGranularity,
+) -> impl Iterator<Item = (Option<i64>, usize)> + 'a {
+    let keys = order.iter().map(move |&index| {
+        days[index as usize].map(|day| match granularity {
+            TimelineGranularity::Day => day,
+            TimelineGranularity::Month => {
+                let (year, month, _) = civil_from_days(day);
+                12 * year + month - 1
+            }
+            TimelineGranularity::Year => civil_from_days(day).0,
+        })
+    });
+    let mut keys = keys.peekable();
+    core::iter::from_fn(move || {
+        let key = keys.next()?;
+        let mut len = 1;
+        while keys.next_if_eq(&key).is_some() {
+            len += 1;
+        }
+        Some((key, len))
+    })
+}
+
+/// Returns the year, month and day of the day since the epoch in the proleptic Gregorian
+/// calendar.
+fn civil_from_days(day: i64) -> (i64, i64, i64) {
+    // Eras of 400 years that start on March 1st, so that the leap day is the last of a year.
+    let day = day + 719_468;
+    let era = day.div_euclid(146_097);
+    let day_of_era = day.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + i64::from(month <= 2);
+    (year, month, day_of_month)
+}
+
+/// Returns the whole milliseconds of a finite timestamp, which `Date` keeps below 2^53.
+#[allow(clippy::cast_possible_truncation)]
+fn whole_ms(timestamp: f64) -> i64 {
+    timestamp as i64
+}
+
+/// Returns a timestamp in milliseconds as `f64`, exact below 2^53 like `Date`.
+#[allow(clippy::cast_precision_loss)]
+fn ms_to_f64(ms: i64) -> f64 {
+    ms as f64
+}
+
+/// Returns the day since the epoch of the date in the proleptic Gregorian calendar, the inverse
+/// of [`civil_from_days()`].
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
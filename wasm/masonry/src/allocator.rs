@@ -0,0 +1,18 @@
+//! Replacement of the global allocator, chosen with a cargo feature.
+//!
+//! The standard library brings dlmalloc, which is larger than needed because the layout allocates
+//! few and mostly long-lived buffers. The main thread, the web worker thread and the helpers share
+//! the memory and allocate concurrently, so the allocators are always wrapped in a lock.
+
+#[cfg(all(feature = "talc", feature = "lol_alloc"))]
+compile_error!("the features `talc` and `lol_alloc` select different global allocators");
+
+#[cfg(feature = "talc")]
+#[global_allocator]
+static ALLOCATOR: talc::Talck<spin::Mutex<()>, talc::WasmHandler> =
+    talc::Talc::new(unsafe { talc::WasmHandler::new() }).lock();
+
+#[cfg(all(feature = "lol_alloc", not(feature = "talc")))]
+#[global_allocator]
+static ALLOCATOR: lol_alloc::LockedAllocator<lol_alloc::FreeListAllocator> =
+    lol_alloc::LockedAllocator::new(lol_alloc::FreeListAllocator::new());
@@ -0,0 +1,177 @@
+//! Decodes image files, scales them down to thumbnails and encodes them again, behind the
+//! `codecs` feature.
+//!
+//! JPEG and PNG files are decoded by the pure-Rust `jpeg-decoder` and `png` crates, so an import
+//! can generate its thumbnails in any web worker that initialized the module, like the functions
+//! of `image.rs`, without a canvas. The decoders add a few hundred kilobytes to the module, which
+//! is why they are optional.
+use alloc::{format, string::ToString, vec::Vec};
+use std::io::Cursor;
+
+use masonry_core::convert::usize_to_u32;
+use masonry_core::resize;
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+
+/// The quality of the JPEG thumbnails, out of 100.
+const JPEG_QUALITY: u8 = 85;
+
+/// The format of an image file, recognized by its first bytes.
+#[derive(Clone, Copy)]
+enum Format {
+    Jpeg,
+    Png,
+}
+
+impl Format {
+    fn detect(bytes: &[u8]) -> Option<Format> {
+        if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(Format::Jpeg)
+        } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Format::Png)
+        } else {
+            None
+        }
+    }
+}
+
+/// A decoded image with red, green, blue and alpha bytes row by row.
+struct Rgba {
+    pixels: Vec<u8>,
+    width: usize,
+    height: usize,
+}
+
+/// Decodes a JPEG or PNG file, scales it down to a thumbnail that is at most `max_edge` pixels
+/// on its longer side and returns it encoded in the same format, e.g. to generate the thumbnails
+/// of an import in a web worker.
+///
+/// JPEG thumbnails are encoded with a quality of 85 and PNG thumbnails keep their transparency.
+/// The size of the thumbnail is the one [`crate::image::thumbnail_size()`] returns, so images
+/// that are already small enough are only encoded again. Throws if the file is neither a JPEG nor
+/// a PNG file or cannot be decoded.
+#[wasm_bindgen]
+pub fn resize_thumbnail(bytes: &[u8], max_edge: u32) -> Result<Vec<u8>, JsValue> {
+    let format = Format::detect(bytes)
+        .ok_or_else(|| invalid_input("Invalid image: neither a JPEG nor a PNG file"))?;
+    let image = match format {
+        Format::Jpeg => decode_jpeg(bytes)?,
+        Format::Png => decode_png(bytes)?,
+    };
+    let (width, height) = resize::thumbnail_size(image.width, image.height, max_edge as usize);
+    let thumbnail = resize::downscale(&image.pixels, image.width, image.height, max_edge as usize)
+        .ok_or_else(|| invalid_input("Invalid image: the file has no pixels"))?;
+    match format {
+        Format::Jpeg => encode_jpeg(&thumbnail, width, height),
+        Format::Png => encode_png(&thumbnail, width, height),
+    }
+}
+
+fn decode_jpeg(bytes: &[u8]) -> Result<Rgba, JsValue> {
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+    let samples = decoder.decode().map_err(|error| invalid_image(&error))?;
+    let info = decoder
+        .info()
+        .ok_or_else(|| invalid_input("Invalid image: the JPEG file has no frame"))?;
+    let pixels = match info.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => to_rgba(&samples, 1, |gray| [gray[0]; 3]),
+        // 16 bit samples are in native byte order, of which the higher byte is kept.
+        jpeg_decoder::PixelFormat::L16 => to_rgba(&samples, 2, |gray| {
+            [u16::from_ne_bytes([gray[0], gray[1]]).to_be_bytes()[0]; 3]
+        }),
+        jpeg_decoder::PixelFormat::RGB24 => to_rgba(&samples, 3, |rgb| [rgb[0], rgb[1], rgb[2]]),
+        jpeg_decoder::PixelFormat::CMYK32 => to_rgba(&samples, 4, |cmyk| {
+            let black = 255 - u16::from(cmyk[3]);
+            // The quotient is at most 255.
+            #[allow(clippy::cast_possible_truncation)]
+            [0, 1, 2].map(|i| ((255 - u16::from(cmyk[i])) * black / 255) as u8)
+        }),
+    };
+    Ok(Rgba {
+        pixels,
+        width: usize::from(info.width),
+        height: usize::from(info.height),
+    })
+}
+
+fn decode_png(bytes: &[u8]) -> Result<Rgba, JsValue> {
+    let mut decoder = png::Decoder::new(Cursor::new(bytes));
+    // Palettes, transparency chunks and bit depths other than 8 are converted to 8 bit samples.
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|error| invalid_image(&error))?;
+    let mut samples = alloc::vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut samples)
+        .map_err(|error| invalid_image(&error))?;
+    let samples = &samples[..info.buffer_size()];
+    let pixels = match info.color_type {
+        png::ColorType::Grayscale => to_rgba(samples, 1, |gray| [gray[0]; 3]),
+        png::ColorType::GrayscaleAlpha => {
+            let mut pixels = to_rgba(samples, 2, |gray| [gray[0]; 3]);
+            for (pixel, gray) in pixels.chunks_exact_mut(4).zip(samples.chunks_exact(2)) {
+                pixel[3] = gray[1];
+            }
+            pixels
+        }
+        png::ColorType::Rgb => to_rgba(samples, 3, |rgb| [rgb[0], rgb[1], rgb[2]]),
+        png::ColorType::Rgba => samples.to_vec(),
+        png::ColorType::Indexed => {
+            return Err(invalid_input(
+                "Invalid image: the PNG palette was not expanded",
+            ));
+        }
+    };
+    Ok(Rgba {
+        pixels,
+        width: info.width as usize,
+        height: info.height as usize,
+    })
+}
+
+/// Converts opaque pixels of `channels` bytes each to red, green, blue and alpha bytes.
+fn to_rgba(samples: &[u8], channels: usize, rgb: impl Fn(&[u8]) -> [u8; 3]) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(samples.len() / channels * 4);
+    for pixel in samples.chunks_exact(channels) {
+        pixels.extend_from_slice(&rgb(pixel));
+        pixels.push(255);
+    }
+    pixels
+}
+
+fn encode_jpeg(thumbnail: &[u8], width: usize, height: usize) -> Result<Vec<u8>, JsValue> {
+    let too_large = || invalid_input("Invalid image: too large for a JPEG file");
+    let width = u16::try_from(width).map_err(|_| too_large())?;
+    let height = u16::try_from(height).map_err(|_| too_large())?;
+    let mut file = Vec::new();
+    jpeg_encoder::Encoder::new(&mut file, JPEG_QUALITY)
+        .encode(thumbnail, width, height, jpeg_encoder::ColorType::Rgba)
+        .map_err(|error| invalid_image(&error))?;
+    Ok(file)
+}
+
+fn encode_png(thumbnail: &[u8], width: usize, height: usize) -> Result<Vec<u8>, JsValue> {
+    let mut file = Vec::new();
+    let mut encoder = png::Encoder::new(&mut file, usize_to_u32(width), usize_to_u32(height));
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| {
+            writer.write_image_data(thumbnail)?;
+            writer.finish()
+        })
+        .map_err(|error| invalid_image(&error))?;
+    Ok(file)
+}
+
+fn invalid_image(error: &impl ToString) -> JsValue {
+    JsValue::from(Error::InvalidInput(format!(
+        "Invalid image: {}",
+        error.to_string()
+    )))
+}
+
+fn invalid_input(message: &str) -> JsValue {
+    JsValue::from(Error::InvalidInput(message.to_string()))
+}
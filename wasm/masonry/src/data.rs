@@ -1,46 +1,504 @@
+use alloc::{
+    boxed::Box,
+    rc::{Rc, Weak},
+    vec::Vec,
+};
+use core::{
+    cell::{Cell, RefCell},
+    ptr,
+};
+
+use masonry_core::convert::usize_to_u32;
+use masonry_core::selection::Selection;
+use masonry_core::{Layout, MasonryConfig, MasonryType, Pages, Transform};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::sync::{
+    failure, is_computing, progress_of, queue_microtask, set_timeout, when_idle, Thenable,
+};
 
-use crate::layout::Layout;
+/// Milliseconds between two reports of [`Progress`].
+const PROGRESS_INTERVAL: i32 = 100;
 
+#[derive(Clone, Copy)]
 pub struct Computation {
-    pub width: u16,
+    pub width: u32,
     pub config: MasonryConfig,
+    /// Whether only the items after the most recent computation are laid out, see
+    /// `Layout::compute_remaining()`.
+    pub remaining: bool,
+    /// The lane of the computation in the queue of the web worker threads.
+    pub priority: Priority,
     pub layout_ptr: *mut Layout,
+    /// Where the result is stored once the computation completed.
+    pub output_ptr: *mut ComputeResult,
+}
+
+impl Computation {
+    pub fn new(
+        width: u32,
+        config: MasonryConfig,
+        layout: &mut Layout,
+        output: &mut ComputeResult,
+    ) -> Computation {
+        Computation {
+            width,
+            config,
+            remaining: false,
+            priority: Priority::UserBlocking,
+            layout_ptr: layout as _,
+            output_ptr: output as _,
+        }
+    }
+
+    /// Lays out the rest of the items after a computation that stopped early.
+    pub fn remaining(self) -> Computation {
+        Computation {
+            remaining: true,
+            ..self
+        }
+    }
+
+    pub fn with_priority(self, priority: Priority) -> Computation {
+        Computation { priority, ..self }
+    }
+}
+
+/// How urgently a computation is needed, which decides the order in which the web worker threads
+/// take them, see `sync::queue()`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Priority {
+    /// The user waits for the result, e.g. after resizing the window. These computations are
+    /// taken first and preempt the ones in the background if every thread is busy.
+    UserBlocking,
+    /// The result may be needed later, e.g. a neighboring zoom level. These computations only run
+    /// once no user-blocking one is waiting, and the `Promise`s of the user-blocking ones do not
+    /// wait for them.
+    Background,
+}
+
+/// A copy of the layout with other arguments, which is either computed with a neighboring zoom
+/// level in the background (see `MasonryWorker::set_zoom_levels()`) or another thumbnail size to
+/// interpolate towards (see `MasonryWorker::interpolate()`), or kept from an earlier computation
+/// (see `MasonryWorker::set_cache_size()` and `MasonryWorker::transition()`).
+pub struct Precomputed {
+    pub width: u32,
+    pub config: MasonryConfig,
+    pub layout: Layout,
+    /// Where the result is stored once the computation completed.
+    pub result: ComputeResult,
 }
 
+/// Arguments of the most recent computation, which are included in layout snapshots.
+#[derive(Clone, Copy)]
+pub struct Request {
+    pub width: u32,
+    pub kind: MasonryType,
+    pub thumbnail_size: u32,
+    pub padding: u32,
+}
+
+/// Result of a computation, which the `Promise` of the computation resolves with.
 #[wasm_bindgen]
+#[derive(Clone, Copy, Default)]
+pub struct ComputeResult {
+    #[wasm_bindgen(js_name = containerHeight)]
+    pub height: u32,
+    /// Milliseconds the layout took, without the time the computation waited in the queue.
+    pub elapsed: f64,
+    #[wasm_bindgen(js_name = numItems)]
+    pub num_items: u32,
+    /// Number of rows, or 0 in a vertical masonry layout, see `LayoutStats::rows`.
+    pub rows: u32,
+    /// Number of items in the longest row, which is the column count of column layouts.
+    pub columns: u32,
+    /// Position of the first item that was laid out, see `Layout::laid_out_from()`. The items
+    /// before it kept their transforms, so only the items from there on have to be rendered again.
+    #[wasm_bindgen(js_name = firstDirtyIndex)]
+    pub first_dirty_index: u32,
+    /// Milliseconds it took to count the rows and columns after the layout.
+    #[wasm_bindgen(js_name = statsElapsed)]
+    pub stats_elapsed: f64,
+    /// Milliseconds it took to copy the transforms for the readers on other threads, see
+    /// `Layout::publish()`.
+    #[wasm_bindgen(js_name = publishElapsed)]
+    pub publish_elapsed: f64,
+}
+
+/// Rectangle of an item and the crop of its thumbnail, see `MasonryWorker::transform()`.
+#[wasm_bindgen(js_name = Transform)]
 #[derive(Clone, Copy)]
-pub enum MasonryType {
-    Vertical,
-    Horizontal,
-    Grid,
+pub struct ItemTransform {
+    pub width: u32,
+    pub height: u32,
+    pub top: u32,
+    pub left: u32,
+    /// How far the thumbnail overhangs the rectangle at the top.
+    #[wasm_bindgen(js_name = cropTop)]
+    pub crop_top: u32,
+    /// How far the thumbnail overhangs the rectangle on the left.
+    #[wasm_bindgen(js_name = cropLeft)]
+    pub crop_left: u32,
+    pub cropped: bool,
 }
 
-pub struct MasonryConfig {
-    pub kind: MasonryType,
-    pub thumbnail_size: u16,
-    pub padding: u16,
+/// What changed with an update of a marquee drag, see `MasonryWorker::drag_update()`.
+#[wasm_bindgen]
+pub struct DragUpdate {
+    /// Pixels to scroll by in this frame because the marquee reaches towards an edge of the
+    /// viewport, negative for up.
+    #[wasm_bindgen(js_name = autoscrollDelta)]
+    pub autoscroll_delta: f32,
+    entered: Vec<u32>,
+    left: Vec<u32>,
 }
 
-impl MasonryConfig {
-    pub const DEFAULT_THUMBNAIL_SIZE: u16 = 300;
-    pub const DEFAULT_PADDING: u16 = 8;
+#[wasm_bindgen]
+impl DragUpdate {
+    /// Indices of the items that the update selected.
+    #[wasm_bindgen(getter, js_name = enteredIndices)]
+    pub fn entered_indices(&self) -> Vec<u32> {
+        self.entered.clone()
+    }
+
+    /// Indices of the items that the update deselected because the marquee no longer covers them.
+    #[wasm_bindgen(getter, js_name = leftIndices)]
+    pub fn left_indices(&self) -> Vec<u32> {
+        self.left.clone()
+    }
+}
 
-    pub fn new(kind: MasonryType, thumbnail_size: u16, padding: u16) -> MasonryConfig {
-        MasonryConfig {
-            kind,
-            thumbnail_size,
-            padding,
+impl DragUpdate {
+    pub fn new(autoscroll_delta: f32, entered: Vec<u32>, left: Vec<u32>) -> DragUpdate {
+        DragUpdate {
+            autoscroll_delta,
+            entered,
+            left,
         }
     }
 }
 
-impl Computation {
-    pub fn new(width: u16, config: MasonryConfig, layout: &mut Layout) -> Computation {
-        Computation {
-            width,
-            config,
-            layout_ptr: layout as _,
+/// The layout split into pages, see `MasonryWorker::paginate()` and
+/// `MasonryWorker::split_into()`.
+#[wasm_bindgen]
+pub struct Pagination(Pages);
+
+#[wasm_bindgen]
+impl Pagination {
+    #[wasm_bindgen(getter, js_name = pageCount)]
+    pub fn page_count(&self) -> u32 {
+        usize_to_u32(self.0.len())
+    }
+
+    /// Positions of the items on the page in reading order, or an empty array if there are fewer
+    /// pages.
+    pub fn page_items(&self, page: usize) -> Vec<u32> {
+        self.0
+            .page(page)
+            .map_or_else(Vec::new, |(items, _)| items.to_vec())
+    }
+
+    /// Height of the page up to the bottom of its lowest item and the outer padding, e.g. for the
+    /// height of a pane, or 0 if there are fewer pages.
+    pub fn page_height(&self, page: usize) -> u32 {
+        self.0.height(page).unwrap_or(0)
+    }
+
+    /// Transforms of the items on the page relative to the top of the page, in the order of
+    /// [`Pagination::page_items()`]. Every item takes seven numbers like
+    /// `MasonryWorker::export_transforms()`.
+    pub fn page_transforms(&self, page: usize) -> Vec<u32> {
+        self.0.page(page).map_or_else(Vec::new, |(_, transforms)| {
+            transforms.iter().flat_map(Transform::to_lanes).collect()
+        })
+    }
+}
+
+impl From<Pages> for Pagination {
+    fn from(pages: Pages) -> Pagination {
+        Pagination(pages)
+    }
+}
+
+/// A marquee drag in progress, see `MasonryWorker::drag_update()`.
+pub struct Drag {
+    /// The items that were selected before the drag and stay selected, if it extends the
+    /// selection.
+    pub base: Selection,
+    /// Indices of the items under the marquee, in ascending order.
+    pub covered: Vec<usize>,
+}
+
+impl From<&Transform> for ItemTransform {
+    fn from(transform: &Transform) -> ItemTransform {
+        ItemTransform {
+            width: transform.width(),
+            height: transform.height(),
+            top: transform.top(),
+            left: transform.left(),
+            crop_top: transform.crop_top(),
+            crop_left: transform.crop_left(),
+            cropped: transform.is_cropped(),
+        }
+    }
+}
+
+impl ComputeResult {
+    pub fn new(height: u32, elapsed: f64, layout: &Layout) -> ComputeResult {
+        let stats = layout.stats();
+        ComputeResult {
+            height,
+            elapsed,
+            num_items: usize_to_u32(layout.num_items()),
+            rows: stats.rows,
+            columns: stats.columns,
+            first_dirty_index: usize_to_u32(layout.laid_out_from()),
+            stats_elapsed: 0.0,
+            publish_elapsed: 0.0,
+        }
+    }
+}
+
+/// Callbacks that are called with the result after every computation of a layout, see
+/// `MasonryWorker::on_layout()`.
+#[derive(Default)]
+pub struct Observers {
+    callbacks: RefCell<Vec<js_sys::Function>>,
+    /// Number of computations that were sent to the web worker thread. Coalesced computations
+    /// resolve together, so only the `Promise` of the most recent one notifies the callbacks.
+    sent: Cell<u32>,
+}
+
+impl Observers {
+    pub fn add(&self, callback: js_sys::Function) {
+        self.callbacks.borrow_mut().push(callback);
+    }
+
+    /// Removes the callback and returns whether it was added before.
+    pub fn remove(&self, callback: &js_sys::Function) -> bool {
+        let mut callbacks = self.callbacks.borrow_mut();
+        let len = callbacks.len();
+        callbacks.retain(|added| !js_sys::Object::is(added, callback));
+        callbacks.len() != len
+    }
+
+    pub fn clear(&self) {
+        self.callbacks.borrow_mut().clear();
+    }
+
+    /// Calls the callbacks with the result of a computation on the main thread once the current
+    /// call returned, so that they can use the worker.
+    pub fn notify(self: &Rc<Self>, result: ComputeResult) {
+        if self.callbacks.borrow().is_empty() {
+            return;
+        }
+        let observers = Rc::clone(self);
+        let call = Closure::once_into_js(move |result: JsValue| observers.call(&result));
+        drop(
+            js_sys::Promise::resolve(&JsValue::from(result))
+                .unchecked_ref::<Thenable>()
+                .then(&call),
+        );
+    }
+
+    /// Calls the callbacks with the result that the `Promise` of a computation on the web worker
+    /// thread resolves with, unless another computation was sent in the meantime.
+    pub fn notify_after(self: &Rc<Self>, promise: &js_sys::Promise) -> js_sys::Promise {
+        let ticket = self.send();
+        let observers = Rc::clone(self);
+        let call = Closure::once_into_js(move |result: JsValue| {
+            observers.notify_if_latest(ticket, &result);
+            result
+        });
+        promise.unchecked_ref::<Thenable>().then(&call)
+    }
+
+    /// Counts a computation that is sent to the web worker thread and returns its ticket for
+    /// [`Observers::notify_if_latest()`].
+    pub fn send(&self) -> u32 {
+        let ticket = self.sent.get().wrapping_add(1);
+        self.sent.set(ticket);
+        ticket
+    }
+
+    /// Calls the callbacks with the result of the computation with the ticket, unless another
+    /// computation was sent in the meantime.
+    pub fn notify_if_latest(&self, ticket: u32, result: &JsValue) {
+        if self.sent.get() == ticket {
+            self.call(result);
+        }
+    }
+
+    fn call(&self, result: &JsValue) {
+        // Callbacks may add or remove callbacks.
+        let callbacks = self.callbacks.borrow().clone();
+        for callback in callbacks {
+            // A callback that throws does not keep the others from being called.
+            drop(callback.call1(&JsValue::UNDEFINED, result));
+        }
+    }
+}
+
+/// Callback that reports the progress of long computations on the web worker thread, see
+/// `MasonryWorker::on_progress()`.
+#[derive(Default)]
+pub struct Progress {
+    callback: RefCell<Option<js_sys::Function>>,
+    /// Whether a computation is watched already.
+    watching: Cell<bool>,
+}
+
+impl Progress {
+    pub fn set_callback(&self, callback: Option<js_sys::Function>) {
+        *self.callback.borrow_mut() = callback;
+    }
+
+    /// Reports the progress of the layout with the given number of items regularly until no
+    /// computation of it is queued or in progress anymore.
+    ///
+    /// The layout is only compared with the computations, so it may be freed in the meantime.
+    pub fn watch(self: &Rc<Self>, layout: *const Layout, num_items: u32) {
+        if self.callback.borrow().is_some() && !self.watching.replace(true) {
+            self.schedule(layout, num_items);
+        }
+    }
+
+    fn schedule(self: &Rc<Self>, layout: *const Layout, num_items: u32) {
+        let progress = Rc::clone(self);
+        let report = Closure::once_into_js(move || progress.report(layout, num_items));
+        set_timeout(&report, PROGRESS_INTERVAL);
+    }
+
+    fn report(self: &Rc<Self>, layout: *const Layout, num_items: u32) {
+        let callback = self.callback.borrow().clone();
+        match callback {
+            Some(callback) if is_computing(layout) => {
+                let processed = progress_of(layout).min(num_items);
+                let percent = f64::from(processed) * 100.0 / f64::from(num_items.max(1));
+                // A callback that throws is called again with the next report.
+                drop(callback.call2(
+                    &JsValue::UNDEFINED,
+                    &JsValue::from(percent),
+                    &JsValue::from(processed),
+                ));
+                self.schedule(layout, num_items);
+            }
+            _ => self.watching.set(false),
+        }
+    }
+}
+
+/// Callbacks of `MasonryWorker::compute_with_callback()` that wait for their computation.
+///
+/// Two functions that are created once call the pending callbacks, so that calls do not create
+/// closures or `Promise`s of their own: one after a computation on the main thread and one once the
+/// web worker threads are idle, which all computations that are sent in the meantime share.
+pub struct Callbacks {
+    /// The callbacks and the error that they are called with instead of the result, if any.
+    pending: RefCell<Vec<(js_sys::Function, Option<JsValue>)>>,
+    /// Where the result of the computations is stored.
+    output: Cell<*const ComputeResult>,
+    observers: Rc<Observers>,
+    /// Ticket of the most recent computation that was sent, see [`Observers::send()`].
+    ticket: Cell<u32>,
+    /// Whether the pending callbacks wait for the web worker threads.
+    waiting: Cell<bool>,
+    /// Whether the pending callbacks are called in a microtask.
+    scheduled: Cell<bool>,
+    call_soon: JsValue,
+    call_when_idle: JsValue,
+}
+
+impl Callbacks {
+    pub fn new(observers: Rc<Observers>) -> Rc<Callbacks> {
+        Rc::new_cyclic(|callbacks: &Weak<Callbacks>| {
+            let weak = callbacks.clone();
+            let call_soon = Closure::wrap(Box::new(move || {
+                if let Some(callbacks) = weak.upgrade() {
+                    callbacks.scheduled.set(false);
+                    // Callbacks that were added in the meantime wait for the web worker threads.
+                    if !callbacks.waiting.get() {
+                        callbacks.call(None, false);
+                    }
+                }
+            }) as Box<dyn FnMut()>);
+            let weak = callbacks.clone();
+            let call_when_idle = Closure::wrap(Box::new(move |_: JsValue| {
+                if let Some(callbacks) = weak.upgrade() {
+                    callbacks.waiting.set(false);
+                    callbacks.call(failure().as_ref(), true);
+                }
+            }) as Box<dyn FnMut(JsValue)>);
+            Callbacks {
+                pending: RefCell::new(Vec::new()),
+                output: Cell::new(ptr::null()),
+                observers,
+                ticket: Cell::new(0),
+                waiting: Cell::new(false),
+                scheduled: Cell::new(false),
+                call_soon: call_soon.into_js_value(),
+                call_when_idle: call_when_idle.into_js_value(),
+            }
+        })
+    }
+
+    /// Calls the callback with the result of a computation on the main thread once the current
+    /// call returned, so that it can use the worker.
+    pub fn call_soon(&self, callback: js_sys::Function, output: &ComputeResult) {
+        self.output.set(output);
+        self.pending.borrow_mut().push((callback, None));
+        if !self.scheduled.replace(true) {
+            queue_microtask(&self.call_soon);
+        }
+    }
+
+    /// Calls the callback with the error once the current call returned.
+    pub fn fail(&self, callback: js_sys::Function, error: JsValue) {
+        self.pending.borrow_mut().push((callback, Some(error)));
+        if !self.scheduled.replace(true) {
+            queue_microtask(&self.call_soon);
+        }
+    }
+
+    /// Calls the callback with the result once the web worker threads finished the computation
+    /// that was just sent with the ticket of the observers.
+    pub fn call_when_idle(&self, callback: js_sys::Function, output: &ComputeResult, ticket: u32) {
+        self.output.set(output);
+        self.ticket.set(ticket);
+        self.pending.borrow_mut().push((callback, None));
+        if !self.waiting.replace(true) {
+            when_idle(&self.call_when_idle);
+        }
+    }
+
+    /// Drops the pending callbacks, e.g. because the worker that owns the output is freed.
+    pub fn clear(&self) {
+        self.pending.borrow_mut().clear();
+    }
+
+    /// Calls the pending callbacks with the result or the error, and the observers if the result
+    /// comes from the web worker threads. The computations on the main thread notified them.
+    fn call(&self, failure: Option<&JsValue>, sent: bool) {
+        let pending = self.pending.take();
+        if pending.is_empty() {
+            return;
+        }
+        // SAFETY: The worker that owns the output drops the pending callbacks before it is freed.
+        // Callbacks that failed right away may be pending before any output was set.
+        let result = unsafe { self.output.get().as_ref() }
+            .copied()
+            .unwrap_or_default();
+        if sent && failure.is_none() {
+            self.observers
+                .notify_if_latest(self.ticket.get(), &JsValue::from(result));
+        }
+        for (callback, error) in pending {
+            // A callback that throws does not keep the others from being called.
+            drop(match error.as_ref().or(failure) {
+                Some(error) => callback.call1(&JsValue::UNDEFINED, error),
+                None => callback.call2(&JsValue::UNDEFINED, &JsValue::NULL, &JsValue::from(result)),
+            });
         }
     }
 }
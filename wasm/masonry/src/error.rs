@@ -0,0 +1,92 @@
+//! The errors that the methods throw and the `Promise`s reject with.
+//!
+//! Every error is an `Error` whose `code` names the variant of [`Error`], e.g.
+//! `"InvalidConfig"`, so that callers can branch on the kind of failure instead of parsing the
+//! message, which is only meant for humans.
+use alloc::string::String;
+use wasm_bindgen::prelude::*;
+
+/// Why a call failed.
+pub enum Error {
+    /// A parameter of the config or the options cannot be laid out, see
+    /// `Layout::check_config()`.
+    InvalidConfig(String),
+    /// Data that was passed in cannot be read, e.g. a snapshot, a serialized layout or an image.
+    InvalidInput(String),
+    /// An index refers to an item that does not exist.
+    IndexOutOfBounds(String),
+    /// Data that was passed in does not hold a value for every item, e.g. imported transforms.
+    InvalidDimensions(String),
+    /// The call needs the layout while a computation of it is still pending.
+    Busy(String),
+    /// The web worker thread failed, see `MasonryWorker::fail()`.
+    WorkerFailed(String),
+    /// The web worker thread did not finish in time, see `MasonryWorker::set_compute_timeout()`.
+    Timeout(String),
+    /// The main thread panicked, see `MasonryWorker::on_panic()`.
+    #[cfg(feature = "panic-hook")]
+    Panicked(String),
+}
+
+impl Error {
+    /// Returns the `code` of the `Error`, which is the name of the variant.
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::InvalidConfig(_) => "InvalidConfig",
+            Error::InvalidInput(_) => "InvalidInput",
+            Error::IndexOutOfBounds(_) => "IndexOutOfBounds",
+            Error::InvalidDimensions(_) => "InvalidDimensions",
+            Error::Busy(_) => "Busy",
+            Error::WorkerFailed(_) => "WorkerFailed",
+            Error::Timeout(_) => "Timeout",
+            #[cfg(feature = "panic-hook")]
+            Error::Panicked(_) => "Panicked",
+        }
+    }
+
+    /// Returns the `name` of the `Error`, which the configuration, worker and timeout errors had
+    /// before they got a code as well.
+    fn name(&self) -> &'static str {
+        match self {
+            Error::InvalidConfig(_) => "MasonryConfigError",
+            Error::InvalidInput(_) => "MasonryInputError",
+            Error::IndexOutOfBounds(_) => "MasonryRangeError",
+            Error::InvalidDimensions(_) => "MasonryDimensionsError",
+            Error::Busy(_) => "MasonryBusyError",
+            Error::WorkerFailed(_) => "MasonryWorkerError",
+            Error::Timeout(_) => "MasonryTimeoutError",
+            #[cfg(feature = "panic-hook")]
+            Error::Panicked(_) => "MasonryPanicError",
+        }
+    }
+
+    // The arm of `Panicked` only exists with the `panic-hook` feature, so it cannot be merged.
+    #[allow(clippy::match_same_arms)]
+    fn message(&self) -> &str {
+        match self {
+            Error::InvalidConfig(message)
+            | Error::InvalidInput(message)
+            | Error::IndexOutOfBounds(message)
+            | Error::InvalidDimensions(message)
+            | Error::Busy(message)
+            | Error::WorkerFailed(message)
+            | Error::Timeout(message) => message,
+            #[cfg(feature = "panic-hook")]
+            Error::Panicked(message) => message,
+        }
+    }
+}
+
+impl From<Error> for JsValue {
+    fn from(error: Error) -> JsValue {
+        let js_error = js_sys::Error::new(error.message());
+        js_error.set_name(error.name());
+        drop(js_sys::Reflect::set(
+            &js_error,
+            &"code".into(),
+            &error.code().into(),
+        ));
+        JsValue::from(js_error)
+    }
+}
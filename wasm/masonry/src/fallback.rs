@@ -0,0 +1,180 @@
+//! Computations of workers whose web worker cannot share the memory, because `SharedArrayBuffer`
+//! is missing, e.g. on pages that are not cross-origin isolated.
+//!
+//! [`crate::spawn::MasonryWorkerBuilder`] selects this automatically. The web worker then
+//! instantiates the module on its own and keeps a `MasonryWorker` of its own instead of running
+//! [`crate::sync::run()`]. Every computation posts a snapshot of the layout together with a
+//! `MessagePort` for the reply, see
+//! [`crate::masonry_worker::MasonryWorker::compute_snapshot()`], and the packed transforms that
+//! come back are imported like with
+//! [`crate::masonry_worker::MasonryWorker::unpack_transforms()`].
+use alloc::{format, string::String, vec, vec::Vec};
+use masonry_core::{Layout, Transform};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::data::ComputeResult;
+use crate::error::Error;
+use crate::masonry_worker::Worker;
+use crate::sync::{compute_locally, Thenable};
+
+#[wasm_bindgen]
+extern "C" {
+    type MessageChannel;
+
+    #[wasm_bindgen(constructor)]
+    fn new() -> MessageChannel;
+
+    #[wasm_bindgen(method, getter)]
+    fn port1(this: &MessageChannel) -> MessagePort;
+
+    #[wasm_bindgen(method, getter)]
+    fn port2(this: &MessageChannel) -> MessagePort;
+
+    #[derive(Clone)]
+    type MessagePort;
+
+    #[wasm_bindgen(method, setter)]
+    fn set_onmessage(this: &MessagePort, handler: &JsValue);
+
+    #[wasm_bindgen(method)]
+    fn close(this: &MessagePort);
+}
+
+/// Returns whether the memory of the module can be shared with web workers.
+pub fn has_shared_memory() -> bool {
+    js_sys::Reflect::has(&js_sys::global(), &"SharedArrayBuffer".into()).unwrap_or(false)
+}
+
+/// Returns the message that starts the web worker without the memory: the `fallback` flag, so
+/// that `worker.js` instantiates the module with a memory of its own, and with `module` the
+/// compiled module, see [`crate::masonry_worker::init_message()`].
+pub fn init_message(module: bool) -> JsValue {
+    let message = js_sys::Object::new();
+    drop(js_sys::Reflect::set(
+        &message,
+        &"fallback".into(),
+        &JsValue::TRUE,
+    ));
+    if module {
+        drop(js_sys::Reflect::set(
+            &message,
+            &"module".into(),
+            &wasm_bindgen::module(),
+        ));
+    }
+    message.into()
+}
+
+/// Posts a snapshot of the layout to the web worker and returns a `Promise` that resolves with
+/// the result once the transforms of the reply are imported into the layout.
+///
+/// The `Promise` rejects with the error of the web worker if it could not compute the snapshot.
+/// The layout and the output must stay in place until then, like for the computations that are
+/// sent to [`crate::sync::run()`].
+pub fn send(
+    worker: &Worker,
+    snapshot: &str,
+    layout: *mut Layout,
+    output: *mut ComputeResult,
+) -> js_sys::Promise {
+    let channel = MessageChannel::new();
+    let port = channel.port1();
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let receiver = port.clone();
+        let on_reply = Closure::once_into_js(move |event: JsValue| {
+            receiver.close();
+            let data = js_sys::Reflect::get(&event, &"data".into()).unwrap_or(JsValue::UNDEFINED);
+            let imported = match data.dyn_into::<js_sys::ArrayBuffer>() {
+                // SAFETY: The caller awaits the `Promise`, so nothing else uses the layout and the
+                // output until it resolves.
+                Ok(buffer) => unsafe { import(&mut *layout, &mut *output, &buffer) },
+                Err(data) => {
+                    Err(js_sys::Reflect::get(&data, &"error".into()).unwrap_or(JsValue::UNDEFINED))
+                }
+            };
+            drop(match imported {
+                // SAFETY: See above.
+                Ok(()) => resolve.call1(&JsValue::NULL, &JsValue::from(unsafe { *output })),
+                Err(error) => reject.call1(&JsValue::NULL, &error),
+            });
+        });
+        port.set_onmessage(&on_reply);
+    });
+    let message = js_sys::Object::new();
+    let reply_port = JsValue::from(channel.port2());
+    for (name, value) in [
+        ("snapshot", JsValue::from_str(snapshot)),
+        ("port", reply_port.clone()),
+    ] {
+        drop(js_sys::Reflect::set(
+            &message,
+            &JsValue::from_str(name),
+            &value,
+        ));
+    }
+    worker.post_message_with_transfer(&message, &js_sys::Array::of1(&reply_port));
+    promise
+}
+
+/// Calls the callback with `null` and the result of the `Promise`, or with the error that it
+/// rejects with, like [`crate::masonry_worker::MasonryWorker::compute_with_callback()`].
+pub fn call_when_settled(promise: &js_sys::Promise, callback: js_sys::Function) {
+    let on_error = callback.clone();
+    let on_fulfilled = Closure::once_into_js(move |result: JsValue| {
+        drop(callback.call2(&JsValue::NULL, &JsValue::NULL, &result));
+    });
+    let on_rejected = Closure::once_into_js(move |error: JsValue| {
+        drop(on_error.call1(&JsValue::NULL, &error));
+    });
+    drop(
+        promise
+            .unchecked_ref::<Thenable>()
+            .then_or(&on_fulfilled, &on_rejected),
+    );
+}
+
+/// Returns the numbers in a buffer of
+/// [`crate::masonry_worker::MasonryWorker::pack_transforms()`]: the container height followed by
+/// the lanes of the transforms.
+pub fn unpack(buffer: &js_sys::ArrayBuffer) -> Result<Vec<u32>, JsValue> {
+    let len = buffer.byte_length() as usize / 4;
+    if len == 0 || buffer.byte_length() % 4 != 0 || (len - 1) % Transform::LANES != 0 {
+        let message = format!("Invalid packed transforms: {} bytes", buffer.byte_length());
+        return Err(Error::InvalidInput(message).into());
+    }
+    let mut lanes = vec![0; len];
+    js_sys::Uint32Array::new(buffer).copy_to(&mut lanes);
+    Ok(lanes)
+}
+
+/// Returns the error of [`crate::masonry_worker::MasonryWorker::import_transforms()`] if the
+/// number of lanes is not the one of a transform for every item.
+pub fn check_transforms(layout: &Layout, len: usize) -> Result<(), JsValue> {
+    if len == Transform::LANES * layout.num_items() {
+        return Ok(());
+    }
+    let message = format!("{} transform lanes for {} items", len, layout.num_items());
+    Err(Error::InvalidDimensions(message).into())
+}
+
+/// Imports the packed transforms of a reply into the layout and stores the result.
+fn import(
+    layout: &mut Layout,
+    output: &mut ComputeResult,
+    buffer: &js_sys::ArrayBuffer,
+) -> Result<(), JsValue> {
+    let lanes = unpack(buffer)?;
+    check_transforms(layout, lanes.len() - 1)?;
+    compute_locally(layout, output, |layout| {
+        layout.import_transforms(&lanes[1..]);
+        lanes[0]
+    });
+    Ok(())
+}
+
+/// Returns the error for a computation whose web worker could not be started.
+pub fn not_started() -> JsValue {
+    let message = String::from("The web worker could not be started");
+    Error::WorkerFailed(message).into()
+}
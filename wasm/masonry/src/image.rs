@@ -0,0 +1,123 @@
+//! Image processing for the thumbnails of the items, which runs wherever it is called.
+//!
+//! The functions only take and return plain data, so they can be called from any web worker that
+//! initialized the module, e.g. one that decodes the files of an import, to keep the main thread
+//! free. The web worker thread of [`crate::sync::run()`] does not take calls, because it never
+//! returns to its event loop.
+use alloc::{format, string::String, vec::Vec};
+
+use masonry_core::color;
+use masonry_core::convert::usize_to_u32;
+use masonry_core::placeholder::{self, MAX_PLACEHOLDER_SIZE};
+use masonry_core::{resize, similarity};
+use wasm_bindgen::prelude::*;
+
+use crate::error::Error;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Returns the `ThumbHash` of an image as base64, which a `ThumbHash` decoder like the
+/// `thumbhash` package renders as a blurry placeholder while the thumbnail loads.
+///
+/// The pixels are given as red, green, blue and alpha bytes row by row, e.g. the `data` of an
+/// `ImageData`. The image should be scaled down to at most 100 pixels per side first, e.g. by
+/// drawing the thumbnail to a small canvas. Throws if the image is empty or larger, or if there
+/// are fewer pixels than its size.
+#[wasm_bindgen]
+pub fn encode_placeholder(pixels: &[u8], width: u32, height: u32) -> Result<String, JsValue> {
+    match placeholder::encode(pixels, width as usize, height as usize) {
+        Some(hash) => Ok(base64(&hash)),
+        None => Err(JsValue::from(Error::InvalidInput(format!(
+            "Invalid placeholder image: {}x{} pixels from {} bytes, at most {} per side",
+            width,
+            height,
+            pixels.len(),
+            MAX_PLACEHOLDER_SIZE
+        )))),
+    }
+}
+
+/// Returns the dominant color of an image as `0xRRGGBB`, e.g. for the background of an item while
+/// its thumbnail loads, or `undefined` if the image is fully transparent or there are fewer pixels
+/// than its size.
+///
+/// The pixels are given as red, green, blue and alpha bytes row by row, e.g. the `data` of an
+/// `ImageData`. The color is the average of the most frequent group of similar colors, so the
+/// background of a photo wins over small saturated details. Large images are sampled.
+#[wasm_bindgen]
+pub fn dominant_color(pixels: &[u8], width: u32, height: u32) -> Option<u32> {
+    color::dominant(pixels, width as usize, height as usize)
+}
+
+/// Scales the decoded pixels of an image down to a thumbnail and returns its pixels, e.g. to
+/// generate the thumbnails of an import in a web worker from files the module cannot decode.
+///
+/// The pixels are given and returned as red, green, blue and alpha bytes row by row, e.g. the
+/// `data` of an `ImageData`. The thumbnail keeps the aspect ratio and is at most `max_edge` pixels
+/// on its longer side, see [`thumbnail_size()`]. Decoding the file and encoding the thumbnail are
+/// left to the caller, e.g. to `createImageBitmap()` and `OffscreenCanvas.convertToBlob()`. With
+/// the `codecs` feature, `resize_thumbnail()` does both for JPEG and PNG files. Throws if the
+/// image is empty or there are fewer pixels than the size.
+#[wasm_bindgen]
+pub fn resize_pixels(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    max_edge: u32,
+) -> Result<Vec<u8>, JsValue> {
+    resize::downscale(pixels, width as usize, height as usize, max_edge as usize).ok_or_else(|| {
+        JsValue::from(Error::InvalidInput(format!(
+            "Invalid image: {}x{} pixels from {} bytes",
+            width,
+            height,
+            pixels.len()
+        )))
+    })
+}
+
+/// Returns the width and height of the thumbnail that [`resize_pixels()`] returns for an image
+/// of the given size. Images are never scaled up.
+#[wasm_bindgen]
+pub fn thumbnail_size(width: u32, height: u32, max_edge: u32) -> Vec<u32> {
+    let (width, height) =
+        resize::thumbnail_size(width as usize, height as usize, max_edge as usize);
+    [width, height].map(usize_to_u32).to_vec()
+}
+
+/// Returns the perceptual hash of an image as a `BigInt`, e.g. to find duplicates that were
+/// resized or compressed again, or `undefined` if it is empty or there are fewer pixels than its
+/// size.
+///
+/// The pixels are given as red, green, blue and alpha bytes row by row, e.g. the `data` of an
+/// `ImageData`, preferably of a thumbnail. Images that look alike have hashes that differ in few
+/// bits, see [`hash_distance()`].
+#[wasm_bindgen]
+pub fn perceptual_hash(pixels: &[u8], width: u32, height: u32) -> Option<u64> {
+    similarity::perceptual_hash(pixels, width as usize, height as usize)
+}
+
+/// Returns the number of bits in which two perceptual hashes differ. Up to about 10 of the 64
+/// bits usually means the images look alike.
+#[wasm_bindgen]
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    similarity::hash_distance(a, b)
+}
+
+/// Encodes bytes as base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, &byte)| bits | u32::from(byte) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(char::from(BASE64[((bits >> (18 - 6 * i)) & 63) as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
@@ -2,12 +2,28 @@
 #![no_std]
 #![feature(stdsimd)]
 #![feature(atomic_mut_ptr)]
+#![feature(thread_local)]
 extern crate alloc;
 extern crate core;
+// Provides the panic hook, see `panic.rs`, and the readers of the decoders, see `codec.rs`.
+#[cfg(any(feature = "panic-hook", feature = "codecs"))]
+extern crate std;
 
+#[cfg(any(feature = "talc", feature = "lol_alloc"))]
+mod allocator;
+#[cfg(feature = "codecs")]
+mod codec;
+// The code that `wasm_bindgen` generates to free the `Copy` structs drops a copy.
+#[allow(clippy::drop_copy)]
 mod data;
-mod layout;
+mod error;
+mod fallback;
+mod image;
 mod masonry_worker;
-mod packed;
+mod measure;
+mod panic;
+#[cfg(feature = "threads")]
+mod pool;
+mod prerender;
+mod spawn;
 mod sync;
-mod util;
@@ -1,52 +1,2352 @@
-use crate::data::{Computation, MasonryConfig, MasonryType};
-use crate::layout::{Layout, Transform};
-use crate::sync::{receive_output, send_computation};
+use crate::data::{
+    Callbacks, Computation, ComputeResult, Drag, DragUpdate, ItemTransform, Observers, Pagination,
+    Precomputed, Priority, Progress, Request,
+};
+use crate::error::Error;
+use crate::fallback;
+use crate::measure::measure;
+use crate::panic::{during, Operation};
+use crate::spawn::{is_standard_runtime, Spawn};
+use crate::sync::{
+    cancel_computation, cancel_then, compute_locally, fail, failure, invalidate_views,
+    is_computing, memory_version, progress_of, queue_computation, restart, restarts,
+    send_background, send_computation, set_instrumented, with_timeout, worker_ready, Thenable,
+};
+use masonry_core::cancel::set_slice_size;
+use masonry_core::convert::{f32_to_u32, f64_to_f32, f64_to_u32, u32_to_f32, usize_to_u32};
+use masonry_core::probe;
+use masonry_core::selection::Selection;
+use masonry_core::timeline::timeline;
+use masonry_core::{
+    Breakpoint, ColumnInfo, ConfigError, Direction, HeightUpdate, ItemRange, LastRowBehavior,
+    Layout, MasonryConfig, MasonryType, MemoryStats, RoundingMode, RowInfo, ScrollAlignment,
+    StickyHeader, TaggedItem, TimelineGranularity, TimelineSection, Transform,
+};
 
+use alloc::{boxed::Box, format, rc::Rc, string::String, vec::Vec};
+use core::{
+    cell::{Cell, RefCell},
+    fmt::Write,
+    ptr,
+};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+#[wasm_bindgen]
+extern "C" {
+    /// A web worker that runs the `worker.js` script, created by the caller.
+    #[derive(Clone)]
+    pub type Worker;
+
+    #[wasm_bindgen(constructor, catch)]
+    pub(crate) fn new(script_url: &str, options: &JsValue) -> Result<Worker, JsValue>;
+
+    #[wasm_bindgen(method, js_name = postMessage)]
+    pub(crate) fn post_message(this: &Worker, message: &JsValue);
+
+    #[wasm_bindgen(method, js_name = postMessage)]
+    pub(crate) fn post_message_with_transfer(this: &Worker, message: &JsValue, transfer: &JsValue);
+
+    #[wasm_bindgen(method)]
+    fn terminate(this: &Worker);
+
+    #[wasm_bindgen(method, setter)]
+    pub(crate) fn set_onerror(this: &Worker, handler: &JsValue);
+
+    #[wasm_bindgen(method, setter)]
+    pub(crate) fn set_onmessage(this: &Worker, handler: &JsValue);
+}
+
+/// Number of items of the layout that [`MasonryWorker::warm_up()`] computes.
+const WARM_UP_ITEMS: usize = 64;
+/// Container width of the layout that [`MasonryWorker::warm_up()`] computes.
+const WARM_UP_WIDTH: u32 = 1200;
+/// Distance from the edges of the viewport within which a marquee drag scrolls.
+const AUTOSCROLL_EDGE: f32 = 48.0;
+/// Pixels per frame that a marquee drag scrolls at most, once it reaches the edge.
+const MAX_AUTOSCROLL: f32 = 24.0;
 
 #[wasm_bindgen]
 pub struct MasonryWorker {
     layout: Layout,
+    /// Result of the most recent computation that ran to completion.
+    result: ComputeResult,
+    /// Arguments of the most recent computation, see [`MasonryWorker::export_snapshot()`].
+    request: Option<Request>,
+    /// Kind, thumbnail size and padding of the most recent computation or
+    /// [`MasonryWorker::set_config()`], which [`MasonryWorker::compute_width()`] reuses.
+    defaults: (MasonryType, u32, u32),
+    sync_threshold: usize,
+    /// Width difference in CSS pixels below which a computation with an unchanged config keeps
+    /// the most recent transforms, see [`MasonryWorker::set_resize_threshold()`].
+    resize_threshold: u32,
+    /// Milliseconds after which a pending computation rejects, or 0 to wait for it forever.
+    compute_timeout: u32,
+    /// Priority of the computations that are sent to the web worker threads, see
+    /// [`MasonryWorker::compute_with_options()`].
+    priority: Priority,
+    /// Horizontal and vertical gap that replace the padding passed to the computations.
+    gaps: Option<(u32, u32)>,
+    outer_padding: u32,
+    max_content_width: u32,
+    caption_height: u32,
+    metadata_width: u32,
+    device_pixel_ratio: f32,
+    /// Reference size and limits of the gaps that scale with the thumbnail size, see
+    /// [`MasonryWorker::set_gap_scaling()`].
+    gap_scaling: (u32, u32, u32),
+    /// Target height and tolerance of the rows of the horizontal layout, see
+    /// [`MasonryWorker::set_row_height()`].
+    row_height: (u32, f32),
+    /// Thumbnail sizes of the zoom levels, sorted and without duplicates.
+    zoom_levels: Vec<u32>,
+    /// Copies of the layout that are computed with the zoom levels next to the most recent
+    /// computation. They are boxed, so that they stay in place while the web worker threads
+    /// compute them.
+    #[allow(clippy::vec_box)]
+    precomputed: Vec<Box<Precomputed>>,
+    /// Width and config of the most recent computation of the layout.
+    computed: Option<(u32, MasonryConfig)>,
+    /// Maximum number of earlier computations that are kept, see
+    /// [`MasonryWorker::set_cache_size()`].
+    cache_size: usize,
+    /// Copies of the layout that are computed with earlier arguments, the most recently used last.
+    cached: Vec<Precomputed>,
+    /// Copy of the layout that [`MasonryWorker::interpolate()`] computed with the thumbnail size
+    /// to interpolate towards.
+    zoom_target: Option<Box<Precomputed>>,
+    /// Copy of the computation before [`MasonryWorker::transition()`], which the items are
+    /// animated from.
+    transition_source: Option<Precomputed>,
+    /// Callbacks of [`MasonryWorker::on_layout()`], which the `Promise`s of the computations
+    /// share.
+    observers: Rc<Observers>,
+    /// Layout that [`MasonryWorker::warm_up()`] computes on the web worker thread, which is kept
+    /// because the thread may still use it.
+    warm_up: Option<Box<Precomputed>>,
+    /// Callbacks of [`MasonryWorker::compute_with_callback()`] that wait for their computation.
+    callbacks: Rc<Callbacks>,
+    /// Callback of [`MasonryWorker::on_progress()`].
+    progress: Rc<Progress>,
+    /// Item count above which the progress of computations is reported.
+    progress_threshold: usize,
+    /// Items selected with [`MasonryWorker::select()`] and the other selection methods.
+    selection: Selection,
+    /// Marquee drag of [`MasonryWorker::drag_update()`] in progress.
+    drag: Option<Drag>,
+    /// Number of items without the section headers and the sections of
+    /// [`MasonryWorker::set_timeline()`], whose headers follow the items.
+    timeline: Option<(usize, Vec<TimelineSection>)>,
+    /// How the web worker is started once the first computation is sent to it, see
+    /// [`crate::spawn::MasonryWorkerBuilder`].
+    spawn: Cell<Option<Spawn>>,
+    /// How the web worker is started again once it died, see
+    /// [`MasonryWorker::replay_on_failure()`].
+    respawn: Option<Spawn>,
+    /// The web worker that was started for the deferred spawn, shared with the computations that
+    /// replace it once it died.
+    spawned: Rc<RefCell<Option<Worker>>>,
+    /// Whether the web worker runs its own instance of the module because the memory cannot be
+    /// shared, see [`crate::fallback`].
+    fallback: bool,
+    /// Whether the web worker is started for a runtime without the DOM, see
+    /// [`crate::spawn::is_standard_runtime()`].
+    standard: bool,
+}
+
+impl MasonryWorker {
+    /// Defers starting the web worker until it is needed, see
+    /// [`crate::spawn::MasonryWorkerBuilder`]. With `fallback`, the web worker gets its own
+    /// instance of the module instead of the memory, see [`crate::fallback`]. With `standard`, it
+    /// is started for a runtime without the DOM, see [`crate::spawn::is_standard_runtime()`].
+    pub(crate) fn defer_spawn(&mut self, spawn: Spawn, fallback: bool, standard: bool) {
+        self.respawn = Some(spawn.clone());
+        *self.spawn.get_mut() = Some(spawn);
+        self.fallback = fallback;
+        self.standard = standard;
+    }
+
+    /// Creates a new worker that lays out the items of an existing layout, e.g. one that was
+    /// prepared natively or kept from a previous worker. Its chunk runner is removed, because the
+    /// main thread must not block, and the web worker threads share their work with the helper
+    /// threads of this module, if any.
+    pub fn with_layout(mut layout: Layout) -> MasonryWorker {
+        layout.set_chunk_runner(None);
+        let observers = Rc::<Observers>::default();
+        MasonryWorker {
+            layout,
+            result: ComputeResult::default(),
+            request: None,
+            defaults: (
+                MasonryType::Vertical,
+                MasonryConfig::DEFAULT_THUMBNAIL_SIZE,
+                MasonryConfig::DEFAULT_PADDING,
+            ),
+            sync_threshold: 0,
+            resize_threshold: 0,
+            compute_timeout: 0,
+            priority: Priority::UserBlocking,
+            gaps: None,
+            outer_padding: 0,
+            max_content_width: 0,
+            caption_height: 0,
+            metadata_width: 0,
+            gap_scaling: (0, 0, 0),
+            row_height: (0, 0.0),
+            device_pixel_ratio: 1.0,
+            zoom_levels: Vec::new(),
+            precomputed: Vec::new(),
+            computed: None,
+            cache_size: 0,
+            cached: Vec::new(),
+            zoom_target: None,
+            transition_source: None,
+            warm_up: None,
+            callbacks: Callbacks::new(Rc::clone(&observers)),
+            observers,
+            progress: Rc::default(),
+            progress_threshold: 0,
+            selection: Selection::new(),
+            drag: None,
+            timeline: None,
+            spawn: Cell::new(None),
+            respawn: None,
+            spawned: Rc::default(),
+            fallback: false,
+            standard: false,
+        }
+    }
+
+    /// Returns the layout, e.g. to create a new worker from it with
+    /// [`MasonryWorker::with_layout()`].
+    ///
+    /// The web worker thread must not be busy, because it uses the layout.
+    pub fn into_layout(self) -> Layout {
+        self.layout
+    }
 }
 
 #[wasm_bindgen]
 impl MasonryWorker {
     #[wasm_bindgen(constructor)]
     /// Creates a new worker from a worker that was initialized with the `worker.js` script.
+    ///
+    /// Any number of workers can be created, e.g. one per gallery. They have their own items and
+    /// settings but share the web worker thread, so only one web worker has to be started.
     pub fn new(num_items: usize) -> MasonryWorker {
-        MasonryWorker {
-            layout: Layout::new(
-                num_items,
-                MasonryConfig::DEFAULT_THUMBNAIL_SIZE,
-                MasonryConfig::DEFAULT_PADDING,
+        MasonryWorker::with_layout(Layout::new(
+            num_items,
+            MasonryConfig::DEFAULT_THUMBNAIL_SIZE,
+            MasonryConfig::DEFAULT_PADDING,
+        ))
+    }
+
+    /// Creates a new worker and hands the memory and the compiled module to the given web worker,
+    /// which must run the `worker.js` script.
+    ///
+    /// The web worker is created by the caller, e.g. with
+    /// `new Worker(new URL('worker.js', import.meta.url), { type: 'module' })`, so that bundlers
+    /// can find the script and emit it as an asset. It instantiates the module that the main
+    /// thread compiled already instead of fetching and compiling it again.
+    pub fn with_worker(num_items: usize, worker: &Worker) -> MasonryWorker {
+        worker.post_message(&init_message(!is_standard_runtime()));
+        MasonryWorker::new(num_items)
+    }
+
+    /// Hands the memory and the compiled module to another web worker that runs the `worker.js` script, so
+    /// that it computes the layouts of other workers while the first one is busy.
+    ///
+    /// All workers share the web workers, however many workers are created. The web workers only
+    /// work on different layouts at the same time, so one per gallery that is shown at once is
+    /// enough. At most seven web workers join, additional ones stay idle.
+    #[allow(clippy::unused_self)]
+    pub fn add_worker(&self, worker: &Worker) {
+        worker.post_message(&init_message(!is_standard_runtime()));
+    }
+
+    /// Frees the worker once the web worker thread no longer uses it and returns a `Promise` that
+    /// resolves afterwards.
+    ///
+    /// Unlike `free()`, this is safe while a computation is pending: the computation is cancelled
+    /// and the items are only freed after the web worker thread stopped working on them. If the
+    /// web worker is given, it is terminated as well and the shared state is reset, so that a new
+    /// worker can be created with [`MasonryWorker::with_worker()`] and a new web worker. The web
+    /// worker should only be given when disposing the last worker that uses it. Any method called
+    /// on the disposed worker throws an error.
+    pub fn dispose(self, worker: Option<Worker>) -> js_sys::Promise {
+        self.observers.clear();
+        self.callbacks.clear();
+        self.progress.set_callback(None);
+        for level in self
+            .precomputed
+            .iter()
+            .chain(&self.zoom_target)
+            .chain(&self.warm_up)
+        {
+            cancel_computation(&level.layout);
+        }
+        let layout: *const Layout = &self.layout;
+        cancel_then(layout, move || {
+            if let Some(worker) = worker {
+                worker.terminate();
+                // The replacement waits for its own web worker thread.
+                drop(restart());
+            }
+            drop(self);
+        })
+    }
+
+    /// Returns a `Promise` that resolves once the web worker thread has finished its
+    /// initialization and runs the `run` loop.
+    ///
+    /// Computations sent earlier are not lost, but awaiting this first avoids waiting for the
+    /// worker thread inside of the first [`MasonryWorker::compute()`] and makes startup failures
+    /// visible at a single place. Without `SharedArrayBuffer` (see [`crate::fallback`]), the web
+    /// worker does not run the `run` loop, and it resolves once the web worker was started.
+    pub fn ready(&self) -> js_sys::Promise {
+        self.start();
+        if self.fallback {
+            return match failure() {
+                Some(error) => js_sys::Promise::reject(&error),
+                None => js_sys::Promise::resolve(&JsValue::UNDEFINED),
+            };
+        }
+        worker_ready()
+    }
+
+    /// Starts the web worker of a worker from [`crate::spawn::MasonryWorkerBuilder`] if it has
+    /// not been started yet, e.g. while the gallery is about to be shown, instead of with the first
+    /// computation. [`MasonryWorker::ready()`] and [`MasonryWorker::warm_up()`] start it as well.
+    pub fn start(&self) {
+        if let Some(spawn) = self.spawn.take() {
+            let message = if self.fallback {
+                fallback::init_message(!self.standard)
+            } else {
+                init_message(!self.standard)
+            };
+            *self.spawned.borrow_mut() = spawn.spawn(&message, self.standard);
+        }
+    }
+
+    /// Returns the web worker that was started for a worker from
+    /// [`crate::spawn::MasonryWorkerBuilder`], e.g. to terminate it with
+    /// [`MasonryWorker::dispose()`].
+    pub fn spawned_worker(&self) -> Option<Worker> {
+        self.spawned.borrow().clone()
+    }
+
+    /// Gets the web worker thread ready for the first computation and returns a `Promise` that
+    /// resolves afterwards, e.g. while the library is still loading.
+    ///
+    /// Like [`MasonryWorker::ready()`] it waits until the web worker has instantiated the module,
+    /// and then lays out a small dummy layout on both threads, so that the first real computation
+    /// does not pay for starting up and delay the first paint of the gallery. The `Promise`
+    /// rejects like the one of [`MasonryWorker::compute()`]. Calling it again, or without
+    /// `SharedArrayBuffer`, only waits for [`MasonryWorker::ready()`].
+    pub fn warm_up(&mut self) -> js_sys::Promise {
+        if self.warm_up.is_some() || self.fallback {
+            return self.ready();
+        }
+        let config = MasonryConfig::new(
+            MasonryType::Vertical,
+            MasonryConfig::DEFAULT_THUMBNAIL_SIZE,
+            MasonryConfig::DEFAULT_PADDING,
+        );
+        compute_locally(
+            &mut warm_up_layout(),
+            &mut ComputeResult::default(),
+            |layout| layout.compute(WARM_UP_WIDTH, &config),
+        );
+        let mut target = Box::new(Precomputed {
+            width: WARM_UP_WIDTH,
+            config,
+            layout: warm_up_layout(),
+            result: ComputeResult::default(),
+        });
+        let computation = Computation::new(
+            WARM_UP_WIDTH,
+            config,
+            &mut target.layout,
+            &mut target.result,
+        );
+        let promise = self.send(computation);
+        self.warm_up = Some(target);
+        promise
+    }
+
+    /// Reports that the web worker thread failed, e.g. from its `onerror` and `onmessageerror`
+    /// handlers, or when `worker.js` posts an `{ error }` message because the WebAssembly module
+    /// could not be loaded.
+    ///
+    /// Pending and future `Promise`s of [`MasonryWorker::compute()`] and
+    /// [`MasonryWorker::ready()`] reject with an `Error` named `MasonryWorkerError` and the given
+    /// message, so callers can recover, e.g. by falling back to [`MasonryWorker::compute_sync()`].
+    #[allow(clippy::unused_self)]
+    pub fn fail(&self, message: String) {
+        fail(message);
+    }
+
+    /// Prepares a replacement for a web worker thread that died, e.g. because it ran out of
+    /// memory or was terminated by the browser, and returns the `Promise` of
+    /// [`MasonryWorker::ready()`] for it.
+    ///
+    /// Workers from [`crate::spawn::MasonryWorkerBuilder`] that share the memory replace their
+    /// web worker on their own, see [`MasonryWorker::compute()`]. For web workers that the caller
+    /// started, report the failure with [`MasonryWorker::fail()`] first, which rejects the pending
+    /// computations, then call this and start a new web worker with `worker.js` and the same
+    /// memory. The items and their dimensions are kept, so the rejected computations can simply
+    /// be sent again once the `Promise` resolves. Web workers that were added with
+    /// [`MasonryWorker::add_worker()`] have to be terminated and replaced as well.
+    pub fn restart(&mut self) -> js_sys::Promise {
+        // The computation in progress may have stopped halfway.
+        self.layout.invalidate();
+        restart()
+    }
+
+    /// Computes the transforms of all items.
+    ///
+    /// If the item count does not exceed the threshold set with
+    /// [`MasonryWorker::set_sync_threshold()`], the layout is computed on the main thread instead.
+    ///
+    /// Calling this method again while a computation is still pending is allowed. The requests
+    /// are coalesced: the computation in progress is cancelled, only the most recent request is
+    /// computed and all pending `Promise`s resolve once it is finished. The computations of other
+    /// workers, e.g. of other galleries, share the web worker thread and are queued after it.
+    ///
+    /// The `Promise` resolves with a [`ComputeResult`], which holds the container height, how long
+    /// the layout took, its number of items, rows and columns, e.g. for a debug overlay, and the
+    /// first item whose transform may have changed. It rejects with an `Error` named
+    /// `MasonryConfigError` that names the offending parameter if the items cannot be laid out
+    /// with the arguments, e.g. because the padding is wider than the container (see
+    /// `Layout::check_config()`). Without items or with a width of 0 it resolves with a height of
+    /// 0 in every layout kind and the queries return no items. It rejects with an `Error` named
+    /// `MasonryTimeoutError` if the web worker thread does not finish in time (see
+    /// [`MasonryWorker::set_compute_timeout()`]).
+    ///
+    /// If the web worker of a worker from [`crate::spawn::MasonryWorkerBuilder`] dies while the
+    /// computation is pending, e.g. because it ran out of memory or was terminated by the browser,
+    /// a new web worker is started with the same memory and the computation is sent again. The
+    /// `Promise` only rejects with the `MasonryWorkerError` if the new web worker fails as well.
+    ///
+    /// # Safety
+    ///
+    /// The returned `Promise` must be `await`ed. Calls to any method of [`MasonryWorker`] other
+    /// than `compute`, [`MasonryWorker::cancel_pending()`], [`MasonryWorker::is_busy()`] and
+    /// [`MasonryWorker::progress()`] while the `Promise` is still pending will lead to undefined
+    /// behaviour. As long as the value is `await`ed you can enjoy lock free concurrency.
+    pub fn compute(
+        &mut self,
+        width: u32,
+        kind: MasonryType,
+        thumbnail_size: u32,
+        padding: u32,
+    ) -> js_sys::Promise {
+        if let Some(result) = self.skip_resize(width, kind, thumbnail_size, padding) {
+            return js_sys::Promise::resolve(&JsValue::from(result));
+        }
+        let config = match self.request(width, kind, thumbnail_size, padding) {
+            Ok(config) => config,
+            Err(error) => return js_sys::Promise::reject(&error),
+        };
+        if !is_computing(&self.layout) {
+            let adopted =
+                self.adopt_cached(width, &config) || self.adopt_zoom_level(width, &config);
+            self.precompute_zoom_levels(width, kind, thumbnail_size, padding);
+            if adopted {
+                return js_sys::Promise::resolve(&JsValue::from(self.result));
+            }
+        }
+        if self.layout.num_items() <= self.sync_threshold && !is_computing(&self.layout) {
+            self.lay_out_locally(|layout| layout.compute(width, &config));
+            return js_sys::Promise::resolve(&JsValue::from(self.result));
+        }
+        let computation = Computation::new(width, config, &mut self.layout, &mut self.result);
+        self.send(computation)
+    }
+
+    /// Computes the transforms of all items like [`MasonryWorker::compute()`] with the given
+    /// width and the kind, thumbnail size and padding of the most recent computation or
+    /// [`MasonryWorker::set_config()`], e.g. when the container is resized.
+    ///
+    /// Before either, the vertical masonry layout with the default thumbnail size and padding is
+    /// computed.
+    pub fn compute_width(&mut self, width: u32) -> js_sys::Promise {
+        let (kind, thumbnail_size, padding) = self.defaults;
+        self.compute(width, kind, thumbnail_size, padding)
+    }
+
+    /// Computes the transforms of all items like [`MasonryWorker::compute()`] with the parameters
+    /// given in an object, e.g. `{ kind: MasonryType.Grid, thumbnailSize: 200, padding: 8 }`.
+    ///
+    /// The properties are named like those of [`MasonryConfig`] in camel case, and `padding`
+    /// sets both gaps unless `gapX` or `gapY` are given as well. Missing properties keep their
+    /// current values (see [`MasonryWorker::get_config()`]), and the given ones persist like
+    /// after [`MasonryWorker::set_config()`]. The `Promise` rejects with an `Error` named
+    /// `MasonryConfigError` if a property is not a number or the kind is unknown, and otherwise
+    /// like the one of [`MasonryWorker::compute()`].
+    ///
+    /// `priority` is `"user-blocking"` by default, or `"background"` for speculative work, e.g.
+    /// a layout that may be needed later. A user-blocking computation is taken before the ones
+    /// in the background and preempts one of them if every web worker thread is busy, which is
+    /// queued again once it stopped. The `Promise` of a computation in the background only waits
+    /// for the other ones in the background. The priority does not persist.
+    pub fn compute_with_options(&mut self, width: u32, options: &JsValue) -> js_sys::Promise {
+        let parsed = self
+            .options_config(options)
+            .and_then(|config| Ok((config, options_priority(options)?)));
+        match parsed {
+            Ok((config, priority)) => {
+                self.set_config(&config);
+                self.priority = priority;
+                let promise = self.compute_width(width);
+                self.priority = Priority::UserBlocking;
+                promise
+            }
+            Err(error) => js_sys::Promise::reject(&error),
+        }
+    }
+
+    /// Computes the transforms of all items like [`MasonryWorker::compute()`], but calls the
+    /// callback once it is done instead of returning a `Promise`, e.g. for frameworks with their
+    /// own scheduler.
+    ///
+    /// The callback is called with `null` and the [`ComputeResult`], or with the error that the
+    /// `Promise` of [`MasonryWorker::compute()`] would reject with, except that there is no
+    /// timeout (see [`MasonryWorker::set_compute_timeout()`]). Calls do not create a closure or
+    /// `Promise` each: the callbacks of a computation on the main thread are called in a
+    /// microtask after the call returned, and the ones of all computations that are sent to the
+    /// web worker thread in the meantime once it is done. The same rules as for
+    /// [`MasonryWorker::compute()`] apply until the callback was called.
+    pub fn compute_with_callback(
+        &mut self,
+        width: u32,
+        kind: MasonryType,
+        thumbnail_size: u32,
+        padding: u32,
+        callback: js_sys::Function,
+    ) {
+        if let Some(result) = self.skip_resize(width, kind, thumbnail_size, padding) {
+            return self.callbacks.call_soon(callback, &result);
+        }
+        let config = match self.request(width, kind, thumbnail_size, padding) {
+            Ok(config) => config,
+            Err(error) => return self.callbacks.fail(callback, error),
+        };
+        if !is_computing(&self.layout) {
+            let adopted =
+                self.adopt_cached(width, &config) || self.adopt_zoom_level(width, &config);
+            self.precompute_zoom_levels(width, kind, thumbnail_size, padding);
+            if adopted {
+                return self.callbacks.call_soon(callback, &self.result);
+            }
+        }
+        if self.layout.num_items() <= self.sync_threshold && !is_computing(&self.layout) {
+            self.lay_out_locally(|layout| layout.compute(width, &config));
+            return self.callbacks.call_soon(callback, &self.result);
+        }
+        let computation = Computation::new(width, config, &mut self.layout, &mut self.result);
+        if self.fallback {
+            let promise = self.send(computation);
+            return fallback::call_when_settled(&promise, callback);
+        }
+        self.start();
+        if let Err(error) = queue_computation(computation) {
+            return self.callbacks.fail(callback, error);
+        }
+        self.watch_progress();
+        let ticket = self.observers.send();
+        self.callbacks
+            .call_when_idle(callback, &self.result, ticket);
+    }
+
+    /// Computes the transforms of all items like [`MasonryWorker::compute()`], but lays out the
+    /// items up to the given index on the main thread first, so that they can be rendered right
+    /// away.
+    ///
+    /// This makes e.g. changes of the thumbnail size feel instant in big libraries. `end` should
+    /// be the index after the last item in the viewport plus about one row of overscan, because
+    /// the last row before it is not full yet and can still change. The remaining items are laid
+    /// out by the web worker thread afterwards. In the meantime the transforms of the items before
+    /// `end` can be read, but apart from that the same rules as for [`MasonryWorker::compute()`]
+    /// apply until the returned `Promise` resolves with the result of the remaining items.
+    ///
+    /// A reversed layout (see [`MasonryWorker::set_reversed()`]) is computed like
+    /// [`MasonryWorker::compute()`], because its first rows depend on the last items.
+    pub fn compute_visible_first(
+        &mut self,
+        end: usize,
+        width: u32,
+        kind: MasonryType,
+        thumbnail_size: u32,
+        padding: u32,
+    ) -> js_sys::Promise {
+        if self.layout.is_reversed() {
+            return self.compute(width, kind, thumbnail_size, padding);
+        }
+        if let Some(result) = self.skip_resize(width, kind, thumbnail_size, padding) {
+            return js_sys::Promise::resolve(&JsValue::from(result));
+        }
+        let config = match self.request(width, kind, thumbnail_size, padding) {
+            Ok(config) => config,
+            Err(error) => return js_sys::Promise::reject(&error),
+        };
+        if is_computing(&self.layout) {
+            // The layout belongs to the web worker thread until it is done.
+            let computation = Computation::new(width, config, &mut self.layout, &mut self.result);
+            return self.send(computation);
+        }
+        let adopted = self.adopt_cached(width, &config) || self.adopt_zoom_level(width, &config);
+        self.precompute_zoom_levels(width, kind, thumbnail_size, padding);
+        if adopted {
+            return js_sys::Promise::resolve(&JsValue::from(self.result));
+        }
+        self.lay_out_locally(|layout| layout.compute_until(end, width, &config));
+        if end >= self.layout.num_items() {
+            return js_sys::Promise::resolve(&JsValue::from(self.result));
+        }
+        let computation = Computation::new(width, config, &mut self.layout, &mut self.result);
+        self.send(computation.remaining())
+    }
+
+    /// Lays out the items from `start` to `end` on the main thread like
+    /// [`MasonryWorker::compute_visible_first()`], keeping the transforms of the items before
+    /// `start`, e.g. because only the last section changed.
+    ///
+    /// The items before `start` must not have changed since the most recent computation, apart
+    /// from dimensions set with [`MasonryWorker::set_dimension()`], which are laid out again. If
+    /// the width or config differ from the most recent computation, all items up to `end` are laid
+    /// out. The remaining items are laid out by the web worker thread afterwards, and the same
+    /// rules as for [`MasonryWorker::compute_visible_first()`] apply, including the fallback for
+    /// reversed layouts.
+    pub fn compute_range(
+        &mut self,
+        start: usize,
+        end: usize,
+        width: u32,
+        kind: MasonryType,
+        thumbnail_size: u32,
+        padding: u32,
+    ) -> js_sys::Promise {
+        if self.layout.is_reversed() {
+            return self.compute(width, kind, thumbnail_size, padding);
+        }
+        if let Some(result) = self.skip_resize(width, kind, thumbnail_size, padding) {
+            return js_sys::Promise::resolve(&JsValue::from(result));
+        }
+        let config = match self.request(width, kind, thumbnail_size, padding) {
+            Ok(config) => config,
+            Err(error) => return js_sys::Promise::reject(&error),
+        };
+        if is_computing(&self.layout) {
+            // The layout belongs to the web worker thread until it is done.
+            let computation = Computation::new(width, config, &mut self.layout, &mut self.result);
+            return self.send(computation);
+        }
+        self.lay_out_locally(|layout| layout.compute_range(start, end, width, &config));
+        if end >= self.layout.num_items() {
+            return js_sys::Promise::resolve(&JsValue::from(self.result));
+        }
+        let computation = Computation::new(width, config, &mut self.layout, &mut self.result);
+        self.send(computation.remaining())
+    }
+
+    /// Computes the layouts with two thumbnail sizes, so that e.g. a pinch zoom can animate
+    /// continuously between them with [`MasonryWorker::interpolated_transforms()`] instead of
+    /// jumping from one to the other.
+    ///
+    /// The layout is computed with `from_size` like [`MasonryWorker::compute()`] and a copy of the
+    /// items with `to_size` and the same width, kind and padding. A precomputed zoom level or a
+    /// cached computation with `to_size` is taken over as the copy (see
+    /// [`MasonryWorker::set_zoom_levels()`] and [`MasonryWorker::set_cache_size()`]), and a later
+    /// computation with `to_size` takes over the copy and resolves right away, e.g. once the
+    /// gesture ends. The returned `Promise` resolves with the result of `from_size` once both are
+    /// computed. It rejects like the one of [`MasonryWorker::compute()`], and if a computation of
+    /// this worker is still pending.
+    pub fn interpolate(
+        &mut self,
+        width: u32,
+        kind: MasonryType,
+        from_size: u32,
+        to_size: u32,
+        padding: u32,
+    ) -> js_sys::Promise {
+        if is_computing(&self.layout) {
+            return js_sys::Promise::reject(&still_pending("interpolate"));
+        }
+        for thumbnail_size in [from_size, to_size] {
+            let config = self.config(kind, thumbnail_size, padding);
+            if let Err(error) = self.layout.check_config(width, &config) {
+                return js_sys::Promise::reject(&invalid_config(error));
+            }
+        }
+        let target = self.prepare_zoom_target(width, self.config(kind, to_size, padding));
+        let current = self.compute(width, kind, from_size, padding);
+        match target {
+            Some(target) => {
+                let first =
+                    Closure::once_into_js(|results: JsValue| js_sys::Array::from(&results).get(0));
+                js_sys::Promise::all(&js_sys::Array::of2(&current, &target))
+                    .unchecked_ref::<Thenable>()
+                    .then(&first)
+            }
+            None => current,
+        }
+    }
+
+    /// Returns the transforms of the items from `start` to `end` the fraction `t` between the two
+    /// layouts of [`MasonryWorker::interpolate()`], from the one with `from_size` at 0 to the one
+    /// with `to_size` at 1.
+    ///
+    /// Every item takes four numbers: the width, height, top and left. They are not rounded, so
+    /// that the items move smoothly. Items that are hidden in one of the layouts keep the transform
+    /// of the other one. Without an up-to-date copy, e.g. because the items changed since, the
+    /// transforms of the layout are returned as they are. It must not be called while a `Promise`
+    /// returned by [`MasonryWorker::interpolate()`] is still pending.
+    pub fn interpolated_transforms(&self, t: f32, start: usize, end: usize) -> Vec<f32> {
+        let target = self.zoom_target_layout();
+        let end = end.min(self.layout.num_items());
+        (start.min(end)..end)
+            .flat_map(|index| {
+                self.layout
+                    .interpolate_transform(target, index, t)
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    /// Returns the container height the fraction `t` between the heights of the two layouts of
+    /// [`MasonryWorker::interpolate()`], like [`MasonryWorker::interpolated_transforms()`].
+    pub fn interpolated_height(&self, t: f32) -> f32 {
+        let from = u32_to_f32(self.result.height);
+        match &self.zoom_target {
+            Some(target)
+                if !is_computing(&target.layout) && self.layout.is_fork_current(&target.layout) =>
+            {
+                from + (u32_to_f32(target.result.height) - from) * t.clamp(0.0, 1.0)
+            }
+            _ => from,
+        }
+    }
+
+    /// Drops the copy of the layout that [`MasonryWorker::interpolate()`] computed, e.g. because
+    /// the pinch zoom was cancelled, and cancels its computation if it is still pending.
+    pub fn end_interpolation(&mut self) {
+        if let Some(target) = self.zoom_target.take() {
+            cancel_computation(&target.layout);
+            if is_computing(&target.layout) {
+                // Dropped with the zoom levels once the web worker thread let go of it.
+                self.precomputed.push(target);
+            }
+        }
+    }
+
+    /// Computes the layout like [`MasonryWorker::compute()`] but keeps the transforms of the most
+    /// recent computation, so that the items can be animated to their new spots, e.g. when
+    /// switching between the grid and the vertical masonry layout.
+    ///
+    /// Once the returned `Promise` resolved, [`MasonryWorker::get_transition_pair()`] returns the
+    /// old and the new transform of every item and [`MasonryWorker::transition_height()`] the
+    /// container height that fits both, until [`MasonryWorker::end_transition()`] drops the old
+    /// transforms. If the most recent computation did not run to completion, there are no old
+    /// transforms and the items start at their new spots. The `Promise` rejects like the one of
+    /// [`MasonryWorker::compute()`], and if a computation of this worker is still pending.
+    pub fn transition(
+        &mut self,
+        width: u32,
+        kind: MasonryType,
+        thumbnail_size: u32,
+        padding: u32,
+    ) -> js_sys::Promise {
+        if is_computing(&self.layout) {
+            return js_sys::Promise::reject(&still_pending("start a transition"));
+        }
+        self.transition_source = match (self.computed, self.layout.fork_computed()) {
+            (Some((width, config)), Some(layout)) => Some(Precomputed {
+                width,
+                config,
+                layout,
+                result: self.result,
+            }),
+            _ => None,
+        };
+        self.compute(width, kind, thumbnail_size, padding)
+    }
+
+    /// Returns the transform of the item before and after [`MasonryWorker::transition()`].
+    ///
+    /// The array contains the width, height, top and left of the old transform followed by the
+    /// same values of the new transform, like [`MasonryWorker::get_transform_pair()`]. Items that
+    /// are hidden in one of the layouts have an empty transform there. The old transform is equal
+    /// to the new one if there is no transition or the items or settings changed since it started.
+    /// Returns an empty array if the index is out of bounds. It must not be called while the
+    /// `Promise` returned by [`MasonryWorker::transition()`] is still pending.
+    pub fn get_transition_pair(&self, index: usize) -> Vec<u32> {
+        let current = match self.layout.get_transform(index) {
+            Some(transform) => transform,
+            None => return Vec::new(),
+        };
+        let previous = self
+            .transition_source()
+            .and_then(|source| source.layout.get_transform(index))
+            .unwrap_or(current);
+        [previous.to_array(), current.to_array()].concat()
+    }
+
+    /// Returns the larger of the container heights before and after
+    /// [`MasonryWorker::transition()`], so that no item is cut off while it moves, or the current
+    /// height if there is no transition.
+    pub fn transition_height(&self) -> u32 {
+        let height = self.result.height;
+        self.transition_source()
+            .map_or(height, |source| source.result.height.max(height))
+    }
+
+    /// Drops the transforms that [`MasonryWorker::transition()`] kept, e.g. once the animation
+    /// finished.
+    pub fn end_transition(&mut self) {
+        self.transition_source = None;
+    }
+
+    /// Computes the transforms of all items on the main thread and returns the container height.
+    ///
+    /// For small item counts this is faster than waking up the web worker thread. Throws the same
+    /// error as [`MasonryWorker::compute()`] rejects with if the arguments are invalid. It must not
+    /// be called while a `Promise` returned by [`MasonryWorker::compute()`] is still pending.
+    pub fn compute_sync(
+        &mut self,
+        width: u32,
+        kind: MasonryType,
+        thumbnail_size: u32,
+        padding: u32,
+    ) -> Result<u32, JsValue> {
+        let config = self.request(width, kind, thumbnail_size, padding)?;
+        if self.adopt_cached(width, &config) {
+            return Ok(self.result.height);
+        }
+        Ok(self.lay_out_locally(|layout| layout.compute(width, &config)))
+    }
+
+    /// Estimates the container height without laying out the items, e.g. to size the scrollbar
+    /// while the dimensions are still loading.
+    ///
+    /// The estimate is extrapolated from the average aspect ratio of the items whose dimension
+    /// is known, or from the default aspect ratio (see
+    /// [`MasonryWorker::set_default_aspect_ratio()`]) if there are none. It must not be called
+    /// while a `Promise` returned by [`MasonryWorker::compute()`] is still pending.
+    pub fn estimate_height(
+        &mut self,
+        width: u32,
+        kind: MasonryType,
+        thumbnail_size: u32,
+        padding: u32,
+    ) -> f32 {
+        let config = self.config(kind, thumbnail_size, padding);
+        self.layout.estimate_height(width, &config)
+    }
+
+    /// Returns the layout kind that suits the aspect ratios of the visible items best, e.g. for an
+    /// "Auto" view mode.
+    ///
+    /// Mostly square items get the grid, mostly landscape items the horizontal layout and portraits
+    /// or a mix of sizes the vertical masonry layout. Items whose dimension is not known yet are
+    /// ignored. It must not be called while a `Promise` returned by [`MasonryWorker::compute()`]
+    /// is still pending.
+    pub fn suggest_layout(&self) -> MasonryType {
+        self.layout.suggest_layout()
+    }
+
+    /// Lays out only the items that were added with [`MasonryWorker::resize()`] since the most
+    /// recent computation and returns the new container height.
+    ///
+    /// This is meant for items streaming in, e.g. during an import, and runs on the main thread
+    /// because only the new items (and the last row of a horizontal layout) are computed. The
+    /// width and config of the most recent computation are reused. Changed dimensions of items
+    /// that were already computed are not taken into account. It must not be called while a
+    /// `Promise` returned by [`MasonryWorker::compute()`] is still pending.
+    pub fn compute_appended(&mut self) -> u32 {
+        self.lay_out_locally(Layout::compute_appended)
+    }
+
+    /// Adds `count` items at the end, e.g. the next page of a remote library, lays them out below
+    /// the existing content and returns the new container height.
+    ///
+    /// The new items get the default aspect ratio as an estimate until their dimensions are set
+    /// with [`MasonryWorker::set_dimension()`], so the scrollbar grows right away without
+    /// computing the existing items again. [`MasonryWorker::compute_incremental()`] then only lays
+    /// out the items from the first one with a new dimension on. Like
+    /// [`MasonryWorker::compute_appended()`] it runs on the main thread, reuses the width and
+    /// config of the most recent computation and must not be called while a `Promise` returned by
+    /// [`MasonryWorker::compute()`] is still pending.
+    pub fn append(&mut self, count: usize) -> u32 {
+        self.lay_out_locally(|layout| layout.append(count))
+    }
+
+    /// Lays out the items again, starting at the first item whose dimension changed since the
+    /// most recent computation, and returns the new container height.
+    ///
+    /// Everything before that item is reused, which makes this a lot faster than a full
+    /// computation when e.g. the dimensions of a few items at the end are updated. Items that
+    /// were added with [`MasonryWorker::resize()`] are laid out as well. The width and config of
+    /// the most recent computation are reused. It must not be called while a `Promise` returned
+    /// by [`MasonryWorker::compute()`] is still pending.
+    pub fn compute_incremental(&mut self) -> u32 {
+        self.lay_out_locally(Layout::compute_incremental)
+    }
+
+    /// Sets the dimensions of a batch of items that arrived, e.g. while an import reads the files,
+    /// as triples of index, width and height, and lays out the part of the layout they affect.
+    ///
+    /// Items whose dimension did not arrive yet are laid out with the default aspect ratio (see
+    /// [`MasonryWorker::set_default_aspect_ratio()`]). Every batch returns the next update of the
+    /// height: the items from `first` up to `end` were laid out again and the ones from `end` on
+    /// only moved down by `offset`, so they can be translated instead of rendered again. The
+    /// horizontal layout usually only lays out a few rows per batch, the other kinds lay out
+    /// every item from the first arrival on. The sequence of updates ends once `remaining` is 0.
+    /// Like [`MasonryWorker::compute_incremental()`] it runs on the main thread and reuses the
+    /// width and config of the most recent computation. Throws if the length of the batch is not
+    /// a multiple of 3 or an index is out of bounds, or an `Error` whose `code` is `Busy` while a
+    /// `Promise` returned by [`MasonryWorker::compute()`] is still pending, in which case nothing
+    /// is set.
+    pub fn stream_dimensions(&mut self, batch: &[u32]) -> Result<HeightUpdate, JsValue> {
+        if is_computing(&self.layout) {
+            return Err(still_pending("stream dimensions"));
+        }
+        if batch.len() % 3 != 0 {
+            return Err(invalid_dimensions(
+                "expected triples of index, width and height",
+            ));
+        }
+        let num_items = self.layout.num_items();
+        if let Some(index) = batch
+            .iter()
+            .step_by(3)
+            .find(|&&index| index as usize >= num_items)
+        {
+            let message = format!("index {} for {} items", index, num_items);
+            return Err(Error::IndexOutOfBounds(message).into());
+        }
+        for triple in batch.chunks_exact(3) {
+            let (width, height) = clamp_dimension(f64::from(triple[1]), f64::from(triple[2]));
+            self.layout.set_dimension(triple[0] as usize, width, height);
+        }
+        let mut update = HeightUpdate::default();
+        self.lay_out_locally(|layout| {
+            update = layout.compute_arrivals();
+            update.height
+        });
+        Ok(update)
+    }
+
+    /// Lays out items that were prepended with [`MasonryWorker::insert()`] at index 0 above the
+    /// existing content and returns how far the existing items moved down, or to the right in a
+    /// filmstrip.
+    ///
+    /// This is meant for loading an earlier page: the items that were already laid out keep their
+    /// arrangement and are only shifted, so adding the returned offset to `scrollTop`, or
+    /// `scrollLeft` of a filmstrip, in the same frame keeps the viewport stationary. The new container height is the previous one plus the
+    /// offset. The dimensions of the `count` new items have to be set before. The width and config
+    /// of the most recent computation are reused. It runs on the main thread and must not be
+    /// called while a `Promise` returned by [`MasonryWorker::compute()`] is still pending. Throws
+    /// an `IndexOutOfBounds` error if fewer than `count` items were inserted at index 0 since.
+    pub fn compute_prepended(&mut self, count: usize) -> Result<u32, JsValue> {
+        let prepended = self.layout.prepended_items();
+        if count > prepended {
+            let message = format!("{} items to prepend, {} were inserted", count, prepended);
+            return Err(Error::IndexOutOfBounds(message).into());
+        }
+        Ok(self.lay_out_locally(|layout| layout.compute_prepended(count).unwrap_or(0)))
+    }
+
+    /// Enables or disables keeping the transforms of the previous computation.
+    ///
+    /// This is needed for [`MasonryWorker::changed_items()`]. It is disabled by default because
+    /// it copies all transforms before every computation.
+    pub fn set_track_changes(&mut self, enabled: bool) {
+        self.layout.set_track_changes(enabled);
+    }
+
+    /// Enables or disables reading the transforms of the last completed computation in
+    /// [`MasonryWorker::get_transform()`] while the web worker thread computes the next one.
+    ///
+    /// Without it, a renderer that reads the transforms during a computation can see a mix of old
+    /// and new positions. It is disabled by default because it copies all transforms after every
+    /// computation. It must not be called while a `Promise` returned by
+    /// [`MasonryWorker::compute()`] is still pending.
+    pub fn set_double_buffered(&mut self, enabled: bool) {
+        self.layout.set_double_buffered(enabled);
+    }
+
+    /// Returns the indices of the items whose transform changed in the most recent computation.
+    ///
+    /// Items that were not rendered before count as changed. Returns an empty array if changes
+    /// are not tracked (see [`MasonryWorker::set_track_changes()`]).
+    pub fn changed_items(&self) -> Vec<u32> {
+        self.layout.changed_items().map(usize_to_u32).collect()
+    }
+
+    /// Returns the previous and the new transform of the item at the given index, e.g. for FLIP
+    /// animations.
+    ///
+    /// The array contains the width, height, top and left of the previous transform followed by
+    /// the same values of the new transform. If the item was not computed before or changes are
+    /// not tracked (see [`MasonryWorker::set_track_changes()`]), the previous transform is equal
+    /// to the new one. Returns an empty array if the index is out of bounds.
+    pub fn get_transform_pair(&self, index: usize) -> Vec<u32> {
+        let current = match self.layout.get_transform(index) {
+            Some(transform) => transform,
+            None => return Vec::new(),
+        };
+        let previous = self.layout.get_previous_transform(index).unwrap_or(current);
+        [previous.to_array(), current.to_array()].concat()
+    }
+
+    /// Returns the `scrollTop` that keeps the anchor item visually stationary after a computation.
+    ///
+    /// `previous_offset_within_item` is the distance between the top of the anchor item and the
+    /// `scrollTop` before the computation. It is scaled with the height of the item if changes are
+    /// tracked (see [`MasonryWorker::set_track_changes()`]). Returns 0 if the index is out of
+    /// bounds.
+    pub fn anchor_offset(&self, anchor_index: u32, previous_offset_within_item: f32) -> f32 {
+        self.layout
+            .anchor_offset(anchor_index as usize, previous_offset_within_item)
+            .unwrap_or(0.0)
+    }
+
+    /// Returns the `scrollTop` that brings the item to the top, center or bottom of a viewport of
+    /// the given height, e.g. to reveal the selected image in any layout kind.
+    ///
+    /// The offset is never negative and not clamped to the container height, which the browser
+    /// does by itself. Returns `undefined` if the index is out of bounds or the item is hidden.
+    pub fn offset_of(
+        &self,
+        index: u32,
+        alignment: ScrollAlignment,
+        viewport_height: f32,
+    ) -> Option<f32> {
+        self.layout.offset_of(
+            index as usize,
+            alignment,
+            f32_to_u32(viewport_height.max(0.0)),
+        )
+    }
+
+    /// Returns the `scrollLeft` that brings the item to the left, center or right of a viewport
+    /// of the given width, like [`MasonryWorker::offset_of()`] for strips that scroll
+    /// horizontally. `Top` aligns the left edges and `Bottom` the right ones.
+    pub fn offset_of_x(
+        &self,
+        index: u32,
+        alignment: ScrollAlignment,
+        viewport_width: f32,
+    ) -> Option<f32> {
+        self.layout.offset_of_x(
+            index as usize,
+            alignment,
+            f32_to_u32(viewport_width.max(0.0)),
+        )
+    }
+
+    /// Returns a coarse profile of the layout for a minimap next to the scrollbar, with two
+    /// numbers for each of `buckets` equal bands from the top to the bottom of the layout.
+    ///
+    /// The first number of a band is the fraction that thumbnails cover, from 0 to 1, so dense
+    /// regions and gaps can be drawn without reading every transform. The second is 1 if a
+    /// section header starts in the band and 0 otherwise. All numbers are 0 before the first
+    /// computation.
+    pub fn minimap(&self, buckets: u32) -> Vec<f32> {
+        self.layout.minimap(buckets as usize, self.result.height)
+    }
+
+    /// Returns the position of the section header for each of `buckets` equal bands from the top
+    /// to the bottom of the layout, e.g. to label the track of a fast-scroll scrubber with dates
+    /// or folder names.
+    ///
+    /// A band gets the header whose section it starts in, and the bands above the first header
+    /// get the first one. The positions can be mapped to labels with
+    /// [`MasonryWorker::timeline_section()`], [`MasonryWorker::get_tag()`] or
+    /// [`MasonryWorker::item_index()`]. The result is empty without section headers or before the
+    /// first computation.
+    pub fn scrubber_labels(&self, buckets: u32) -> Vec<u32> {
+        self.layout
+            .scrubber_labels(buckets as usize, self.result.height)
+    }
+
+    /// Returns the scroll offset at which the section of the header at the given position starts,
+    /// e.g. to scroll to the label that the scrubber was released on, or `undefined` if the item
+    /// at the position is not a section header.
+    pub fn offset_for_label(&self, label: u32) -> Option<f32> {
+        self.layout.section_top(label as usize).map(u32_to_f32)
+    }
+
+    /// Sets a breakpoint table, so the layout picks its parameters for the current width itself.
+    ///
+    /// Every breakpoint consists of four numbers: the minimum container width, the thumbnail
+    /// size, the padding (the gap between the items in both directions) and the number of columns
+    /// (0 to derive it from the thumbnail size). The breakpoint with the largest minimum width
+    /// that fits the container replaces the thumbnail size and padding passed to
+    /// [`MasonryWorker::compute()`] and the column count set with
+    /// [`MasonryWorker::set_column_count()`]. A trailing incomplete breakpoint is ignored and an
+    /// empty table removes all breakpoints.
+    pub fn set_breakpoints(&mut self, table: &[u32]) {
+        let breakpoints = table
+            .chunks_exact(4)
+            .map(|breakpoint| Breakpoint {
+                min_width: breakpoint[0],
+                thumbnail_size: breakpoint[1],
+                padding: breakpoint[2],
+                columns: breakpoint[3],
+            })
+            .collect();
+        self.layout.set_breakpoints(breakpoints);
+    }
+
+    /// Collapses the gaps of containers narrower than the given width, e.g. a side panel, or
+    /// never with 0.
+    ///
+    /// Below the width the gaps step down from the padding passed to
+    /// [`MasonryWorker::compute()`] or a breakpoint, e.g. 8 to 4 and then 2 once the container is
+    /// half as wide, and the vertical masonry and grid layout keep at least two columns when the
+    /// number of columns is derived from the thumbnail size.
+    pub fn set_gap_collapse_width(&mut self, width: u32) {
+        self.layout.set_gap_collapse_width(width);
+    }
+
+    /// Sets the number of columns of the vertical masonry and grid layout.
+    ///
+    /// By default (0) the number of columns is derived from the container width and the
+    /// thumbnail size. With an explicit count the layout always has exactly that many columns and
+    /// the thumbnail size passed to [`MasonryWorker::compute()`] is ignored by these layouts; it
+    /// follows from the container width instead.
+    pub fn set_column_count(&mut self, column_count: u16) {
+        self.layout.set_column_count(column_count);
+    }
+
+    /// Limits the number of columns of the vertical masonry and grid layout.
+    ///
+    /// The number of columns that is derived from the thumbnail size is clamped, so that e.g. a
+    /// narrow window never collapses to a single giant column (`min_columns`) and an ultrawide
+    /// monitor does not produce lots of tiny columns (`max_columns`). The columns are widened or
+    /// narrowed to fill the container width. A limit of 0, the default, means no limit. An
+    /// explicit count set with [`MasonryWorker::set_column_count()`] is not limited.
+    pub fn set_column_limits(&mut self, min_columns: u16, max_columns: u16) {
+        self.layout.set_column_limits(min_columns, max_columns);
+    }
+
+    /// Limits the aspect ratio (width / height) the items are laid out with.
+    ///
+    /// Extremely tall screenshots and wide panoramas are laid out as if they were only as narrow
+    /// as `min_aspect_ratio` or as wide as `max_aspect_ratio`, and center-cropped to fit. The crop
+    /// offsets of the [`Transform`] tell how far the thumbnail is shifted inside its rectangle.
+    /// A limit of 0 means no limit. By default items are at most 3 times as tall as wide or vice
+    /// versa. The next computation lays out all items again.
+    pub fn set_aspect_ratio_limits(&mut self, min_aspect_ratio: f32, max_aspect_ratio: f32) {
+        self.layout
+            .set_aspect_ratio_limits(min_aspect_ratio, max_aspect_ratio);
+    }
+
+    /// Sets the aspect ratio (width / height) of items whose dimension is not known yet.
+    ///
+    /// Items with a dimension of 0×0, or whose dimension was not set at all, are laid out as
+    /// placeholders with this aspect ratio, so the gallery can render a stable skeleton while the
+    /// thumbnails load. It is 1 (square) by default. The next computation lays out all items
+    /// again.
+    pub fn set_default_aspect_ratio(&mut self, default_aspect_ratio: f32) {
+        self.layout.set_default_aspect_ratio(default_aspect_ratio);
+    }
+
+    /// Gives panoramas a row of their own that spans the full width.
+    ///
+    /// Items that are wider than the given aspect ratio (width / height) are laid out like items
+    /// marked with [`MasonryWorker::set_full_width()`] in all layouts, and are scaled to fit
+    /// instead of being cropped to the aspect ratio limits. An aspect ratio of 0, the default,
+    /// disables it. The next computation lays out all items again.
+    pub fn set_panorama_aspect_ratio(&mut self, panorama_aspect_ratio: f32) {
+        self.layout.set_panorama_aspect_ratio(panorama_aspect_ratio);
+    }
+
+    /// Caps the height of the rows in the horizontal layout, e.g. for rows of portrait images.
+    ///
+    /// Items that would be taller are center-cropped instead of making the row higher. How far
+    /// the thumbnail is shifted up inside its rectangle is reported as the crop offset of the
+    /// [`Transform`]. A height of 0 disables the cap, which is the default. The next computation
+    /// lays out all items again.
+    pub fn set_max_row_height(&mut self, max_row_height: u32) {
+        self.layout.set_max_row_height(max_row_height);
+    }
+
+    /// Sets how the last row of the horizontal layout is laid out if it is not full.
+    ///
+    /// By default it is left-aligned at the thumbnail size. It can also be stretched to the
+    /// container width like the other rows (the row height is capped by
+    /// [`MasonryWorker::set_max_row_height()`]) or hidden, in which case its items get an empty
+    /// transform and the container height ends above it.
+    pub fn set_last_row_behavior(&mut self, last_row: LastRowBehavior) {
+        self.layout.set_last_row_behavior(last_row);
+    }
+
+    /// Sets how the items of the horizontal layout are rounded to whole pixels.
+    ///
+    /// The rows are scaled to fill the container width, which results in fractional positions
+    /// and sizes. By default they are rounded down, which can leave a 1px gap between adjacent
+    /// items at some container widths. They can also be rounded to the nearest pixel, or their
+    /// edges can be rounded instead of their sizes, so that the rounding error is carried over to
+    /// the next item in the row. The next computation lays out all items again.
+    pub fn set_rounding_mode(&mut self, rounding: RoundingMode) {
+        self.layout.set_rounding_mode(rounding);
+    }
+
+    /// Enables or disables balancing the rows of the horizontal layout.
+    ///
+    /// By default rows are filled one after another, which occasionally produces very short or
+    /// very tall rows. When enabled, the row breaks are chosen so that the row heights deviate as
+    /// little as possible from the thumbnail size across the whole gallery. This is slower, and
+    /// [`MasonryWorker::compute_appended()`] and [`MasonryWorker::compute_incremental()`] lay out
+    /// all items again.
+    pub fn set_balance_rows(&mut self, enabled: bool) {
+        self.layout.set_balance_rows(enabled);
+    }
+
+    /// Enables or disables moving an item into the last row of the horizontal layout if it would
+    /// hold a single item otherwise, e.g. after a filter change.
+    ///
+    /// The last item of the row before moves down and the rest of that row is scaled up to fill
+    /// the width again. Only rows of at least three items give one away, and rows are not
+    /// rebalanced across row breaks and full-width items. Disabled by default.
+    pub fn set_avoid_widows(&mut self, enabled: bool) {
+        self.layout.set_avoid_widows(enabled);
+    }
+
+    /// Lays out the gallery bottom-up, e.g. for chronological feeds.
+    ///
+    /// The last items sit at the bottom right and the row (or the ragged column ends) that is not
+    /// full is at the top. The transforms are still measured from the top, so rendering stays the
+    /// same; keeping the view anchored at the bottom is a matter of scrolling to the new container
+    /// height. Since appended items change the whole layout, [`MasonryWorker::compute_appended()`]
+    /// and [`MasonryWorker::compute_incremental()`] lay out all items again. Column balancing
+    /// (see [`MasonryWorker::set_balance_columns()`]) has no effect in this mode, and neither has
+    /// this on a filmstrip.
+    pub fn set_reversed(&mut self, reversed: bool) {
+        self.layout.set_reversed(reversed);
+    }
+
+    /// Enables or disables evening out the bottom edge of the vertical masonry layout.
+    ///
+    /// Without it, the column heights can differ by up to a full thumbnail at the end. When
+    /// enabled, the last items (at most one per column) are moved between the columns to minimize
+    /// the difference between the highest and the lowest column, so they may end up slightly out
+    /// of order.
+    pub fn set_balance_columns(&mut self, enabled: bool) {
+        self.layout.set_balance_columns(enabled);
+    }
+
+    /// Keeps the items of the vertical masonry layout in their previous column when the width
+    /// changes a little, unless that column is more than `max_penalty` pixels lower than the
+    /// shortest one.
+    ///
+    /// Without it, every item goes into the shortest column, so resizing the window by a few
+    /// pixels can shuffle most items between the columns. With a penalty of about half a
+    /// thumbnail, the items mostly stay in their column as long as the number of columns does not
+    /// change, at the cost of less even columns. It only applies if the most recent computation
+    /// was a vertical masonry layout as well. 0 disables it, which is the default.
+    pub fn set_column_stability(&mut self, max_penalty: u32) {
+        self.layout.set_column_stability(max_penalty);
+    }
+
+    /// Sizes the cells of the grid and mosaic layouts to the median aspect ratio of the items
+    /// instead of squares, e.g. 3:2 cells for a library of landscape photos.
+    ///
+    /// Mostly portrait or landscape libraries then waste less space and crop less of the
+    /// thumbnails. Items that are not measured yet, hidden and full-width items do not count.
+    /// The median is updated in every computation, and all items are laid out again when it
+    /// changes. Disabled by default.
+    pub fn set_median_cells(&mut self, enabled: bool) {
+        self.layout.set_median_cells(enabled);
+    }
+
+    /// Lays out every `period`th item of `MasonryType.Mosaic` as a large cell that spans two
+    /// columns and two rows, e.g. 5 for one large cell in every five items.
+    ///
+    /// A large cell only goes where it fits into the current row, otherwise the next item that
+    /// fits gets it, so the small items always fill the cells around it without holes. 0 lays out
+    /// all items as small cells like the grid. The default is 5.
+    pub fn set_mosaic_period(&mut self, period: u32) {
+        self.layout.set_mosaic_period(period);
+    }
+
+    /// Fills `MasonryType.Grid` column by column instead of row by row, so that the order of the
+    /// items, and with it the DOM and tab order, runs from top to bottom and then to the next
+    /// column.
+    ///
+    /// The items between two items that span the full width or break before fill as few rows as
+    /// they fit into, so the last column can end early. Spans are ignored in this order.
+    pub fn set_column_major(&mut self, enabled: bool) {
+        self.layout.set_column_major(enabled);
+    }
+
+    /// Sets all parameters of the computations at once, which persist until they are changed.
+    ///
+    /// The kind, thumbnail size and `gap_x` replace the arguments of
+    /// [`MasonryWorker::compute_width()`], and the other fields call the setters like
+    /// [`MasonryWorker::set_gaps()`] and [`MasonryWorker::set_caption_height()`]. Equal gaps
+    /// leave the padding to the arguments of [`MasonryWorker::compute()`], like before any call to
+    /// [`MasonryWorker::set_gaps()`].
+    pub fn set_config(&mut self, config: &MasonryConfig) {
+        self.defaults = (config.kind, config.thumbnail_size, config.gap_x);
+        self.gaps = Some((config.gap_x, config.gap_y)).filter(|(gap_x, gap_y)| gap_x != gap_y);
+        self.outer_padding = config.outer_padding;
+        self.max_content_width = config.max_content_width;
+        self.caption_height = config.caption_height;
+        self.metadata_width = config.metadata_width;
+        self.set_device_pixel_ratio(config.device_pixel_ratio);
+        self.gap_scaling = (config.gap_reference_size, config.min_gap, config.max_gap);
+        self.row_height = (config.target_row_height, config.row_height_tolerance);
+    }
+
+    /// Sets the thumbnail size that [`MasonryWorker::compute_width()`] computes with, without
+    /// computing anything yet.
+    ///
+    /// Several parameters can be changed in a row, e.g. together with
+    /// [`MasonryWorker::set_padding()`], and are laid out by a single computation afterwards.
+    /// Until then the transforms and queries stay those of the most recent computation, and
+    /// [`MasonryWorker::compute_incremental()`] keeps its parameters.
+    pub fn set_thumbnail_size(&mut self, thumbnail_size: u32) {
+        self.defaults.1 = thumbnail_size;
+    }
+
+    /// Sets the padding that [`MasonryWorker::compute_width()`] computes with, without computing
+    /// anything yet, like [`MasonryWorker::set_thumbnail_size()`].
+    ///
+    /// The padding is the gap between the items in both directions, unless
+    /// [`MasonryWorker::set_gaps()`] replaced it.
+    pub fn set_padding(&mut self, padding: u32) {
+        self.defaults.2 = padding;
+    }
+
+    /// Returns the parameters that [`MasonryWorker::compute_width()`] computes with, see
+    /// [`MasonryWorker::set_config()`].
+    pub fn get_config(&self) -> MasonryConfig {
+        let (kind, thumbnail_size, padding) = self.defaults;
+        self.config(kind, thumbnail_size, padding)
+    }
+
+    /// Sets the space between the items and the edges of the container.
+    ///
+    /// By default the padding passed to [`MasonryWorker::compute()`] is the gap between the items
+    /// in both directions and there is no space around them. Once set, `gap_x` and `gap_y` replace
+    /// that padding, e.g. for designs with a tighter vertical rhythm than horizontal spacing. The
+    /// outer padding is added on every side of the container, including the bottom of the
+    /// returned container height.
+    pub fn set_gaps(&mut self, gap_x: u32, gap_y: u32, outer_padding: u32) {
+        self.gaps = Some((gap_x, gap_y));
+        self.outer_padding = outer_padding;
+    }
+
+    /// Scales the gaps between the items proportionally with the thumbnail size, so that zooming
+    /// out does not leave gaps that are large compared to tiny thumbnails.
+    ///
+    /// The padding or gaps apply at the reference thumbnail size and both directions are scaled
+    /// by the same factor at other sizes, rounded to whole pixels and kept between `min_gap` and
+    /// `max_gap` (0 for no limit). The outer padding stays fixed. A reference size of 0, the
+    /// default, keeps the gaps fixed.
+    pub fn set_gap_scaling(&mut self, reference_size: u32, min_gap: u32, max_gap: u32) {
+        self.gap_scaling = (reference_size, min_gap, max_gap);
+    }
+
+    /// Sets the height the rows of the horizontal layout aim for instead of the thumbnail size,
+    /// e.g. high rows for a hero-like view or low ones for a dense contact sheet, while the other
+    /// kinds keep the thumbnail size.
+    ///
+    /// A row that would have to shrink to make room for one more item ends before that item
+    /// instead if it is at most `tolerance` times the target higher then, e.g. 0.2 for 20%, and
+    /// closer to the target than shrunk. A target of 0, the default, uses the thumbnail size and
+    /// a tolerance of 0 always shrinks the rows. Balanced rows already keep as close to the
+    /// target as they can.
+    pub fn set_row_height(&mut self, target_row_height: u32, tolerance: f32) {
+        self.row_height = (target_row_height, tolerance);
+    }
+
+    /// Limits the width of the area the items are laid out in.
+    ///
+    /// When the container is wider (apart from the outer padding, see
+    /// [`MasonryWorker::set_gaps()`]), the items are centered in it. The transforms already
+    /// include the offset, which is returned by [`MasonryWorker::content_left()`] for positioning
+    /// other elements like headers. A width of 0, the default, means no limit.
+    pub fn set_max_content_width(&mut self, max_content_width: u32) {
+        self.max_content_width = max_content_width;
+    }
+
+    /// Reserves space below every thumbnail for a caption, e.g. the filename or tags.
+    ///
+    /// The height of every transform includes the caption, so the thumbnail fills the top
+    /// `height - caption_height` pixels and the caption is rendered below it without overlapping
+    /// the next row. Crops (see [`MasonryWorker::set_max_row_height()`]) only apply to the
+    /// thumbnail. The default is 0.
+    pub fn set_caption_height(&mut self, caption_height: u32) {
+        self.caption_height = caption_height;
+    }
+
+    /// Reserves space for the metadata next to the thumbnail in every row of the list layout.
+    ///
+    /// A row is then as wide as the thumbnail size, the horizontal gap and the metadata width
+    /// together, or the width of the content if that is narrower. The default is 0, which makes
+    /// every row fill the content width.
+    pub fn set_metadata_width(&mut self, metadata_width: u32) {
+        self.metadata_width = metadata_width;
+    }
+
+    /// Snaps the layout to physical pixels, e.g. `window.devicePixelRatio`.
+    ///
+    /// With fractional ratios like 1.25 or 1.5, positions in whole CSS pixels fall between
+    /// physical pixels, which blurs the thumbnails and leaves 1px seams between them. With a
+    /// ratio other than 1, the widths and sizes passed to the computations stay in CSS pixels,
+    /// but the layout is computed in whole physical pixels: the transforms, container heights,
+    /// [`MasonryWorker::content_left()`] and the coordinates of all queries are in physical
+    /// pixels, so they must be divided by the ratio for CSS. The default is 1. The next
+    /// computation lays out all items again.
+    pub fn set_device_pixel_ratio(&mut self, device_pixel_ratio: f32) {
+        self.device_pixel_ratio = if device_pixel_ratio > 0.0 {
+            device_pixel_ratio
+        } else {
+            1.0
+        };
+    }
+
+    /// Returns how far the items are inset from the left edge of the container in the most
+    /// recent computation, including the outer padding.
+    pub fn content_left(&self) -> u32 {
+        self.layout.content_left()
+    }
+
+    /// Sizes the columns so that they span exactly the full width.
+    ///
+    /// The thumbnail size rarely divides the container width, so by default the vertical masonry
+    /// and grid layout leave a gap after the last column. With auto-fit the columns are widened or
+    /// narrowed to cover it, which leaves less than a pixel per column. The cell size that was
+    /// used is returned by [`MasonryWorker::resolved_thumbnail_size()`]. The next computation
+    /// lays out all items again.
+    pub fn set_auto_fit(&mut self, auto_fit: bool) {
+        self.layout.set_auto_fit(auto_fit);
+    }
+
+    /// Returns the size the thumbnails were laid out with in the most recent computation.
+    ///
+    /// This is the width of the columns without the horizontal gap in the vertical masonry and
+    /// grid layout, and the base row height in the horizontal layout.
+    pub fn resolved_thumbnail_size(&self) -> u32 {
+        self.layout.resolved_thumbnail_size()
+    }
+
+    /// Set the item count up to which [`MasonryWorker::compute()`] runs on the main thread.
+    ///
+    /// The returned `Promise` is already resolved in that case. The default is 0, which means
+    /// that every computation is sent to the web worker thread.
+    pub fn set_sync_threshold(&mut self, num_items: usize) {
+        self.sync_threshold = num_items;
+    }
+
+    /// Sets the width difference in CSS pixels below which [`MasonryWorker::compute()`] and its
+    /// variants keep the transforms of the most recent computation and resolve right away with
+    /// its result.
+    ///
+    /// This only applies while nothing changed since that computation: the config has to be the
+    /// same and no item may have been added, removed or changed. The items then keep their places
+    /// in a container that got slightly wider or narrower, e.g. because a scrollbar appeared or
+    /// disappeared, instead of being laid out again. The difference is measured from the width
+    /// the transforms were computed with, so several small resizes in a row add up. The default
+    /// is 0, which computes every request.
+    pub fn set_resize_threshold(&mut self, threshold: u32) {
+        self.resize_threshold = threshold;
+    }
+
+    /// Rejects the `Promise`s of [`MasonryWorker::compute()`] and
+    /// [`MasonryWorker::compute_visible_first()`] with an `Error` named `MasonryTimeoutError` if
+    /// the web worker thread does not finish within the given number of milliseconds.
+    ///
+    /// This signals a web worker thread that hangs or missed a notification instead of leaving
+    /// the gallery frozen. The computation is not cancelled, so the layout must still be treated
+    /// like one whose computation is pending. If the web worker thread hangs, report it with
+    /// [`MasonryWorker::fail()`] and replace it with [`MasonryWorker::restart()`]. A timeout of 0
+    /// waits forever, which is the default.
+    pub fn set_compute_timeout(&mut self, timeout_ms: u32) {
+        self.compute_timeout = timeout_ms;
+    }
+
+    /// Computes the layouts of the neighboring zoom levels in the background, so that switching
+    /// to one of them is instant.
+    ///
+    /// The thumbnail sizes are the steps of the zoom slider. [`MasonryWorker::compute()`] and
+    /// [`MasonryWorker::compute_visible_first()`] send copies of the items with the next smaller
+    /// and the next larger size to the web worker threads, which lay them out with the same width,
+    /// kind and padding once no other computation is waiting. A later computation with one of
+    /// these sizes takes over the precomputed transforms and resolves right away, unless the items
+    /// or settings changed in the meantime. Every zoom level keeps its own copy of the items and
+    /// transforms. An empty array disables it, which is the default.
+    pub fn set_zoom_levels(&mut self, thumbnail_sizes: &[u32]) {
+        let mut zoom_levels = thumbnail_sizes.to_vec();
+        zoom_levels.sort_unstable();
+        zoom_levels.dedup();
+        self.zoom_levels = zoom_levels;
+        if self.zoom_levels.is_empty() {
+            self.retain_zoom_levels(|_, _| false);
+        }
+    }
+
+    /// Keeps the layouts of up to the given number of earlier computations, so that going back to
+    /// a previous width or config is instant, e.g. when the window is resized back and forth.
+    ///
+    /// Before [`MasonryWorker::compute()`], [`MasonryWorker::compute_visible_first()`] or
+    /// [`MasonryWorker::compute_sync()`] computes with other arguments than the most recent
+    /// computation, a copy of the items and transforms of that computation is kept. A later
+    /// computation with the same width, kind, thumbnail size, padding and settings takes it over
+    /// and resolves right away. The copies that were used least recently are dropped first, and
+    /// all of them are dropped once the items or settings change. A size of 0 disables it, which
+    /// is the default.
+    pub fn set_cache_size(&mut self, num_layouts: usize) {
+        self.cache_size = num_layouts;
+        let excess = self.cached.len().saturating_sub(num_layouts);
+        self.cached.drain(..excess);
+    }
+
+    /// Records the phases of every computation with `performance.mark()` and
+    /// `performance.measure()`, so that profiles in the developer tools show how long the layout, the
+    /// stats and publishing the transforms took, or stops recording them.
+    ///
+    /// The measures are named `masonry layout`, `masonry stats` and `masonry publish` and appear
+    /// on the timeline of the thread that computed the layout. The same durations are part of
+    /// every [`ComputeResult`] as `elapsed`, `statsElapsed` and `publishElapsed` whether this is
+    /// enabled or not, together with the item count, e.g. to collect them in the field. Like
+    /// [`MasonryWorker::set_slice_size()`], this applies to all workers. It is disabled by
+    /// default, because every measure stays in the performance timeline until it is cleared.
+    #[allow(clippy::unused_self)]
+    pub fn set_instrumented(&mut self, enabled: bool) {
+        set_instrumented(enabled);
+    }
+
+    /// Lays out huge collections in slices of the given number of items.
+    ///
+    /// Between two slices the web worker thread publishes how far it got (see
+    /// [`MasonryWorker::progress()`]) and checks whether the computation was cancelled or replaced
+    /// by a newer one, in which case it stops and picks up the newest request. Inside a slice the
+    /// shared state is not touched at all. Smaller sizes than the default of 64 are raised to it.
+    #[allow(clippy::unused_self)]
+    pub fn set_slice_size(&mut self, num_items: u32) {
+        set_slice_size(num_items);
+    }
+
+    /// Returns how many positions of the layout the pending computation has processed at the end
+    /// of its last slice.
+    ///
+    /// Unlike the other methods, this may be called while a computation is pending, e.g. to show
+    /// a progress indicator for huge collections.
+    pub fn progress(&self) -> u32 {
+        progress_of(&self.layout)
+    }
+
+    /// Sets a callback that is called with the percentage and the number of processed items
+    /// every 100 ms while a computation on the web worker thread is pending, e.g. to show a
+    /// progress bar instead of a frozen gallery for huge libraries, or removes it if `undefined`
+    /// is given.
+    ///
+    /// Only computations of more items than the threshold of
+    /// [`MasonryWorker::set_progress_threshold()`] are reported, and only if they take longer
+    /// than 100 ms. The numbers are those of [`MasonryWorker::progress()`], so they grow once per
+    /// slice (see [`MasonryWorker::set_slice_size()`]). Coalesced computations are reported as
+    /// one. Computations on the main thread block it and are not reported.
+    pub fn on_progress(&self, callback: Option<js_sys::Function>) {
+        self.progress.set_callback(callback);
+    }
+
+    /// Sets the item count above which the progress of computations is reported to the callback
+    /// of [`MasonryWorker::on_progress()`]. The default is 0, which reports every computation
+    /// that takes long enough.
+    pub fn set_progress_threshold(&mut self, num_items: usize) {
+        self.progress_threshold = num_items;
+    }
+
+    /// Stops the pending computation early.
+    ///
+    /// The `Promise` returned by [`MasonryWorker::compute()`] still resolves, but
+    /// [`MasonryWorker::get_height()`] keeps returning the height of the last computation that
+    /// ran to completion. The transforms of a cancelled computation may be partially updated, so
+    /// they should not be read until another computation has finished.
+    pub fn cancel_pending(&self) {
+        cancel_computation(&self.layout);
+    }
+
+    /// Returns true while a computation of this worker is still pending.
+    pub fn is_busy(&self) -> bool {
+        is_computing(&self.layout)
+    }
+
+    /// Returns the transforms of all items, so that they can be sent to another thread in a
+    /// message.
+    ///
+    /// Every item takes seven numbers: the width, height, top and left followed by the crop
+    /// offsets and the cropped flag like [`MasonryWorker::get_transform()`].
+    ///
+    /// Together with [`MasonryWorker::import_transforms()`] this is the fallback for browsers
+    /// without `SharedArrayBuffer` of workers whose web worker is started by the caller, where the
+    /// web worker has its own instance of the module. It computes the layout with
+    /// [`MasonryWorker::compute_sync()`] and posts the exported transforms back to the main
+    /// thread. [`MasonryWorker::pack_transforms()`] avoids copying them in the message. Workers
+    /// from [`crate::spawn::MasonryWorkerBuilder`] do this on their own.
+    pub fn export_transforms(&self) -> Vec<u32> {
+        self.layout.export_transforms()
+    }
+
+    /// Replaces the transforms with the ones returned by [`MasonryWorker::export_transforms()`]
+    /// of another instance, which computed the given container height.
+    ///
+    /// Afterwards the transforms and queries work like after a computation of this instance.
+    /// Throws an `Error` whose `code` is `InvalidDimensions` if there are not seven numbers for
+    /// every item, in which case the layout is unchanged. It must not be called while a `Promise`
+    /// returned by [`MasonryWorker::compute()`] is still pending.
+    pub fn import_transforms(&mut self, transforms: &[u32], height: u32) -> Result<(), JsValue> {
+        fallback::check_transforms(&self.layout, transforms.len())?;
+        self.lay_out_locally(|layout| {
+            layout.import_transforms(transforms);
+            height
+        });
+        Ok(())
+    }
+
+    /// Returns the container height and the transforms of all items packed into a new
+    /// `ArrayBuffer`, which can be transferred to another thread instead of copied, e.g. with
+    /// `postMessage(buffer, [buffer])`.
+    ///
+    /// The buffer holds the height followed by the seven numbers of every item like
+    /// [`MasonryWorker::export_transforms()`], all as unsigned 32-bit integers. Unlike the array
+    /// of [`MasonryWorker::export_transforms()`], it is not a view into the memory of the module,
+    /// so the web worker of the fallback without `SharedArrayBuffer` can hand it over with the
+    /// reply to [`MasonryWorker::unpack_transforms()`] without cloning hundreds of thousands of
+    /// numbers.
+    pub fn pack_transforms(&self) -> js_sys::ArrayBuffer {
+        let lanes = self.layout.export_transforms();
+        let packed = js_sys::Uint32Array::new_with_length(usize_to_u32(lanes.len()) + 1);
+        packed.set_index(0, self.result.height);
+        packed.subarray(1, packed.length()).copy_from(&lanes);
+        packed.buffer()
+    }
+
+    /// Replaces the transforms with the ones in a buffer returned by
+    /// [`MasonryWorker::pack_transforms()`] of another instance and returns its container height.
+    ///
+    /// Afterwards the transforms and queries work like after
+    /// [`MasonryWorker::import_transforms()`]. Throws if the buffer does not hold a height and
+    /// whole transforms, or like [`MasonryWorker::import_transforms()`] if they are not the
+    /// transforms of all items, in which case the layout is unchanged. It must not be called
+    /// while a `Promise` returned by [`MasonryWorker::compute()`] is still pending.
+    pub fn unpack_transforms(&mut self, buffer: &js_sys::ArrayBuffer) -> Result<u32, JsValue> {
+        let lanes = fallback::unpack(buffer)?;
+        self.import_transforms(&lanes[1..], lanes[0])?;
+        Ok(lanes[0])
+    }
+
+    /// Computes the layout of a snapshot from [`MasonryWorker::export_snapshot()`] with the
+    /// arguments of the computation it holds and returns the transforms like
+    /// [`MasonryWorker::pack_transforms()`].
+    ///
+    /// This is what the web worker does for every computation of a worker from
+    /// [`crate::spawn::MasonryWorkerBuilder`] if `SharedArrayBuffer` is missing (see
+    /// `worker.js`). Throws like [`MasonryWorker::import_snapshot()`] and
+    /// [`MasonryWorker::compute_sync()`], or if the snapshot holds no computation.
+    pub fn compute_snapshot(&mut self, json: &str) -> Result<js_sys::ArrayBuffer, JsValue> {
+        self.import_snapshot(json)?;
+        let request = self.request.ok_or_else(|| invalid_snapshot("request"))?;
+        self.compute_sync(
+            request.width,
+            request.kind,
+            request.thumbnail_size,
+            request.padding,
+        )?;
+        Ok(self.pack_transforms())
+    }
+
+    /// Returns the dimensions, flags, order and transforms of the items and the container height
+    /// of the most recent computation as bytes, e.g. to cache the layout in `IndexedDB`.
+    ///
+    /// The configuration is not included. It must not be called while a `Promise` returned by
+    /// [`MasonryWorker::compute()`] is still pending.
+    pub fn serialize(&self) -> Vec<u8> {
+        self.layout.serialize(self.result.height)
+    }
+
+    /// Restores the items from bytes returned by [`MasonryWorker::serialize()`] and returns the
+    /// container height, so that the gallery can be shown at its previous scroll position before
+    /// the dimensions are loaded again.
+    ///
+    /// The configuration has to be set like before serializing, because the transforms are not
+    /// computed again. Throws if the bytes are not a serialized layout of this version, e.g. a
+    /// cache of an older version, in which case the layout is unchanged. It must not be called
+    /// while a `Promise` returned by [`MasonryWorker::compute()`] is still pending.
+    pub fn deserialize(&mut self, bytes: &[u8]) -> Result<u32, JsValue> {
+        let (mut restored, height) = (None, self.result.height);
+        self.lay_out_locally(|layout| {
+            restored = layout.deserialize(bytes);
+            restored.unwrap_or(height)
+        });
+        restored.ok_or_else(|| {
+            let message = String::from("Invalid serialized layout");
+            Error::InvalidInput(message).into()
+        })
+    }
+
+    /// Returns the whole layout as JSON, e.g. to attach it to a bug report about a layout glitch.
+    ///
+    /// The snapshot holds the arguments of the most recent computation, the gaps, paddings,
+    /// caption height and device pixel ratio, the settings of the layout as returned by
+    /// `Layout::export_settings()`, the breakpoints like [`MasonryWorker::set_breakpoints()`], the
+    /// container height, the items as returned by `Layout::export_items()` and
+    /// `Layout::export_margins()`, the order and the transforms like
+    /// [`MasonryWorker::export_transforms()`].
+    ///
+    /// Not included are the tags of the items, the focused item, the zoom levels and the
+    /// selection. It must not be called while a `Promise` returned by
+    /// [`MasonryWorker::compute()`] is still pending.
+    pub fn export_snapshot(&self) -> String {
+        let request = match self.request {
+            Some(request) => format!(
+                "{{\"width\":{},\"kind\":{},\"thumbnailSize\":{},\"padding\":{}}}",
+                request.width, request.kind as u32, request.thumbnail_size, request.padding
+            ),
+            None => String::from("null"),
+        };
+        let gaps = match self.gaps {
+            Some((gap_x, gap_y)) => format!("[{},{}]", gap_x, gap_y),
+            None => String::from("null"),
+        };
+        let order = match self.layout.requested_order() {
+            Some(ids) => json_array(ids),
+            None => String::from("null"),
+        };
+        let mut settings = String::from("{");
+        for (i, (name, value)) in self.layout.export_settings().into_iter().enumerate() {
+            if i > 0 {
+                settings.push(',');
+            }
+            let _ = write!(settings, "\"{}\":{}", name, value);
+        }
+        settings.push('}');
+        let breakpoints: Vec<u32> = self
+            .layout
+            .breakpoints()
+            .iter()
+            .flat_map(|breakpoint| {
+                [
+                    breakpoint.min_width,
+                    breakpoint.thumbnail_size,
+                    breakpoint.padding,
+                    breakpoint.columns,
+                ]
+            })
+            .collect();
+        format!(
+            "{{\"version\":{},\"request\":{},\"gaps\":{},\"outerPadding\":{},\"maxContentWidth\":{},\
+             \"captionHeight\":{},\"metadataWidth\":{},\"devicePixelRatio\":{},\
+             \"gapScaling\":[{},{},{}],\"targetRowHeight\":{},\"rowHeightTolerance\":{},\
+             \"settings\":{},\"breakpoints\":{},\"height\":{},\"items\":{},\"margins\":{},\
+             \"order\":{},\"transforms\":{}}}",
+            SNAPSHOT_VERSION,
+            request,
+            gaps,
+            self.outer_padding,
+            self.max_content_width,
+            self.caption_height,
+            self.metadata_width,
+            self.device_pixel_ratio,
+            self.gap_scaling.0,
+            self.gap_scaling.1,
+            self.gap_scaling.2,
+            self.row_height.0,
+            self.row_height.1,
+            settings,
+            json_array(&breakpoints),
+            self.result.height,
+            json_array(&self.layout.export_items()),
+            json_array(&self.layout.export_margins()),
+            order,
+            json_array(&self.layout.export_transforms()),
+        )
+    }
+
+    /// Restores a layout from JSON returned by [`MasonryWorker::export_snapshot()`] and returns
+    /// its container height, so that a reported glitch can be reproduced.
+    ///
+    /// The transforms are taken as they are, so they show the glitch even if it was fixed since.
+    /// Computing the layout again with the restored arguments shows the current result. Settings
+    /// that an older snapshot does not hold keep their value. Throws if the JSON is not a snapshot
+    /// of this version, in which case the layout is unchanged. It must not be called while a
+    /// `Promise` returned by [`MasonryWorker::compute()`] is still pending.
+    pub fn import_snapshot(&mut self, json: &str) -> Result<u32, JsValue> {
+        let snapshot = js_sys::JSON::parse(json).map_err(|_| invalid_snapshot("JSON"))?;
+        if f64_to_u32(number(&snapshot, "version")?) != SNAPSHOT_VERSION {
+            return Err(invalid_snapshot("version"));
+        }
+        let request = field(&snapshot, "request")?;
+        let request = if request.is_null() {
+            None
+        } else {
+            let kind = masonry_type(f64_to_u32(number(&request, "kind")?))
+                .ok_or_else(|| invalid_snapshot("kind"))?;
+            Some(Request {
+                width: f64_to_u32(number(&request, "width")?),
+                kind,
+                thumbnail_size: f64_to_u32(number(&request, "thumbnailSize")?),
+                padding: f64_to_u32(number(&request, "padding")?),
+            })
+        };
+        let gaps = if field(&snapshot, "gaps")?.is_null() {
+            None
+        } else {
+            match lanes(&snapshot, "gaps")?[..] {
+                [gap_x, gap_y] => Some((gap_x, gap_y)),
+                _ => return Err(invalid_snapshot("gaps")),
+            }
+        };
+        let outer_padding = f64_to_u32(number(&snapshot, "outerPadding")?);
+        let max_content_width = f64_to_u32(number(&snapshot, "maxContentWidth")?);
+        let caption_height = f64_to_u32(number(&snapshot, "captionHeight")?);
+        // Snapshots from before the list layout have no metadata width.
+        let metadata_width = f64_to_u32(field(&snapshot, "metadataWidth")?.as_f64().unwrap_or(0.0));
+        let device_pixel_ratio = f64_to_f32(number(&snapshot, "devicePixelRatio")?);
+        // Snapshots from before gap scaling have fixed gaps.
+        let gap_scaling = if field(&snapshot, "gapScaling")?.is_undefined() {
+            (0, 0, 0)
+        } else {
+            match lanes(&snapshot, "gapScaling")?[..] {
+                [reference_size, min_gap, max_gap] => (reference_size, min_gap, max_gap),
+                _ => return Err(invalid_snapshot("gapScaling")),
+            }
+        };
+        // Snapshots from before the target row height use the thumbnail size.
+        let row_height = (
+            f64_to_u32(field(&snapshot, "targetRowHeight")?.as_f64().unwrap_or(0.0)),
+            f64_to_f32(
+                field(&snapshot, "rowHeightTolerance")?
+                    .as_f64()
+                    .unwrap_or(0.0),
             ),
+        );
+        let settings = snapshot_settings(&snapshot, &self.layout)?;
+        let breakpoints = optional_lanes(&snapshot, "breakpoints")?;
+        let height = f64_to_u32(number(&snapshot, "height")?);
+        let items = lanes(&snapshot, "items")?;
+        let margins = optional_lanes(&snapshot, "margins")?;
+        if let Some(margins) = &margins {
+            // Every item takes four numbers in the items and five in the margins.
+            if 4 * margins.len() != 5 * items.len() {
+                return Err(invalid_snapshot("margins"));
+            }
+        }
+        let order = if field(&snapshot, "order")?.is_null() {
+            None
+        } else {
+            Some(lanes(&snapshot, "order")?)
+        };
+        let transforms = lanes(&snapshot, "transforms")?;
+
+        // The aspect ratios of the restored items are derived with the restored settings.
+        let previous = (
+            self.layout.export_settings(),
+            self.layout.breakpoints().to_vec(),
+        );
+        if !self.layout.import_settings(&settings) {
+            return Err(invalid_snapshot("settings"));
+        }
+        if let Some(table) = &breakpoints {
+            self.set_breakpoints(table);
+        }
+        if !self.layout.restore(&items, order, &transforms) {
+            self.layout.import_settings(&previous.0);
+            self.layout.set_breakpoints(previous.1);
+            return Err(invalid_snapshot("items"));
         }
+        if let Some(margins) = &margins {
+            self.layout.import_margins(margins);
+        }
+        self.lay_out_locally(|_| height);
+        self.request = request;
+        self.gaps = gaps;
+        self.outer_padding = outer_padding;
+        self.max_content_width = max_content_width;
+        self.caption_height = caption_height;
+        self.metadata_width = metadata_width;
+        self.set_device_pixel_ratio(device_pixel_ratio);
+        self.gap_scaling = gap_scaling;
+        self.row_height = row_height;
+        Ok(height)
+    }
+
+    /// Checks that no two visible items overlap and that every visible item has a size and lies
+    /// inside of the container of the given width, which has to be the width of the most recent
+    /// computation.
+    ///
+    /// Every violation takes three numbers: the [`masonry_core::ViolationKind`], the index of the
+    /// item and the index of the item that overlaps it, or the same index again. An empty array
+    /// means that the layout is valid. This is meant for debugging and checks all items, so it is
+    /// slow for big layouts. It must not be called while a `Promise` returned by
+    /// [`MasonryWorker::compute()`] is still pending.
+    pub fn validate(&self, width: u32) -> Vec<u32> {
+        self.layout
+            .validate(width)
+            .iter()
+            .flat_map(|violation| [violation.kind as u32, violation.index, violation.other])
+            .collect()
+    }
+
+    /// Returns how much memory the items take up and the size of the WebAssembly memory, e.g. to
+    /// show it in a debug panel or to decide when to call [`MasonryWorker::shrink_to_fit()`].
+    pub fn memory_stats(&self) -> MemoryStats {
+        self.layout.memory_stats()
+    }
+
+    /// Returns a number that changes whenever typed arrays created from `memory.buffer` may no
+    /// longer show the current memory.
+    ///
+    /// Any call that allocates, e.g. [`MasonryWorker::resize()`] or a computation, can grow the
+    /// WebAssembly memory, after which the views of the old buffer are too short or detached.
+    /// [`MasonryWorker::shrink_to_fit()`] frees the transforms that
+    /// [`MasonryWorker::get_transform()`] pointed to. Views should be cached together with the
+    /// version and be created again from `memory.buffer` once it changed.
+    #[allow(clippy::unused_self)]
+    pub fn memory_version(&self) -> u32 {
+        memory_version()
+    }
+
+    /// Returns a `Float32Array` with the width, height, top and left of every item in CSS pixels,
+    /// which is rewritten after every computation, so that a renderer can read the positions every
+    /// frame without calling into the module or copying them.
+    ///
+    /// The first call starts keeping the floats next to the transforms, which costs a copy of the
+    /// transforms per computation from then on. The array is a view into the memory of the module,
+    /// so it has to be created again once [`MasonryWorker::memory_version()`] changed, which also
+    /// happens when the floats moved or the item count changed. A computation on the web worker
+    /// thread rewrites them right before its `Promise` resolves, so a frame in between can see
+    /// both layouts mixed. Throws if a computation is pending.
+    pub fn transforms_view(&mut self) -> Result<js_sys::Float32Array, JsValue> {
+        if is_computing(&self.layout) {
+            return Err(still_pending("create the transforms view"));
+        }
+        self.layout.set_float_view(true);
+        // SAFETY: The view is only read until the memory version changes, see above.
+        Ok(unsafe { js_sys::Float32Array::view(self.layout.float_view()) })
+    }
+
+    /// Returns a number that changes whenever the array of [`MasonryWorker::transforms_view()`]
+    /// was rewritten, so that a renderer can skip frames in which nothing moved.
+    pub fn transforms_generation(&self) -> u32 {
+        self.layout.float_view_generation()
+    }
+
+    /// Returns height of the container from the most recent computation.
+    ///
+    /// This is the height the `Promise` of the computation resolved with, so components that
+    /// missed it can size the scroll container without computing the layout again. It stays the
+    /// height of the last computation that ran to completion until the next one finishes. Like
+    /// the transforms, it is in physical pixels if a device pixel ratio is set (see
+    /// [`MasonryWorker::set_device_pixel_ratio()`]).
+    pub fn get_height(&self) -> u32 {
+        self.result.height
+    }
+
+    /// Registers a callback that is called with the [`ComputeResult`] after every computation of
+    /// the layout, e.g. so that the scrollbar, a minimap and a debug overlay can each react to
+    /// layout changes without being handed the `Promise`s of the computations.
+    ///
+    /// Besides [`MasonryWorker::compute()`] and its variants, this includes the computations on
+    /// the main thread like [`MasonryWorker::compute_appended()`], restored layouts and adopted
+    /// zoom levels or cached computations. Coalesced computations call it once with the final
+    /// result, and [`MasonryWorker::compute_visible_first()`] once for the visible items and once
+    /// for the rest. The callbacks are called after the method that computed returned, in the
+    /// order they were registered, and can use the worker. A callback that throws does not keep
+    /// the others from being called.
+    pub fn on_layout(&self, callback: js_sys::Function) {
+        self.observers.add(callback);
+    }
+
+    /// Removes a callback registered with [`MasonryWorker::on_layout()`] and returns whether it
+    /// was registered.
+    pub fn off_layout(&self, callback: &js_sys::Function) -> bool {
+        self.observers.remove(callback)
+    }
+
+    /// Splits the most recent computation into pages of the given height, e.g. to print or export
+    /// a contact sheet, so that no item straddles a page break.
+    ///
+    /// The pages are cut above the first item that would cross the bottom of a page, and the
+    /// items from there on continue on the next page after the outer padding. The result holds
+    /// the positions of the items on every page and their transforms relative to the top of the
+    /// page. Only items that are higher than a page cross its bottom. Compute the layout at the
+    /// width of the page first.
+    pub fn paginate(&self, page_height: u32) -> Pagination {
+        self.layout.paginate(page_height).into()
+    }
+
+    /// Distributes the most recent computation over the given number of containers of the same
+    /// width, e.g. the two panes of a compare view, and returns one page per container.
+    ///
+    /// The pages are as high as needed to fit the items into that many containers, cut between
+    /// rows like with [`MasonryWorker::paginate()`]. The second container continues where the
+    /// first one ends, so the order across the panes is the one of the single layout. Compute
+    /// the layout at the width of a container first and size every container with
+    /// `page_height()`.
+    pub fn split_into(&self, containers: u32) -> Pagination {
+        self.layout.split_into(containers as usize).into()
+    }
+
+    /// Returns the tops of the rows in ascending order, e.g. to snap the scroll position to row
+    /// starts in the horizontal layout, so that no row is cut in half at the top of the viewport.
+    ///
+    /// Rows are runs of items that share their top, including full-width items and section
+    /// headers. A vertical masonry layout has no rows and returns an empty array.
+    pub fn snap_offsets(&self) -> Vec<u32> {
+        self.layout.snap_offsets()
+    }
+
+    /// Returns the row top of [`MasonryWorker::snap_offsets()`] that is closest to the scroll
+    /// position, e.g. for the target of a scroll that just ended, or `undefined` without rows.
+    pub fn nearest_snap(&self, scroll_top: f32) -> Option<f32> {
+        self.layout
+            .nearest_snap(f32_to_u32(scroll_top.max(0.0)))
+            .map(u32_to_f32)
+    }
+
+    /// Returns the top, height and first and last index of the row with the given index, e.g. to
+    /// snap the scroll position to rows or to select a whole row, or `undefined` if there are
+    /// fewer rows.
+    ///
+    /// Rows are runs of items that share their top, see [`ComputeResult::rows`] for their count.
+    /// In a vertical masonry layout use [`MasonryWorker::column_info()`] instead.
+    pub fn row_info(&self, row: usize) -> Option<RowInfo> {
+        self.layout.row_info(row)
+    }
+
+    /// Returns the left offset, width, bottom and item count of the column with the given index
+    /// of a vertical masonry or grid layout, or `undefined` if the column has no items or the
+    /// layout has no columns.
+    pub fn column_info(&self, column: usize) -> Option<ColumnInfo> {
+        self.layout.column_info(column)
+    }
+
+    /// Returns the first and last index of the items that are visible in the scroll window.
+    ///
+    /// The window is extended by `overscan` pixels at the top and bottom, so that items can be
+    /// rendered before they scroll into view. Returns `undefined` if no item is visible.
+    pub fn items_in_viewport(
+        &self,
+        scroll_top: f32,
+        viewport_height: f32,
+        overscan: f32,
+    ) -> Option<ItemRange> {
+        let start = f32_to_u32((scroll_top - overscan).max(0.0));
+        let end = f32_to_u32((scroll_top + viewport_height + overscan).max(0.0));
+        self.layout
+            .items_in_range(start, end)
+            .map(|(first, last)| ItemRange {
+                first: usize_to_u32(first),
+                last: usize_to_u32(last),
+            })
+    }
+
+    /// Returns the first and last index of the items that are visible in a scroll window that
+    /// scrolls horizontally, e.g. for a filmstrip, like [`MasonryWorker::items_in_viewport()`].
+    ///
+    /// The window is extended by `overscan` pixels on the left and right. Every call checks all
+    /// items, because their lefts are not indexed like their tops.
+    pub fn items_in_viewport_x(
+        &self,
+        scroll_left: f32,
+        viewport_width: f32,
+        overscan: f32,
+    ) -> Option<ItemRange> {
+        let start = f32_to_u32((scroll_left - overscan).max(0.0));
+        let end = f32_to_u32((scroll_left + viewport_width + overscan).max(0.0));
+        self.layout
+            .items_in_range_x(start, end)
+            .map(|(first, last)| ItemRange {
+                first: usize_to_u32(first),
+                last: usize_to_u32(last),
+            })
+    }
+
+    /// Returns the indices of the items that are visible in the scroll window like
+    /// [`MasonryWorker::items_in_viewport()`], each followed by its tag, see
+    /// [`MasonryWorker::set_tag()`].
+    pub fn tagged_items_in_viewport(
+        &self,
+        scroll_top: f32,
+        viewport_height: f32,
+        overscan: f32,
+    ) -> Vec<u32> {
+        let indices = self
+            .items_in_viewport(scroll_top, viewport_height, overscan)
+            .into_iter()
+            .flat_map(|range| range.first..=range.last);
+        self.tagged(indices)
+    }
+
+    /// Returns the first and last index of the items that will be visible soon, e.g. to load their
+    /// thumbnails first, given the `scrollTop`, the scroll velocity in pixels per second (positive
+    /// when scrolling down) and the viewport height.
+    ///
+    /// The viewport is extended in the scroll direction by the distance of half a second at the
+    /// velocity, but at most four viewport heights. Returns `undefined` if no item is in the
+    /// window.
+    pub fn prefetch_window(
+        &self,
+        scroll_top: f32,
+        velocity: f32,
+        viewport_height: f32,
+    ) -> Option<ItemRange> {
+        self.layout
+            .prefetch_window(
+                f32_to_u32(scroll_top.max(0.0)),
+                velocity,
+                f32_to_u32(viewport_height.max(0.0)),
+            )
+            .map(|(first, last)| ItemRange {
+                first: usize_to_u32(first),
+                last: usize_to_u32(last),
+            })
+    }
+
+    /// Returns the section header that should be rendered sticky at the given `scrollTop`.
+    ///
+    /// Section headers are the items marked with [`MasonryWorker::set_full_width()`]. The sticky
+    /// header is the last one that scrolled past the top of the viewport. When the next header
+    /// approaches, the sticky one is pushed up by `offset` pixels, so it should be rendered at
+    /// `scrollTop - offset`. Returns `undefined` if no header scrolled past the top yet.
+    pub fn sticky_header(&self, scroll_top: f32) -> Option<StickyHeader> {
+        self.layout
+            .sticky_header(f32_to_u32(scroll_top.max(0.0)))
+            .map(|(index, offset)| StickyHeader {
+                index: usize_to_u32(index),
+                offset,
+            })
+    }
+
+    /// Returns the index of the item at the given position relative to the container.
+    ///
+    /// Returns `undefined` if there is no item at that position, e.g. when it is in the padding
+    /// between items.
+    pub fn item_at(&self, x: f32, y: f32) -> Option<u32> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        self.layout
+            .item_at(f32_to_u32(x), f32_to_u32(y))
+            .map(usize_to_u32)
+    }
+
+    /// Returns the indices of all items that overlap the rectangle, e.g. for drag-to-select.
+    ///
+    /// The rectangle is relative to the container and does not need the items to be rendered.
+    pub fn items_in_rect(&self, x: f32, y: f32, width: f32, height: f32) -> Vec<u32> {
+        let (left, top) = (x.max(0.0), y.max(0.0));
+        let (right, bottom) = ((x + width).max(0.0), (y + height).max(0.0));
+        self.layout
+            .items_in_rect(
+                f32_to_u32(left),
+                f32_to_u32(top),
+                f32_to_u32(right - left),
+                f32_to_u32(bottom - top),
+            )
+            .map(usize_to_u32)
+            .collect()
+    }
+
+    /// Returns the index and tag of the item at the given position relative to the container
+    /// like [`MasonryWorker::item_at()`], see [`MasonryWorker::set_tag()`].
+    pub fn tagged_item_at(&self, x: f32, y: f32) -> Option<TaggedItem> {
+        let index = self.item_at(x, y)?;
+        self.layout
+            .tag(index as usize)
+            .map(|tag| TaggedItem { index, tag })
+    }
+
+    /// Returns the indices of all items that overlap the rectangle like
+    /// [`MasonryWorker::items_in_rect()`], each followed by its tag, see
+    /// [`MasonryWorker::set_tag()`].
+    pub fn tagged_items_in_rect(&self, x: f32, y: f32, width: f32, height: f32) -> Vec<u32> {
+        self.tagged(self.items_in_rect(x, y, width, height))
+    }
+
+    /// Selects or deselects the item at the given index.
+    ///
+    /// The selection is kept in the worker, so that ranges and marquees are resolved with the
+    /// geometry of the layout the same way in every layout kind. Its indices are the ones returned
+    /// by the queries. They shift with [`MasonryWorker::insert()`], [`MasonryWorker::remove()`]
+    /// and [`MasonryWorker::move_items()`], and items beyond the item count are not selected.
+    pub fn select(&mut self, index: usize, selected: bool) {
+        if index < self.layout.num_items() {
+            self.selection.set(index, selected);
+        }
+    }
+
+    /// Flips the item at the given index, e.g. for a click with the modifier key, and returns
+    /// whether it is selected afterwards.
+    pub fn toggle_selected(&mut self, index: usize) -> bool {
+        index < self.layout.num_items() && self.selection.toggle(index)
+    }
+
+    /// Selects the items from `anchor` to `focus` in reading order, e.g. for a shift-click from
+    /// the previously clicked item.
+    ///
+    /// The reading order sorts the items by their top and then by their left, so the range covers
+    /// whole rows in the grid, horizontal and list layouts, and all items whose tops lie between
+    /// both items across the columns of the vertical masonry layout. Unless `extend` is true, the
+    /// previous selection is replaced.
+    pub fn select_range(&mut self, anchor: u32, focus: u32, extend: bool) {
+        if !extend {
+            self.selection.clear();
+        }
+        self.selection
+            .select_range(&self.layout, anchor as usize, focus as usize);
+    }
+
+    /// Selects the items that overlap the rectangle like [`MasonryWorker::items_in_rect()`], e.g.
+    /// for marquee selection. Unless `extend` is true, the previous selection is replaced.
+    pub fn select_rect(&mut self, x: f32, y: f32, width: f32, height: f32, extend: bool) {
+        if !extend {
+            self.selection.clear();
+        }
+        let (left, top) = (x.max(0.0), y.max(0.0));
+        let (right, bottom) = ((x + width).max(0.0), (y + height).max(0.0));
+        self.selection.select_rect(
+            &self.layout,
+            f32_to_u32(left),
+            f32_to_u32(top),
+            f32_to_u32(right - left),
+            f32_to_u32(bottom - top),
+        );
+    }
+
+    /// Starts a marquee drag, e.g. on `pointerdown`. Unless `extend` is true, the previous
+    /// selection is cleared, and otherwise it stays selected wherever the marquee goes.
+    pub fn drag_start(&mut self, extend: bool) {
+        let base = if extend {
+            self.selection.clone()
+        } else {
+            self.selection.clear();
+            Selection::new()
+        };
+        self.drag = Some(Drag {
+            base,
+            covered: Vec::new(),
+        });
+    }
+
+    /// Moves the marquee of a drag to the given rectangle relative to the container, e.g. on
+    /// every `pointermove` and on every frame while it autoscrolls, and returns what changed.
+    ///
+    /// The items under the marquee are selected and the ones it no longer covers are deselected
+    /// again, and the returned indices tell which ones, so that only those have to be re-rendered
+    /// however many items the marquee spans. Once the marquee reaches within 48 pixels of the top
+    /// or bottom of the viewport, `autoscrollDelta` is the number of pixels to scroll by in this
+    /// frame, up to 24 at the edge and beyond, and 0 otherwise. It stops at both ends of the
+    /// container. Without [`MasonryWorker::drag_start()`], the first update starts a drag that
+    /// replaces the selection.
+    pub fn drag_update(
+        &mut self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        scroll_top: f32,
+        viewport_height: f32,
+    ) -> DragUpdate {
+        let (left, top) = (x.max(0.0), y.max(0.0));
+        let (right, bottom) = ((x + width).max(0.0), (y + height).max(0.0));
+        let mut covered: Vec<usize> = self
+            .layout
+            .items_in_rect(
+                f32_to_u32(left),
+                f32_to_u32(top),
+                f32_to_u32(right - left),
+                f32_to_u32(bottom - top),
+            )
+            .collect();
+        covered.sort_unstable();
+
+        let selection = &mut self.selection;
+        let drag = self.drag.get_or_insert_with(|| {
+            selection.clear();
+            Drag {
+                base: Selection::new(),
+                covered: Vec::new(),
+            }
+        });
+        let mut changed = |from: &[usize], to: &[usize], selected: bool| -> Vec<u32> {
+            from.iter()
+                .filter(|&&index| to.binary_search(&index).is_err() && !drag.base.contains(index))
+                .map(|&index| {
+                    selection.set(index, selected);
+                    usize_to_u32(index)
+                })
+                .collect()
+        };
+        let entered = changed(&covered, &drag.covered, true);
+        let left_indices = changed(&drag.covered, &covered, false);
+        drag.covered = covered;
+
+        let viewport_bottom = scroll_top + viewport_height;
+        let depth_above = (scroll_top + AUTOSCROLL_EDGE - top).clamp(0.0, AUTOSCROLL_EDGE);
+        let depth_below = (bottom - viewport_bottom + AUTOSCROLL_EDGE).clamp(0.0, AUTOSCROLL_EDGE);
+        let delta = (depth_below - depth_above) / AUTOSCROLL_EDGE * MAX_AUTOSCROLL;
+        let max_scroll_top = (u32_to_f32(self.result.height) - viewport_height).max(0.0);
+        let autoscroll_delta =
+            (scroll_top + delta).clamp(0.0, max_scroll_top.max(scroll_top)) - scroll_top;
+        DragUpdate::new(autoscroll_delta, entered, left_indices)
+    }
+
+    /// Ends the marquee drag, e.g. on `pointerup`. The items stay selected.
+    pub fn drag_end(&mut self) {
+        self.drag = None;
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection.clear();
+    }
+
+    pub fn is_selected(&self, index: usize) -> bool {
+        self.selection.contains(index)
+    }
+
+    /// Returns the number of selected items.
+    pub fn selected_count(&self) -> u32 {
+        usize_to_u32(self.selection.count())
     }
 
-    /// Computes the transforms of all items.
-    ///
-    /// # Safety
+    /// Returns the indices of the selected items in ascending order.
+    pub fn selected_indices(&self) -> Vec<u32> {
+        self.selection.iter().map(usize_to_u32).collect()
+    }
+
+    /// Returns the index of the item after the item at `index` in the order in which they appear
+    /// on the screen, e.g. for the arrow keys of a full-screen viewer, so that it steps through the
+    /// items like the user sees them in the grid: by their top and then from left to right,
+    /// including section headers, pinned items and spanning items where they are placed.
     ///
-    /// The returned `Promise` must be `await`ed. Calls to any other method of [`MasonryWorker`]
-    /// while the `Promise` is still pending will lead to undefined behaviour. As long as the value
-    /// is `await`ed you can enjoy lock free concurrency.
-    pub fn compute(
-        &mut self,
-        width: u16,
-        kind: MasonryType,
-        thumbnail_size: u16,
-        padding: u16,
-    ) -> js_sys::Promise {
-        send_computation(Computation::new(
-            width,
-            MasonryConfig::new(kind, thumbnail_size, padding),
-            &mut self.layout,
-        ))
+    /// Like the other queries, it works on positions, see [`MasonryWorker::item_index()`]. Returns
+    /// `undefined` after the last item or if the item is not laid out.
+    pub fn next_visual(&self, index: u32) -> Option<u32> {
+        self.layout.next_visual(index as usize).map(usize_to_u32)
     }
 
-    /// Returns height of the container from the most recent computation.
-    pub fn get_height(&self) -> u32 {
-        receive_output()
+    /// Returns the index of the item before the item at `index` in the order in which they appear
+    /// on the screen, see [`MasonryWorker::next_visual()`].
+    pub fn prev_visual(&self, index: u32) -> Option<u32> {
+        self.layout.prev_visual(index as usize).map(usize_to_u32)
+    }
+
+    /// Returns the index of the item next to the item at `index` in the given direction, e.g. for
+    /// navigating with the arrow keys.
+    ///
+    /// Returns `undefined` if there is no item in that direction.
+    pub fn neighbor(&self, index: u32, direction: Direction) -> Option<u32> {
+        self.layout
+            .neighbor(index as usize, direction)
+            .map(usize_to_u32)
     }
 
     /// Set the number of items that need to be computed.
@@ -54,11 +2354,327 @@ impl MasonryWorker {
     /// Memory is never deallocated which means that even if the new len is smaller than the current
     /// item count, it will not free the memory of previous items. This is done to avoid allocating
     /// a lot. Allocations can be vary in performance depending on the provided allocator. This
-    /// makes no efforts and uses the standard library allocator.
+    /// makes no efforts and uses the global allocator (see the `talc` and `lol_alloc` features).
     pub fn resize(&mut self, new_len: usize) {
+        self.selection.remove(new_len, usize::MAX);
         self.layout.resize(new_len);
     }
 
+    /// Frees the memory that [`MasonryWorker::resize()`] kept for items beyond the item count,
+    /// e.g. after switching from a huge location to a small one.
+    ///
+    /// The dimensions of the freed items are forgotten. It must not be called while a `Promise`
+    /// returned by [`MasonryWorker::compute()`] is still pending.
+    pub fn shrink_to_fit(&mut self) {
+        self.layout.shrink_to_fit();
+        invalidate_views();
+    }
+
+    /// Hides the items that are filtered out without removing their dimensions.
+    ///
+    /// Bit `i % 8` of byte `i / 8` has to be set for the item at index `i` to be visible. Items
+    /// beyond the end of the bitmask are visible, so an empty array shows all items. Hidden items
+    /// are skipped by the layout, get a transform with zero width and height and are never
+    /// returned by queries. The layout can be updated with
+    /// [`MasonryWorker::compute_incremental()`] afterwards.
+    pub fn set_visibility(&mut self, bitmask: &js_sys::Uint8Array) {
+        self.layout.set_visibility(&bitmask.to_vec());
+    }
+
+    /// Inserts `count` items at the given index and shifts the dimensions of the following items.
+    ///
+    /// The dimensions of the inserted items have to be set before the next computation. The
+    /// layout can be updated with [`MasonryWorker::compute_incremental()`] afterwards.
+    pub fn insert(&mut self, index: usize, count: usize) {
+        self.selection
+            .insert(index.min(self.layout.num_items()), count);
+        self.layout.insert(index, count);
+    }
+
+    /// Removes `count` items at the given index and shifts the dimensions of the following items.
+    ///
+    /// The layout can be updated with [`MasonryWorker::compute_incremental()`] afterwards.
+    pub fn remove(&mut self, index: usize, count: usize) {
+        self.selection.remove(index, count);
+        self.layout.remove(index, count);
+    }
+
+    /// Moves the item at `from` to the index `to`, e.g. after reordering it with drag and drop.
+    ///
+    /// The layout can be updated with [`MasonryWorker::compute_incremental()`] afterwards.
+    pub fn move_item(&mut self, from: usize, to: usize) {
+        self.move_items(from, 1, to);
+    }
+
+    /// Moves `count` consecutive items starting at `from` so that they start at the index `to`.
+    pub fn move_items(&mut self, from: usize, count: usize, to: usize) {
+        let num_items = self.layout.num_items();
+        if from < num_items {
+            let count = count.min(num_items - from);
+            self.selection
+                .move_items(from, count, to.min(num_items - count));
+        }
+        self.layout.move_items(from, count, to);
+    }
+
+    /// Lays out the items in the order of the given item ids, e.g. after sorting the gallery.
+    ///
+    /// The dimensions stay keyed by the item id, so they do not need to be set again. The
+    /// transforms and the indices returned by queries refer to the position in the layout
+    /// instead. Positions without a valid id keep the item with the same index. Note that
+    /// [`MasonryWorker::insert()`], [`MasonryWorker::remove()`] and
+    /// [`MasonryWorker::move_item()`] shift the item ids, so the order has to be set again
+    /// afterwards.
+    pub fn set_order(&mut self, permutation: &js_sys::Uint32Array) {
+        self.layout.set_order(permutation.to_vec());
+    }
+
+    /// Lays out the items in the order of their dimensions again.
+    pub fn clear_order(&mut self) {
+        self.layout.clear_order();
+    }
+
+    /// Records up to `limit` changes of the dimensions, the item count, the order and the
+    /// positions of the items, so that they can be undone with [`MasonryWorker::undo()`], e.g.
+    /// after removing images from the gallery by accident.
+    ///
+    /// The calls that are recorded are [`MasonryWorker::resize()`],
+    /// [`MasonryWorker::set_dimension()`], [`MasonryWorker::insert()`],
+    /// [`MasonryWorker::remove()`], [`MasonryWorker::move_items()`] and
+    /// [`MasonryWorker::set_order()`]. A limit of 0 disables the log, which is the default.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.layout.set_history_limit(limit);
+    }
+
+    /// Reverts the most recent recorded change, lays out the items again like
+    /// [`MasonryWorker::compute_incremental()`] and returns the new container height, or
+    /// `undefined` if there is nothing to undo.
+    ///
+    /// It must not be called while a `Promise` returned by [`MasonryWorker::compute()`] is still
+    /// pending.
+    pub fn undo(&mut self) -> Option<u32> {
+        if !self.layout.undo() {
+            return None;
+        }
+        Some(self.compute_incremental())
+    }
+
+    /// Applies the most recently undone change again like [`MasonryWorker::undo()`], or returns
+    /// `undefined` if there is nothing to redo. Any other recorded change forgets the undone ones.
+    pub fn redo(&mut self) -> Option<u32> {
+        if !self.layout.redo() {
+            return None;
+        }
+        Some(self.compute_incremental())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.layout.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.layout.can_redo()
+    }
+
+    /// Pins or unpins the item at the given index.
+    ///
+    /// Pinned items are laid out before all other items in every layout kind, so the first pinned
+    /// item is always at the top left, e.g. for an "import images" placeholder. Like with
+    /// [`MasonryWorker::set_order()`], the transforms and the indices returned by queries refer
+    /// to the position in the layout as soon as an item is pinned. Use
+    /// [`MasonryWorker::item_index()`] to get the item at a position.
+    pub fn set_pinned(&mut self, index: usize, pinned: bool) {
+        self.layout.set_pinned(index, pinned);
+    }
+
+    /// Lays out the item at the given index in a row of its own that spans the full width.
+    ///
+    /// This works in every layout kind, e.g. for section banners or info cards. The height is
+    /// derived from the dimension of the item and the following items continue below it.
+    pub fn set_full_width(&mut self, index: usize, full_width: bool) {
+        self.layout.set_full_width(index, full_width);
+    }
+
+    /// Gives the full-width item at the given index a fixed height in CSS pixels instead of the
+    /// one that follows from its dimension, e.g. for a section header, or removes it with 0.
+    pub fn set_fixed_height(&mut self, index: usize, height: u16) {
+        self.layout.set_fixed_height(index, height);
+    }
+
+    /// Enlarges the item at the given index in place, e.g. to 3 times the thumbnail size for an
+    /// inline preview, and lets the surrounding items reflow around it. Only one item is focused
+    /// at a time, so this returns the previously focused item to its size.
+    ///
+    /// The item gets a row of its own that spans the full width and is `scale` times the
+    /// thumbnail size high, or as high as its aspect ratio implies for a scale of 0. The following
+    /// items continue below it. The next computation returns the transforms of the focused item
+    /// and of the displaced ones, which [`MasonryWorker::changed_items()`] lists for animating
+    /// them. Inserting, removing, moving or resizing the items ends the focus.
+    pub fn set_focus(&mut self, index: usize, scale: f32) {
+        let height = if scale > 0.0 {
+            u16::try_from(f32_to_u32(scale * u32_to_f32(self.defaults.1))).unwrap_or(u16::MAX)
+        } else {
+            0
+        };
+        self.layout.set_focus(Some(index), height);
+    }
+
+    /// Returns the item of [`MasonryWorker::set_focus()`] to its size and lets the surrounding
+    /// items reflow back.
+    pub fn clear_focus(&mut self) {
+        self.layout.set_focus(None, 0);
+    }
+
+    /// Returns the index of the item enlarged with [`MasonryWorker::set_focus()`], if any.
+    #[must_use]
+    pub fn focused_item(&self) -> Option<usize> {
+        self.layout.focus()
+    }
+
+    /// Lays out the items as a timeline: sorted by their timestamps, the newest first, and grouped
+    /// into days, months or years with a section header before each, and returns the granularity.
+    ///
+    /// The timestamps are in milliseconds since the Unix epoch, one per item, and `NaN` for items
+    /// without a date, which end up in a last section. The granularity is the finest one whose
+    /// sections hold at least 8 items on average, and the days start at midnight in the time zone
+    /// with the given offset to UTC in minutes, e.g. `-new Date().getTimezoneOffset()`.
+    ///
+    /// The headers are items of their own that follow the items, so the item count grows by the
+    /// number of sections, and they are full width with the given height in every layout kind.
+    /// Like with [`MasonryWorker::set_order()`], the queries return positions: use
+    /// [`MasonryWorker::timeline_section()`] to tell the headers from the items and
+    /// [`MasonryWorker::item_index()`] to get the item at a position. Call this again after the
+    /// items or their timestamps changed.
+    pub fn set_timeline(
+        &mut self,
+        timestamps: &[f64],
+        header_height: u16,
+        utc_offset_minutes: i32,
+    ) -> TimelineGranularity {
+        self.remove_timeline_headers();
+        let num_items = timestamps.len();
+        let timeline = timeline(timestamps, utc_offset_minutes);
+        self.layout.resize(num_items + timeline.sections.len());
+        let mut order = Vec::with_capacity(num_items + timeline.sections.len());
+        for (header, section) in timeline.sections.iter().enumerate() {
+            let index = num_items + header;
+            self.layout.set_full_width(index, true);
+            self.layout.set_fixed_height(index, header_height);
+            order.push(usize_to_u32(index));
+            let first = section.first as usize;
+            order.extend_from_slice(&timeline.order[first..first + section.len as usize]);
+        }
+        self.layout.set_order(order);
+        self.timeline = Some((num_items, timeline.sections));
+        timeline.granularity
+    }
+
+    /// Removes the section headers of [`MasonryWorker::set_timeline()`] and lays out the items in
+    /// the order of their dimensions again.
+    pub fn clear_timeline(&mut self) {
+        if let Some((num_items, sections)) = &self.timeline {
+            if self.layout.num_items() == num_items + sections.len() {
+                self.layout.resize(*num_items);
+            }
+        }
+        self.remove_timeline_headers();
+        self.layout.clear_order();
+    }
+
+    /// Returns the section whose header is laid out at the given position, or `undefined` if the
+    /// item at the position is not a header of [`MasonryWorker::set_timeline()`].
+    pub fn timeline_section(&self, position: usize) -> Option<TimelineSection> {
+        let (num_items, sections) = self.timeline.as_ref()?;
+        if position >= self.layout.num_items() {
+            return None;
+        }
+        let index = self.layout.item_index(position);
+        sections.get(index.checked_sub(*num_items)?).copied()
+    }
+
+    /// Lays out the section after the section header at the given index with its own kind.
+    ///
+    /// Section headers are the items marked with [`MasonryWorker::set_full_width()`] and a
+    /// section lasts until the next header, e.g. a "Recently added" strip in grid mode followed
+    /// by the main collection in vertical masonry. Sections without a kind of their own use the
+    /// kind passed to [`MasonryWorker::compute()`]. The rows of horizontal sections are filled one
+    /// by one, and only the columns of the last section can be balanced. Section kinds have no
+    /// effect in reversed layouts (see [`MasonryWorker::set_reversed()`]).
+    pub fn set_section_kind(&mut self, index: usize, kind: MasonryType) {
+        self.layout.set_section_kind(index, Some(kind));
+    }
+
+    /// Lays out the section after the section header at the given index with the kind of the
+    /// computation again.
+    pub fn clear_section_kind(&mut self, index: usize) {
+        self.layout.set_section_kind(index, None);
+    }
+
+    /// Lets the item at the given index span 2 columns, 2 rows or both, e.g. to show favorites or
+    /// highly rated images larger. 1 by 1 is the default, larger spans are treated as 2.
+    ///
+    /// In the grid and mosaic layouts the following items fill the free cells around it. An item
+    /// that is two columns wide but does not fit into the rest of a row moves to the next row and
+    /// leaves the cell empty. In the vertical masonry layout the item is two columns wide and as
+    /// high as its aspect ratio needs, so only the columns count. The horizontal, fixed rows,
+    /// list and filmstrip layouts ignore the span.
+    pub fn set_span(&mut self, index: usize, columns: u32, rows: u32) {
+        self.layout.set_span(index, columns, rows);
+    }
+
+    /// Adds space around the item at the given index on top of the padding, e.g. around a hero
+    /// image or a section header. The margins are in CSS pixels and at most 255 per side, 0 on
+    /// all sides removes them.
+    ///
+    /// The vertical masonry and list layouts make room for the margins, so the item is narrower by
+    /// its left and right margin and the items above and below it move away. The horizontal,
+    /// fixed rows, grid, mosaic and filmstrip layouts ignore the margins. Snapshots do not include
+    /// them.
+    pub fn set_item_margin(&mut self, index: usize, top: u32, right: u32, bottom: u32, left: u32) {
+        self.layout.set_item_margin(index, top, right, bottom, left);
+    }
+
+    /// Starts a new row at the item with the given index, e.g. for visual grouping.
+    ///
+    /// In the horizontal layout the unfinished row before it is left as it is, like the last row.
+    /// In the vertical masonry layout the item and everything after it is placed below all
+    /// columns.
+    pub fn set_break_before(&mut self, index: usize, break_before: bool) {
+        self.layout.set_break_before(index, break_before);
+    }
+
+    /// Returns the index of the item that is laid out at the given position.
+    ///
+    /// This is the identity unless an order was set or items are pinned.
+    pub fn item_index(&self, position: usize) -> usize {
+        self.layout.item_index(position)
+    }
+
+    /// Attaches a number to the item at the given index, e.g. the id the application knows it by,
+    /// which the tagged queries return together with the index.
+    ///
+    /// The tag stays with the item when items are inserted, removed or moved, unlike an array of
+    /// ids next to the layout that has to be spliced the same way. It does not change the layout.
+    /// Every item starts with 0.
+    pub fn set_tag(&mut self, index: usize, tag: u32) {
+        self.layout.set_tag(index, tag);
+    }
+
+    /// Returns the tag of the item with the given index in the results of the queries, or
+    /// `undefined` if there is no such item, see [`MasonryWorker::set_tag()`].
+    pub fn get_tag(&self, index: usize) -> Option<u32> {
+        self.layout.tag(index)
+    }
+
+    /// Sets the EXIF orientation (1 to 8) of the item at the given index.
+    ///
+    /// For the orientations 5 to 8 the image is rotated by 90 degrees, so the width and height
+    /// passed to [`MasonryWorker::set_dimension()`] are swapped inside the layout. The dimension
+    /// should be the one stored in the file, before the rotation. The default is 1.
+    pub fn set_orientation(&mut self, index: usize, orientation: u8) {
+        self.layout.set_orientation(index, orientation);
+    }
+
     /// Set the dimension of one item at the given index if it is smaller than the item count.
     ///
     /// You have to set the dimensions of the items if you want to compute a vertical or horizontal
@@ -67,9 +2683,147 @@ impl MasonryWorker {
         self.layout.set_dimension(index, src_width, src_height);
     }
 
+    /// Returns the position and size of the item at the given index as an inline style, e.g.
+    /// `transform: translate(12px, 34px); width: 100px; height: 80px;`, or `undefined` if there is
+    /// no such item.
+    ///
+    /// This saves building the string from the fields of [`MasonryWorker::get_transform()`] in
+    /// JavaScript. The lengths are in CSS pixels, so they are divided by the device pixel ratio
+    /// of the most recent computation. Reading the transforms from memory is faster for many items
+    /// per frame, because every call allocates a string.
+    pub fn get_transform_css(&self, index: usize) -> Option<String> {
+        let transform = self.layout.get_transform(index)?;
+        let ratio = self.layout.device_pixel_ratio();
+        let [width, height, top, left] = transform
+            .to_array()
+            .map(|length| u32_to_f32(length) / ratio);
+        Some(format!(
+            "transform: translate({}px, {}px); width: {}px; height: {}px;",
+            left, top, width, height
+        ))
+    }
+
+    /// Sets the dimensions of the items from the start as pairs of width and height.
+    ///
+    /// This is the same as calling [`MasonryWorker::set_dimension()`] for every pair, but it only
+    /// crosses the boundary to JavaScript once, e.g. when the dimensions were sent to another
+    /// thread in a message.
+    pub fn set_dimensions(&mut self, dimensions: &[u16]) {
+        for (index, pair) in dimensions.chunks_exact(2).enumerate() {
+            self.layout.set_dimension(index, pair[0], pair[1]);
+        }
+    }
+
+    /// Sets the dimensions of the items from the start from an array of objects with a `width`
+    /// and `height` or a `w` and `h`, and optionally an EXIF `orientation` (see
+    /// [`MasonryWorker::set_orientation()`]).
+    ///
+    /// This saves building the pairs for [`MasonryWorker::set_dimensions()`] when the metadata of
+    /// the files is kept in objects, e.g. `[{ width: 1920, height: 1080 }, { w: 600, h: 800,
+    /// orientation: 6 }]`. A missing width or height leaves the dimension unknown and a missing
+    /// orientation keeps the one of the item. Dimensions beyond 65535 pixels are scaled down. Throws
+    /// if the value is not an array or one of its elements is not an object, in which case the
+    /// items before that element were set already, and if there are more elements than items, in
+    /// which case nothing is set.
+    pub fn set_dimensions_from(&mut self, objects: &JsValue) -> Result<(), JsValue> {
+        if !js_sys::Array::is_array(objects) {
+            return Err(invalid_dimensions("expected an array"));
+        }
+        let objects = objects.unchecked_ref::<js_sys::Array>();
+        if objects.length() as usize > self.layout.num_items() {
+            let message = format!(
+                "{} dimensions for {} items",
+                objects.length(),
+                self.layout.num_items()
+            );
+            return Err(Error::IndexOutOfBounds(message).into());
+        }
+        for (index, object) in objects.iter().enumerate() {
+            if !object.is_object() {
+                return Err(invalid_dimensions(&format!("no object at index {}", index)));
+            }
+            if let Some(orientation) = field(&object, "orientation")?.as_f64() {
+                self.layout
+                    .set_orientation(index, u8::try_from(f64_to_u32(orientation)).unwrap_or(0));
+            }
+            let (width, height) =
+                clamp_dimension(side(&object, "width", "w")?, side(&object, "height", "h")?);
+            self.layout.set_dimension(index, width, height);
+        }
+        Ok(())
+    }
+
+    /// Sets the dimension and orientation of the item at the given index from the header of its
+    /// file and returns whether they could be read, see [`crate::measure::probe_dimensions()`].
+    ///
+    /// Nothing is decoded, so this is the fastest way to measure the files of a large import. The
+    /// dimension is scaled down like in [`MasonryWorker::set_dimensions_from()`] if it is larger.
+    pub fn set_dimension_from_bytes(&mut self, index: usize, bytes: &[u8]) -> bool {
+        let info = match probe::image_info(bytes) {
+            Some(info) => info,
+            None => return false,
+        };
+        self.layout.set_orientation(index, info.orientation);
+        let (width, height) = clamp_dimension(f64::from(info.width), f64::from(info.height));
+        self.layout.set_dimension(index, width, height);
+        true
+    }
+
+    /// Reads the dimensions of images and sets them for the items from the given index on. The
+    /// returned `Promise` resolves with the number of images once all dimensions are set.
+    ///
+    /// The images can be `ImageBitmap`s, image elements or anything else with a `width` and
+    /// `height`, which are read right away, or `Blob`s, e.g. the `File`s of an import. `Blob`s
+    /// are decoded with `createImageBitmap()`, which the browser does off the main thread, so new
+    /// files do not have to be decoded just to measure them. Their EXIF orientation is applied,
+    /// so no orientation should be set for them. Images that cannot be decoded get an unknown
+    /// dimension.
+    ///
+    /// # Safety
+    ///
+    /// The returned `Promise` must be `await`ed before the layout is computed or the worker is
+    /// freed, because the dimensions are set when it resolves.
+    pub fn measure_dimensions(&mut self, start: usize, images: &js_sys::Array) -> js_sys::Promise {
+        let dimensions: js_sys::Array = images.iter().map(|image| measure(&image)).collect();
+        let layout: *mut Layout = &mut self.layout;
+        let set = Closure::once_into_js(move |dimensions: JsValue| {
+            let dimensions = dimensions.unchecked_into::<js_sys::Array>();
+            // SAFETY: The worker is alive and not computing until the `Promise` resolved, see
+            // above.
+            let layout = unsafe { &mut *layout };
+            for (index, dimension) in (start..).zip(dimensions.iter()) {
+                let dimension = dimension.unchecked_into::<js_sys::Array>();
+                let lane = |lane| dimension.get(lane).as_f64().unwrap_or(0.0);
+                let (width, height) = clamp_dimension(lane(0), lane(1));
+                layout.set_dimension(index, width, height);
+            }
+            JsValue::from(dimensions.length())
+        });
+        js_sys::Promise::all(&dimensions)
+            .unchecked_ref::<Thenable>()
+            .then(&set)
+    }
+
+    /// Returns the transform of the item at the given index as a `Transform` object with typed
+    /// properties, or `undefined` if the index is out of bounds.
+    ///
+    /// The object holds a copy with the same values as [`MasonryWorker::get_transform()`], so it
+    /// stays valid after the next computation and when the memory grows. To position many items
+    /// per frame, reading the memory behind [`MasonryWorker::get_transform()`] avoids allocating
+    /// an object per item.
+    pub fn transform(&self, index: usize) -> Option<ItemTransform> {
+        self.layout.get_transform(index).map(ItemTransform::from)
+    }
+
     /// Returns a pointer to the transform of the item at the given index.
     ///
-    /// The [`Transform`] object can be used to set the absolute position of an element.
+    /// The [`Transform`] object can be used to set the absolute position of an element. The width,
+    /// height, top and left are followed by the top and left crop offsets of the thumbnail and 1
+    /// if it is cropped or 0 otherwise (see [`MasonryWorker::set_max_row_height()`] and
+    /// [`MasonryWorker::set_aspect_ratio_limits()`]). Grid cells are square, so their thumbnails
+    /// are cropped unless they are square as well. A cropped thumbnail is scaled to cover its
+    /// rectangle and centered like with `object-fit: cover`, and the crop offsets are how far it
+    /// overhangs the rectangle at the top and on the left.
     ///
     /// # Safety
     ///
@@ -83,3 +2837,648 @@ impl MasonryWorker {
         }
     }
 }
+
+impl MasonryWorker {
+    /// Checks the arguments of a computation, remembers them for snapshots and returns the
+    /// config, or the error that the computation rejects with.
+    fn request(
+        &mut self,
+        width: u32,
+        kind: MasonryType,
+        thumbnail_size: u32,
+        padding: u32,
+    ) -> Result<MasonryConfig, JsValue> {
+        let config = self.config(kind, thumbnail_size, padding);
+        if let Err(error) = self.layout.check_config(width, &config) {
+            return Err(invalid_config(error));
+        }
+        if !is_computing(&self.layout) {
+            self.cache_layout(width, &config);
+        }
+        self.computed = Some((width, config));
+        self.defaults = (kind, thumbnail_size, padding);
+        self.request = Some(Request {
+            width,
+            kind,
+            thumbnail_size,
+            padding,
+        });
+        Ok(config)
+    }
+
+    /// Returns the result of the most recent computation, with no item laid out again, if a
+    /// request is close enough to it to keep its transforms, see
+    /// [`MasonryWorker::set_resize_threshold()`].
+    fn skip_resize(
+        &mut self,
+        width: u32,
+        kind: MasonryType,
+        thumbnail_size: u32,
+        padding: u32,
+    ) -> Option<ComputeResult> {
+        let (computed_width, computed_config) = self.computed?;
+        let difference = width.max(computed_width) - width.min(computed_width);
+        if difference >= self.resize_threshold
+            || computed_config != self.config(kind, thumbnail_size, padding)
+            || is_computing(&self.layout)
+            || !self.layout.is_up_to_date()
+        {
+            return None;
+        }
+        Some(ComputeResult {
+            elapsed: 0.0,
+            first_dirty_index: self.result.num_items,
+            stats_elapsed: 0.0,
+            publish_elapsed: 0.0,
+            ..self.result
+        })
+    }
+
+    /// Returns the current config with the properties of the options of
+    /// [`MasonryWorker::compute_with_options()`] that are given.
+    fn options_config(&self, options: &JsValue) -> Result<MasonryConfig, JsValue> {
+        let mut config = self.get_config();
+        if options.is_undefined() || options.is_null() {
+            return Ok(config);
+        }
+        let option = |name: &str| -> Result<Option<f64>, JsValue> {
+            let value = field(options, name)?;
+            if value.is_undefined() {
+                return Ok(None);
+            }
+            match value.as_f64() {
+                Some(number) if number >= 0.0 => Ok(Some(number)),
+                _ => Err(invalid_options(name)),
+            }
+        };
+        if let Some(kind) = option("kind")? {
+            config.kind = masonry_type(f64_to_u32(kind)).ok_or_else(|| invalid_options("kind"))?;
+        }
+        if let Some(padding) = option("padding")? {
+            config.gap_x = f64_to_u32(padding);
+            config.gap_y = f64_to_u32(padding);
+        }
+        for (name, value) in [
+            ("thumbnailSize", &mut config.thumbnail_size),
+            ("gapX", &mut config.gap_x),
+            ("gapY", &mut config.gap_y),
+            ("outerPadding", &mut config.outer_padding),
+            ("maxContentWidth", &mut config.max_content_width),
+            ("captionHeight", &mut config.caption_height),
+            ("metadataWidth", &mut config.metadata_width),
+            ("gapReferenceSize", &mut config.gap_reference_size),
+            ("minGap", &mut config.min_gap),
+            ("maxGap", &mut config.max_gap),
+            ("targetRowHeight", &mut config.target_row_height),
+        ] {
+            if let Some(number) = option(name)? {
+                *value = f64_to_u32(number);
+            }
+        }
+        if let Some(ratio) = option("devicePixelRatio")? {
+            config.device_pixel_ratio = f64_to_f32(ratio);
+        }
+        if let Some(tolerance) = option("rowHeightTolerance")? {
+            config.row_height_tolerance = f64_to_f32(tolerance);
+        }
+        Ok(config)
+    }
+
+    fn config(&self, kind: MasonryType, thumbnail_size: u32, padding: u32) -> MasonryConfig {
+        let (gap_x, gap_y) = self.gaps.unwrap_or((padding, padding));
+        MasonryConfig::new(kind, thumbnail_size, padding)
+            .with_gaps(gap_x, gap_y, self.outer_padding)
+            .with_max_content_width(self.max_content_width)
+            .with_caption_height(self.caption_height)
+            .with_metadata_width(self.metadata_width)
+            .with_device_pixel_ratio(self.device_pixel_ratio)
+            .with_gap_scaling(self.gap_scaling.0, self.gap_scaling.1, self.gap_scaling.2)
+            .with_row_height(self.row_height.0, self.row_height.1)
+    }
+
+    /// Turns the section headers of the timeline back into ordinary items, in case they became
+    /// items since, and forgets the timeline.
+    fn remove_timeline_headers(&mut self) {
+        if let Some((num_items, sections)) = self.timeline.take() {
+            for index in num_items..num_items + sections.len() {
+                self.layout.set_full_width(index, false);
+                self.layout.set_fixed_height(index, 0);
+            }
+        }
+    }
+
+    /// Returns the indices of the items each followed by its tag.
+    fn tagged(&self, indices: impl IntoIterator<Item = u32>) -> Vec<u32> {
+        indices
+            .into_iter()
+            .flat_map(|index| [index, self.layout.tag(index as usize).unwrap_or(0)])
+            .collect()
+    }
+
+    /// Sends the computation to the web worker threads and rejects the returned `Promise` after
+    /// the timeout, if any.
+    fn send(&self, computation: Computation) -> js_sys::Promise {
+        self.start();
+        let observed = ptr::eq(computation.layout_ptr, &self.layout);
+        let mut promise = if self.fallback {
+            // Only the layout itself is sent, the copies are computed on the main thread.
+            match self.spawned.borrow().as_ref() {
+                Some(worker) => fallback::send(
+                    worker,
+                    &self.export_snapshot(),
+                    computation.layout_ptr,
+                    computation.output_ptr,
+                ),
+                None => js_sys::Promise::reject(&failure().unwrap_or_else(fallback::not_started)),
+            }
+        } else {
+            let computation = computation.with_priority(self.priority);
+            self.replay_on_failure(send_computation(computation), computation)
+        };
+        if observed {
+            promise = self.observers.notify_after(&promise);
+            self.watch_progress();
+        }
+        match self.compute_timeout {
+            0 => promise,
+            timeout_ms => with_timeout(&promise, timeout_ms),
+        }
+    }
+
+    /// Starts the web worker of a worker from [`crate::spawn::MasonryWorkerBuilder`] again if the
+    /// web worker threads failed while the computation was pending, and sends the computation to
+    /// the new one. The returned `Promise` resolves with its result, or rejects with the error of
+    /// the new web worker if it fails as well. Other errors, e.g. of the config, are kept.
+    ///
+    /// Only the first of the pending computations that sees the failure replaces the web worker,
+    /// the others are queued for the replacement. Without `SharedArrayBuffer` (see
+    /// [`crate::fallback`]), and for web workers that the caller started, see
+    /// [`MasonryWorker::restart()`].
+    fn replay_on_failure(
+        &self,
+        promise: js_sys::Promise,
+        computation: Computation,
+    ) -> js_sys::Promise {
+        let spawn = match &self.respawn {
+            Some(spawn) => spawn.clone(),
+            None => return promise,
+        };
+        let spawned = Rc::clone(&self.spawned);
+        let standard = self.standard;
+        let restarts_before = restarts();
+        let on_rejected = Closure::once_into_js(move |error: JsValue| {
+            // Another computation may have replaced the web worker already.
+            let restarted = restarts() != restarts_before;
+            if !restarted && failure().is_none() {
+                return js_sys::Promise::reject(&error);
+            }
+            if !restarted {
+                if let Some(worker) = spawned.borrow_mut().take() {
+                    worker.terminate();
+                }
+                // SAFETY: The layout stays in place until the `Promise` settled, see
+                // `MasonryWorker::compute()`. The computation may have stopped halfway.
+                unsafe { (*computation.layout_ptr).invalidate() };
+                drop(restart());
+                *spawned.borrow_mut() = spawn.spawn(&init_message(!standard), standard);
+            }
+            send_computation(computation)
+        });
+        promise
+            .unchecked_ref::<Thenable>()
+            .then_or(&JsValue::UNDEFINED, &on_rejected)
+    }
+
+    /// Keeps a copy of the most recent computation if it had other arguments than the next one,
+    /// and drops the copies whose items or settings are out of date.
+    fn cache_layout(&mut self, width: u32, config: &MasonryConfig) {
+        let layout = &self.layout;
+        self.cached
+            .retain(|cached| layout.is_fork_current(&cached.layout));
+        let (cached_width, cached_config) = match self.computed {
+            Some(computed) if self.cache_size > 0 && computed != (width, *config) => computed,
+            _ => return,
+        };
+        let layout = match self.layout.fork_computed() {
+            Some(layout) => layout,
+            None => return,
+        };
+        self.cached
+            .retain(|cached| cached.width != cached_width || cached.config != cached_config);
+        if self.cached.len() == self.cache_size {
+            self.cached.remove(0);
+        }
+        self.cached.push(Precomputed {
+            width: cached_width,
+            config: cached_config,
+            layout,
+            result: self.result,
+        });
+    }
+
+    /// Takes over the cached computation with the width and config, if it is still up to date,
+    /// and returns whether it was taken over.
+    fn adopt_cached(&mut self, width: u32, config: &MasonryConfig) -> bool {
+        let index = self
+            .cached
+            .iter()
+            .position(|cached| cached.width == width && cached.config == *config);
+        match index {
+            Some(index) => {
+                let mut cached = self.cached.remove(index);
+                self.take_over(&mut cached)
+            }
+            None => false,
+        }
+    }
+
+    /// Takes over the precomputed zoom level or the copy of [`MasonryWorker::interpolate()`] with
+    /// the width and config, if it is finished and still up to date, and returns whether it was
+    /// taken over.
+    fn adopt_zoom_level(&mut self, width: u32, config: &MasonryConfig) -> bool {
+        if let Some(mut target) = self.zoom_target.take() {
+            if target.width != width || target.config != *config || is_computing(&target.layout) {
+                self.zoom_target = Some(target);
+            } else if self.take_over(&mut target) {
+                return true;
+            }
+        }
+        let layout = &self.layout;
+        let index = self.precomputed.iter().position(|level| {
+            level.width == width
+                && level.config == *config
+                && !is_computing(&level.layout)
+                && layout.is_fork_current(&level.layout)
+        });
+        match index {
+            Some(index) => {
+                let mut level = self.precomputed.swap_remove(index);
+                self.take_over(&mut level)
+            }
+            None => false,
+        }
+    }
+
+    /// Takes over the transforms and the result of a copy of the layout and publishes them.
+    fn take_over(&mut self, copy: &mut Precomputed) -> bool {
+        if !self.layout.adopt(&mut copy.layout) {
+            return false;
+        }
+        // Every item was laid out again compared to the transforms before.
+        self.result = ComputeResult {
+            first_dirty_index: 0,
+            ..copy.result
+        };
+        if self.layout.publish() {
+            invalidate_views();
+        }
+        self.observers.notify(self.result);
+        true
+    }
+
+    /// Reports the progress of the computation that was just sent if it has enough items, see
+    /// [`MasonryWorker::on_progress()`].
+    fn watch_progress(&self) {
+        if self.layout.num_items() > self.progress_threshold {
+            self.progress
+                .watch(&self.layout, usize_to_u32(self.layout.num_items()));
+        }
+    }
+
+    /// Computes the layout on the main thread like [`compute_locally()`] and notifies the
+    /// callbacks of [`MasonryWorker::on_layout()`].
+    fn lay_out_locally(&mut self, compute: impl FnOnce(&mut Layout) -> u32) -> u32 {
+        let operation = Operation {
+            name: "a layout on the main thread",
+            num_items: self.layout.num_items(),
+            computed: self.computed,
+        };
+        let height = during(operation, || {
+            compute_locally(&mut self.layout, &mut self.result, compute)
+        });
+        self.observers.notify(self.result);
+        height
+    }
+
+    /// Prepares the copy of the layout that [`MasonryWorker::interpolate()`] interpolates towards
+    /// and returns the `Promise` of its computation, or `None` if it is already computed.
+    fn prepare_zoom_target(
+        &mut self,
+        width: u32,
+        config: MasonryConfig,
+    ) -> Option<js_sys::Promise> {
+        let is_done = |layout: &Layout, copy: &Precomputed| {
+            copy.width == width
+                && copy.config == config
+                && !is_computing(&copy.layout)
+                && layout.is_fork_current(&copy.layout)
+        };
+        if matches!(&self.zoom_target, Some(target) if is_done(&self.layout, target)) {
+            return None;
+        }
+        self.end_interpolation();
+        let layout = &self.layout;
+        if let Some(index) = self
+            .precomputed
+            .iter()
+            .position(|level| is_done(layout, level))
+        {
+            self.zoom_target = Some(self.precomputed.swap_remove(index));
+            return None;
+        }
+        if let Some(index) = self
+            .cached
+            .iter()
+            .position(|cached| is_done(layout, cached))
+        {
+            self.zoom_target = Some(Box::new(self.cached.remove(index)));
+            return None;
+        }
+
+        let mut target = Box::new(Precomputed {
+            width,
+            config,
+            layout: self.layout.fork(),
+            result: ComputeResult::default(),
+        });
+        let promise = if self.layout.num_items() <= self.sync_threshold || self.fallback {
+            compute_locally(&mut target.layout, &mut target.result, |layout| {
+                layout.compute(width, &config)
+            });
+            None
+        } else {
+            let computation =
+                Computation::new(width, config, &mut target.layout, &mut target.result);
+            Some(self.send(computation))
+        };
+        self.zoom_target = Some(target);
+        promise
+    }
+
+    /// Returns the copy of the computation before [`MasonryWorker::transition()`], if the items
+    /// and settings did not change since.
+    fn transition_source(&self) -> Option<&Precomputed> {
+        self.transition_source
+            .as_ref()
+            .filter(|source| self.layout.is_fork_current(&source.layout))
+    }
+
+    /// Returns the copy of [`MasonryWorker::interpolate()`] if it can be read, or the layout
+    /// itself.
+    fn zoom_target_layout(&self) -> &Layout {
+        match &self.zoom_target {
+            Some(target) if !is_computing(&target.layout) => &target.layout,
+            _ => &self.layout,
+        }
+    }
+
+    /// Sends copies of the layout with the zoom levels next to the thumbnail size to the web
+    /// worker threads, unless they are already precomputed, and drops the other zoom levels.
+    /// Without `SharedArrayBuffer` nothing is precomputed.
+    fn precompute_zoom_levels(
+        &mut self,
+        width: u32,
+        kind: MasonryType,
+        thumbnail_size: u32,
+        padding: u32,
+    ) {
+        if self.fallback {
+            return;
+        }
+        let smaller = self
+            .zoom_levels
+            .iter()
+            .rev()
+            .find(|&&size| size < thumbnail_size);
+        let larger = self.zoom_levels.iter().find(|&&size| size > thumbnail_size);
+        let configs: Vec<MasonryConfig> = smaller
+            .into_iter()
+            .chain(larger)
+            .map(|&size| self.config(kind, size, padding))
+            .filter(|config| self.layout.check_config(width, config).is_ok())
+            .collect();
+        self.retain_zoom_levels(|layout, level| {
+            level.width == width
+                && configs.contains(&level.config)
+                && layout.is_fork_current(&level.layout)
+        });
+        for config in configs {
+            let layout = &self.layout;
+            if self.precomputed.iter().any(|level| {
+                level.width == width
+                    && level.config == config
+                    && layout.is_fork_current(&level.layout)
+            }) {
+                continue;
+            }
+            let mut level = Box::new(Precomputed {
+                width,
+                config,
+                layout: self.layout.fork(),
+                result: ComputeResult::default(),
+            });
+            self.start();
+            send_background(Computation::new(
+                width,
+                config,
+                &mut level.layout,
+                &mut level.result,
+            ));
+            self.precomputed.push(level);
+        }
+    }
+
+    /// Drops the zoom levels for which the predicate returns false. Their computations are
+    /// cancelled, but the ones that a web worker thread still uses are only dropped later.
+    fn retain_zoom_levels(&mut self, keep: impl Fn(&Layout, &Precomputed) -> bool) {
+        let layout = &self.layout;
+        self.precomputed.retain(|level| {
+            if keep(layout, level) {
+                return true;
+            }
+            cancel_computation(&level.layout);
+            is_computing(&level.layout)
+        });
+    }
+}
+
+/// Incremented whenever the format of [`MasonryWorker::export_snapshot()`] changes.
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// Returns the message that initializes a web worker running `worker.js` with the shared memory.
+/// With `module`, the compiled module is cloned with the message instead of fetched again, which
+/// the runtimes without the DOM do not support, see [`crate::spawn::is_standard_runtime()`].
+pub(crate) fn init_message(module: bool) -> JsValue {
+    let message = js_sys::Object::new();
+    drop(js_sys::Reflect::set(
+        &message,
+        &"memory".into(),
+        &wasm_bindgen::memory(),
+    ));
+    if module {
+        drop(js_sys::Reflect::set(
+            &message,
+            &"module".into(),
+            &wasm_bindgen::module(),
+        ));
+    }
+    message.into()
+}
+
+/// Returns the layout of [`MasonryWorker::warm_up()`], whose items have a few different aspect
+/// ratios.
+fn warm_up_layout() -> Layout {
+    let mut layout = Layout::new(
+        WARM_UP_ITEMS,
+        MasonryConfig::DEFAULT_THUMBNAIL_SIZE,
+        MasonryConfig::DEFAULT_PADDING,
+    );
+    for (index, shape) in (0..WARM_UP_ITEMS).zip(0_u16..) {
+        let (width, height) = (shape % 5, shape % 3);
+        layout.set_dimension(index, 400 + 100 * width, 300 + 200 * height);
+    }
+    layout
+}
+
+fn json_array(values: &[u32]) -> String {
+    let mut json = String::with_capacity(2 + 8 * values.len());
+    json.push('[');
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let _ = write!(json, "{}", value);
+    }
+    json.push(']');
+    json
+}
+
+/// Returns the error for an action that needs the layout while a computation of it is pending.
+fn still_pending(action: &str) -> JsValue {
+    let message = format!("Cannot {} while a computation is pending", action);
+    JsValue::from(Error::Busy(message))
+}
+
+/// Returns the error that computations with an invalid config reject with.
+pub(crate) fn invalid_config(error: ConfigError) -> JsValue {
+    let message = format!("Invalid layout config: {}", error);
+    JsValue::from(Error::InvalidConfig(message))
+}
+
+/// Returns the layout kind with the given number, as it is passed from JavaScript.
+fn masonry_type(kind: u32) -> Option<MasonryType> {
+    [
+        MasonryType::Vertical,
+        MasonryType::Horizontal,
+        MasonryType::Grid,
+        MasonryType::List,
+        MasonryType::Mosaic,
+        MasonryType::FixedRows,
+        MasonryType::Filmstrip,
+    ]
+    .into_iter()
+    .find(|&known| known as u32 == kind)
+}
+
+/// Returns the priority that an options object of [`MasonryWorker::compute_with_options()`]
+/// gives.
+fn options_priority(options: &JsValue) -> Result<Priority, JsValue> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(Priority::UserBlocking);
+    }
+    let value = field(options, "priority")?;
+    if value.is_undefined() {
+        return Ok(Priority::UserBlocking);
+    }
+    match value.as_string().as_deref() {
+        Some("user-blocking") => Ok(Priority::UserBlocking),
+        Some("background") => Ok(Priority::Background),
+        _ => Err(invalid_options("priority")),
+    }
+}
+
+/// Returns the error for an options object whose property with the given name is invalid.
+fn invalid_options(name: &str) -> JsValue {
+    let message = format!("Invalid layout options: {}", name);
+    JsValue::from(Error::InvalidConfig(message))
+}
+
+/// Returns the error for a snapshot whose field with the given name is missing or invalid.
+fn invalid_snapshot(name: &str) -> JsValue {
+    let message = format!("Invalid layout snapshot: {}", name);
+    JsValue::from(Error::InvalidInput(message))
+}
+
+/// Returns the error for dimensions that cannot be read, see
+/// [`MasonryWorker::set_dimensions_from()`].
+fn invalid_dimensions(reason: &str) -> JsValue {
+    let message = format!("Invalid dimensions: {}", reason);
+    JsValue::from(Error::InvalidInput(message))
+}
+
+/// Converts a dimension in pixels to the range of [`Layout::set_dimension()`]. Both sides are
+/// scaled alike, so that the aspect ratio stays the same.
+fn clamp_dimension(width: f64, height: f64) -> (u16, u16) {
+    let scale = (f64::from(u16::MAX) / width.max(height)).min(1.0);
+    let [width, height] =
+        [width, height].map(|side| u16::try_from(f64_to_u32(side * scale)).unwrap_or(u16::MAX));
+    (width, height)
+}
+
+/// Reads a side of a dimension from the property with the long or the short name, or 0 if
+/// neither is a number.
+fn side(object: &JsValue, name: &str, short_name: &str) -> Result<f64, JsValue> {
+    let side = match field(object, name)?.as_f64() {
+        Some(side) => side,
+        None => field(object, short_name)?.as_f64().unwrap_or(0.0),
+    };
+    Ok(if side > 0.0 { side } else { 0.0 })
+}
+
+fn field(object: &JsValue, name: &str) -> Result<JsValue, JsValue> {
+    js_sys::Reflect::get(object, &JsValue::from_str(name))
+}
+
+fn number(object: &JsValue, name: &str) -> Result<f64, JsValue> {
+    field(object, name)?
+        .as_f64()
+        .ok_or_else(|| invalid_snapshot(name))
+}
+
+/// Reads an array of numbers, which are converted like the elements of a `Uint32Array`.
+fn lanes(object: &JsValue, name: &str) -> Result<Vec<u32>, JsValue> {
+    let array = field(object, name)?;
+    if !js_sys::Array::is_array(&array) {
+        return Err(invalid_snapshot(name));
+    }
+    Ok(js_sys::Uint32Array::new(&array).to_vec())
+}
+
+/// Reads an array like [`lanes()`], or returns `None` if a snapshot from before it was included
+/// does not hold it.
+fn optional_lanes(object: &JsValue, name: &str) -> Result<Option<Vec<u32>>, JsValue> {
+    if field(object, name)?.is_undefined() {
+        return Ok(None);
+    }
+    lanes(object, name).map(Some)
+}
+
+/// Reads the settings of a snapshot that the layout knows. Snapshots from before the settings
+/// were included hold none, so the layout keeps its current ones.
+fn snapshot_settings(
+    snapshot: &JsValue,
+    layout: &Layout,
+) -> Result<Vec<(&'static str, f64)>, JsValue> {
+    let values = field(snapshot, "settings")?;
+    let mut settings = Vec::new();
+    if values.is_undefined() {
+        return Ok(settings);
+    }
+    for (name, _) in layout.export_settings() {
+        let value = field(&values, name)?;
+        if !value.is_undefined() {
+            settings.push((name, value.as_f64().ok_or_else(|| invalid_snapshot(name))?));
+        }
+    }
+    Ok(settings)
+}
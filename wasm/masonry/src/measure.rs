@@ -0,0 +1,85 @@
+//! Reads the dimensions of images for the layout, see
+//! [`crate::masonry_worker::MasonryWorker::measure_dimensions()`].
+//!
+//! The web worker thread waits for computations in [`crate::sync::run()`] and never returns to its
+//! event loop, so it cannot receive images in messages. Blobs are decoded with
+//! `createImageBitmap()` instead, which the browser does off the main thread, so the main thread
+//! only reads the width and height of the result. Faster still, [`probe_dimensions()`] reads them
+//! from the first bytes of the file without decoding it at all.
+use masonry_core::{probe, ImageInfo};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::sync::Thenable;
+
+#[wasm_bindgen]
+extern "C" {
+    type ImageBitmap;
+
+    #[wasm_bindgen(method)]
+    fn close(this: &ImageBitmap);
+
+    #[wasm_bindgen(catch, js_name = createImageBitmap)]
+    fn create_image_bitmap(image: &JsValue, options: &JsValue) -> Result<js_sys::Promise, JsValue>;
+}
+
+/// Reads the dimensions and EXIF orientation from the header of a JPEG, PNG, WebP or TIFF file,
+/// or returns `undefined` if the bytes are not one of these formats or cut off too early.
+///
+/// The first 64 KiB of the file are enough for almost all images. The dimension is the one stored
+/// in the file and can be larger than the 65535 pixels per side that
+/// `MasonryWorker::set_dimension()` takes, so rather pass the bytes to
+/// `MasonryWorker::set_dimension_from_bytes()`, which scales it down and sets the orientation.
+#[wasm_bindgen]
+pub fn probe_dimensions(bytes: &[u8]) -> Option<ImageInfo> {
+    probe::image_info(bytes)
+}
+
+/// Returns a `Promise` that resolves with the width and height of the image as an array, or with
+/// `[0, 0]` if it has no dimension and cannot be decoded.
+///
+/// Images with a `width` and `height`, like `ImageBitmap`s and image elements, are read right
+/// away. Anything else, e.g. a `Blob`, is decoded with its EXIF orientation applied and the
+/// decoded bitmap is closed afterwards.
+pub fn measure(image: &JsValue) -> js_sys::Promise {
+    if let Some(dimension) = dimension_of(image) {
+        return js_sys::Promise::resolve(&dimension);
+    }
+    let options = js_sys::Object::new();
+    drop(js_sys::Reflect::set(
+        &options,
+        &JsValue::from_str("imageOrientation"),
+        &JsValue::from_str("from-image"),
+    ));
+    let bitmap = match create_image_bitmap(image, &options) {
+        Ok(bitmap) => bitmap,
+        // Not an image source at all, e.g. `undefined`.
+        Err(_) => return js_sys::Promise::resolve(&unknown()),
+    };
+    let read = Closure::once_into_js(|bitmap: JsValue| {
+        let dimension = dimension_of(&bitmap).unwrap_or_else(unknown);
+        bitmap.unchecked_ref::<ImageBitmap>().close();
+        dimension
+    });
+    let reject = Closure::once_into_js(|_: JsValue| unknown());
+    bitmap.unchecked_ref::<Thenable>().then_or(&read, &reject)
+}
+
+/// Returns the width and height of the image as an array if it has both.
+fn dimension_of(image: &JsValue) -> Option<JsValue> {
+    if !image.is_object() {
+        return None;
+    }
+    let side = |name: &str| {
+        js_sys::Reflect::get(image, &JsValue::from_str(name))
+            .ok()?
+            .as_f64()
+    };
+    let (width, height) = (side("width")?, side("height")?);
+    Some(js_sys::Array::of2(&JsValue::from(width), &JsValue::from(height)).into())
+}
+
+/// The dimension of an image that could not be measured.
+fn unknown() -> JsValue {
+    js_sys::Array::of2(&JsValue::from(0), &JsValue::from(0)).into()
+}
@@ -0,0 +1,146 @@
+//! Reports panics to JavaScript together with the computation they interrupted, see
+//! `MasonryWorker::on_panic()`.
+//!
+//! A panic traps the instance of the module on the thread that panicked. On the web worker thread
+//! this left the pending `Promise`s waiting forever, so the gallery froze without any
+//! diagnostics. The hook needs the standard library and is only built with the `panic-hook`
+//! feature, but the operations are always tracked, because that is cheap.
+use core::cell::Cell;
+use masonry_core::MasonryConfig;
+use wasm_bindgen::JsValue;
+
+/// The computation that the current thread is running, see [`during()`].
+#[thread_local]
+static OPERATION: Cell<Option<Operation>> = Cell::new(None);
+
+/// What a thread is doing, which is included in the report of a panic.
+#[derive(Clone, Copy)]
+#[cfg_attr(not(feature = "panic-hook"), allow(dead_code))]
+pub struct Operation {
+    pub name: &'static str,
+    pub num_items: usize,
+    /// Width and config of the computation, if they are known.
+    pub computed: Option<(u32, MasonryConfig)>,
+}
+
+/// Runs the closure as the operation that a panic is reported with.
+pub fn during<R>(operation: Operation, run: impl FnOnce() -> R) -> R {
+    let previous = OPERATION.replace(Some(operation));
+    let result = run();
+    OPERATION.set(previous);
+    result
+}
+
+/// Calls the callback of `MasonryWorker::on_panic()` with the error that the `Promise`s reject
+/// with if a web worker thread panicked since the last call. Must be called on the main thread.
+pub fn forward_from_worker(error: &JsValue) {
+    #[cfg(feature = "panic-hook")]
+    hook::forward_from_worker(error);
+    #[cfg(not(feature = "panic-hook"))]
+    let _ = error;
+}
+
+#[cfg(feature = "panic-hook")]
+mod hook {
+    use alloc::{boxed::Box, format, string::String};
+    use core::cell::{Cell, RefCell};
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use wasm_bindgen::prelude::*;
+
+    use super::{Operation, OPERATION};
+    use crate::error::Error;
+    use crate::masonry_worker::MasonryWorker;
+    use crate::sync::{fail, release_input_lock};
+
+    /// The callback of [`MasonryWorker::on_panic()`], which only the main thread has.
+    #[thread_local]
+    static CALLBACK: RefCell<Option<js_sys::Function>> = RefCell::new(None);
+    /// Whether a web worker thread panicked and the callback was not called about it yet.
+    static WORKER_PANICKED: AtomicBool = AtomicBool::new(false);
+    /// Whether the hook was installed, which only has to happen once for all threads.
+    static INSTALLED: AtomicBool = AtomicBool::new(false);
+    /// Whether this is the main thread, which is the one that calls [`MasonryWorker::on_panic()`].
+    #[thread_local]
+    static IS_MAIN_THREAD: Cell<bool> = Cell::new(false);
+    /// Whether this thread is reporting a panic, so that a panic in the report is not reported.
+    #[thread_local]
+    static REPORTING: Cell<bool> = Cell::new(false);
+
+    #[wasm_bindgen]
+    impl MasonryWorker {
+        /// Sets a callback that is called with an `Error` when the module panics, e.g. to send it
+        /// to an error tracker, or removes it if `undefined` is given. The callback is shared by
+        /// all workers.
+        ///
+        /// The message holds the panic together with the operation in progress, the item count,
+        /// the width and the config of the computation. A panic on the main thread calls the
+        /// callback with an `Error` whose `code` is `Panicked` before the call that panicked
+        /// throws. A panic on any other thread, e.g. the web worker thread or a helper thread,
+        /// marks the web worker thread as failed like [`MasonryWorker::fail()`], so the pending
+        /// `Promise`s reject with the report and the `code` `WorkerFailed`, and the callback is
+        /// called with the same `Error` on the main thread. The main thread is the one that calls
+        /// this method. Only available if the module was built with the `panic-hook` feature.
+        #[allow(clippy::unused_self)]
+        pub fn on_panic(&self, callback: Option<js_sys::Function>) {
+            IS_MAIN_THREAD.set(true);
+            CALLBACK.replace(callback);
+            if !INSTALLED.swap(true, Ordering::SeqCst) {
+                std::panic::set_hook(Box::new(report));
+            }
+        }
+    }
+
+    pub fn forward_from_worker(error: &JsValue) {
+        if WORKER_PANICKED.swap(false, Ordering::SeqCst) {
+            call(error);
+        }
+    }
+
+    /// Reports a panic to the main thread, either to the callback directly or by failing the web
+    /// worker thread. Any other thread, e.g. a helper thread, fails the web worker thread as well,
+    /// even outside of a computation.
+    fn report(info: &std::panic::PanicInfo) {
+        if REPORTING.replace(true) {
+            return;
+        }
+        // The thread traps right after the hook, so a lock it holds would never be released.
+        release_input_lock();
+        let message = describe(info, OPERATION.get());
+        if IS_MAIN_THREAD.get() {
+            call(&JsValue::from(Error::Panicked(message)));
+        } else {
+            WORKER_PANICKED.store(true, Ordering::SeqCst);
+            fail(message);
+        }
+        REPORTING.set(false);
+    }
+
+    /// Returns the message of a panic with the operation it interrupted.
+    fn describe(info: &std::panic::PanicInfo, operation: Option<Operation>) -> String {
+        let operation = match operation {
+            Some(operation) => operation,
+            None => return format!("{} outside of a computation", info),
+        };
+        let computed = match operation.computed {
+            Some((width, config)) => format!(
+                " at a width of {} (kind {}, thumbnail size {}, gaps {} and {})",
+                width, config.kind as u32, config.thumbnail_size, config.gap_x, config.gap_y
+            ),
+            None => String::new(),
+        };
+        format!(
+            "{} during {} of {} items{}",
+            info, operation.name, operation.num_items, computed
+        )
+    }
+
+    /// Calls the callback of this thread, if there is one. A callback that throws is ignored,
+    /// because the module traps right afterwards anyway.
+    fn call(error: &JsValue) {
+        if let Ok(callback) = CALLBACK.try_borrow() {
+            if let Some(callback) = callback.as_ref() {
+                drop(callback.call1(&JsValue::UNDEFINED, error));
+            }
+        }
+    }
+}
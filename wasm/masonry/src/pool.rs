@@ -0,0 +1,149 @@
+//! Pool of helper threads that share the per-item work of huge layouts.
+//!
+//! Helpers are additional web workers that call [`run_helper()`], just like the web worker thread
+//! calls [`crate::sync::run()`]. The web worker thread splits a task into chunks with
+//! [`for_each_chunk()`] and works on them as well, so the result is the same with any number of
+//! helpers, including none. Only the work that does not depend on other items is shared; placing
+//! the items stays sequential and stitches the chunks together.
+use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicUsize, Ordering};
+
+use wasm_bindgen::prelude::*;
+
+use crate::sync::{atomic_notify, atomic_wait32};
+
+/// Number of helpers that wait for tasks.
+static HELPERS: AtomicU32 = AtomicU32::new(0);
+/// Incremented for every task, which wakes up the helpers.
+static GENERATION: AtomicI32 = AtomicI32::new(0);
+/// Whether the current task accepts helpers, and the number of helpers working on it.
+static STATE: AtomicI32 = AtomicI32::new(0);
+/// Index of the next chunk that nobody works on yet.
+static NEXT_CHUNK: AtomicUsize = AtomicUsize::new(0);
+/// Number of chunks that are not finished yet.
+static REMAINING: AtomicI32 = AtomicI32::new(0);
+/// The current task, which is only read by helpers that were admitted by `STATE`.
+static TASK_FN: AtomicUsize = AtomicUsize::new(0);
+static TASK_DATA: AtomicUsize = AtomicUsize::new(0);
+static TASK_CHUNKS: AtomicUsize = AtomicUsize::new(0);
+/// Set while a web worker thread shares a task, because the helpers only work on one at a time.
+static TASK_LOCK: AtomicBool = AtomicBool::new(false);
+
+const OPEN: i32 = 1 << 30;
+
+/// Function to be called in every helper web worker to share the work of the web worker thread.
+///
+/// # Safety
+///
+/// It blocks forever, so it must only be called from a dedicated web worker after the module
+/// was initialized with the shared memory.
+#[wasm_bindgen]
+pub fn run_helper() {
+    HELPERS.fetch_add(1, Ordering::SeqCst);
+    let mut generation = GENERATION.load(Ordering::SeqCst);
+    loop {
+        atomic_wait32(&GENERATION, generation, -1);
+        let current = GENERATION.load(Ordering::SeqCst);
+        if current == generation {
+            continue;
+        }
+        generation = current;
+        if join() {
+            work();
+            STATE.fetch_sub(1, Ordering::SeqCst);
+            atomic_notify(&STATE, 1);
+        }
+    }
+}
+
+/// Runs the task for every chunk index below `chunks`, spread over the calling thread and all
+/// helpers, and returns once every chunk is finished.
+///
+/// Must only be called from a web worker thread, because it blocks. If another web worker thread
+/// is sharing a task at the same time, the calling thread works on all chunks itself.
+pub fn for_each_chunk<F: Fn(usize) + Sync>(chunks: usize, task: &F) {
+    let remaining = match i32::try_from(chunks) {
+        Ok(remaining)
+            if remaining >= 2
+                && HELPERS.load(Ordering::SeqCst) > 0
+                && !TASK_LOCK.swap(true, Ordering::Acquire) =>
+        {
+            remaining
+        }
+        _ => {
+            (0..chunks).for_each(task);
+            return;
+        }
+    };
+
+    TASK_FN.store(run_chunk::<F> as *const () as usize, Ordering::SeqCst);
+    TASK_DATA.store(task as *const F as usize, Ordering::SeqCst);
+    TASK_CHUNKS.store(chunks, Ordering::SeqCst);
+    NEXT_CHUNK.store(0, Ordering::SeqCst);
+    REMAINING.store(remaining, Ordering::SeqCst);
+    STATE.store(OPEN, Ordering::SeqCst);
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    atomic_notify(&GENERATION, u32::MAX);
+
+    work();
+    loop {
+        let remaining = REMAINING.load(Ordering::SeqCst);
+        if remaining == 0 {
+            break;
+        }
+        atomic_wait32(&REMAINING, remaining, -1);
+    }
+
+    // Late helpers must not see the next task through the fields of this one, which point to the
+    // stack of the caller.
+    STATE.fetch_and(!OPEN, Ordering::SeqCst);
+    loop {
+        let state = STATE.load(Ordering::SeqCst);
+        if state == 0 {
+            break;
+        }
+        atomic_wait32(&STATE, state, -1);
+    }
+    TASK_LOCK.store(false, Ordering::Release);
+}
+
+/// Runs the chunks of a task from the layout with [`for_each_chunk()`], see
+/// [`Layout::set_chunk_runner()`](masonry_core::Layout::set_chunk_runner).
+pub fn run_chunks(chunks: usize, task: &(dyn Fn(usize) + Sync)) {
+    for_each_chunk(chunks, &task);
+}
+
+/// Admits a helper to the current task if it still accepts helpers.
+fn join() -> bool {
+    let mut state = STATE.load(Ordering::SeqCst);
+    while state & OPEN != 0 {
+        match STATE.compare_exchange_weak(state, state + 1, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => return true,
+            Err(current) => state = current,
+        }
+    }
+    false
+}
+
+/// Works on chunks of the current task until none are left.
+fn work() {
+    // SAFETY: The function was stored by `for_each_chunk` from a `run_chunk` instance, whose data
+    // lives until the task is closed, which waits for all admitted helpers.
+    let run: unsafe fn(usize, usize) =
+        unsafe { core::mem::transmute(TASK_FN.load(Ordering::SeqCst)) };
+    let data = TASK_DATA.load(Ordering::SeqCst);
+    let chunks = TASK_CHUNKS.load(Ordering::SeqCst);
+    loop {
+        let chunk = NEXT_CHUNK.fetch_add(1, Ordering::SeqCst);
+        if chunk >= chunks {
+            break;
+        }
+        unsafe { run(data, chunk) };
+        if REMAINING.fetch_sub(1, Ordering::SeqCst) == 1 {
+            atomic_notify(&REMAINING, 1);
+        }
+    }
+}
+
+unsafe fn run_chunk<F: Fn(usize)>(data: usize, chunk: usize) {
+    (*(data as *const F))(chunk);
+}
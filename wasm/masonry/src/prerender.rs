@@ -0,0 +1,40 @@
+//! Layouts computed without a [`crate::masonry_worker::MasonryWorker`], e.g. in Node.js or the main
+//! process of Electron before the window with the gallery exists.
+use alloc::vec::Vec;
+use wasm_bindgen::prelude::*;
+
+use masonry_core::{Layout, MasonryConfig};
+
+use crate::data::ComputeResult;
+use crate::masonry_worker::invalid_config;
+use crate::sync::compute_locally;
+
+/// Computes the layout of items with the given dimensions, as pairs of width and height, at the
+/// container width and returns the container height followed by the seven numbers of every item.
+///
+/// This needs neither a web worker nor a DOM, so the initial layout of a gallery can be computed
+/// ahead of time and shown without a blank frame: the renderer passes the `buffer` of the result
+/// to `MasonryWorker::unpack_transforms()` of a worker with as many items, and computes the layout
+/// itself once the window is resized. Throws a `MasonryConfigError` like
+/// `MasonryWorker::compute()` if the arguments are invalid.
+#[wasm_bindgen]
+pub fn compute_layout(
+    dimensions: &[u16],
+    width: u32,
+    config: &MasonryConfig,
+) -> Result<Vec<u32>, JsValue> {
+    let mut layout = Layout::new(dimensions.len() / 2, config.thumbnail_size, config.gap_x);
+    layout.check_config(width, config).map_err(invalid_config)?;
+    for (index, pair) in dimensions.chunks_exact(2).enumerate() {
+        layout.set_dimension(index, pair[0], pair[1]);
+    }
+    let mut result = ComputeResult::default();
+    let height = compute_locally(&mut layout, &mut result, |layout| {
+        layout.compute(width, config)
+    });
+    let transforms = layout.export_transforms();
+    let mut packed = Vec::with_capacity(transforms.len() + 1);
+    packed.push(height);
+    packed.extend(transforms);
+    Ok(packed)
+}
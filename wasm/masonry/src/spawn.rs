@@ -0,0 +1,244 @@
+//! Construction of workers whose web worker is only started once it is needed.
+use alloc::string::String;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use masonry_core::MasonryConfig;
+
+use crate::fallback::has_shared_memory;
+use crate::masonry_worker::{MasonryWorker, Worker};
+use crate::sync::fail;
+
+#[wasm_bindgen]
+extern "C" {
+    type Url;
+
+    #[wasm_bindgen(constructor, catch, js_class = URL)]
+    fn new(url: &str, base: &str) -> Result<Url, JsValue>;
+
+    #[wasm_bindgen(method, getter)]
+    fn href(this: &Url) -> String;
+
+    type ErrorEvent;
+
+    #[wasm_bindgen(method, js_name = preventDefault)]
+    fn prevent_default(this: &ErrorEvent);
+}
+
+/// Returns whether the module runs in Deno or another runtime without the DOM that implements
+/// the standard web workers, e.g. to precompute layouts on a server.
+///
+/// There, the web worker compiles the module itself instead of receiving the compiled module,
+/// relative script URLs are resolved against the main module, and errors of the web worker are
+/// handled instead of ending the process.
+pub fn is_standard_runtime() -> bool {
+    !js_sys::Reflect::has(&js_sys::global(), &"document".into()).unwrap_or(false)
+}
+
+/// Resolves a relative URL against `Deno.mainModule`, because Deno has no page URL to resolve
+/// the URLs of web workers against.
+fn resolve_script_url(url: String) -> String {
+    let main_module = js_sys::Reflect::get(&js_sys::global(), &"Deno".into())
+        .and_then(|deno| js_sys::Reflect::get(&deno, &"mainModule".into()))
+        .ok()
+        .and_then(|main_module| main_module.as_string());
+    match main_module.map(|base| Url::new(&url, &base)) {
+        Some(Ok(resolved)) => resolved.href(),
+        _ => url,
+    }
+}
+
+/// How the web worker of a worker from [`MasonryWorkerBuilder`] is started.
+#[derive(Clone)]
+pub enum Spawn {
+    /// A module web worker that runs the script at the URL.
+    Script(String),
+    /// A function that returns the web worker, e.g. `createWorker` of the package.
+    Factory(js_sys::Function),
+}
+
+impl Spawn {
+    /// Starts the web worker and posts the message that initializes it, e.g. with the memory and
+    /// the compiled module. If it cannot be started, the web worker thread is marked as failed, so
+    /// the pending `Promise`s reject.
+    ///
+    /// In a standard runtime (see [`is_standard_runtime()`]), a relative script URL is resolved
+    /// against the main module.
+    pub fn spawn(self, message: &JsValue, standard: bool) -> Option<Worker> {
+        let worker = match self {
+            Spawn::Script(url) => {
+                let url = if standard {
+                    resolve_script_url(url)
+                } else {
+                    url
+                };
+                let options = js_sys::Object::new();
+                drop(js_sys::Reflect::set(
+                    &options,
+                    &"type".into(),
+                    &"module".into(),
+                ));
+                let worker = Worker::new(&url, &options);
+                if let Ok(worker) = &worker {
+                    report_failure(worker, standard);
+                }
+                worker
+            }
+            Spawn::Factory(factory) => factory
+                .call0(&JsValue::NULL)
+                .map(JsCast::unchecked_into::<Worker>),
+        };
+        match worker {
+            Ok(worker) => {
+                worker.post_message(message);
+                Some(worker)
+            }
+            Err(error) => {
+                fail(message_of(&error));
+                None
+            }
+        }
+    }
+}
+
+/// Marks the web worker thread as failed once the web worker reports an error, either as an
+/// `error` event or as an `{ error }` message of `worker.js`. Web workers from a factory are left
+/// to the caller, who knows their handlers.
+///
+/// Deno rethrows the errors of web workers on the main thread unless the event is cancelled,
+/// which would end the process, so they are cancelled in standard runtimes.
+fn report_failure(worker: &Worker, standard: bool) {
+    worker.set_onerror(&Closure::once_into_js(move |event: JsValue| {
+        if standard {
+            event.unchecked_ref::<ErrorEvent>().prevent_default();
+        }
+        fail(message_of(&event));
+    }));
+    worker.set_onmessage(&Closure::once_into_js(|event: JsValue| {
+        let data = js_sys::Reflect::get(&event, &"data".into()).unwrap_or(JsValue::UNDEFINED);
+        fail(message_of(&data));
+    }));
+}
+
+/// Returns the message of an `Error`, an `ErrorEvent` or an `{ error }` message.
+fn message_of(value: &JsValue) -> String {
+    ["message", "error"]
+        .into_iter()
+        .filter_map(|name| js_sys::Reflect::get(value, &name.into()).ok())
+        .find_map(|message| message.as_string())
+        .unwrap_or_else(|| String::from("The web worker could not be started"))
+}
+
+/// Collects the parameters of a [`MasonryWorker`] and creates it without starting its web worker.
+///
+/// The web worker is only started by the first computation that is sent to it, or by
+/// [`MasonryWorker::start()`], so galleries that are created up front but never shown cost neither
+/// a web worker nor an instantiation of the module. Computations on the main thread, e.g. below
+/// the threshold of [`MasonryWorker::set_sync_threshold()`], do not start it either.
+///
+/// If `SharedArrayBuffer` is missing, e.g. because the page is not cross-origin isolated, the web
+/// worker gets its own instance of the module instead of the memory, and every computation is
+/// posted to it as a snapshot, see [`crate::fallback`].
+///
+/// In Deno and other runtimes without the DOM, the web worker is started like the standard
+/// describes it, see [`MasonryWorkerBuilder::standard_runtime()`].
+#[wasm_bindgen]
+pub struct MasonryWorkerBuilder {
+    num_items: usize,
+    config: Option<MasonryConfig>,
+    sync_threshold: usize,
+    spawn: Option<Spawn>,
+    standard: Option<bool>,
+}
+
+#[wasm_bindgen]
+impl MasonryWorkerBuilder {
+    #[wasm_bindgen(constructor)]
+    #[must_use]
+    pub fn new() -> MasonryWorkerBuilder {
+        MasonryWorkerBuilder {
+            num_items: 0,
+            config: None,
+            sync_threshold: 0,
+            spawn: None,
+            standard: None,
+        }
+    }
+
+    /// Sets the number of items that the worker starts with, see [`MasonryWorker::resize()`].
+    #[must_use]
+    pub fn num_items(mut self, num_items: usize) -> MasonryWorkerBuilder {
+        self.num_items = num_items;
+        self
+    }
+
+    /// Sets the parameters of the worker like [`MasonryWorker::set_config()`].
+    #[must_use]
+    pub fn config(mut self, config: &MasonryConfig) -> MasonryWorkerBuilder {
+        self.config = Some(*config);
+        self
+    }
+
+    /// Sets the item count up to which layouts are computed on the main thread, see
+    /// [`MasonryWorker::set_sync_threshold()`].
+    #[must_use]
+    pub fn sync_threshold(mut self, num_items: usize) -> MasonryWorkerBuilder {
+        self.sync_threshold = num_items;
+        self
+    }
+
+    /// Sets the URL of the `worker.js` script, which is started as a module web worker.
+    ///
+    /// Its failures to load are reported like with [`MasonryWorker::fail()`].
+    #[must_use]
+    pub fn script_url(mut self, url: String) -> MasonryWorkerBuilder {
+        self.spawn = Some(Spawn::Script(url));
+        self
+    }
+
+    /// Sets a function that starts the web worker and returns it, e.g. `createWorker` of the
+    /// package, which lets bundlers find the script and accepts a Trusted Types policy.
+    ///
+    /// The function is responsible for reporting failures of the web worker with
+    /// [`MasonryWorker::fail()`].
+    #[must_use]
+    pub fn spawn_with(mut self, factory: js_sys::Function) -> MasonryWorkerBuilder {
+        self.spawn = Some(Spawn::Factory(factory));
+        self
+    }
+
+    /// Sets whether the web worker is started for Deno or another runtime without the DOM, which
+    /// is detected by default (see [`is_standard_runtime()`]).
+    ///
+    /// The web worker then compiles the module from its URL instead of receiving the compiled
+    /// module, a relative script URL is resolved against `Deno.mainModule`, and the errors of the
+    /// web worker reject the pending `Promise`s without ending the process. It also works in
+    /// browsers, where it only costs the second compilation.
+    #[must_use]
+    pub fn standard_runtime(mut self, enabled: bool) -> MasonryWorkerBuilder {
+        self.standard = Some(enabled);
+        self
+    }
+
+    /// Creates the worker. Without a script URL or factory, the web worker has to be started by
+    /// the caller like for [`MasonryWorker::new()`].
+    #[must_use]
+    pub fn build(self) -> MasonryWorker {
+        let mut worker = MasonryWorker::new(self.num_items);
+        if let Some(config) = self.config {
+            worker.set_config(&config);
+        }
+        worker.set_sync_threshold(self.sync_threshold);
+        if let Some(spawn) = self.spawn {
+            let standard = self.standard.unwrap_or_else(is_standard_runtime);
+            worker.defer_spawn(spawn, !has_shared_memory(), standard);
+        }
+        worker
+    }
+}
+
+impl Default for MasonryWorkerBuilder {
+    fn default() -> MasonryWorkerBuilder {
+        MasonryWorkerBuilder::new()
+    }
+}
@@ -3,89 +3,652 @@
 //! This whole module is akin to a channel (e.g. [`std::sync::mpsc::channel()`]). However, it uses
 //! statics to avoid sending a receiver to the web worker. As it stands now, there is no nice
 //! [`std::thread::spawn()`] abstraction and it probably won't be added any time.
+//!
+//! Every [`crate::masonry_worker::MasonryWorker`] sends the computations of its own layout, so one
+//! web worker thread serves all of them. The queue holds at most one computation per layout. More
+//! web worker threads can join to compute the layouts of different workers at the same time, but
+//! a layout is never computed by two threads at once.
+//!
+//! Computations have a [`Priority`]. The ones in the background, e.g. those sent with
+//! [`send_background()`], only run once no user-blocking computation is waiting and are not waited
+//! for by the `Promise`s of the user-blocking ones. A user-blocking computation that finds every
+//! thread busy preempts one in the background, which is queued again once it stopped.
 //! ```
+use alloc::{format, string::String, vec::Vec};
 use core::{
     cell::Cell,
-    sync::atomic::{AtomicI32, Ordering},
+    ptr,
+    sync::atomic::{AtomicBool, AtomicI32, AtomicU32, AtomicUsize, Ordering},
 };
 
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use masonry_core::cancel::{
+    is_slot_cancelled, reset, reset_slot, slot_progress, stop_slot, MAX_SLOTS,
+};
+use masonry_core::{ChunkRunner, Layout};
 
-use crate::data::{Computation, MasonryType};
+use crate::data::{Computation, ComputeResult, Priority};
+use crate::error::Error;
+use crate::panic::{during, forward_from_worker, Operation};
 
+/// Locked while user-blocking computations are queued or in progress.
 static MAIN_THREAD: AtomicI32 = AtomicI32::new(UNLOCKED);
-static WORKER_THREAD: AtomicI32 = AtomicI32::new(LOCKED);
-static INPUT: Data<Option<Computation>> = Data::new(None);
-static OUTPUT: Data<u32> = Data::new(0);
+/// Locked while computations in the background are queued or in progress.
+static BACKGROUND_LANE: AtomicI32 = AtomicI32::new(UNLOCKED);
+/// Incremented for every computation that is sent, which wakes up an idle web worker thread.
+static WORK: AtomicI32 = AtomicI32::new(0);
+/// Number of web worker threads that called [`run()`].
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+/// Unlocked once the first web worker thread has started, see [`worker_ready()`].
+static READY: AtomicI32 = AtomicI32::new(LOCKED);
+/// Why the web worker thread stopped working, see [`fail()`]. Guarded by the input lock, because a
+/// web worker thread that panics sets it as well.
+static FAILURE: Data<Option<String>> = Data::new(None);
+static INPUT: Data<Vec<Computation>> = Data::new(Vec::new());
+static INPUT_LOCK: AtomicBool = AtomicBool::new(false);
+/// Whether the current thread holds [`INPUT_LOCK`], see [`release_input_lock()`].
+#[thread_local]
+static HOLDS_INPUT_LOCK: Cell<bool> = Cell::new(false);
+/// The layout that every web worker thread is computing, or null if it is idle, indexed by the
+/// cancellation slot of the thread. Slot 0 belongs to the main thread. Guarded by the input lock.
+static RUNNING: Data<[*mut Layout; MAX_SLOTS]> = Data::new([ptr::null_mut(); MAX_SLOTS]);
+/// Whether the computation in the same slot of [`RUNNING`] runs in the background. Guarded by the
+/// input lock.
+static BACKGROUND: Data<[bool; MAX_SLOTS]> = Data::new([false; MAX_SLOTS]);
+/// Whether the computation in the same slot of [`RUNNING`] was cancelled for a user-blocking one
+/// and has to be queued again. Guarded by the input lock.
+static PREEMPTED: Data<[bool; MAX_SLOTS]> = Data::new([false; MAX_SLOTS]);
+/// Shares the per-item work of huge layouts with the helper threads, see [`crate::pool`]. It
+/// blocks, so it is only installed while a web worker thread computes.
+#[cfg(feature = "threads")]
+const CHUNK_RUNNER: Option<ChunkRunner> = Some(crate::pool::run_chunks);
+#[cfg(not(feature = "threads"))]
+const CHUNK_RUNNER: Option<ChunkRunner> = None;
+/// Number of times the web worker threads were replaced, see [`restart()`].
+static RESTARTS: AtomicU32 = AtomicU32::new(0);
+/// Changes whenever views into the memory may have become stale, see [`memory_version()`].
+static MEMORY_VERSION: AtomicU32 = AtomicU32::new(0);
+/// Number of pages of the memory when [`MEMORY_VERSION`] was last updated.
+static MEMORY_PAGES: AtomicUsize = AtomicUsize::new(0);
+/// Whether the phases of the computations are recorded with `performance.measure()`, see
+/// [`set_instrumented()`].
+static INSTRUMENTED: AtomicBool = AtomicBool::new(false);
 
 const LOCKED: i32 = 0;
 const UNLOCKED: i32 = 1;
 
 /// Function to be called in the web worker thread to compute the new layout.
 ///
+/// Every web worker that calls it joins the pool of threads that compute the layouts, up to one
+/// less than [`MAX_SLOTS`]. Additional ones return right away.
+///
 /// # Safety
 ///
 /// Do not import this function as it is already imported into the web worker thread (see
 /// `worker.js`).
 #[wasm_bindgen]
 pub fn run() {
+    let slot = THREADS.fetch_add(1, Ordering::SeqCst) + 1;
+    if slot >= MAX_SLOTS {
+        return;
+    }
+    READY.store(UNLOCKED, Ordering::SeqCst);
+    atomic_notify(&READY, u32::MAX);
     loop {
-        atomic_wait32(&WORKER_THREAD, LOCKED, -1);
-        if let Some(computation) = INPUT.replace(None) {
-            OUTPUT.set(execute(computation));
+        let work = WORK.load(Ordering::SeqCst);
+        // Keep computing until no newer computation was sent in the meantime. The main thread is
+        // only notified once all threads are done, so every pending `Promise` resolves with the
+        // final result.
+        while let Some(computation) = take_input(slot) {
+            // Only computations in the background are left, which the main thread does not wait
+            // for.
+            notify_idle_lanes();
+            execute(&computation, slot);
+            requeue_if_preempted(computation, slot);
         }
-        // Put the worker thread back to sleep and notify the main thread that work is finished.
-        WORKER_THREAD.store(LOCKED, Ordering::SeqCst);
-        MAIN_THREAD.store(UNLOCKED, Ordering::SeqCst);
-        atomic_notify(&MAIN_THREAD, 1);
+        notify_idle_lanes();
+        atomic_wait32(&WORK, work, -1);
     }
 }
 
 /// Wakes up the web worker thread and "sends" data to receiver.
+///
+/// The new computation replaces the one that is queued for the same layout and cancels the one in
+/// progress if it belongs to the same layout. The returned `Promise` resolves together with all
+/// other pending ones of the same priority once the worker threads have finished all queued
+/// computations of that priority.
 pub fn send_computation(computation: Computation) -> js_sys::Promise {
-    INPUT.set(Some(computation));
-    // Wake up the worker thread and make the main thread wait for the worker thread.
-    MAIN_THREAD.store(LOCKED, Ordering::SeqCst);
-    WORKER_THREAD.store(UNLOCKED, Ordering::SeqCst);
-    atomic_notify(&WORKER_THREAD, 1);
+    let output = computation.output_ptr;
+    let lane = match computation.priority {
+        Priority::UserBlocking => &MAIN_THREAD,
+        Priority::Background => &BACKGROUND_LANE,
+    };
+    if let Err(error) = queue_computation(computation) {
+        return js_sys::Promise::reject(&error);
+    }
+    let result = Closure::once_into_js(move |_: JsValue| {
+        // SAFETY: The worker that owns the output is alive until its `Promise` resolved, see
+        // `execute`.
+        JsValue::from(unsafe { *output })
+    });
+    reject_on_failure(&atomic_wait32_async(lane, LOCKED))
+        .unchecked_ref::<Thenable>()
+        .then(&result)
+}
+
+/// Queues a computation like [`send_computation()`] without creating a `Promise`, e.g. because
+/// the caller waits with [`when_idle()`] already. Returns the error of the web worker thread
+/// instead if it failed.
+pub fn queue_computation(computation: Computation) -> Result<(), JsValue> {
+    if let Some(error) = failure() {
+        return Err(error);
+    }
+    // Make the main thread wait for the worker threads.
+    queue(computation);
+    Ok(())
+}
+
+/// Calls the function once the worker threads have finished all queued computations, when the
+/// `Promise`s of [`send_computation()`] resolve, or once the web worker thread failed, which
+/// [`failure()`] tells apart.
+pub fn when_idle(on_idle: &JsValue) {
+    drop(
+        atomic_wait32_async(&MAIN_THREAD, LOCKED)
+            .unchecked_ref::<Thenable>()
+            .then(on_idle),
+    );
+}
+
+/// Queues a computation in the background, e.g. to prepare a layout that may be needed later.
+///
+/// Unlike [`send_computation()`], nothing waits for it: the `Promise`s of the user-blocking
+/// computations ignore it until it is cancelled with [`cancel_computation()`]. Nothing is queued
+/// if the web worker thread failed.
+pub fn send_background(computation: Computation) {
+    if with_input_lock(|| FAILURE.with(|failure| failure.is_none())) {
+        queue(computation.with_priority(Priority::Background));
+    }
+}
+
+/// Replaces the computation that is queued for the same layout and cancels the one in progress
+/// if it belongs to the same layout, then wakes up an idle worker thread, if any.
+fn queue(computation: Computation) {
+    with_input_lock(|| {
+        let layout = computation.layout_ptr;
+        let priority = computation.priority;
+        INPUT.with(|input| {
+            input.retain(|queued| queued.layout_ptr != layout);
+            input.push(computation);
+        });
+        // The thread that computes the layout picks up the new input when it stopped.
+        if let Some(slot) = running_slot(layout) {
+            stop_slot(slot);
+        }
+        match priority {
+            Priority::UserBlocking => {
+                MAIN_THREAD.store(LOCKED, Ordering::SeqCst);
+                preempt_background();
+            }
+            Priority::Background => BACKGROUND_LANE.store(LOCKED, Ordering::SeqCst),
+        }
+    });
+    WORK.fetch_add(1, Ordering::SeqCst);
+    atomic_notify(&WORK, 1);
+}
+
+/// Returns a `Promise` that resolves once the web worker thread has been initialized and is
+/// waiting for computations.
+pub fn worker_ready() -> js_sys::Promise {
+    reject_on_failure(&atomic_wait32_async(&READY, LOCKED))
+}
+
+/// Marks the web worker thread as failed, e.g. because its script could not be loaded.
+///
+/// All pending `Promise`s are woken up and reject with the error, just like every computation
+/// that is sent afterwards. Computations on the main thread keep working. Only the first reason
+/// is kept, e.g. the report of a panic rather than the error event of the trap that follows it.
+pub fn fail(message: String) {
+    with_input_lock(|| {
+        FAILURE.with(|failure| {
+            failure.get_or_insert(message);
+        });
+    });
+    READY.store(UNLOCKED, Ordering::SeqCst);
+    atomic_notify(&READY, u32::MAX);
+    MAIN_THREAD.store(UNLOCKED, Ordering::SeqCst);
+    BACKGROUND_LANE.store(UNLOCKED, Ordering::SeqCst);
+    notify_idle_lanes();
+}
+
+/// Resets the state shared with the web worker threads after one failed, so that new web workers
+/// can take over, and returns the `Promise` of [`worker_ready()`] for them.
+///
+/// All web workers must have been terminated and the new ones must be started afterwards.
+pub fn restart() -> js_sys::Promise {
+    with_input_lock(|| {
+        INPUT.set(Vec::new());
+        RUNNING.set([ptr::null_mut(); MAX_SLOTS]);
+        BACKGROUND.set([false; MAX_SLOTS]);
+        PREEMPTED.set([false; MAX_SLOTS]);
+        (0..MAX_SLOTS).for_each(reset_slot);
+        FAILURE.set(None);
+    });
+    THREADS.store(0, Ordering::SeqCst);
+    RESTARTS.fetch_add(1, Ordering::SeqCst);
+    READY.store(LOCKED, Ordering::SeqCst);
+    MAIN_THREAD.store(UNLOCKED, Ordering::SeqCst);
+    BACKGROUND_LANE.store(UNLOCKED, Ordering::SeqCst);
+    worker_ready()
+}
+
+/// Returns how often the web worker threads were replaced with [`restart()`], e.g. to tell
+/// whether another worker replaced them already.
+pub fn restarts() -> u32 {
+    RESTARTS.load(Ordering::SeqCst)
+}
+
+/// Returns the error that stopped the web worker thread, if any.
+pub fn failure() -> Option<JsValue> {
+    let message = with_input_lock(|| FAILURE.with(|failure| failure.clone()));
+    message.map(|message| JsValue::from(Error::WorkerFailed(message)))
+}
+
+#[wasm_bindgen]
+extern "C" {
+    /// A `Promise` whose `then` accepts any callback, e.g. from [`Closure::once_into_js()`].
+    pub(crate) type Thenable;
+
+    #[wasm_bindgen(method)]
+    pub(crate) fn then(this: &Thenable, on_fulfilled: &JsValue) -> js_sys::Promise;
+
+    #[wasm_bindgen(method, js_name = then)]
+    pub(crate) fn then_or(
+        this: &Thenable,
+        on_fulfilled: &JsValue,
+        on_rejected: &JsValue,
+    ) -> js_sys::Promise;
+
+    #[wasm_bindgen(js_name = setTimeout)]
+    pub(crate) fn set_timeout(handler: &JsValue, timeout: i32);
+
+    #[wasm_bindgen(js_name = queueMicrotask)]
+    pub(crate) fn queue_microtask(callback: &JsValue);
+}
+
+/// Rejects the `Promise` with the error of the web worker thread if it failed in the meantime.
+fn reject_on_failure(promise: &js_sys::Promise) -> js_sys::Promise {
+    let check = Closure::once_into_js(|value: JsValue| match failure() {
+        Some(error) => {
+            forward_from_worker(&error);
+            Err(error)
+        }
+        None => Ok(value),
+    });
+    promise.unchecked_ref::<Thenable>().then(&check)
+}
+
+/// Rejects the `Promise` with an `Error` named `MasonryTimeoutError` if it does not settle within
+/// the given number of milliseconds, e.g. because the web worker thread hangs or missed the
+/// notification.
+///
+/// The computation is not cancelled, because a web worker thread that hangs would not notice.
+pub fn with_timeout(promise: &js_sys::Promise, timeout_ms: u32) -> js_sys::Promise {
+    js_sys::Promise::new(&mut |resolve, reject| {
+        let message = format!("The computation did not finish within {} ms", timeout_ms);
+        let error = JsValue::from(Error::Timeout(message));
+        let reject_late = reject.clone();
+        // Settling the `Promise` again after it resolved has no effect.
+        let expire = Closure::once_into_js(move || {
+            drop(reject_late.call1(&JsValue::UNDEFINED, &error));
+        });
+        set_timeout(&expire, i32::try_from(timeout_ms).unwrap_or(i32::MAX));
+        // The chained `Promise` settles together with the returned one.
+        drop(
+            promise
+                .unchecked_ref::<Thenable>()
+                .then_or(&resolve, &reject),
+        );
+    })
+}
+
+/// Returns the slot of the web worker thread that computes the layout, if any. Must be called
+/// while holding the input lock.
+fn running_slot(layout: *const Layout) -> Option<usize> {
+    RUNNING
+        .get()
+        .iter()
+        .position(|&running| ptr::eq(running, layout))
+}
+
+/// Returns true while a computation of the layout is queued or in progress.
+pub fn is_computing(layout: *const Layout) -> bool {
+    with_input_lock(|| {
+        running_slot(layout).is_some()
+            || INPUT.with(|input| {
+                input
+                    .iter()
+                    .any(|queued| ptr::eq(queued.layout_ptr, layout))
+            })
+    })
+}
+
+/// Signals the web worker thread to stop the computation of the layout that is currently in
+/// progress.
+///
+/// A computation of the layout that was queued but not started yet is dropped as well. The flag is
+/// reset once the worker thread picks up the next computation, so calling this while no
+/// computation of the layout is pending has no effect.
+///
+/// A cancelled computation in the background is waited for like any other until it stopped, so
+/// that the layout can be freed once the main thread is no longer busy.
+pub fn cancel_computation(layout: *const Layout) {
+    with_input_lock(|| {
+        INPUT.with(|input| input.retain(|queued| !ptr::eq(queued.layout_ptr, layout)));
+        if let Some(slot) = running_slot(layout) {
+            stop_slot(slot);
+            PREEMPTED.with(|preempted| preempted[slot] = false);
+            BACKGROUND.with(|background| {
+                if background[slot] {
+                    background[slot] = false;
+                    MAIN_THREAD.store(LOCKED, Ordering::SeqCst);
+                }
+            });
+        }
+    });
+}
+
+/// Returns how many positions of the layout its computation in progress has processed, or 0 if
+/// it is not computed right now.
+pub fn progress_of(layout: *const Layout) -> u32 {
+    with_input_lock(|| running_slot(layout)).map_or(0, slot_progress)
+}
+
+/// Cancels the pending computation of the layout like [`cancel_computation()`] and runs the
+/// closure once the web worker threads no longer use the layout. The returned `Promise` resolves
+/// afterwards.
+pub fn cancel_then(layout: *const Layout, f: impl FnOnce() + 'static) -> js_sys::Promise {
+    cancel_computation(layout);
+    let run = Closure::once_into_js(move |_: JsValue| f());
     atomic_wait32_async(&MAIN_THREAD, LOCKED)
+        .unchecked_ref::<Thenable>()
+        .then(&run)
+}
+
+/// Takes the next computation for the worker thread in the slot, skipping the layouts that other
+/// threads are computing and preferring the user-blocking ones, and marks the lanes without any
+/// computations left as finished.
+fn take_input(slot: usize) -> Option<Computation> {
+    with_input_lock(|| {
+        let mut running = RUNNING.get();
+        let mut background = BACKGROUND.get();
+        running[slot] = ptr::null_mut();
+        background[slot] = false;
+        let (computation, waiting) = INPUT.with(|input| {
+            let available = |queued: &Computation| !running.contains(&queued.layout_ptr);
+            let index = input
+                .iter()
+                .position(|queued| queued.priority == Priority::UserBlocking && available(queued))
+                .or_else(|| input.iter().position(available));
+            let computation = index.map(|index| input.remove(index));
+            let waiting = |priority| input.iter().any(|queued| queued.priority == priority);
+            let waiting = [Priority::UserBlocking, Priority::Background].map(waiting);
+            (computation, waiting)
+        });
+        if let Some(computation) = &computation {
+            running[slot] = computation.layout_ptr;
+            background[slot] = computation.priority == Priority::Background;
+            reset_slot(slot);
+        }
+        let computing = |in_background| {
+            (0..MAX_SLOTS).any(|slot| !running[slot].is_null() && background[slot] == in_background)
+        };
+        if !waiting[0] && !computing(false) {
+            // Only layouts in the background are left, which the main thread does not wait for.
+            MAIN_THREAD.store(UNLOCKED, Ordering::SeqCst);
+        }
+        if !waiting[1] && !computing(true) {
+            BACKGROUND_LANE.store(UNLOCKED, Ordering::SeqCst);
+        }
+        RUNNING.set(running);
+        BACKGROUND.set(background);
+        computation
+    })
+}
+
+/// Cancels a computation in the background if no web worker thread is idle, so that the
+/// user-blocking computation that was just queued does not wait for it. Must be called while
+/// holding the input lock.
+fn preempt_background() {
+    let threads = THREADS.load(Ordering::SeqCst).min(MAX_SLOTS - 1);
+    let running = RUNNING.get();
+    if (1..=threads).any(|slot| running[slot].is_null()) {
+        return;
+    }
+    let background = BACKGROUND.get();
+    PREEMPTED.with(|preempted| {
+        if let Some(slot) = (1..=threads).find(|&slot| background[slot] && !preempted[slot]) {
+            stop_slot(slot);
+            preempted[slot] = true;
+        }
+    });
+}
+
+/// Queues a computation in the background again after it was preempted, as long as its slot is
+/// still cancelled, unless a newer one of the same layout was queued in the meantime.
+fn requeue_if_preempted(computation: Computation, slot: usize) {
+    with_input_lock(|| {
+        let preempted = PREEMPTED.with(|preempted| core::mem::replace(&mut preempted[slot], false));
+        if preempted && is_slot_cancelled(slot) {
+            INPUT.with(|input| {
+                if !input
+                    .iter()
+                    .any(|queued| ptr::eq(queued.layout_ptr, computation.layout_ptr))
+                {
+                    input.push(computation);
+                }
+            });
+        }
+    });
+}
+
+/// Wakes up the `Promise`s that wait for a lane without any computations left.
+fn notify_idle_lanes() {
+    for lane in [&MAIN_THREAD, &BACKGROUND_LANE] {
+        if lane.load(Ordering::SeqCst) == UNLOCKED {
+            atomic_notify(lane, u32::MAX);
+        }
+    }
 }
 
-/// Returns the result of the most recent computation.
-pub fn receive_output() -> u32 {
-    OUTPUT.get()
+/// Runs the closure while holding a spin lock on the input.
+///
+/// The main thread is not allowed to block with `Atomics.wait`, so a spin lock is used instead.
+/// The critical sections only move a few values around, which is why this never spins for long.
+fn with_input_lock<R>(f: impl FnOnce() -> R) -> R {
+    while INPUT_LOCK
+        .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        core::hint::spin_loop();
+    }
+    HOLDS_INPUT_LOCK.set(true);
+    let result = f();
+    HOLDS_INPUT_LOCK.set(false);
+    INPUT_LOCK.store(false, Ordering::Release);
+    result
 }
 
-fn execute(computation: Computation) -> u32 {
-    let (width, config, layout) = {
+/// Releases the input lock if the current thread holds it, e.g. because it panicked in a critical
+/// section and traps without leaving it, so that the other threads and [`fail()`] do not spin
+/// forever.
+#[cfg(feature = "panic-hook")]
+pub fn release_input_lock() {
+    if HOLDS_INPUT_LOCK.replace(false) {
+        INPUT_LOCK.store(false, Ordering::Release);
+    }
+}
+
+/// Returns a number that changes whenever views into the memory that JavaScript created before
+/// may have become stale.
+///
+/// Growing the memory replaces `memory.buffer`, no matter which thread grew it: a view of a
+/// `SharedArrayBuffer` keeps its old length and an `ArrayBuffer` is detached. The web worker thread
+/// only touches the memory from Rust, so only the views of JavaScript are affected.
+pub fn memory_version() -> u32 {
+    let pages = core::arch::wasm32::memory_size::<0>();
+    if MEMORY_PAGES.swap(pages, Ordering::SeqCst) != pages {
+        MEMORY_VERSION.fetch_add(1, Ordering::SeqCst);
+    }
+    MEMORY_VERSION.load(Ordering::SeqCst)
+}
+
+/// Changes the [`memory_version()`] without growing the memory, e.g. because the memory that views
+/// point to was freed.
+pub fn invalidate_views() {
+    MEMORY_VERSION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Computes the layout on the calling thread without involving the web worker thread, stores the
+/// result in `output` and returns the container height.
+///
+/// Must not be called while a web worker thread computes the same layout. The main thread uses
+/// its own cancellation slot, so the computations of the web worker threads are not affected.
+pub fn compute_locally(
+    layout: &mut Layout,
+    output: &mut ComputeResult,
+    compute: impl FnOnce(&mut Layout) -> u32,
+) -> u32 {
+    layout.set_cancel_slot(0);
+    reset();
+    let (height, elapsed) = phase("masonry layout", || compute(layout));
+    *output = finish(height, elapsed, layout);
+    height
+}
+
+fn execute(computation: &Computation, slot: usize) {
+    let (layout, output) = {
         // SAFETY: Never use core::ptr::read. The returned value will be an owned value, which means
         // its destructor will be run at the end of the function. This will lead to a double free.
         // Instead we only get a mutable reference and have to depend on the user to `await` every
         // `Promise` returned from `MasonryWorker::compute`.
-        match unsafe { computation.layout_ptr.as_mut() } {
-            Some(layout) => (computation.width, computation.config, layout),
-            None => return 0,
+        match unsafe {
+            (
+                computation.layout_ptr.as_mut(),
+                computation.output_ptr.as_mut(),
+            )
+        } {
+            (Some(layout), Some(output)) => (layout, output),
+            _ => return,
         }
     };
-    layout.set_thumbnail_size(config.thumbnail_size);
-    layout.set_padding(config.padding);
+    layout.set_cancel_slot(slot);
+    layout.set_chunk_runner(CHUNK_RUNNER);
+    let operation = Operation {
+        name: if computation.remaining {
+            "the remaining layout"
+        } else {
+            "a layout"
+        },
+        num_items: layout.num_items(),
+        computed: Some((computation.width, computation.config)),
+    };
+    let (height, elapsed) = during(operation, || {
+        phase("masonry layout", || {
+            if computation.remaining {
+                layout.compute_remaining()
+            } else {
+                layout.compute(computation.width, &computation.config)
+            }
+        })
+    });
+    // The main thread computes the same layout locally at times, where waiting for helpers throws.
+    layout.set_chunk_runner(None);
+    // A cancelled computation leaves the output of the last completed one untouched.
+    if !is_slot_cancelled(slot) {
+        *output = finish(height, elapsed, layout);
+    }
+}
 
-    match config.kind {
-        MasonryType::Vertical => layout.compute_vertical(width),
-        MasonryType::Horizontal => layout.compute_horizontal(width),
-        MasonryType::Grid => layout.compute_grid(width),
+/// Collects the result of a computation that took `elapsed` milliseconds and publishes its
+/// transforms.
+fn finish(height: u32, elapsed: f64, layout: &mut Layout) -> ComputeResult {
+    let (result, stats_elapsed) = phase("masonry stats", || {
+        ComputeResult::new(height, elapsed, layout)
+    });
+    let (moved, publish_elapsed) = phase("masonry publish", || layout.publish());
+    if moved {
+        invalidate_views();
     }
+    ComputeResult {
+        stats_elapsed,
+        publish_elapsed,
+        ..result
+    }
+}
+
+/// Records the phases of every computation from now on with `performance.mark()` and
+/// `performance.measure()`, so that they show up in the performance panel of the developer tools, or
+/// stops recording them.
+pub fn set_instrumented(enabled: bool) {
+    INSTRUMENTED.store(enabled, Ordering::Relaxed);
+}
+
+/// Runs a phase of a computation and returns its result and how many milliseconds it took.
+///
+/// While the computations are instrumented, the phase is also recorded as a measure with the
+/// given name, on the timeline of the thread that runs it.
+fn phase<R>(name: &str, run: impl FnOnce() -> R) -> (R, f64) {
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = performance, js_name = mark)]
+        fn performance_mark(name: &str);
+        #[wasm_bindgen(js_namespace = performance, js_name = measure)]
+        fn performance_measure(name: &str, start_mark: &str);
+        #[wasm_bindgen(js_namespace = performance, js_name = clearMarks)]
+        fn performance_clear_marks(name: &str);
+    }
+
+    let instrumented = INSTRUMENTED.load(Ordering::Relaxed);
+    if instrumented {
+        performance_mark(name);
+    }
+    let start = now();
+    let result = run();
+    let elapsed = now() - start;
+    if instrumented {
+        // The measure keeps its own copy of the start, so the mark is not needed anymore.
+        performance_measure(name, name);
+        performance_clear_marks(name);
+    }
+    (result, elapsed)
 }
 
-fn atomic_wait32(atomic: &AtomicI32, expression: i32, timeout_ns: i64) -> i32 {
+/// Returns the current time in milliseconds, which is available on the main thread and in web
+/// workers.
+fn now() -> f64 {
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = performance, js_name = now)]
+        fn performance_now() -> f64;
+    }
+
+    performance_now()
+}
+
+pub(crate) fn atomic_wait32(atomic: &AtomicI32, expression: i32, timeout_ns: i64) -> i32 {
     unsafe { core::arch::wasm32::memory_atomic_wait32(atomic.as_mut_ptr(), expression, timeout_ns) }
 }
 
-fn atomic_notify(atomic: &AtomicI32, waiters: u32) -> u32 {
+pub(crate) fn atomic_notify(atomic: &AtomicI32, waiters: u32) -> u32 {
     unsafe { core::arch::wasm32::memory_atomic_notify(atomic.as_mut_ptr(), waiters) }
 }
 
-fn atomic_wait32_async(atomic: &AtomicI32, expression: i32) -> js_sys::Promise {
+/// How long to wait between two checks of an atomic in runtimes without `Atomics.waitAsync`.
+const POLL_INTERVAL: i32 = 1;
+
+fn atomic_wait32_async(atomic: &'static AtomicI32, expression: i32) -> js_sys::Promise {
     #[wasm_bindgen]
     extern "C" {
         type Atomics;
@@ -94,6 +657,9 @@ fn atomic_wait32_async(atomic: &AtomicI32, expression: i32) -> js_sys::Promise {
         #[wasm_bindgen(static_method_of = Atomics, js_name = waitAsync)]
         fn wait_async(buf: &js_sys::Int32Array, index: i32, value: i32) -> WaitAsyncResult;
 
+        #[wasm_bindgen(static_method_of = Atomics, getter, js_name = waitAsync)]
+        fn wait_async_function() -> JsValue;
+
         #[wasm_bindgen(method, getter, structural, js_name = async)]
         fn async_(this: &WaitAsyncResult) -> bool;
 
@@ -101,6 +667,13 @@ fn atomic_wait32_async(atomic: &AtomicI32, expression: i32) -> js_sys::Promise {
         fn value(this: &WaitAsyncResult) -> js_sys::Promise;
     }
 
+    // Deno, Node.js and some browsers do not implement `Atomics.waitAsync` (yet).
+    if !Atomics::wait_async_function().is_function() {
+        return js_sys::Promise::new(&mut |resolve, _reject| {
+            poll_atomic(atomic, expression, resolve);
+        });
+    }
+
     let result = unsafe {
         let buffer = js_sys::Int32Array::view_mut_raw(atomic.as_mut_ptr(), 1);
         Atomics::wait_async(&buffer, 0, expression)
@@ -112,6 +685,18 @@ fn atomic_wait32_async(atomic: &AtomicI32, expression: i32) -> js_sys::Promise {
     }
 }
 
+/// Resolves with `"ok"` like `Atomics.waitAsync` once the atomic no longer holds the given
+/// value, checking it again with `setTimeout` so that the event loop keeps running.
+fn poll_atomic(atomic: &'static AtomicI32, expression: i32, resolve: js_sys::Function) {
+    if atomic.load(Ordering::SeqCst) != expression {
+        // The result of the callback is irrelevant.
+        drop(resolve.call1(&JsValue::UNDEFINED, &JsValue::from_str("ok")));
+        return;
+    }
+    let retry = Closure::once_into_js(move || poll_atomic(atomic, expression, resolve));
+    set_timeout(&retry, POLL_INTERVAL);
+}
+
 /// Wrapper around `Cell` to make it possible to use in statics.
 struct Data<T>(Cell<T>);
 
@@ -123,10 +708,6 @@ impl<T> Data<T> {
     fn set(&self, value: T) {
         self.0.set(value);
     }
-
-    fn replace(&self, value: T) -> T {
-        self.0.replace(value)
-    }
 }
 
 impl<T: Copy> Data<T> {
@@ -135,5 +716,15 @@ impl<T: Copy> Data<T> {
     }
 }
 
+impl<T: Default> Data<T> {
+    /// Runs the closure on the value, which is taken out of the cell in the meantime.
+    fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut value = self.0.take();
+        let result = f(&mut value);
+        self.0.set(value);
+        result
+    }
+}
+
 /// Static values need to be sync.
 unsafe impl<T> Sync for Data<T> {}
@@ -10,52 +10,2158 @@
 */
 export function run(): void;
 /**
+* Starts a web worker that runs `worker.js`, which is a static asset next to this module.
+*
+* With Trusted Types enforced, the URL of the script is passed through the policy, or a function
+* that returns a `TrustedScriptURL` for it.
+*/
+export function createWorker(
+  policy?: { createScriptURL(url: string): unknown } | ((url: string) => unknown),
+): Worker;
+/**
+*/
+export enum MasonryType {
+  Vertical,
+  Horizontal,
+  Grid,
+  List,
+  Mosaic,
+  FixedRows,
+  Filmstrip,
+}
+/**
+*/
+export enum Direction {
+  Up,
+  Down,
+  Left,
+  Right,
+}
+/**
+* Where an item ends up in the viewport when it is scrolled to.
+*/
+export enum ScrollAlignment {
+  Top,
+  Center,
+  Bottom,
+}
+/**
+* How the last row of the horizontal layout is laid out if it is not full.
+*/
+export enum LastRowBehavior {
+  Left,
+  Justify,
+  Hide,
+}
+/**
+* How the scaled positions and sizes of the items are rounded to whole pixels.
+*/
+export enum RoundingMode {
+  Floor,
+  Round,
+  Accumulate,
+}
+/**
+* How finely the items of a timeline are grouped into sections, see
+* [`crate::timeline::timeline()`].
+*/
+export enum TimelineGranularity {
+  Day,
+  Month,
+  Year,
+}
+/**
+* An inclusive range of item indices.
+*/
+export interface ItemRange {
+  free(): void;
+/**
+*/
+  first: number;
+/**
+*/
+  last: number;
+}
+/**
+* The index of an item together with its tag, see [`crate::Layout::set_tag()`].
+*/
+export interface TaggedItem {
+  free(): void;
+/**
+*/
+  index: number;
+/**
+*/
+  tag: number;
+}
+/**
+* A day, month or year of a timeline and the run of items in it.
+*/
+export interface TimelineSection {
+  free(): void;
+/**
+* Start of the day, month or year in milliseconds since the Unix epoch, or `NaN` for the
+* items without a timestamp.
+*/
+  start: number;
+/**
+* Position of the first item of the section in the order of the timeline.
+*/
+  first: number;
+/**
+*/
+  len: number;
+}
+/**
+* The section header that sticks to the top of the viewport.
+*/
+export interface StickyHeader {
+  free(): void;
+/**
+*/
+  index: number;
+/**
+* How far the header is pushed up by the next header.
+*/
+  offset: number;
+}
+/**
+* How much memory a layout holds, e.g. to decide when to shrink it.
+*/
+export interface MemoryStats {
+  free(): void;
+/**
+* Number of items that are laid out.
+*/
+  num_items: number;
+/**
+* Number of items whose dimensions fit into the allocated memory.
+*/
+  item_capacity: number;
+/**
+* Number of items whose transforms fit into the allocated memory.
+*/
+  transform_capacity: number;
+/**
+* Bytes allocated for all per-item buffers together.
+*/
+  bytes: number;
+/**
+* Size of the whole WebAssembly memory in pages of 64 KiB, or 0 on other targets.
+*/
+  heap_pages: number;
+}
+/**
+* The layout after a batch of dimensions arrived, see [`crate::Layout::compute_arrivals()`].
+*/
+export interface HeightUpdate {
+  free(): void;
+/**
+* New container height.
+*/
+  height: number;
+/**
+* Position of the first item that was laid out again.
+*/
+  first: number;
+/**
+* Position from which the items kept their arrangement and only moved down by `offset`.
+* It is the item count if every item from `first` on was laid out again.
+*/
+  end: number;
+/**
+* How far the items from `end` on moved down, which is negative if they moved up.
+*/
+  offset: number;
+/**
+* Number of visible items that are still laid out with the default aspect ratio because
+* their dimension did not arrive yet.
+*/
+  remaining: number;
+}
+/**
+* A run of visible items that share their top, see [`crate::Layout::row_info()`].
+*/
+export interface RowInfo {
+  free(): void;
+/**
+*/
+  top: number;
+/**
+* Height of the tallest item in the row.
+*/
+  height: number;
+/**
+* Position of the first and last item in the row. Hidden items between them are included.
+*/
+  first: number;
+/**
+*/
+  last: number;
+}
+/**
+* A column of the vertical masonry or grid layout, see [`crate::Layout::column_info()`].
+*/
+export interface ColumnInfo {
+  free(): void;
+/**
+*/
+  left: number;
+/**
+*/
+  width: number;
+/**
+* Bottom of the lowest item in the column.
+*/
+  bottom: number;
+/**
+*/
+  num_items: number;
+}
+/**
+* Parameters of a computation. Lengths are in CSS pixels.
+*/
+export interface MasonryConfig {
+  free(): void;
+/**
+*/
+  kind: MasonryType;
+/**
+*/
+  thumbnail_size: number;
+/**
+* Horizontal space between the items
+*/
+  gap_x: number;
+/**
+* Vertical space between the items
+*/
+  gap_y: number;
+/**
+* Space between the items and the edges of the container
+*/
+  outer_padding: number;
+/**
+* Maximum width of the items together, or 0 if they fill the container
+*/
+  max_content_width: number;
+/**
+* Space below every thumbnail that is reserved for a caption
+*/
+  caption_height: number;
+/**
+* Width of the metadata next to the thumbnail in a row of the list layout, or 0 if the rows
+* fill the content width
+*/
+  metadata_width: number;
+/**
+* Number of physical pixels per CSS pixel. All lengths are in CSS pixels, while the layout
+* is computed in whole physical pixels.
+*/
+  device_pixel_ratio: number;
+/**
+* Thumbnail size at which the gaps are `gap_x` and `gap_y`, which scale proportionally with
+* the thumbnail size otherwise, or 0 if the gaps are fixed
+*/
+  gap_reference_size: number;
+/**
+* Smallest gap that scaling shrinks the gaps to
+*/
+  min_gap: number;
+/**
+* Largest gap that scaling grows the gaps to, or 0 for no limit
+*/
+  max_gap: number;
+/**
+* Height the rows of the horizontal layout aim for instead of the thumbnail size, or 0 to
+* use the thumbnail size
+*/
+  target_row_height: number;
+/**
+* Fraction of the target height by which a row of the horizontal layout may be higher to
+* end before the item that overflows it, or 0 if rows only ever shrink to fit
+*/
+  row_height_tolerance: number;
+}
+/**
+* Rectangle of an item and the crop of its thumbnail, see `MasonryWorker::transform()`.
+*/
+export interface Transform {
+  free(): void;
+/**
+*/
+  width: number;
+/**
+*/
+  height: number;
+/**
+*/
+  top: number;
+/**
+*/
+  left: number;
+/**
+* How far the thumbnail overhangs the rectangle at the top.
+*/
+  cropTop: number;
+/**
+* How far the thumbnail overhangs the rectangle on the left.
+*/
+  cropLeft: number;
+/**
+*/
+  cropped: boolean;
+}
+/**
+* What changed with an update of a marquee drag, see `MasonryWorker::drag_update()`.
+*/
+export interface DragUpdate {
+  free(): void;
+/**
+* Pixels to scroll by in this frame because the marquee reaches towards an edge of the
+* viewport, negative for up.
+*/
+  autoscrollDelta: number;
+/**
+* Indices of the items that the update selected.
+* @returns {Uint32Array}
+*/
+  readonly enteredIndices: Uint32Array;
+/**
+* Indices of the items that the update deselected because the marquee no longer covers them.
+* @returns {Uint32Array}
+*/
+  readonly leftIndices: Uint32Array;
+}
+/**
+* The layout split into pages, see `MasonryWorker::paginate()` and
+* `MasonryWorker::split_into()`.
+*/
+export interface Pagination {
+  free(): void;
+/**
+* @returns {number}
+*/
+  readonly pageCount: number;
+/**
+* Positions of the items on the page in reading order, or an empty array if there are fewer
+* pages.
+* @param {number} page
+* @returns {Uint32Array}
+*/
+  page_items(page: number): Uint32Array;
+/**
+* Height of the page up to the bottom of its lowest item and the outer padding, e.g. for the
+* height of a pane, or 0 if there are fewer pages.
+* @param {number} page
+* @returns {number}
+*/
+  page_height(page: number): number;
+/**
+* Transforms of the items on the page relative to the top of the page, in the order of
+* [`Pagination::page_items()`]. Every item takes seven numbers like
+* `MasonryWorker::export_transforms()`.
+* @param {number} page
+* @returns {Uint32Array}
+*/
+  page_transforms(page: number): Uint32Array;
+}
+/**
+*/
+export class MasonryWorker {
+  free(): void;
+/**
+* Creates a new worker from a worker that was initialized with the `worker.js` script.
+*
+* Any number of workers can be created, e.g. one per gallery. They have their own items and
+* settings but share the web worker thread, so only one web worker has to be started.
+* @param {number} num_items
+*/
+  constructor(num_items: number);
+/**
+* Creates a new worker and hands the memory and the compiled module to the given web worker,
+* which must run the `worker.js` script.
+*
+* The web worker is created by the caller, e.g. with
+* `new Worker(new URL('worker.js', import.meta.url), { type: 'module' })`, so that bundlers
+* can find the script and emit it as an asset. It instantiates the module that the main
+* thread compiled already instead of fetching and compiling it again.
+* @param {number} num_items
+* @param {Worker} worker
+* @returns {MasonryWorker}
+*/
+  static with_worker(num_items: number, worker: Worker): MasonryWorker;
+/**
+* Hands the memory and the compiled module to another web worker that runs the `worker.js` script, so
+* that it computes the layouts of other workers while the first one is busy.
+*
+* All workers share the web workers, however many workers are created. The web workers only
+* work on different layouts at the same time, so one per gallery that is shown at once is
+* enough. At most seven web workers join, additional ones stay idle.
+* @param {Worker} worker
+*/
+  add_worker(worker: Worker): void;
+/**
+* Frees the worker once the web worker thread no longer uses it and returns a `Promise` that
+* resolves afterwards.
+*
+* Unlike `free()`, this is safe while a computation is pending: the computation is cancelled
+* and the items are only freed after the web worker thread stopped working on them. If the
+* web worker is given, it is terminated as well and the shared state is reset, so that a new
+* worker can be created with [`MasonryWorker::with_worker()`] and a new web worker. The web
+* worker should only be given when disposing the last worker that uses it. Any method called
+* on the disposed worker throws an error.
+* @param {Worker | undefined} worker
+* @returns {Promise<any>}
+*/
+  dispose(worker?: Worker): Promise<any>;
+/**
+* Returns a `Promise` that resolves once the web worker thread has finished its
+* initialization and runs the `run` loop.
+*
+* Computations sent earlier are not lost, but awaiting this first avoids waiting for the
+* worker thread inside of the first [`MasonryWorker::compute()`] and makes startup failures
+* visible at a single place. Without `SharedArrayBuffer` (see [`crate::fallback`]), the web
+* worker does not run the `run` loop, and it resolves once the web worker was started.
+* @returns {Promise<any>}
+*/
+  ready(): Promise<any>;
+/**
+* Starts the web worker of a worker from [`crate::spawn::MasonryWorkerBuilder`] if it has
+* not been started yet, e.g. while the gallery is about to be shown, instead of with the first
+* computation. [`MasonryWorker::ready()`] and [`MasonryWorker::warm_up()`] start it as well.
+*/
+  start(): void;
+/**
+* Returns the web worker that was started for a worker from
+* [`crate::spawn::MasonryWorkerBuilder`], e.g. to terminate it with
+* [`MasonryWorker::dispose()`].
+* @returns {Worker | undefined}
+*/
+  spawned_worker(): Worker | undefined;
+/**
+* Gets the web worker thread ready for the first computation and returns a `Promise` that
+* resolves afterwards, e.g. while the library is still loading.
+*
+* Like [`MasonryWorker::ready()`] it waits until the web worker has instantiated the module,
+* and then lays out a small dummy layout on both threads, so that the first real computation
+* does not pay for starting up and delay the first paint of the gallery. The `Promise`
+* rejects like the one of [`MasonryWorker::compute()`]. Calling it again, or without
+* `SharedArrayBuffer`, only waits for [`MasonryWorker::ready()`].
+* @returns {Promise<any>}
+*/
+  warm_up(): Promise<any>;
+/**
+* Reports that the web worker thread failed, e.g. from its `onerror` and `onmessageerror`
+* handlers, or when `worker.js` posts an `{ error }` message because the WebAssembly module
+* could not be loaded.
+*
+* Pending and future `Promise`s of [`MasonryWorker::compute()`] and
+* [`MasonryWorker::ready()`] reject with an `Error` named `MasonryWorkerError` and the given
+* message, so callers can recover, e.g. by falling back to [`MasonryWorker::compute_sync()`].
+* @param {string} message
+*/
+  fail(message: string): void;
+/**
+* Prepares a replacement for a web worker thread that died, e.g. because it ran out of
+* memory or was terminated by the browser, and returns the `Promise` of
+* [`MasonryWorker::ready()`] for it.
+*
+* Report the failure with [`MasonryWorker::fail()`] first, which rejects the pending
+* computations, then call this and start a new web worker with `worker.js` and the same
+* memory. The items and their dimensions are kept, so the rejected computations can simply
+* be sent again once the `Promise` resolves. Web workers that were added with
+* [`MasonryWorker::add_worker()`] have to be terminated and replaced as well.
+* @returns {Promise<any>}
+*/
+  restart(): Promise<any>;
+/**
+* Computes the transforms of all items.
+*
+* If the item count does not exceed the threshold set with
+* [`MasonryWorker::set_sync_threshold()`], the layout is computed on the main thread instead.
+*
+* Calling this method again while a computation is still pending is allowed. The requests
+* are coalesced: the computation in progress is cancelled, only the most recent request is
+* computed and all pending `Promise`s resolve once it is finished. The computations of other
+* workers, e.g. of other galleries, share the web worker thread and are queued after it.
+*
+* The `Promise` resolves with a [`ComputeResult`], which holds the container height, how long
+* the layout took, its number of items, rows and columns, e.g. for a debug overlay, and the
+* first item whose transform may have changed. It rejects with an `Error` named
+* `MasonryConfigError` that names the offending parameter if the items cannot be laid out
+* with the arguments, e.g. because the padding is wider than the container (see
+* `Layout::check_config()`). Without items or with a width of 0 it resolves with a height of
+* 0 in every layout kind and the queries return no items. It rejects with an `Error` named
+* `MasonryTimeoutError` if the web worker thread does not finish in time (see
+* [`MasonryWorker::set_compute_timeout()`]).
+*
+* # Safety
+*
+* The returned `Promise` must be `await`ed. Calls to any method of [`MasonryWorker`] other
+* than `compute`, [`MasonryWorker::cancel_pending()`], [`MasonryWorker::is_busy()`] and
+* [`MasonryWorker::progress()`] while the `Promise` is still pending will lead to undefined
+* behaviour. As long as the value is `await`ed you can enjoy lock free concurrency.
+* @param {number} width
+* @param {MasonryType} kind
+* @param {number} thumbnail_size
+* @param {number} padding
+* @returns {Promise<any>}
+*/
+  compute(width: number, kind: MasonryType, thumbnail_size: number, padding: number): Promise<any>;
+/**
+* Computes the transforms of all items like [`MasonryWorker::compute()`] with the given
+* width and the kind, thumbnail size and padding of the most recent computation or
+* [`MasonryWorker::set_config()`], e.g. when the container is resized.
+*
+* Before either, the vertical masonry layout with the default thumbnail size and padding is
+* computed.
+* @param {number} width
+* @returns {Promise<any>}
+*/
+  compute_width(width: number): Promise<any>;
+/**
+* Computes the transforms of all items like [`MasonryWorker::compute()`] with the parameters
+* given in an object, e.g. `{ kind: MasonryType.Grid, thumbnailSize: 200, padding: 8 }`.
+*
+* The properties are named like those of [`MasonryConfig`] in camel case, and `padding`
+* sets both gaps unless `gapX` or `gapY` are given as well. Missing properties keep their
+* current values (see [`MasonryWorker::get_config()`]), and the given ones persist like
+* after [`MasonryWorker::set_config()`]. The `Promise` rejects with an `Error` named
+* `MasonryConfigError` if a property is not a number or the kind is unknown, and otherwise
+* like the one of [`MasonryWorker::compute()`].
+*
+* `priority` is `"user-blocking"` by default, or `"background"` for speculative work, e.g.
+* a layout that may be needed later. A user-blocking computation is taken before the ones
+* in the background and preempts one of them if every web worker thread is busy, which is
+* queued again once it stopped. The `Promise` of a computation in the background only waits
+* for the other ones in the background. The priority does not persist.
+* @param {number} width
+* @param {any} options
+* @returns {Promise<any>}
+*/
+  compute_with_options(width: number, options: any): Promise<any>;
+/**
+* Computes the transforms of all items like [`MasonryWorker::compute()`], but calls the
+* callback once it is done instead of returning a `Promise`, e.g. for frameworks with their
+* own scheduler.
+*
+* The callback is called with `null` and the [`ComputeResult`], or with the error that the
+* `Promise` of [`MasonryWorker::compute()`] would reject with, except that there is no
+* timeout (see [`MasonryWorker::set_compute_timeout()`]). Calls do not create a closure or
+* `Promise` each: the callbacks of a computation on the main thread are called in a
+* microtask after the call returned, and the ones of all computations that are sent to the
+* web worker thread in the meantime once it is done. The same rules as for
+* [`MasonryWorker::compute()`] apply until the callback was called.
+* @param {number} width
+* @param {MasonryType} kind
+* @param {number} thumbnail_size
+* @param {number} padding
+* @param {Function} callback
+*/
+  compute_with_callback(width: number, kind: MasonryType, thumbnail_size: number, padding: number, callback: Function): void;
+/**
+* Computes the transforms of all items like [`MasonryWorker::compute()`], but lays out the
+* items up to the given index on the main thread first, so that they can be rendered right
+* away.
+*
+* This makes e.g. changes of the thumbnail size feel instant in big libraries. `end` should
+* be the index after the last item in the viewport plus about one row of overscan, because
+* the last row before it is not full yet and can still change. The remaining items are laid
+* out by the web worker thread afterwards. In the meantime the transforms of the items before
+* `end` can be read, but apart from that the same rules as for [`MasonryWorker::compute()`]
+* apply until the returned `Promise` resolves with the result of the remaining items.
+*
+* A reversed layout (see [`MasonryWorker::set_reversed()`]) is computed like
+* [`MasonryWorker::compute()`], because its first rows depend on the last items.
+* @param {number} end
+* @param {number} width
+* @param {MasonryType} kind
+* @param {number} thumbnail_size
+* @param {number} padding
+* @returns {Promise<any>}
+*/
+  compute_visible_first(end: number, width: number, kind: MasonryType, thumbnail_size: number, padding: number): Promise<any>;
+/**
+* Lays out the items from `start` to `end` on the main thread like
+* [`MasonryWorker::compute_visible_first()`], keeping the transforms of the items before
+* `start`, e.g. because only the last section changed.
+*
+* The items before `start` must not have changed since the most recent computation, apart
+* from dimensions set with [`MasonryWorker::set_dimension()`], which are laid out again. If
+* the width or config differ from the most recent computation, all items up to `end` are laid
+* out. The remaining items are laid out by the web worker thread afterwards, and the same
+* rules as for [`MasonryWorker::compute_visible_first()`] apply, including the fallback for
+* reversed layouts.
+* @param {number} start
+* @param {number} end
+* @param {number} width
+* @param {MasonryType} kind
+* @param {number} thumbnail_size
+* @param {number} padding
+* @returns {Promise<any>}
+*/
+  compute_range(start: number, end: number, width: number, kind: MasonryType, thumbnail_size: number, padding: number): Promise<any>;
+/**
+* Computes the layouts with two thumbnail sizes, so that e.g. a pinch zoom can animate
+* continuously between them with [`MasonryWorker::interpolated_transforms()`] instead of
+* jumping from one to the other.
+*
+* The layout is computed with `from_size` like [`MasonryWorker::compute()`] and a copy of the
+* items with `to_size` and the same width, kind and padding. A precomputed zoom level or a
+* cached computation with `to_size` is taken over as the copy (see
+* [`MasonryWorker::set_zoom_levels()`] and [`MasonryWorker::set_cache_size()`]), and a later
+* computation with `to_size` takes over the copy and resolves right away, e.g. once the
+* gesture ends. The returned `Promise` resolves with the result of `from_size` once both are
+* computed. It rejects like the one of [`MasonryWorker::compute()`], and if a computation of
+* this worker is still pending.
+* @param {number} width
+* @param {MasonryType} kind
+* @param {number} from_size
+* @param {number} to_size
+* @param {number} padding
+* @returns {Promise<any>}
+*/
+  interpolate(width: number, kind: MasonryType, from_size: number, to_size: number, padding: number): Promise<any>;
+/**
+* Returns the transforms of the items from `start` to `end` the fraction `t` between the two
+* layouts of [`MasonryWorker::interpolate()`], from the one with `from_size` at 0 to the one
+* with `to_size` at 1.
+*
+* Every item takes four numbers: the width, height, top and left. They are not rounded, so
+* that the items move smoothly. Items that are hidden in one of the layouts keep the transform
+* of the other one. Without an up-to-date copy, e.g. because the items changed since, the
+* transforms of the layout are returned as they are. It must not be called while a `Promise`
+* returned by [`MasonryWorker::interpolate()`] is still pending.
+* @param {number} t
+* @param {number} start
+* @param {number} end
+* @returns {Float32Array}
+*/
+  interpolated_transforms(t: number, start: number, end: number): Float32Array;
+/**
+* Returns the container height the fraction `t` between the heights of the two layouts of
+* [`MasonryWorker::interpolate()`], like [`MasonryWorker::interpolated_transforms()`].
+* @param {number} t
+* @returns {number}
+*/
+  interpolated_height(t: number): number;
+/**
+* Drops the copy of the layout that [`MasonryWorker::interpolate()`] computed, e.g. because
+* the pinch zoom was cancelled, and cancels its computation if it is still pending.
+*/
+  end_interpolation(): void;
+/**
+* Computes the layout like [`MasonryWorker::compute()`] but keeps the transforms of the most
+* recent computation, so that the items can be animated to their new spots, e.g. when
+* switching between the grid and the vertical masonry layout.
+*
+* Once the returned `Promise` resolved, [`MasonryWorker::get_transition_pair()`] returns the
+* old and the new transform of every item and [`MasonryWorker::transition_height()`] the
+* container height that fits both, until [`MasonryWorker::end_transition()`] drops the old
+* transforms. If the most recent computation did not run to completion, there are no old
+* transforms and the items start at their new spots. The `Promise` rejects like the one of
+* [`MasonryWorker::compute()`], and if a computation of this worker is still pending.
+* @param {number} width
+* @param {MasonryType} kind
+* @param {number} thumbnail_size
+* @param {number} padding
+* @returns {Promise<any>}
+*/
+  transition(width: number, kind: MasonryType, thumbnail_size: number, padding: number): Promise<any>;
+/**
+* Returns the transform of the item before and after [`MasonryWorker::transition()`].
+*
+* The array contains the width, height, top and left of the old transform followed by the
+* same values of the new transform, like [`MasonryWorker::get_transform_pair()`]. Items that
+* are hidden in one of the layouts have an empty transform there. The old transform is equal
+* to the new one if there is no transition or the items or settings changed since it started.
+* Returns an empty array if the index is out of bounds. It must not be called while the
+* `Promise` returned by [`MasonryWorker::transition()`] is still pending.
+* @param {number} index
+* @returns {Uint32Array}
+*/
+  get_transition_pair(index: number): Uint32Array;
+/**
+* Returns the larger of the container heights before and after
+* [`MasonryWorker::transition()`], so that no item is cut off while it moves, or the current
+* height if there is no transition.
+* @returns {number}
+*/
+  transition_height(): number;
+/**
+* Drops the transforms that [`MasonryWorker::transition()`] kept, e.g. once the animation
+* finished.
+*/
+  end_transition(): void;
+/**
+* Computes the transforms of all items on the main thread and returns the container height.
+*
+* For small item counts this is faster than waking up the web worker thread. Throws the same
+* error as [`MasonryWorker::compute()`] rejects with if the arguments are invalid. It must not
+* be called while a `Promise` returned by [`MasonryWorker::compute()`] is still pending.
+* @param {number} width
+* @param {MasonryType} kind
+* @param {number} thumbnail_size
+* @param {number} padding
+* @returns {number}
+*/
+  compute_sync(width: number, kind: MasonryType, thumbnail_size: number, padding: number): number;
+/**
+* Estimates the container height without laying out the items, e.g. to size the scrollbar
+* while the dimensions are still loading.
+*
+* The estimate is extrapolated from the average aspect ratio of the items whose dimension
+* is known, or from the default aspect ratio (see
+* [`MasonryWorker::set_default_aspect_ratio()`]) if there are none. It must not be called
+* while a `Promise` returned by [`MasonryWorker::compute()`] is still pending.
+* @param {number} width
+* @param {MasonryType} kind
+* @param {number} thumbnail_size
+* @param {number} padding
+* @returns {number}
+*/
+  estimate_height(width: number, kind: MasonryType, thumbnail_size: number, padding: number): number;
+/**
+* Returns the layout kind that suits the aspect ratios of the visible items best, e.g. for an
+* "Auto" view mode.
+*
+* Mostly square items get the grid, mostly landscape items the horizontal layout and portraits
+* or a mix of sizes the vertical masonry layout. Items whose dimension is not known yet are
+* ignored. It must not be called while a `Promise` returned by [`MasonryWorker::compute()`]
+* is still pending.
+* @returns {MasonryType}
+*/
+  suggest_layout(): MasonryType;
+/**
+* Lays out only the items that were added with [`MasonryWorker::resize()`] since the most
+* recent computation and returns the new container height.
+*
+* This is meant for items streaming in, e.g. during an import, and runs on the main thread
+* because only the new items (and the last row of a horizontal layout) are computed. The
+* width and config of the most recent computation are reused. Changed dimensions of items
+* that were already computed are not taken into account. It must not be called while a
+* `Promise` returned by [`MasonryWorker::compute()`] is still pending.
+* @returns {number}
+*/
+  compute_appended(): number;
+/**
+* Adds `count` items at the end, e.g. the next page of a remote library, lays them out below
+* the existing content and returns the new container height.
+*
+* The new items get the default aspect ratio as an estimate until their dimensions are set
+* with [`MasonryWorker::set_dimension()`], so the scrollbar grows right away without
+* computing the existing items again. [`MasonryWorker::compute_incremental()`] then only lays
+* out the items from the first one with a new dimension on. Like
+* [`MasonryWorker::compute_appended()`] it runs on the main thread, reuses the width and
+* config of the most recent computation and must not be called while a `Promise` returned by
+* [`MasonryWorker::compute()`] is still pending.
+* @param {number} count
+* @returns {number}
+*/
+  append(count: number): number;
+/**
+* Lays out the items again, starting at the first item whose dimension changed since the
+* most recent computation, and returns the new container height.
+*
+* Everything before that item is reused, which makes this a lot faster than a full
+* computation when e.g. the dimensions of a few items at the end are updated. Items that
+* were added with [`MasonryWorker::resize()`] are laid out as well. The width and config of
+* the most recent computation are reused. It must not be called while a `Promise` returned
+* by [`MasonryWorker::compute()`] is still pending.
+* @returns {number}
+*/
+  compute_incremental(): number;
+/**
+* Sets the dimensions of a batch of items that arrived, e.g. while an import reads the files,
+* as triples of index, width and height, and lays out the part of the layout they affect.
+*
+* Items whose dimension did not arrive yet are laid out with the default aspect ratio (see
+* [`MasonryWorker::set_default_aspect_ratio()`]). Every batch returns the next update of the
+* height: the items from `first` up to `end` were laid out again and the ones from `end` on
+* only moved down by `offset`, so they can be translated instead of rendered again. The
+* horizontal layout usually only lays out a few rows per batch, the other kinds lay out
+* every item from the first arrival on. The sequence of updates ends once `remaining` is 0.
+* Like [`MasonryWorker::compute_incremental()`] it runs on the main thread and reuses the
+* width and config of the most recent computation. Throws if the length of the batch is not
+* a multiple of 3 or an index is out of bounds, or an `Error` whose `code` is `Busy` while a
+* `Promise` returned by [`MasonryWorker::compute()`] is still pending, in which case nothing
+* is set.
+* @param {Uint32Array} batch
+* @returns {HeightUpdate}
+*/
+  stream_dimensions(batch: Uint32Array): HeightUpdate;
+/**
+* Lays out items that were prepended with [`MasonryWorker::insert()`] at index 0 above the
+* existing content and returns how far the existing items moved down, or to the right in a
+* filmstrip.
+*
+* This is meant for loading an earlier page: the items that were already laid out keep their
+* arrangement and are only shifted, so adding the returned offset to `scrollTop`, or
+* `scrollLeft` of a filmstrip, in the same frame keeps the viewport stationary. The new container height is the previous one plus the
+* offset. The dimensions of the `count` new items have to be set before. The width and config
+* of the most recent computation are reused. It runs on the main thread and must not be
+* called while a `Promise` returned by [`MasonryWorker::compute()`] is still pending. Throws
+* an `IndexOutOfBounds` error if fewer than `count` items were inserted at index 0 since.
+* @param {number} count
+* @returns {number}
+*/
+  compute_prepended(count: number): number;
+/**
+* Enables or disables keeping the transforms of the previous computation.
+*
+* This is needed for [`MasonryWorker::changed_items()`]. It is disabled by default because
+* it copies all transforms before every computation.
+* @param {boolean} enabled
+*/
+  set_track_changes(enabled: boolean): void;
+/**
+* Enables or disables reading the transforms of the last completed computation in
+* [`MasonryWorker::get_transform()`] while the web worker thread computes the next one.
+*
+* Without it, a renderer that reads the transforms during a computation can see a mix of old
+* and new positions. It is disabled by default because it copies all transforms after every
+* computation. It must not be called while a `Promise` returned by
+* [`MasonryWorker::compute()`] is still pending.
+* @param {boolean} enabled
+*/
+  set_double_buffered(enabled: boolean): void;
+/**
+* Returns the indices of the items whose transform changed in the most recent computation.
+*
+* Items that were not rendered before count as changed. Returns an empty array if changes
+* are not tracked (see [`MasonryWorker::set_track_changes()`]).
+* @returns {Uint32Array}
+*/
+  changed_items(): Uint32Array;
+/**
+* Returns the previous and the new transform of the item at the given index, e.g. for FLIP
+* animations.
+*
+* The array contains the width, height, top and left of the previous transform followed by
+* the same values of the new transform. If the item was not computed before or changes are
+* not tracked (see [`MasonryWorker::set_track_changes()`]), the previous transform is equal
+* to the new one. Returns an empty array if the index is out of bounds.
+* @param {number} index
+* @returns {Uint32Array}
+*/
+  get_transform_pair(index: number): Uint32Array;
+/**
+* Returns the `scrollTop` that keeps the anchor item visually stationary after a computation.
+*
+* `previous_offset_within_item` is the distance between the top of the anchor item and the
+* `scrollTop` before the computation. It is scaled with the height of the item if changes are
+* tracked (see [`MasonryWorker::set_track_changes()`]). Returns 0 if the index is out of
+* bounds.
+* @param {number} anchor_index
+* @param {number} previous_offset_within_item
+* @returns {number}
+*/
+  anchor_offset(anchor_index: number, previous_offset_within_item: number): number;
+/**
+* Returns the `scrollTop` that brings the item to the top, center or bottom of a viewport of
+* the given height, e.g. to reveal the selected image in any layout kind.
+*
+* The offset is never negative and not clamped to the container height, which the browser
+* does by itself. Returns `undefined` if the index is out of bounds or the item is hidden.
+* @param {number} index
+* @param {ScrollAlignment} alignment
+* @param {number} viewport_height
+* @returns {number | undefined}
+*/
+  offset_of(index: number, alignment: ScrollAlignment, viewport_height: number): number | undefined;
+/**
+* Returns the `scrollLeft` that brings the item to the left, center or right of a viewport
+* of the given width, like [`MasonryWorker::offset_of()`] for strips that scroll
+* horizontally. `Top` aligns the left edges and `Bottom` the right ones.
+* @param {number} index
+* @param {ScrollAlignment} alignment
+* @param {number} viewport_width
+* @returns {number | undefined}
+*/
+  offset_of_x(index: number, alignment: ScrollAlignment, viewport_width: number): number | undefined;
+/**
+* Returns a coarse profile of the layout for a minimap next to the scrollbar, with two
+* numbers for each of `buckets` equal bands from the top to the bottom of the layout.
+*
+* The first number of a band is the fraction that thumbnails cover, from 0 to 1, so dense
+* regions and gaps can be drawn without reading every transform. The second is 1 if a
+* section header starts in the band and 0 otherwise. All numbers are 0 before the first
+* computation.
+* @param {number} buckets
+* @returns {Float32Array}
+*/
+  minimap(buckets: number): Float32Array;
+/**
+* Returns the position of the section header for each of `buckets` equal bands from the top
+* to the bottom of the layout, e.g. to label the track of a fast-scroll scrubber with dates
+* or folder names.
+*
+* A band gets the header whose section it starts in, and the bands above the first header
+* get the first one. The positions can be mapped to labels with
+* [`MasonryWorker::timeline_section()`], [`MasonryWorker::get_tag()`] or
+* [`MasonryWorker::item_index()`]. The result is empty without section headers or before the
+* first computation.
+* @param {number} buckets
+* @returns {Uint32Array}
+*/
+  scrubber_labels(buckets: number): Uint32Array;
+/**
+* Returns the scroll offset at which the section of the header at the given position starts,
+* e.g. to scroll to the label that the scrubber was released on, or `undefined` if the item
+* at the position is not a section header.
+* @param {number} label
+* @returns {number | undefined}
+*/
+  offset_for_label(label: number): number | undefined;
+/**
+* Sets a breakpoint table, so the layout picks its parameters for the current width itself.
+*
+* Every breakpoint consists of four numbers: the minimum container width, the thumbnail
+* size, the padding (the gap between the items in both directions) and the number of columns
+* (0 to derive it from the thumbnail size). The breakpoint with the largest minimum width
+* that fits the container replaces the thumbnail size and padding passed to
+* [`MasonryWorker::compute()`] and the column count set with
+* [`MasonryWorker::set_column_count()`]. A trailing incomplete breakpoint is ignored and an
+* empty table removes all breakpoints.
+* @param {Uint32Array} table
+*/
+  set_breakpoints(table: Uint32Array): void;
+/**
+* Collapses the gaps of containers narrower than the given width, e.g. a side panel, or
+* never with 0.
+*
+* Below the width the gaps step down from the padding passed to
+* [`MasonryWorker::compute()`] or a breakpoint, e.g. 8 to 4 and then 2 once the container is
+* half as wide, and the vertical masonry and grid layout keep at least two columns when the
+* number of columns is derived from the thumbnail size.
+* @param {number} width
+*/
+  set_gap_collapse_width(width: number): void;
+/**
+* Sets the number of columns of the vertical masonry and grid layout.
+*
+* By default (0) the number of columns is derived from the container width and the
+* thumbnail size. With an explicit count the layout always has exactly that many columns and
+* the thumbnail size passed to [`MasonryWorker::compute()`] is ignored by these layouts; it
+* follows from the container width instead.
+* @param {number} column_count
+*/
+  set_column_count(column_count: number): void;
+/**
+* Limits the number of columns of the vertical masonry and grid layout.
+*
+* The number of columns that is derived from the thumbnail size is clamped, so that e.g. a
+* narrow window never collapses to a single giant column (`min_columns`) and an ultrawide
+* monitor does not produce lots of tiny columns (`max_columns`). The columns are widened or
+* narrowed to fill the container width. A limit of 0, the default, means no limit. An
+* explicit count set with [`MasonryWorker::set_column_count()`] is not limited.
+* @param {number} min_columns
+* @param {number} max_columns
+*/
+  set_column_limits(min_columns: number, max_columns: number): void;
+/**
+* Limits the aspect ratio (width / height) the items are laid out with.
+*
+* Extremely tall screenshots and wide panoramas are laid out as if they were only as narrow
+* as `min_aspect_ratio` or as wide as `max_aspect_ratio`, and center-cropped to fit. The crop
+* offsets of the [`Transform`] tell how far the thumbnail is shifted inside its rectangle.
+* A limit of 0 means no limit. By default items are at most 3 times as tall as wide or vice
+* versa. The next computation lays out all items again.
+* @param {number} min_aspect_ratio
+* @param {number} max_aspect_ratio
+*/
+  set_aspect_ratio_limits(min_aspect_ratio: number, max_aspect_ratio: number): void;
+/**
+* Sets the aspect ratio (width / height) of items whose dimension is not known yet.
+*
+* Items with a dimension of 0×0, or whose dimension was not set at all, are laid out as
+* placeholders with this aspect ratio, so the gallery can render a stable skeleton while the
+* thumbnails load. It is 1 (square) by default. The next computation lays out all items
+* again.
+* @param {number} default_aspect_ratio
+*/
+  set_default_aspect_ratio(default_aspect_ratio: number): void;
+/**
+* Gives panoramas a row of their own that spans the full width.
+*
+* Items that are wider than the given aspect ratio (width / height) are laid out like items
+* marked with [`MasonryWorker::set_full_width()`] in all layouts, and are scaled to fit
+* instead of being cropped to the aspect ratio limits. An aspect ratio of 0, the default,
+* disables it. The next computation lays out all items again.
+* @param {number} panorama_aspect_ratio
+*/
+  set_panorama_aspect_ratio(panorama_aspect_ratio: number): void;
+/**
+* Caps the height of the rows in the horizontal layout, e.g. for rows of portrait images.
+*
+* Items that would be taller are center-cropped instead of making the row higher. How far
+* the thumbnail is shifted up inside its rectangle is reported as the crop offset of the
+* [`Transform`]. A height of 0 disables the cap, which is the default. The next computation
+* lays out all items again.
+* @param {number} max_row_height
+*/
+  set_max_row_height(max_row_height: number): void;
+/**
+* Sets how the last row of the horizontal layout is laid out if it is not full.
+*
+* By default it is left-aligned at the thumbnail size. It can also be stretched to the
+* container width like the other rows (the row height is capped by
+* [`MasonryWorker::set_max_row_height()`]) or hidden, in which case its items get an empty
+* transform and the container height ends above it.
+* @param {LastRowBehavior} last_row
+*/
+  set_last_row_behavior(last_row: LastRowBehavior): void;
+/**
+* Sets how the items of the horizontal layout are rounded to whole pixels.
+*
+* The rows are scaled to fill the container width, which results in fractional positions
+* and sizes. By default they are rounded down, which can leave a 1px gap between adjacent
+* items at some container widths. They can also be rounded to the nearest pixel, or their
+* edges can be rounded instead of their sizes, so that the rounding error is carried over to
+* the next item in the row. The next computation lays out all items again.
+* @param {RoundingMode} rounding
+*/
+  set_rounding_mode(rounding: RoundingMode): void;
+/**
+* Enables or disables balancing the rows of the horizontal layout.
+*
+* By default rows are filled one after another, which occasionally produces very short or
+* very tall rows. When enabled, the row breaks are chosen so that the row heights deviate as
+* little as possible from the thumbnail size across the whole gallery. This is slower, and
+* [`MasonryWorker::compute_appended()`] and [`MasonryWorker::compute_incremental()`] lay out
+* all items again.
+* @param {boolean} enabled
+*/
+  set_balance_rows(enabled: boolean): void;
+/**
+* Enables or disables moving an item into the last row of the horizontal layout if it would
+* hold a single item otherwise, e.g. after a filter change.
+*
+* The last item of the row before moves down and the rest of that row is scaled up to fill
+* the width again. Only rows of at least three items give one away, and rows are not
+* rebalanced across row breaks and full-width items. Disabled by default.
+* @param {boolean} enabled
+*/
+  set_avoid_widows(enabled: boolean): void;
+/**
+* Lays out the gallery bottom-up, e.g. for chronological feeds.
+*
+* The last items sit at the bottom right and the row (or the ragged column ends) that is not
+* full is at the top. The transforms are still measured from the top, so rendering stays the
+* same; keeping the view anchored at the bottom is a matter of scrolling to the new container
+* height. Since appended items change the whole layout, [`MasonryWorker::compute_appended()`]
+* and [`MasonryWorker::compute_incremental()`] lay out all items again. Column balancing
+* (see [`MasonryWorker::set_balance_columns()`]) has no effect in this mode, and neither has
+* this on a filmstrip.
+* @param {boolean} reversed
+*/
+  set_reversed(reversed: boolean): void;
+/**
+* Enables or disables evening out the bottom edge of the vertical masonry layout.
+*
+* Without it, the column heights can differ by up to a full thumbnail at the end. When
+* enabled, the last items (at most one per column) are moved between the columns to minimize
+* the difference between the highest and the lowest column, so they may end up slightly out
+* of order.
+* @param {boolean} enabled
+*/
+  set_balance_columns(enabled: boolean): void;
+/**
+* Keeps the items of the vertical masonry layout in their previous column when the width
+* changes a little, unless that column is more than `max_penalty` pixels lower than the
+* shortest one.
+*
+* Without it, every item goes into the shortest column, so resizing the window by a few
+* pixels can shuffle most items between the columns. With a penalty of about half a
+* thumbnail, the items mostly stay in their column as long as the number of columns does not
+* change, at the cost of less even columns. It only applies if the most recent computation
+* was a vertical masonry layout as well. 0 disables it, which is the default.
+* @param {number} max_penalty
+*/
+  set_column_stability(max_penalty: number): void;
+/**
+* Sizes the cells of the grid and mosaic layouts to the median aspect ratio of the items
+* instead of squares, e.g. 3:2 cells for a library of landscape photos.
+*
+* Mostly portrait or landscape libraries then waste less space and crop less of the
+* thumbnails. Items that are not measured yet, hidden and full-width items do not count.
+* The median is updated in every computation, and all items are laid out again when it
+* changes. Disabled by default.
+* @param {boolean} enabled
+*/
+  set_median_cells(enabled: boolean): void;
+/**
+* Lays out every `period`th item of `MasonryType.Mosaic` as a large cell that spans two
+* columns and two rows, e.g. 5 for one large cell in every five items.
+*
+* A large cell only goes where it fits into the current row, otherwise the next item that
+* fits gets it, so the small items always fill the cells around it without holes. 0 lays out
+* all items as small cells like the grid. The default is 5.
+* @param {number} period
+*/
+  set_mosaic_period(period: number): void;
+/**
+* Fills `MasonryType.Grid` column by column instead of row by row, so that the order of the
+* items, and with it the DOM and tab order, runs from top to bottom and then to the next
+* column.
+*
+* The items between two items that span the full width or break before fill as few rows as
+* they fit into, so the last column can end early. Spans are ignored in this order.
+* @param {boolean} enabled
+*/
+  set_column_major(enabled: boolean): void;
+/**
+* Sets all parameters of the computations at once, which persist until they are changed.
+*
+* The kind, thumbnail size and `gap_x` replace the arguments of
+* [`MasonryWorker::compute_width()`], and the other fields call the setters like
+* [`MasonryWorker::set_gaps()`] and [`MasonryWorker::set_caption_height()`]. Equal gaps
+* leave the padding to the arguments of [`MasonryWorker::compute()`], like before any call to
+* [`MasonryWorker::set_gaps()`].
+* @param {MasonryConfig} config
+*/
+  set_config(config: MasonryConfig): void;
+/**
+* Sets the thumbnail size that [`MasonryWorker::compute_width()`] computes with, without
+* computing anything yet.
+*
+* Several parameters can be changed in a row, e.g. together with
+* [`MasonryWorker::set_padding()`], and are laid out by a single computation afterwards.
+* Until then the transforms and queries stay those of the most recent computation, and
+* [`MasonryWorker::compute_incremental()`] keeps its parameters.
+* @param {number} thumbnail_size
+*/
+  set_thumbnail_size(thumbnail_size: number): void;
+/**
+* Sets the padding that [`MasonryWorker::compute_width()`] computes with, without computing
+* anything yet, like [`MasonryWorker::set_thumbnail_size()`].
+*
+* The padding is the gap between the items in both directions, unless
+* [`MasonryWorker::set_gaps()`] replaced it.
+* @param {number} padding
+*/
+  set_padding(padding: number): void;
+/**
+* Returns the parameters that [`MasonryWorker::compute_width()`] computes with, see
+* [`MasonryWorker::set_config()`].
+* @returns {MasonryConfig}
+*/
+  get_config(): MasonryConfig;
+/**
+* Sets the space between the items and the edges of the container.
+*
+* By default the padding passed to [`MasonryWorker::compute()`] is the gap between the items
+* in both directions and there is no space around them. Once set, `gap_x` and `gap_y` replace
+* that padding, e.g. for designs with a tighter vertical rhythm than horizontal spacing. The
+* outer padding is added on every side of the container, including the bottom of the
+* returned container height.
+* @param {number} gap_x
+* @param {number} gap_y
+* @param {number} outer_padding
+*/
+  set_gaps(gap_x: number, gap_y: number, outer_padding: number): void;
+/**
+* Scales the gaps between the items proportionally with the thumbnail size, so that zooming
+* out does not leave gaps that are large compared to tiny thumbnails.
+*
+* The padding or gaps apply at the reference thumbnail size and both directions are scaled
+* by the same factor at other sizes, rounded to whole pixels and kept between `min_gap` and
+* `max_gap` (0 for no limit). The outer padding stays fixed. A reference size of 0, the
+* default, keeps the gaps fixed.
+* @param {number} reference_size
+* @param {number} min_gap
+* @param {number} max_gap
+*/
+  set_gap_scaling(reference_size: number, min_gap: number, max_gap: number): void;
+/**
+* Sets the height the rows of the horizontal layout aim for instead of the thumbnail size,
+* e.g. high rows for a hero-like view or low ones for a dense contact sheet, while the other
+* kinds keep the thumbnail size.
+*
+* A row that would have to shrink to make room for one more item ends before that item
+* instead if it is at most `tolerance` times the target higher then, e.g. 0.2 for 20%, and
+* closer to the target than shrunk. A target of 0, the default, uses the thumbnail size and
+* a tolerance of 0 always shrinks the rows. Balanced rows already keep as close to the
+* target as they can.
+* @param {number} target_row_height
+* @param {number} tolerance
+*/
+  set_row_height(target_row_height: number, tolerance: number): void;
+/**
+* Limits the width of the area the items are laid out in.
+*
+* When the container is wider (apart from the outer padding, see
+* [`MasonryWorker::set_gaps()`]), the items are centered in it. The transforms already
+* include the offset, which is returned by [`MasonryWorker::content_left()`] for positioning
+* other elements like headers. A width of 0, the default, means no limit.
+* @param {number} max_content_width
+*/
+  set_max_content_width(max_content_width: number): void;
+/**
+* Reserves space below every thumbnail for a caption, e.g. the filename or tags.
+*
+* The height of every transform includes the caption, so the thumbnail fills the top
+* `height - caption_height` pixels and the caption is rendered below it without overlapping
+* the next row. Crops (see [`MasonryWorker::set_max_row_height()`]) only apply to the
+* thumbnail. The default is 0.
+* @param {number} caption_height
+*/
+  set_caption_height(caption_height: number): void;
+/**
+* Reserves space for the metadata next to the thumbnail in every row of the list layout.
+*
+* A row is then as wide as the thumbnail size, the horizontal gap and the metadata width
+* together, or the width of the content if that is narrower. The default is 0, which makes
+* every row fill the content width.
+* @param {number} metadata_width
+*/
+  set_metadata_width(metadata_width: number): void;
+/**
+* Snaps the layout to physical pixels, e.g. `window.devicePixelRatio`.
+*
+* With fractional ratios like 1.25 or 1.5, positions in whole CSS pixels fall between
+* physical pixels, which blurs the thumbnails and leaves 1px seams between them. With a
+* ratio other than 1, the widths and sizes passed to the computations stay in CSS pixels,
+* but the layout is computed in whole physical pixels: the transforms, container heights,
+* [`MasonryWorker::content_left()`] and the coordinates of all queries are in physical
+* pixels, so they must be divided by the ratio for CSS. The default is 1. The next
+* computation lays out all items again.
+* @param {number} device_pixel_ratio
+*/
+  set_device_pixel_ratio(device_pixel_ratio: number): void;
+/**
+* Returns how far the items are inset from the left edge of the container in the most
+* recent computation, including the outer padding.
+* @returns {number}
+*/
+  content_left(): number;
+/**
+* Sizes the columns so that they span exactly the full width.
+*
+* The thumbnail size rarely divides the container width, so by default the vertical masonry
+* and grid layout leave a gap after the last column. With auto-fit the columns are widened or
+* narrowed to cover it, which leaves less than a pixel per column. The cell size that was
+* used is returned by [`MasonryWorker::resolved_thumbnail_size()`]. The next computation
+* lays out all items again.
+* @param {boolean} auto_fit
+*/
+  set_auto_fit(auto_fit: boolean): void;
+/**
+* Returns the size the thumbnails were laid out with in the most recent computation.
+*
+* This is the width of the columns without the horizontal gap in the vertical masonry and
+* grid layout, and the base row height in the horizontal layout.
+* @returns {number}
+*/
+  resolved_thumbnail_size(): number;
+/**
+* Set the item count up to which [`MasonryWorker::compute()`] runs on the main thread.
+*
+* The returned `Promise` is already resolved in that case. The default is 0, which means
+* that every computation is sent to the web worker thread.
+* @param {number} num_items
+*/
+  set_sync_threshold(num_items: number): void;
+/**
+* Sets the width difference in CSS pixels below which [`MasonryWorker::compute()`] and its
+* variants keep the transforms of the most recent computation and resolve right away with
+* its result.
+*
+* This only applies while nothing changed since that computation: the config has to be the
+* same and no item may have been added, removed or changed. The items then keep their places
+* in a container that got slightly wider or narrower, e.g. because a scrollbar appeared or
+* disappeared, instead of being laid out again. The difference is measured from the width
+* the transforms were computed with, so several small resizes in a row add up. The default
+* is 0, which computes every request.
+* @param {number} threshold
+*/
+  set_resize_threshold(threshold: number): void;
+/**
+* Rejects the `Promise`s of [`MasonryWorker::compute()`] and
+* [`MasonryWorker::compute_visible_first()`] with an `Error` named `MasonryTimeoutError` if
+* the web worker thread does not finish within the given number of milliseconds.
+*
+* This signals a web worker thread that hangs or missed a notification instead of leaving
+* the gallery frozen. The computation is not cancelled, so the layout must still be treated
+* like one whose computation is pending. If the web worker thread hangs, report it with
+* [`MasonryWorker::fail()`] and replace it with [`MasonryWorker::restart()`]. A timeout of 0
+* waits forever, which is the default.
+* @param {number} timeout_ms
+*/
+  set_compute_timeout(timeout_ms: number): void;
+/**
+* Computes the layouts of the neighboring zoom levels in the background, so that switching
+* to one of them is instant.
+*
+* The thumbnail sizes are the steps of the zoom slider. [`MasonryWorker::compute()`] and
+* [`MasonryWorker::compute_visible_first()`] send copies of the items with the next smaller
+* and the next larger size to the web worker threads, which lay them out with the same width,
+* kind and padding once no other computation is waiting. A later computation with one of
+* these sizes takes over the precomputed transforms and resolves right away, unless the items
+* or settings changed in the meantime. Every zoom level keeps its own copy of the items and
+* transforms. An empty array disables it, which is the default.
+* @param {Uint32Array} thumbnail_sizes
+*/
+  set_zoom_levels(thumbnail_sizes: Uint32Array): void;
+/**
+* Keeps the layouts of up to the given number of earlier computations, so that going back to
+* a previous width or config is instant, e.g. when the window is resized back and forth.
+*
+* Before [`MasonryWorker::compute()`], [`MasonryWorker::compute_visible_first()`] or
+* [`MasonryWorker::compute_sync()`] computes with other arguments than the most recent
+* computation, a copy of the items and transforms of that computation is kept. A later
+* computation with the same width, kind, thumbnail size, padding and settings takes it over
+* and resolves right away. The copies that were used least recently are dropped first, and
+* all of them are dropped once the items or settings change. A size of 0 disables it, which
+* is the default.
+* @param {number} num_layouts
+*/
+  set_cache_size(num_layouts: number): void;
+/**
+* Records the phases of every computation with `performance.mark()` and
+* `performance.measure()`, so that profiles in the developer tools show how long the layout, the
+* stats and publishing the transforms took, or stops recording them.
+*
+* The measures are named `masonry layout`, `masonry stats` and `masonry publish` and appear
+* on the timeline of the thread that computed the layout. The same durations are part of
+* every [`ComputeResult`] as `elapsed`, `statsElapsed` and `publishElapsed` whether this is
+* enabled or not, together with the item count, e.g. to collect them in the field. Like
+* [`MasonryWorker::set_slice_size()`], this applies to all workers. It is disabled by
+* default, because every measure stays in the performance timeline until it is cleared.
+* @param {boolean} enabled
+*/
+  set_instrumented(enabled: boolean): void;
+/**
+* Lays out huge collections in slices of the given number of items.
+*
+* Between two slices the web worker thread publishes how far it got (see
+* [`MasonryWorker::progress()`]) and checks whether the computation was cancelled or replaced
+* by a newer one, in which case it stops and picks up the newest request. Inside a slice the
+* shared state is not touched at all. Smaller sizes than the default of 64 are raised to it.
+* @param {number} num_items
+*/
+  set_slice_size(num_items: number): void;
+/**
+* Returns how many positions of the layout the pending computation has processed at the end
+* of its last slice.
+*
+* Unlike the other methods, this may be called while a computation is pending, e.g. to show
+* a progress indicator for huge collections.
+* @returns {number}
+*/
+  progress(): number;
+/**
+* Sets a callback that is called with the percentage and the number of processed items
+* every 100 ms while a computation on the web worker thread is pending, e.g. to show a
+* progress bar instead of a frozen gallery for huge libraries, or removes it if `undefined`
+* is given.
+*
+* Only computations of more items than the threshold of
+* [`MasonryWorker::set_progress_threshold()`] are reported, and only if they take longer
+* than 100 ms. The numbers are those of [`MasonryWorker::progress()`], so they grow once per
+* slice (see [`MasonryWorker::set_slice_size()`]). Coalesced computations are reported as
+* one. Computations on the main thread block it and are not reported.
+* @param {Function | undefined} callback
+*/
+  on_progress(callback?: Function): void;
+/**
+* Sets the item count above which the progress of computations is reported to the callback
+* of [`MasonryWorker::on_progress()`]. The default is 0, which reports every computation
+* that takes long enough.
+* @param {number} num_items
+*/
+  set_progress_threshold(num_items: number): void;
+/**
+* Stops the pending computation early.
+*
+* The `Promise` returned by [`MasonryWorker::compute()`] still resolves, but
+* [`MasonryWorker::get_height()`] keeps returning the height of the last computation that
+* ran to completion. The transforms of a cancelled computation may be partially updated, so
+* they should not be read until another computation has finished.
+*/
+  cancel_pending(): void;
+/**
+* Returns true while a computation of this worker is still pending.
+* @returns {boolean}
+*/
+  is_busy(): boolean;
+/**
+* Returns the transforms of all items, so that they can be sent to another thread in a
+* message.
+*
+* Every item takes seven numbers: the width, height, top and left followed by the crop
+* offsets and the cropped flag like [`MasonryWorker::get_transform()`].
+*
+* Together with [`MasonryWorker::import_transforms()`] this is the fallback for browsers
+* without `SharedArrayBuffer` of workers whose web worker is started by the caller, where the
+* web worker has its own instance of the module. It computes the layout with
+* [`MasonryWorker::compute_sync()`] and posts the exported transforms back to the main
+* thread. [`MasonryWorker::pack_transforms()`] avoids copying them in the message. Workers
+* from [`crate::spawn::MasonryWorkerBuilder`] do this on their own.
+* @returns {Uint32Array}
+*/
+  export_transforms(): Uint32Array;
+/**
+* Replaces the transforms with the ones returned by [`MasonryWorker::export_transforms()`]
+* of another instance, which computed the given container height.
+*
+* Afterwards the transforms and queries work like after a computation of this instance.
+* Throws an `Error` whose `code` is `InvalidDimensions` if there are not seven numbers for
+* every item, in which case the layout is unchanged. It must not be called while a `Promise`
+* returned by [`MasonryWorker::compute()`] is still pending.
+* @param {Uint32Array} transforms
+* @param {number} height
+* @returns {void}
+*/
+  import_transforms(transforms: Uint32Array, height: number): void;
+/**
+* Returns the container height and the transforms of all items packed into a new
+* `ArrayBuffer`, which can be transferred to another thread instead of copied, e.g. with
+* `postMessage(buffer, [buffer])`.
+*
+* The buffer holds the height followed by the seven numbers of every item like
+* [`MasonryWorker::export_transforms()`], all as unsigned 32-bit integers. Unlike the array
+* of [`MasonryWorker::export_transforms()`], it is not a view into the memory of the module,
+* so the web worker of the fallback without `SharedArrayBuffer` can hand it over with the
+* reply to [`MasonryWorker::unpack_transforms()`] without cloning hundreds of thousands of
+* numbers.
+* @returns {ArrayBuffer}
 */
-export enum MasonryType {
-  Vertical,
-  Horizontal,
-  Grid,
-}
+  pack_transforms(): ArrayBuffer;
 /**
+* Replaces the transforms with the ones in a buffer returned by
+* [`MasonryWorker::pack_transforms()`] of another instance and returns its container height.
+*
+* Afterwards the transforms and queries work like after
+* [`MasonryWorker::import_transforms()`]. Throws if the buffer does not hold a height and
+* whole transforms, or like [`MasonryWorker::import_transforms()`] if they are not the
+* transforms of all items, in which case the layout is unchanged. It must not be called
+* while a `Promise` returned by [`MasonryWorker::compute()`] is still pending.
+* @param {ArrayBuffer} buffer
+* @returns {number}
 */
-export class MasonryWorker {
-  free(): void;
+  unpack_transforms(buffer: ArrayBuffer): number;
 /**
-* Creates a new worker from a worker that was initialized with the `worker.js` script.
-* @param {number} num_items
+* Computes the layout of a snapshot from [`MasonryWorker::export_snapshot()`] with the
+* arguments of the computation it holds and returns the transforms like
+* [`MasonryWorker::pack_transforms()`].
+*
+* This is what the web worker does for every computation of a worker from
+* [`crate::spawn::MasonryWorkerBuilder`] if `SharedArrayBuffer` is missing (see
+* `worker.js`). Throws like [`MasonryWorker::import_snapshot()`] and
+* [`MasonryWorker::compute_sync()`], or if the snapshot holds no computation.
+* @param {string} json
+* @returns {ArrayBuffer}
 */
-  constructor(num_items: number);
+  compute_snapshot(json: string): ArrayBuffer;
 /**
-* Computes the transforms of all items.
+* Returns the dimensions, flags, order and transforms of the items and the container height
+* of the most recent computation as bytes, e.g. to cache the layout in `IndexedDB`.
 *
-* # Safety
+* The configuration is not included. It must not be called while a `Promise` returned by
+* [`MasonryWorker::compute()`] is still pending.
+* @returns {Uint8Array}
+*/
+  serialize(): Uint8Array;
+/**
+* Restores the items from bytes returned by [`MasonryWorker::serialize()`] and returns the
+* container height, so that the gallery can be shown at its previous scroll position before
+* the dimensions are loaded again.
+*
+* The configuration has to be set like before serializing, because the transforms are not
+* computed again. Throws if the bytes are not a serialized layout of this version, e.g. a
+* cache of an older version, in which case the layout is unchanged. It must not be called
+* while a `Promise` returned by [`MasonryWorker::compute()`] is still pending.
+* @param {Uint8Array} bytes
+* @returns {number}
+*/
+  deserialize(bytes: Uint8Array): number;
+/**
+* Returns the whole layout as JSON, e.g. to attach it to a bug report about a layout glitch.
+*
+* The snapshot holds the arguments of the most recent computation, the gaps, paddings,
+* caption height and device pixel ratio, the settings of the layout as returned by
+* `Layout::export_settings()`, the breakpoints like [`MasonryWorker::set_breakpoints()`], the
+* container height, the items as returned by `Layout::export_items()` and
+* `Layout::export_margins()`, the order and the transforms like
+* [`MasonryWorker::export_transforms()`].
+*
+* Not included are the tags of the items, the focused item, the zoom levels and the
+* selection. It must not be called while a `Promise` returned by
+* [`MasonryWorker::compute()`] is still pending.
+* @returns {string}
+*/
+  export_snapshot(): string;
+/**
+* Restores a layout from JSON returned by [`MasonryWorker::export_snapshot()`] and returns
+* its container height, so that a reported glitch can be reproduced.
+*
+* The transforms are taken as they are, so they show the glitch even if it was fixed since.
+* Computing the layout again with the restored arguments shows the current result. Settings
+* that an older snapshot does not hold keep their value. Throws if the JSON is not a snapshot
+* of this version, in which case the layout is unchanged. It must not be called while a
+* `Promise` returned by [`MasonryWorker::compute()`] is still pending.
+* @param {string} json
+* @returns {number}
+*/
+  import_snapshot(json: string): number;
+/**
+* Checks that no two visible items overlap and that every visible item has a size and lies
+* inside of the container of the given width, which has to be the width of the most recent
+* computation.
 *
-* The returned `Promise` must be `await`ed. Calls to any other method of [`MasonryWorker`]
-* while the `Promise` is still pending will lead to undefined behaviour. As long as the value
-* is `await`ed you can enjoy lock free concurrency.
+* Every violation takes three numbers: the [`masonry_core::ViolationKind`], the index of the
+* item and the index of the item that overlaps it, or the same index again. An empty array
+* means that the layout is valid. This is meant for debugging and checks all items, so it is
+* slow for big layouts. It must not be called while a `Promise` returned by
+* [`MasonryWorker::compute()`] is still pending.
 * @param {number} width
-* @param {number} kind
-* @param {number} thumbnail_size
-* @param {number} padding
-* @returns {Promise<any>}
+* @returns {Uint32Array}
 */
-  compute(width: number, kind: number, thumbnail_size: number, padding: number): Promise<any>;
+  validate(width: number): Uint32Array;
+/**
+* Returns how much memory the items take up and the size of the WebAssembly memory, e.g. to
+* show it in a debug panel or to decide when to call [`MasonryWorker::shrink_to_fit()`].
+* @returns {MemoryStats}
+*/
+  memory_stats(): MemoryStats;
+/**
+* Returns a number that changes whenever typed arrays created from `memory.buffer` may no
+* longer show the current memory.
+*
+* Any call that allocates, e.g. [`MasonryWorker::resize()`] or a computation, can grow the
+* WebAssembly memory, after which the views of the old buffer are too short or detached.
+* [`MasonryWorker::shrink_to_fit()`] frees the transforms that
+* [`MasonryWorker::get_transform()`] pointed to. Views should be cached together with the
+* version and be created again from `memory.buffer` once it changed.
+* @returns {number}
+*/
+  memory_version(): number;
+/**
+* Returns a `Float32Array` with the width, height, top and left of every item in CSS pixels,
+* which is rewritten after every computation, so that a renderer can read the positions every
+* frame without calling into the module or copying them.
+*
+* The first call starts keeping the floats next to the transforms, which costs a copy of the
+* transforms per computation from then on. The array is a view into the memory of the module,
+* so it has to be created again once [`MasonryWorker::memory_version()`] changed, which also
+* happens when the floats moved or the item count changed. A computation on the web worker
+* thread rewrites them right before its `Promise` resolves, so a frame in between can see
+* both layouts mixed. Throws if a computation is pending.
+* @returns {Float32Array}
+*/
+  transforms_view(): Float32Array;
+/**
+* Returns a number that changes whenever the array of [`MasonryWorker::transforms_view()`]
+* was rewritten, so that a renderer can skip frames in which nothing moved.
+* @returns {number}
+*/
+  transforms_generation(): number;
 /**
 * Returns height of the container from the most recent computation.
+*
+* This is the height the `Promise` of the computation resolved with, so components that
+* missed it can size the scroll container without computing the layout again. It stays the
+* height of the last computation that ran to completion until the next one finishes. Like
+* the transforms, it is in physical pixels if a device pixel ratio is set (see
+* [`MasonryWorker::set_device_pixel_ratio()`]).
 * @returns {number}
 */
   get_height(): number;
 /**
+* Registers a callback that is called with the [`ComputeResult`] after every computation of
+* the layout, e.g. so that the scrollbar, a minimap and a debug overlay can each react to
+* layout changes without being handed the `Promise`s of the computations.
+*
+* Besides [`MasonryWorker::compute()`] and its variants, this includes the computations on
+* the main thread like [`MasonryWorker::compute_appended()`], restored layouts and adopted
+* zoom levels or cached computations. Coalesced computations call it once with the final
+* result, and [`MasonryWorker::compute_visible_first()`] once for the visible items and once
+* for the rest. The callbacks are called after the method that computed returned, in the
+* order they were registered, and can use the worker. A callback that throws does not keep
+* the others from being called.
+* @param {Function} callback
+*/
+  on_layout(callback: Function): void;
+/**
+* Removes a callback registered with [`MasonryWorker::on_layout()`] and returns whether it
+* was registered.
+* @param {Function} callback
+* @returns {boolean}
+*/
+  off_layout(callback: Function): boolean;
+/**
+* Splits the most recent computation into pages of the given height, e.g. to print or export
+* a contact sheet, so that no item straddles a page break.
+*
+* The pages are cut above the first item that would cross the bottom of a page, and the
+* items from there on continue on the next page after the outer padding. The result holds
+* the positions of the items on every page and their transforms relative to the top of the
+* page. Only items that are higher than a page cross its bottom. Compute the layout at the
+* width of the page first.
+* @param {number} page_height
+* @returns {Pagination}
+*/
+  paginate(page_height: number): Pagination;
+/**
+* Distributes the most recent computation over the given number of containers of the same
+* width, e.g. the two panes of a compare view, and returns one page per container.
+*
+* The pages are as high as needed to fit the items into that many containers, cut between
+* rows like with [`MasonryWorker::paginate()`]. The second container continues where the
+* first one ends, so the order across the panes is the one of the single layout. Compute
+* the layout at the width of a container first and size every container with
+* `page_height()`.
+* @param {number} containers
+* @returns {Pagination}
+*/
+  split_into(containers: number): Pagination;
+/**
+* Returns the tops of the rows in ascending order, e.g. to snap the scroll position to row
+* starts in the horizontal layout, so that no row is cut in half at the top of the viewport.
+*
+* Rows are runs of items that share their top, including full-width items and section
+* headers. A vertical masonry layout has no rows and returns an empty array.
+* @returns {Uint32Array}
+*/
+  snap_offsets(): Uint32Array;
+/**
+* Returns the row top of [`MasonryWorker::snap_offsets()`] that is closest to the scroll
+* position, e.g. for the target of a scroll that just ended, or `undefined` without rows.
+* @param {number} scroll_top
+* @returns {number | undefined}
+*/
+  nearest_snap(scroll_top: number): number | undefined;
+/**
+* Returns the top, height and first and last index of the row with the given index, e.g. to
+* snap the scroll position to rows or to select a whole row, or `undefined` if there are
+* fewer rows.
+*
+* Rows are runs of items that share their top, see [`ComputeResult::rows`] for their count.
+* In a vertical masonry layout use [`MasonryWorker::column_info()`] instead.
+* @param {number} row
+* @returns {RowInfo | undefined}
+*/
+  row_info(row: number): RowInfo | undefined;
+/**
+* Returns the left offset, width, bottom and item count of the column with the given index
+* of a vertical masonry or grid layout, or `undefined` if the column has no items or the
+* layout has no columns.
+* @param {number} column
+* @returns {ColumnInfo | undefined}
+*/
+  column_info(column: number): ColumnInfo | undefined;
+/**
+* Returns the first and last index of the items that are visible in the scroll window.
+*
+* The window is extended by `overscan` pixels at the top and bottom, so that items can be
+* rendered before they scroll into view. Returns `undefined` if no item is visible.
+* @param {number} scroll_top
+* @param {number} viewport_height
+* @param {number} overscan
+* @returns {ItemRange | undefined}
+*/
+  items_in_viewport(scroll_top: number, viewport_height: number, overscan: number): ItemRange | undefined;
+/**
+* Returns the first and last index of the items that are visible in a scroll window that
+* scrolls horizontally, e.g. for a filmstrip, like [`MasonryWorker::items_in_viewport()`].
+*
+* The window is extended by `overscan` pixels on the left and right. Every call checks all
+* items, because their lefts are not indexed like their tops.
+* @param {number} scroll_left
+* @param {number} viewport_width
+* @param {number} overscan
+* @returns {ItemRange | undefined}
+*/
+  items_in_viewport_x(scroll_left: number, viewport_width: number, overscan: number): ItemRange | undefined;
+/**
+* Returns the indices of the items that are visible in the scroll window like
+* [`MasonryWorker::items_in_viewport()`], each followed by its tag, see
+* [`MasonryWorker::set_tag()`].
+* @param {number} scroll_top
+* @param {number} viewport_height
+* @param {number} overscan
+* @returns {Uint32Array}
+*/
+  tagged_items_in_viewport(scroll_top: number, viewport_height: number, overscan: number): Uint32Array;
+/**
+* Returns the first and last index of the items that will be visible soon, e.g. to load their
+* thumbnails first, given the `scrollTop`, the scroll velocity in pixels per second (positive
+* when scrolling down) and the viewport height.
+*
+* The viewport is extended in the scroll direction by the distance of half a second at the
+* velocity, but at most four viewport heights. Returns `undefined` if no item is in the
+* window.
+* @param {number} scroll_top
+* @param {number} velocity
+* @param {number} viewport_height
+* @returns {ItemRange | undefined}
+*/
+  prefetch_window(scroll_top: number, velocity: number, viewport_height: number): ItemRange | undefined;
+/**
+* Returns the section header that should be rendered sticky at the given `scrollTop`.
+*
+* Section headers are the items marked with [`MasonryWorker::set_full_width()`]. The sticky
+* header is the last one that scrolled past the top of the viewport. When the next header
+* approaches, the sticky one is pushed up by `offset` pixels, so it should be rendered at
+* `scrollTop - offset`. Returns `undefined` if no header scrolled past the top yet.
+* @param {number} scroll_top
+* @returns {StickyHeader | undefined}
+*/
+  sticky_header(scroll_top: number): StickyHeader | undefined;
+/**
+* Returns the index of the item at the given position relative to the container.
+*
+* Returns `undefined` if there is no item at that position, e.g. when it is in the padding
+* between items.
+* @param {number} x
+* @param {number} y
+* @returns {number | undefined}
+*/
+  item_at(x: number, y: number): number | undefined;
+/**
+* Returns the indices of all items that overlap the rectangle, e.g. for drag-to-select.
+*
+* The rectangle is relative to the container and does not need the items to be rendered.
+* @param {number} x
+* @param {number} y
+* @param {number} width
+* @param {number} height
+* @returns {Uint32Array}
+*/
+  items_in_rect(x: number, y: number, width: number, height: number): Uint32Array;
+/**
+* Returns the index and tag of the item at the given position relative to the container
+* like [`MasonryWorker::item_at()`], see [`MasonryWorker::set_tag()`].
+* @param {number} x
+* @param {number} y
+* @returns {TaggedItem | undefined}
+*/
+  tagged_item_at(x: number, y: number): TaggedItem | undefined;
+/**
+* Returns the indices of all items that overlap the rectangle like
+* [`MasonryWorker::items_in_rect()`], each followed by its tag, see
+* [`MasonryWorker::set_tag()`].
+* @param {number} x
+* @param {number} y
+* @param {number} width
+* @param {number} height
+* @returns {Uint32Array}
+*/
+  tagged_items_in_rect(x: number, y: number, width: number, height: number): Uint32Array;
+/**
+* Selects or deselects the item at the given index.
+*
+* The selection is kept in the worker, so that ranges and marquees are resolved with the
+* geometry of the layout the same way in every layout kind. Its indices are the ones returned
+* by the queries. They shift with [`MasonryWorker::insert()`], [`MasonryWorker::remove()`]
+* and [`MasonryWorker::move_items()`], and items beyond the item count are not selected.
+* @param {number} index
+* @param {boolean} selected
+*/
+  select(index: number, selected: boolean): void;
+/**
+* Flips the item at the given index, e.g. for a click with the modifier key, and returns
+* whether it is selected afterwards.
+* @param {number} index
+* @returns {boolean}
+*/
+  toggle_selected(index: number): boolean;
+/**
+* Selects the items from `anchor` to `focus` in reading order, e.g. for a shift-click from
+* the previously clicked item.
+*
+* The reading order sorts the items by their top and then by their left, so the range covers
+* whole rows in the grid, horizontal and list layouts, and all items whose tops lie between
+* both items across the columns of the vertical masonry layout. Unless `extend` is true, the
+* previous selection is replaced.
+* @param {number} anchor
+* @param {number} focus
+* @param {boolean} extend
+*/
+  select_range(anchor: number, focus: number, extend: boolean): void;
+/**
+* Selects the items that overlap the rectangle like [`MasonryWorker::items_in_rect()`], e.g.
+* for marquee selection. Unless `extend` is true, the previous selection is replaced.
+* @param {number} x
+* @param {number} y
+* @param {number} width
+* @param {number} height
+* @param {boolean} extend
+*/
+  select_rect(x: number, y: number, width: number, height: number, extend: boolean): void;
+/**
+* Starts a marquee drag, e.g. on `pointerdown`. Unless `extend` is true, the previous
+* selection is cleared, and otherwise it stays selected wherever the marquee goes.
+* @param {boolean} extend
+*/
+  drag_start(extend: boolean): void;
+/**
+* Moves the marquee of a drag to the given rectangle relative to the container, e.g. on
+* every `pointermove` and on every frame while it autoscrolls, and returns what changed.
+*
+* The items under the marquee are selected and the ones it no longer covers are deselected
+* again, and the returned indices tell which ones, so that only those have to be re-rendered
+* however many items the marquee spans. Once the marquee reaches within 48 pixels of the top
+* or bottom of the viewport, `autoscrollDelta` is the number of pixels to scroll by in this
+* frame, up to 24 at the edge and beyond, and 0 otherwise. It stops at both ends of the
+* container. Without [`MasonryWorker::drag_start()`], the first update starts a drag that
+* replaces the selection.
+* @param {number} x
+* @param {number} y
+* @param {number} width
+* @param {number} height
+* @param {number} scroll_top
+* @param {number} viewport_height
+* @returns {DragUpdate}
+*/
+  drag_update(x: number, y: number, width: number, height: number, scroll_top: number, viewport_height: number): DragUpdate;
+/**
+* Ends the marquee drag, e.g. on `pointerup`. The items stay selected.
+*/
+  drag_end(): void;
+/**
+*/
+  clear_selection(): void;
+/**
+* @param {number} index
+* @returns {boolean}
+*/
+  is_selected(index: number): boolean;
+/**
+* Returns the number of selected items.
+* @returns {number}
+*/
+  selected_count(): number;
+/**
+* Returns the indices of the selected items in ascending order.
+* @returns {Uint32Array}
+*/
+  selected_indices(): Uint32Array;
+/**
+* Returns the index of the item after the item at `index` in the order in which they appear
+* on the screen, e.g. for the arrow keys of a full-screen viewer, so that it steps through the
+* items like the user sees them in the grid: by their top and then from left to right,
+* including section headers, pinned items and spanning items where they are placed.
+*
+* Like the other queries, it works on positions, see [`MasonryWorker::item_index()`]. Returns
+* `undefined` after the last item or if the item is not laid out.
+* @param {number} index
+* @returns {number | undefined}
+*/
+  next_visual(index: number): number | undefined;
+/**
+* Returns the index of the item before the item at `index` in the order in which they appear
+* on the screen, see [`MasonryWorker::next_visual()`].
+* @param {number} index
+* @returns {number | undefined}
+*/
+  prev_visual(index: number): number | undefined;
+/**
+* Returns the index of the item next to the item at `index` in the given direction, e.g. for
+* navigating with the arrow keys.
+*
+* Returns `undefined` if there is no item in that direction.
+* @param {number} index
+* @param {Direction} direction
+* @returns {number | undefined}
+*/
+  neighbor(index: number, direction: Direction): number | undefined;
+/**
 * Set the number of items that need to be computed.
 *
 * Memory is never deallocated which means that even if the new len is smaller than the current
 * item count, it will not free the memory of previous items. This is done to avoid allocating
 * a lot. Allocations can be vary in performance depending on the provided allocator. This
-* makes no efforts and uses the standard library allocator.
+* makes no efforts and uses the global allocator (see the `talc` and `lol_alloc` features).
 * @param {number} new_len
 */
   resize(new_len: number): void;
 /**
+* Frees the memory that [`MasonryWorker::resize()`] kept for items beyond the item count,
+* e.g. after switching from a huge location to a small one.
+*
+* The dimensions of the freed items are forgotten. It must not be called while a `Promise`
+* returned by [`MasonryWorker::compute()`] is still pending.
+*/
+  shrink_to_fit(): void;
+/**
+* Hides the items that are filtered out without removing their dimensions.
+*
+* Bit `i % 8` of byte `i / 8` has to be set for the item at index `i` to be visible. Items
+* beyond the end of the bitmask are visible, so an empty array shows all items. Hidden items
+* are skipped by the layout, get a transform with zero width and height and are never
+* returned by queries. The layout can be updated with
+* [`MasonryWorker::compute_incremental()`] afterwards.
+* @param {Uint8Array} bitmask
+*/
+  set_visibility(bitmask: Uint8Array): void;
+/**
+* Inserts `count` items at the given index and shifts the dimensions of the following items.
+*
+* The dimensions of the inserted items have to be set before the next computation. The
+* layout can be updated with [`MasonryWorker::compute_incremental()`] afterwards.
+* @param {number} index
+* @param {number} count
+*/
+  insert(index: number, count: number): void;
+/**
+* Removes `count` items at the given index and shifts the dimensions of the following items.
+*
+* The layout can be updated with [`MasonryWorker::compute_incremental()`] afterwards.
+* @param {number} index
+* @param {number} count
+*/
+  remove(index: number, count: number): void;
+/**
+* Moves the item at `from` to the index `to`, e.g. after reordering it with drag and drop.
+*
+* The layout can be updated with [`MasonryWorker::compute_incremental()`] afterwards.
+* @param {number} from
+* @param {number} to
+*/
+  move_item(from: number, to: number): void;
+/**
+* Moves `count` consecutive items starting at `from` so that they start at the index `to`.
+* @param {number} from
+* @param {number} count
+* @param {number} to
+*/
+  move_items(from: number, count: number, to: number): void;
+/**
+* Lays out the items in the order of the given item ids, e.g. after sorting the gallery.
+*
+* The dimensions stay keyed by the item id, so they do not need to be set again. The
+* transforms and the indices returned by queries refer to the position in the layout
+* instead. Positions without a valid id keep the item with the same index. Note that
+* [`MasonryWorker::insert()`], [`MasonryWorker::remove()`] and
+* [`MasonryWorker::move_item()`] shift the item ids, so the order has to be set again
+* afterwards.
+* @param {Uint32Array} permutation
+*/
+  set_order(permutation: Uint32Array): void;
+/**
+* Lays out the items in the order of their dimensions again.
+*/
+  clear_order(): void;
+/**
+* Records up to `limit` changes of the dimensions, the item count, the order and the
+* positions of the items, so that they can be undone with [`MasonryWorker::undo()`], e.g.
+* after removing images from the gallery by accident.
+*
+* The calls that are recorded are [`MasonryWorker::resize()`],
+* [`MasonryWorker::set_dimension()`], [`MasonryWorker::insert()`],
+* [`MasonryWorker::remove()`], [`MasonryWorker::move_items()`] and
+* [`MasonryWorker::set_order()`]. A limit of 0 disables the log, which is the default.
+* @param {number} limit
+*/
+  set_history_limit(limit: number): void;
+/**
+* Reverts the most recent recorded change, lays out the items again like
+* [`MasonryWorker::compute_incremental()`] and returns the new container height, or
+* `undefined` if there is nothing to undo.
+*
+* It must not be called while a `Promise` returned by [`MasonryWorker::compute()`] is still
+* pending.
+* @returns {number | undefined}
+*/
+  undo(): number | undefined;
+/**
+* Applies the most recently undone change again like [`MasonryWorker::undo()`], or returns
+* `undefined` if there is nothing to redo. Any other recorded change forgets the undone ones.
+* @returns {number | undefined}
+*/
+  redo(): number | undefined;
+/**
+* @returns {boolean}
+*/
+  can_undo(): boolean;
+/**
+* @returns {boolean}
+*/
+  can_redo(): boolean;
+/**
+* Pins or unpins the item at the given index.
+*
+* Pinned items are laid out before all other items in every layout kind, so the first pinned
+* item is always at the top left, e.g. for an "import images" placeholder. Like with
+* [`MasonryWorker::set_order()`], the transforms and the indices returned by queries refer
+* to the position in the layout as soon as an item is pinned. Use
+* [`MasonryWorker::item_index()`] to get the item at a position.
+* @param {number} index
+* @param {boolean} pinned
+*/
+  set_pinned(index: number, pinned: boolean): void;
+/**
+* Lays out the item at the given index in a row of its own that spans the full width.
+*
+* This works in every layout kind, e.g. for section banners or info cards. The height is
+* derived from the dimension of the item and the following items continue below it.
+* @param {number} index
+* @param {boolean} full_width
+*/
+  set_full_width(index: number, full_width: boolean): void;
+/**
+* Gives the full-width item at the given index a fixed height in CSS pixels instead of the
+* one that follows from its dimension, e.g. for a section header, or removes it with 0.
+* @param {number} index
+* @param {number} height
+*/
+  set_fixed_height(index: number, height: number): void;
+/**
+* Enlarges the item at the given index in place, e.g. to 3 times the thumbnail size for an
+* inline preview, and lets the surrounding items reflow around it. Only one item is focused
+* at a time, so this returns the previously focused item to its size.
+*
+* The item gets a row of its own that spans the full width and is `scale` times the
+* thumbnail size high, or as high as its aspect ratio implies for a scale of 0. The following
+* items continue below it. The next computation returns the transforms of the focused item
+* and of the displaced ones, which [`MasonryWorker::changed_items()`] lists for animating
+* them. Inserting, removing, moving or resizing the items ends the focus.
+* @param {number} index
+* @param {number} scale
+*/
+  set_focus(index: number, scale: number): void;
+/**
+* Returns the item of [`MasonryWorker::set_focus()`] to its size and lets the surrounding
+* items reflow back.
+*/
+  clear_focus(): void;
+/**
+* Returns the index of the item enlarged with [`MasonryWorker::set_focus()`], if any.
+* @returns {number | undefined}
+*/
+  focused_item(): number | undefined;
+/**
+* Lays out the items as a timeline: sorted by their timestamps, the newest first, and grouped
+* into days, months or years with a section header before each, and returns the granularity.
+*
+* The timestamps are in milliseconds since the Unix epoch, one per item, and `NaN` for items
+* without a date, which end up in a last section. The granularity is the finest one whose
+* sections hold at least 8 items on average, and the days start at midnight in the time zone
+* with the given offset to UTC in minutes, e.g. `-new Date().getTimezoneOffset()`.
+*
+* The headers are items of their own that follow the items, so the item count grows by the
+* number of sections, and they are full width with the given height in every layout kind.
+* Like with [`MasonryWorker::set_order()`], the queries return positions: use
+* [`MasonryWorker::timeline_section()`] to tell the headers from the items and
+* [`MasonryWorker::item_index()`] to get the item at a position. Call this again after the
+* items or their timestamps changed.
+* @param {Float64Array} timestamps
+* @param {number} header_height
+* @param {number} utc_offset_minutes
+* @returns {TimelineGranularity}
+*/
+  set_timeline(timestamps: Float64Array, header_height: number, utc_offset_minutes: number): TimelineGranularity;
+/**
+* Removes the section headers of [`MasonryWorker::set_timeline()`] and lays out the items in
+* the order of their dimensions again.
+*/
+  clear_timeline(): void;
+/**
+* Returns the section whose header is laid out at the given position, or `undefined` if the
+* item at the position is not a header of [`MasonryWorker::set_timeline()`].
+* @param {number} position
+* @returns {TimelineSection | undefined}
+*/
+  timeline_section(position: number): TimelineSection | undefined;
+/**
+* Lays out the section after the section header at the given index with its own kind.
+*
+* Section headers are the items marked with [`MasonryWorker::set_full_width()`] and a
+* section lasts until the next header, e.g. a "Recently added" strip in grid mode followed
+* by the main collection in vertical masonry. Sections without a kind of their own use the
+* kind passed to [`MasonryWorker::compute()`]. The rows of horizontal sections are filled one
+* by one, and only the columns of the last section can be balanced. Section kinds have no
+* effect in reversed layouts (see [`MasonryWorker::set_reversed()`]).
+* @param {number} index
+* @param {MasonryType} kind
+*/
+  set_section_kind(index: number, kind: MasonryType): void;
+/**
+* Lays out the section after the section header at the given index with the kind of the
+* computation again.
+* @param {number} index
+*/
+  clear_section_kind(index: number): void;
+/**
+* Lets the item at the given index span 2 columns, 2 rows or both, e.g. to show favorites or
+* highly rated images larger. 1 by 1 is the default, larger spans are treated as 2.
+*
+* In the grid and mosaic layouts the following items fill the free cells around it. An item
+* that is two columns wide but does not fit into the rest of a row moves to the next row and
+* leaves the cell empty. In the vertical masonry layout the item is two columns wide and as
+* high as its aspect ratio needs, so only the columns count. The horizontal, fixed rows,
+* list and filmstrip layouts ignore the span.
+* @param {number} index
+* @param {number} columns
+* @param {number} rows
+*/
+  set_span(index: number, columns: number, rows: number): void;
+/**
+* Adds space around the item at the given index on top of the padding, e.g. around a hero
+* image or a section header. The margins are in CSS pixels and at most 255 per side, 0 on
+* all sides removes them.
+*
+* The vertical masonry and list layouts make room for the margins, so the item is narrower by
+* its left and right margin and the items above and below it move away. The horizontal,
+* fixed rows, grid, mosaic and filmstrip layouts ignore the margins. Snapshots do not include
+* them.
+* @param {number} index
+* @param {number} top
+* @param {number} right
+* @param {number} bottom
+* @param {number} left
+*/
+  set_item_margin(index: number, top: number, right: number, bottom: number, left: number): void;
+/**
+* Starts a new row at the item with the given index, e.g. for visual grouping.
+*
+* In the horizontal layout the unfinished row before it is left as it is, like the last row.
+* In the vertical masonry layout the item and everything after it is placed below all
+* columns.
+* @param {number} index
+* @param {boolean} break_before
+*/
+  set_break_before(index: number, break_before: boolean): void;
+/**
+* Returns the index of the item that is laid out at the given position.
+*
+* This is the identity unless an order was set or items are pinned.
+* @param {number} position
+* @returns {number}
+*/
+  item_index(position: number): number;
+/**
+* Attaches a number to the item at the given index, e.g. the id the application knows it by,
+* which the tagged queries return together with the index.
+*
+* The tag stays with the item when items are inserted, removed or moved, unlike an array of
+* ids next to the layout that has to be spliced the same way. It does not change the layout.
+* Every item starts with 0.
+* @param {number} index
+* @param {number} tag
+*/
+  set_tag(index: number, tag: number): void;
+/**
+* Returns the tag of the item with the given index in the results of the queries, or
+* `undefined` if there is no such item, see [`MasonryWorker::set_tag()`].
+* @param {number} index
+* @returns {number | undefined}
+*/
+  get_tag(index: number): number | undefined;
+/**
+* Sets the EXIF orientation (1 to 8) of the item at the given index.
+*
+* For the orientations 5 to 8 the image is rotated by 90 degrees, so the width and height
+* passed to [`MasonryWorker::set_dimension()`] are swapped inside the layout. The dimension
+* should be the one stored in the file, before the rotation. The default is 1.
+* @param {number} index
+* @param {number} orientation
+*/
+  set_orientation(index: number, orientation: number): void;
+/**
 * Set the dimension of one item at the given index if it is smaller than the item count.
 *
 * You have to set the dimensions of the items if you want to compute a vertical or horizontal
@@ -66,9 +2172,96 @@ export class MasonryWorker {
 */
   set_dimension(index: number, src_width: number, src_height: number): void;
 /**
+* Returns the position and size of the item at the given index as an inline style, e.g.
+* `transform: translate(12px, 34px); width: 100px; height: 80px;`, or `undefined` if there is
+* no such item.
+*
+* This saves building the string from the fields of [`MasonryWorker::get_transform()`] in
+* JavaScript. The lengths are in CSS pixels, so they are divided by the device pixel ratio
+* of the most recent computation. Reading the transforms from memory is faster for many items
+* per frame, because every call allocates a string.
+* @param {number} index
+* @returns {string | undefined}
+*/
+  get_transform_css(index: number): string | undefined;
+/**
+* Sets the dimensions of the items from the start as pairs of width and height.
+*
+* This is the same as calling [`MasonryWorker::set_dimension()`] for every pair, but it only
+* crosses the boundary to JavaScript once, e.g. when the dimensions were sent to another
+* thread in a message.
+* @param {Uint16Array} dimensions
+*/
+  set_dimensions(dimensions: Uint16Array): void;
+/**
+* Sets the dimensions of the items from the start from an array of objects with a `width`
+* and `height` or a `w` and `h`, and optionally an EXIF `orientation` (see
+* [`MasonryWorker::set_orientation()`]).
+*
+* This saves building the pairs for [`MasonryWorker::set_dimensions()`] when the metadata of
+* the files is kept in objects, e.g. `[{ width: 1920, height: 1080 }, { w: 600, h: 800,
+* orientation: 6 }]`. A missing width or height leaves the dimension unknown and a missing
+* orientation keeps the one of the item. Dimensions beyond 65535 pixels are scaled down. Throws
+* if the value is not an array or one of its elements is not an object, in which case the
+* items before that element were set already, and if there are more elements than items, in
+* which case nothing is set.
+* @param {any} objects
+* @returns {void}
+*/
+  set_dimensions_from(objects: any): void;
+/**
+* Sets the dimension and orientation of the item at the given index from the header of its
+* file and returns whether they could be read, see [`crate::measure::probe_dimensions()`].
+*
+* Nothing is decoded, so this is the fastest way to measure the files of a large import. The
+* dimension is scaled down like in [`MasonryWorker::set_dimensions_from()`] if it is larger.
+* @param {number} index
+* @param {Uint8Array} bytes
+* @returns {boolean}
+*/
+  set_dimension_from_bytes(index: number, bytes: Uint8Array): boolean;
+/**
+* Reads the dimensions of images and sets them for the items from the given index on. The
+* returned `Promise` resolves with the number of images once all dimensions are set.
+*
+* The images can be `ImageBitmap`s, image elements or anything else with a `width` and
+* `height`, which are read right away, or `Blob`s, e.g. the `File`s of an import. `Blob`s
+* are decoded with `createImageBitmap()`, which the browser does off the main thread, so new
+* files do not have to be decoded just to measure them. Their EXIF orientation is applied,
+* so no orientation should be set for them. Images that cannot be decoded get an unknown
+* dimension.
+*
+* # Safety
+*
+* The returned `Promise` must be `await`ed before the layout is computed or the worker is
+* freed, because the dimensions are set when it resolves.
+* @param {number} start
+* @param {Array<any>} images
+* @returns {Promise<any>}
+*/
+  measure_dimensions(start: number, images: Array<any>): Promise<any>;
+/**
+* Returns the transform of the item at the given index as a `Transform` object with typed
+* properties, or `undefined` if the index is out of bounds.
+*
+* The object holds a copy with the same values as [`MasonryWorker::get_transform()`], so it
+* stays valid after the next computation and when the memory grows. To position many items
+* per frame, reading the memory behind [`MasonryWorker::get_transform()`] avoids allocating
+* an object per item.
+* @param {number} index
+* @returns {Transform | undefined}
+*/
+  transform(index: number): Transform | undefined;
+/**
 * Returns a pointer to the transform of the item at the given index.
 *
-* The [`Transform`] object can be used to set the absolute position of an element.
+* The [`Transform`] object can be used to set the absolute position of an element. The width,
+* height, top and left are followed by the top and left crop offsets of the thumbnail and 1
+* if it is cropped or 0 otherwise (see [`MasonryWorker::set_max_row_height()`] and
+* [`MasonryWorker::set_aspect_ratio_limits()`]). Grid cells are square, so their thumbnails
+* are cropped unless they are square as well. A cropped thumbnail is scaled to cover its
+* rectangle and centered like with `object-fit: cover`, and the crop offsets are how far it
+* overhangs the rectangle at the top and on the left.
 *
 * # Safety
 *
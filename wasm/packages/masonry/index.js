@@ -20,6 +20,21 @@ export function run() {
   }
 }
 
+// Starts a web worker that runs `worker.js`, which is a static asset next to this module.
+//
+// With Trusted Types enforced, e.g. in Electron, `new Worker()` only accepts a `TrustedScriptURL`.
+// The URL of the script is then passed through the given policy, or any function that returns a
+// `TrustedScriptURL` for it, instead of being used as it is.
+export function createWorker(policy) {
+  if (policy === undefined) {
+    // Kept literal, so that bundlers find the script and emit it as an asset.
+    return new Worker(new URL('./worker.js', import.meta.url), { type: 'module' });
+  }
+  const url = new URL('./worker.js', import.meta.url).href;
+  const scriptURL = typeof policy === 'function' ? policy(url) : policy.createScriptURL(url);
+  return new Worker(scriptURL, { type: 'module' });
+}
+
 export const MasonryType = Object.freeze({
   Vertical: 0,
   0: 'Vertical',
@@ -27,19 +42,81 @@ export const MasonryType = Object.freeze({
   1: 'Horizontal',
   Grid: 2,
   2: 'Grid',
+  List: 3,
+  3: 'List',
+  Mosaic: 4,
+  4: 'Mosaic',
+  FixedRows: 5,
+  5: 'FixedRows',
+  Filmstrip: 6,
+  6: 'Filmstrip',
+});
+
+export const Direction = Object.freeze({
+  Up: 0,
+  0: 'Up',
+  Down: 1,
+  1: 'Down',
+  Left: 2,
+  2: 'Left',
+  Right: 3,
+  3: 'Right',
+});
+
+export const ScrollAlignment = Object.freeze({
+  Top: 0,
+  0: 'Top',
+  Center: 1,
+  1: 'Center',
+  Bottom: 2,
+  2: 'Bottom',
+});
+
+export const LastRowBehavior = Object.freeze({
+  Left: 0,
+  0: 'Left',
+  Justify: 1,
+  1: 'Justify',
+  Hide: 2,
+  2: 'Hide',
+});
+
+export const RoundingMode = Object.freeze({
+  Floor: 0,
+  0: 'Floor',
+  Round: 1,
+  1: 'Round',
+  Accumulate: 2,
+  2: 'Accumulate',
+});
+
+export const TimelineGranularity = Object.freeze({
+  Day: 0,
+  0: 'Day',
+  Month: 1,
+  1: 'Month',
+  Year: 2,
+  2: 'Year',
 });
 
 export class MasonryWorker {
   worker;
 
-  constructor(num_items) {
-    if (IS_SIMD_SUPPORTED) {
+  constructor(num_items, worker) {
+    if (worker !== undefined) {
+      this.worker = worker;
+    } else if (IS_SIMD_SUPPORTED) {
       this.worker = new SimdMasonryWorker(num_items);
     } else {
       this.worker = new ScalarMasonryWorker(num_items);
     }
   }
 
+  static with_worker(num_items, worker) {
+    const MasonryWorkerType = IS_SIMD_SUPPORTED ? SimdMasonryWorker : ScalarMasonryWorker;
+    return new MasonryWorker(num_items, MasonryWorkerType.with_worker(num_items, worker));
+  }
+
   compute(width, kind, thumbnail_size, padding) {
     return this.worker.compute(width, kind, thumbnail_size, padding);
   }
@@ -59,15 +136,49 @@ export class MasonryWorker {
   get_transform(index) {
     return this.worker.get_transform(index);
   }
+
+  compute_snapshot(json) {
+    return this.worker.compute_snapshot(json);
+  }
+}
+
+// The other methods and accessors of the bindings are forwarded as they are, so that new ones do
+// not have to be added here one by one. Both builds export the same API.
+for (const [name, descriptor] of Object.entries(
+  Object.getOwnPropertyDescriptors(SimdMasonryWorker.prototype),
+)) {
+  if (name in MasonryWorker.prototype) {
+    continue;
+  }
+  if (typeof descriptor.value === 'function') {
+    descriptor.value = function (...args) {
+      return this.worker[name](...args);
+    };
+  } else {
+    if (descriptor.get !== undefined) {
+      descriptor.get = function () {
+        return this.worker[name];
+      };
+    }
+    if (descriptor.set !== undefined) {
+      descriptor.set = function (value) {
+        this.worker[name] = value;
+      };
+    }
+  }
+  Object.defineProperty(MasonryWorker.prototype, name, descriptor);
 }
 
-async function init(maybe_memory) {
+// A compiled module skips fetching and compiling the `.wasm` file, e.g. in the web worker.
+async function init(maybe_memory, maybe_module) {
   IS_SIMD_SUPPORTED = await simd();
   if (IS_SIMD_SUPPORTED) {
-    return simdInit(new URL('./masonry-simd/masonry_bg.wasm', import.meta.url), maybe_memory);
+    const input = maybe_module ?? new URL('./masonry-simd/masonry_bg.wasm', import.meta.url);
+    return simdInit(input, maybe_memory);
   } else {
     console.warn('SIMD instructions are not supported. Falling back to scalar code.');
-    return scalarInit(new URL('./masonry-scalar/masonry_bg.wasm', import.meta.url), maybe_memory);
+    const input = maybe_module ?? new URL('./masonry-scalar/masonry_bg.wasm', import.meta.url);
+    return scalarInit(input, maybe_memory);
   }
 }
 
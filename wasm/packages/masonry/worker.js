@@ -1,5 +1,35 @@
-import { default as init, run } from './index.js';
+import { default as init, run, MasonryWorker } from './index.js';
+
+// Without `SharedArrayBuffer`, the web worker keeps its own `MasonryWorker`, which computes the
+// snapshots that the main thread posts with a port for the reply.
+let fallbackWorker;
+
 self.onmessage = async (event) => {
-  await init(event.data);
-  run();
+  if (event.data.snapshot !== undefined) {
+    const { snapshot, port } = event.data;
+    try {
+      const buffer = (await fallbackWorker).compute_snapshot(snapshot);
+      port.postMessage(buffer, [buffer]);
+    } catch (error) {
+      port.postMessage({ error });
+    }
+    return;
+  }
+  // The memory alone, or together with the module that the main thread compiled already.
+  const { memory, module, fallback } =
+    event.data instanceof WebAssembly.Memory ? { memory: event.data } : event.data;
+  const initialized = init(memory, module);
+  if (fallback) {
+    fallbackWorker = initialized.then(() => new MasonryWorker(0));
+  }
+  try {
+    await initialized;
+  } catch (error) {
+    // Errors of an async handler do not reach `onerror` of the main thread.
+    self.postMessage({ error: String(error) });
+    return;
+  }
+  if (!fallback) {
+    run();
+  }
 };